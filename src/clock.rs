@@ -0,0 +1,201 @@
+//! Time source abstraction.
+//!
+//! Worker pacing, the sequence matcher's timestamps and the foreground
+//! process cache all read time through a [`Clock`] instead of calling
+//! `Instant::now()` / `thread::sleep` directly, and the workers wait on
+//! their channels through [`recv_timeout`]. Production uses
+//! [`SystemClock`]; tests use [`VirtualClock`] to step time by hand so
+//! cadence, dedup and window-expiry checks don't depend on wall-clock
+//! scheduling.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+
+/// Source of monotonic time plus the ability to wait for a point in it.
+pub trait Clock: Send + Sync {
+    /// Current monotonic time.
+    fn now(&self) -> Instant;
+
+    /// Blocks until `deadline` has been reached. Returns immediately when
+    /// the deadline is already in the past.
+    fn sleep_until(&self, deadline: Instant);
+
+    /// Blocks for `duration` measured on this clock.
+    #[inline]
+    fn sleep(&self, duration: Duration) {
+        if !duration.is_zero() {
+            self.sleep_until(self.now() + duration);
+        }
+    }
+
+    /// Real time a channel wait of `timeout` on this clock may block for.
+    #[inline]
+    fn channel_wait(&self, timeout: Duration) -> Duration {
+        timeout
+    }
+}
+
+/// Receives from `rx`, giving up once `timeout` has passed on `clock`.
+///
+/// A virtual clock never blocks here: with nothing queued it jumps to the
+/// deadline and reports a timeout, just as its `sleep_until` does.
+#[inline]
+pub fn recv_timeout<T>(
+    clock: &dyn Clock,
+    rx: &Receiver<T>,
+    timeout: Duration,
+) -> Result<T, RecvTimeoutError> {
+    let deadline = clock.now() + timeout;
+    match rx.recv_timeout(clock.channel_wait(timeout)) {
+        Err(RecvTimeoutError::Timeout) => {
+            clock.sleep_until(deadline);
+            Err(RecvTimeoutError::Timeout)
+        }
+        result => result,
+    }
+}
+
+/// Wall clock backed by `Instant::now` and `thread::sleep`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    #[inline(always)]
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    #[inline]
+    fn sleep_until(&self, deadline: Instant) {
+        let now = Instant::now();
+        if deadline > now {
+            std::thread::sleep(deadline - now);
+        }
+    }
+}
+
+/// Manually driven clock for deterministic tests.
+///
+/// Time only moves when [`advance`](Self::advance) is called or when a
+/// caller sleeps: `sleep_until` jumps the clock straight to the deadline
+/// instead of blocking, so a simulated `event_duration` hold advances time
+/// by exactly that amount. The clock never moves backwards.
+#[allow(dead_code)]
+#[derive(Debug)]
+pub struct VirtualClock {
+    origin: Instant,
+    elapsed_ns: AtomicU64,
+}
+
+#[allow(dead_code)]
+impl VirtualClock {
+    pub fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+            elapsed_ns: AtomicU64::new(0),
+        }
+    }
+
+    /// Moves the clock forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        self.elapsed_ns
+            .fetch_add(duration.as_nanos() as u64, Ordering::AcqRel);
+    }
+
+    /// Total time elapsed on this clock since it was created.
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_nanos(self.elapsed_ns.load(Ordering::Acquire))
+    }
+}
+
+impl Default for VirtualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for VirtualClock {
+    #[inline]
+    fn now(&self) -> Instant {
+        self.origin + self.elapsed()
+    }
+
+    #[inline]
+    fn sleep_until(&self, deadline: Instant) {
+        let target = deadline.saturating_duration_since(self.origin).as_nanos() as u64;
+        self.elapsed_ns.fetch_max(target, Ordering::AcqRel);
+    }
+
+    #[inline]
+    fn channel_wait(&self, _timeout: Duration) -> Duration {
+        Duration::ZERO
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_virtual_clock_starts_at_origin() {
+        let clock = VirtualClock::new();
+        assert_eq!(clock.elapsed(), Duration::ZERO);
+        assert_eq!(clock.now(), clock.now());
+    }
+
+    #[test]
+    fn test_virtual_clock_advance() {
+        let clock = VirtualClock::new();
+        let start = clock.now();
+        clock.advance(Duration::from_millis(16));
+        assert_eq!(clock.now() - start, Duration::from_millis(16));
+    }
+
+    #[test]
+    fn test_virtual_clock_sleep_jumps_to_deadline() {
+        let clock = VirtualClock::new();
+        let start = clock.now();
+        clock.sleep(Duration::from_millis(40));
+        assert_eq!(clock.now() - start, Duration::from_millis(40));
+        clock.sleep_until(start + Duration::from_millis(100));
+        assert_eq!(clock.now() - start, Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_virtual_clock_never_moves_backwards() {
+        let clock = VirtualClock::new();
+        let start = clock.now();
+        clock.advance(Duration::from_millis(50));
+        clock.sleep_until(start + Duration::from_millis(10));
+        assert_eq!(clock.now() - start, Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_virtual_clock_recv_timeout() {
+        let clock = VirtualClock::new();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let start = clock.now();
+
+        // A queued message comes back without moving the clock.
+        tx.send(1).unwrap();
+        assert_eq!(recv_timeout(&clock, &rx, Duration::from_millis(50)), Ok(1));
+        assert_eq!(clock.now(), start);
+
+        // An empty channel times out at once, the clock at the deadline.
+        assert_eq!(
+            recv_timeout(&clock, &rx, Duration::from_millis(50)),
+            Err(RecvTimeoutError::Timeout)
+        );
+        assert_eq!(clock.now() - start, Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_system_clock_past_deadline_returns_immediately() {
+        let clock = SystemClock;
+        let before = clock.now();
+        clock.sleep_until(before - Duration::from_millis(1));
+        assert!(clock.now() - before < Duration::from_millis(50));
+    }
+}
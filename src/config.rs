@@ -1,1701 +0,0 @@
-//! Application configuration management.
-//!
-//! Handles loading, saving, and validation of application settings
-//! including key mappings and runtime parameters.
-
-use serde::{Deserialize, Serialize};
-use smallvec::SmallVec;
-use std::convert::Infallible;
-use std::{collections::HashMap, fs, path::Path, str::FromStr};
-
-use crate::i18n::Language;
-
-/// Device API preference for input handling.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
-pub enum DeviceApiPreference {
-    /// Auto-detect best API.
-    #[default]
-    Auto,
-    /// Force XInput.
-    XInput,
-    /// Force Raw Input.
-    RawInput,
-}
-
-/// XInput capture mode strategy.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
-pub enum XInputCaptureMode {
-    /// Captures the most sustained input pattern.
-    MostSustained,
-    /// Captures the last stable input before release.
-    LastStable,
-    /// Prioritizes diagonal directions over straight directions with combo key support.
-    #[default]
-    DiagonalPriority,
-}
-
-impl FromStr for XInputCaptureMode {
-    type Err = Infallible;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "LastStable" => Ok(Self::LastStable),
-            "DiagonalPriority" => Ok(Self::DiagonalPriority),
-            _ => Ok(Self::default()),
-        }
-    }
-}
-
-impl XInputCaptureMode {
-    pub fn as_str(&self) -> &'static str {
-        match self {
-            Self::MostSustained => "MostSustained",
-            Self::LastStable => "LastStable",
-            Self::DiagonalPriority => "DiagonalPriority",
-        }
-    }
-
-    pub fn all_modes() -> &'static [XInputCaptureMode] {
-        &[
-            XInputCaptureMode::MostSustained,
-            XInputCaptureMode::LastStable,
-            XInputCaptureMode::DiagonalPriority,
-        ]
-    }
-}
-
-/// Main application configuration structure.
-#[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct AppConfig {
-    /// Display tray icon
-    pub show_tray_icon: bool,
-    /// Show notification messages
-    pub show_notifications: bool,
-    /// Keep window always on top
-    #[serde(default)]
-    pub always_on_top: bool,
-    /// Use dark theme mode
-    #[serde(default)]
-    pub dark_mode: bool,
-    /// Application language
-    #[serde(default)]
-    pub language: Language,
-    /// Toggle hotkey name
-    pub switch_key: String,
-    /// Hotkey used to finalize a sequence capture in the settings dialog.
-    /// Pressing this key during recording stops the capture, and the key
-    /// itself is not added to the sequence.
-    #[serde(default = "default_sequence_finalize_key")]
-    pub sequence_finalize_key: String,
-    /// Analog-stick deadzone applied to every XInput device.
-    /// Values below the absolute threshold are treated as neutral.
-    #[serde(default = "default_xinput_stick_deadzone")]
-    pub xinput_stick_deadzone: i16,
-    /// Trigger threshold applied to every XInput device.
-    /// Trigger values at or below the threshold are treated as released.
-    #[serde(default = "default_xinput_trigger_threshold")]
-    pub xinput_trigger_threshold: u8,
-    /// Minimum single-event delta in pixels required for a mouse event
-    /// to count toward the motion accumulator. Acts as a noise floor that
-    /// filters out hardware jitter and hand tremor below the threshold.
-    #[serde(default = "default_mouse_move_per_event_min_px")]
-    pub mouse_move_per_event_min_px: u32,
-    /// Accumulated motion in pixels required to fire a directional trigger.
-    /// Only events that pass `mouse_move_per_event_min_px` contribute to
-    /// this accumulator.
-    #[serde(default = "default_mouse_move_min_trigger_px")]
-    pub mouse_move_min_trigger_px: u32,
-    /// Reverse-direction distance in pixels that must accumulate before
-    /// the same direction can fire again. Lets the user double-flick in
-    /// one direction without spamming on a single continuous swipe.
-    #[serde(default = "default_mouse_move_rearm_px")]
-    pub mouse_move_rearm_px: u32,
-    /// Key mapping configurations
-    pub mappings: Vec<KeyMapping>,
-    /// Input timeout in milliseconds
-    #[serde(default = "default_input_timeout")]
-    pub input_timeout: u64,
-    /// Default key repeat interval in milliseconds
-    #[serde(default = "default_interval")]
-    pub interval: u64,
-    /// Default key press duration in milliseconds
-    #[serde(default = "default_event_duration")]
-    pub event_duration: u64,
-    /// Worker thread count (0 for auto-detection)
-    #[serde(default = "default_worker_count")]
-    pub worker_count: usize,
-    /// Process whitelist (empty means all processes)
-    #[serde(default)]
-    pub process_whitelist: Vec<String>,
-    /// HID device baselines for button detection
-    #[serde(default)]
-    pub hid_baselines: Vec<HidDeviceBaseline>,
-    /// Raw Input capture mode strategy
-    #[serde(default = "default_capture_mode")]
-    pub rawinput_capture_mode: String,
-    /// XInput capture mode strategy
-    #[serde(default = "default_xinput_capture_mode")]
-    pub xinput_capture_mode: String,
-    /// Device API preferences (VID:PID -> API preference)
-    #[serde(default)]
-    pub device_api_preferences: HashMap<String, DeviceApiPreference>,
-}
-
-/// HID device baseline configuration for button state detection.
-#[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct HidDeviceBaseline {
-    /// Device identifier (vendor_id, product_id, serial or handle)
-    pub device_id: String,
-    /// Baseline HID data (idle state with no buttons pressed)
-    pub baseline_data: Vec<u8>,
-}
-
-/// Key mapping configuration for trigger-target pairs.
-#[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct KeyMapping {
-    /// Trigger key name (single key or combo)
-    pub trigger_key: String,
-    /// Input sequence for combo triggers (e.g., "↓,→,A")
-    /// If set, this takes precedence over trigger_key for matching
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub trigger_sequence: Option<String>,
-    /// Time window for sequence completion in milliseconds
-    #[serde(default = "default_sequence_window")]
-    pub sequence_window_ms: u64,
-    /// Target keys to send (supports multiple keys for simultaneous press)
-    /// Uses SmallVec with inline capacity of 4 to reduce heap allocations for common cases
-    #[serde(default = "default_target_keys")]
-    pub target_keys: SmallVec<[String; 4]>,
-    /// Target mode: 0=Single, 1=Multi (simultaneous), 2=Sequence (sequential)
-    #[serde(default)]
-    pub target_mode: u8,
-    /// Optional override for repeat interval
-    #[serde(default)]
-    pub interval: Option<u64>,
-    /// Optional override for press duration
-    #[serde(default)]
-    pub event_duration: Option<u64>,
-    /// Enable turbo mode (auto-repeat)
-    #[serde(default = "default_turbo_enabled")]
-    pub turbo_enabled: bool,
-    /// Mouse move speed in pixels per move (only for mouse movement)
-    #[serde(default = "default_move_speed")]
-    pub move_speed: i32,
-    /// 0-based positions within `target_keys` that stay pressed after
-    /// the body plays. Values past `target_keys.len()` or 16 are ignored
-    /// at runtime.
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub hold_indices: Option<SmallVec<[u8; 4]>>,
-    /// Extra key names pressed and held after the body completes. Mouse
-    /// movement and scroll names fire one edge event per pass.
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub append_keys: Option<SmallVec<[String; 4]>>,
-}
-
-fn default_move_speed() -> i32 {
-    5
-}
-
-fn default_turbo_enabled() -> bool {
-    true
-}
-
-fn default_target_keys() -> SmallVec<[String; 4]> {
-    SmallVec::new()
-}
-
-fn default_sequence_window() -> u64 {
-    500 // 500ms default window for sequence completion
-}
-
-impl KeyMapping {
-    /// Checks if this mapping uses sequence trigger
-    #[inline]
-    pub fn is_sequence_trigger(&self) -> bool {
-        self.trigger_sequence.is_some()
-    }
-
-    /// Gets the sequence string if available
-    #[inline]
-    pub fn sequence_string(&self) -> Option<&str> {
-        self.trigger_sequence.as_deref()
-    }
-
-    /// Gets the target keys slice
-    pub fn get_target_keys(&self) -> &[String] {
-        &self.target_keys
-    }
-
-    /// Adds a target key (sequence mode allows duplicates)
-    pub fn add_target_key(&mut self, key: String) {
-        // Sequence mode (target_mode == 2) allows duplicates
-        if self.target_mode == 2 || !self.target_keys.contains(&key) {
-            self.target_keys.push(key);
-        }
-    }
-
-    /// Removes a target key by index
-    pub fn remove_target_key_at(&mut self, index: usize) {
-        if index < self.target_keys.len() {
-            self.target_keys.remove(index);
-        }
-    }
-
-    /// Clears all target keys
-    pub fn clear_target_keys(&mut self) {
-        self.target_keys.clear();
-    }
-
-    /// Gets target keys as display string (comma separated)
-    pub fn target_keys_display(&self) -> String {
-        if self.target_keys.is_empty() {
-            String::new()
-        } else {
-            self.target_keys.join(", ")
-        }
-    }
-}
-
-fn default_input_timeout() -> u64 {
-    5
-}
-fn default_interval() -> u64 {
-    5
-}
-fn default_event_duration() -> u64 {
-    5
-}
-fn default_worker_count() -> usize {
-    0 // 0 means auto-detect based on CPU cores
-}
-fn default_capture_mode() -> String {
-    "MostSustained".to_string()
-}
-fn default_xinput_capture_mode() -> String {
-    "MostSustained".to_string()
-}
-fn default_sequence_finalize_key() -> String {
-    "RETURN".to_string()
-}
-fn default_xinput_stick_deadzone() -> i16 {
-    7849
-}
-fn default_xinput_trigger_threshold() -> u8 {
-    30
-}
-fn default_mouse_move_per_event_min_px() -> u32 {
-    2
-}
-fn default_mouse_move_min_trigger_px() -> u32 {
-    20
-}
-fn default_mouse_move_rearm_px() -> u32 {
-    10
-}
-
-impl Default for AppConfig {
-    /// Creates a default configuration with sensible defaults.
-    fn default() -> Self {
-        Self {
-            show_tray_icon: true,
-            show_notifications: true,
-            always_on_top: false,
-            dark_mode: false,
-            language: Language::default(),
-            switch_key: "DELETE".to_string(),
-            sequence_finalize_key: default_sequence_finalize_key(),
-            xinput_stick_deadzone: default_xinput_stick_deadzone(),
-            xinput_trigger_threshold: default_xinput_trigger_threshold(),
-            mouse_move_per_event_min_px: default_mouse_move_per_event_min_px(),
-            mouse_move_min_trigger_px: default_mouse_move_min_trigger_px(),
-            mouse_move_rearm_px: default_mouse_move_rearm_px(),
-            mappings: vec![KeyMapping {
-                trigger_key: "Q".to_string(),
-                trigger_sequence: None,
-                sequence_window_ms: default_sequence_window(),
-                target_keys: SmallVec::from_vec(vec!["Q".to_string()]),
-                interval: None,
-                event_duration: None,
-                turbo_enabled: true,
-                move_speed: 10,
-                target_mode: 0,
-                hold_indices: None,
-                append_keys: None,
-            }],
-            input_timeout: default_input_timeout(),
-            interval: default_interval(),
-            event_duration: default_event_duration(),
-            worker_count: default_worker_count(),
-            process_whitelist: vec![], // Empty means all processes enabled
-            hid_baselines: Vec::new(),
-            rawinput_capture_mode: default_capture_mode(),
-            xinput_capture_mode: default_xinput_capture_mode(),
-            device_api_preferences: HashMap::new(),
-        }
-    }
-}
-
-impl AppConfig {
-    /// Loads configuration from file. Missing or incompatible files are
-    /// replaced with the default configuration on disk.
-    ///
-    /// # Errors
-    ///
-    /// Returns an error only when the file cannot be read or written; a
-    /// parse failure resets the file to defaults instead.
-    pub fn load_or_create<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
-        let p = path.as_ref();
-        if !p.exists() {
-            let default_config = Self::default();
-            default_config.save_to_file(p)?;
-            return Ok(default_config);
-        }
-        match Self::load_from_file(p) {
-            Ok(config) => Ok(config),
-            Err(err) => {
-                eprintln!(
-                    "Config at {} failed to parse ({}); rewriting with defaults.",
-                    p.display(),
-                    err
-                );
-                let default_config = Self::default();
-                default_config.save_to_file(p)?;
-                Ok(default_config)
-            }
-        }
-    }
-
-    /// Loads configuration from a TOML file.
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if the file cannot be read or parsed. Callers
-    /// that want the "reset on incompatible" behaviour should use
-    /// `load_or_create`.
-    pub fn load_from_file<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
-        let content = fs::read_to_string(path)?;
-        let mut config: AppConfig = toml::from_str(&content)?;
-
-        // Clamp minimum timing values so pathological configs stay sane.
-        if config.input_timeout < 2 {
-            config.input_timeout = 2;
-        }
-        if config.interval < 5 {
-            config.interval = 5;
-        }
-        if config.event_duration < 2 {
-            config.event_duration = 2;
-        }
-
-        config.process_whitelist.sort();
-        config.process_whitelist.dedup();
-
-        Ok(config)
-    }
-
-    /// Saves configuration to a TOML file.
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if the file cannot be written or serialized.
-    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
-        // Generate header and main config in one go
-        let header = format!(
-            "# ═══════════════════════════════════════════════════════\n\
-             #  🌸 Sorahk Configuration File 🌸\n\
-             # ═══════════════════════════════════════════════════════\n\n\
-             # ─── General Settings ───\n\
-             show_tray_icon = {}       # Show system tray icon on startup\n\
-             show_notifications = {}   # Enable/disable system notifications\n\
-             always_on_top = {}       # Keep window always on top of other windows\n\
-             dark_mode = {}           # Use dark theme (false = light theme, true = dark theme)\n\
-             language = \"{}\"        # UI language: \"English\", \"SimplifiedChinese\", \"TraditionalChinese\", \"Japanese\"\n\n\
-             # ─── Performance Settings ───\n\
-             input_timeout = {}          # Input timeout in ms\n\
-             interval = {}                # Default repeat interval between keystrokes (ms)\n\
-             event_duration = {}          # Duration of each simulated key press (ms)\n\
-             worker_count = {}            # Number of turbo workers (0 = auto-detect based on CPU cores)\n\n\
-             # ─── XInput Settings ───\n\
-             xinput_capture_mode = \"{}\"  # XInput capture mode: \"DiagonalPriority\", \"MostSustained\", \"LastStable\"\n\
-                                           # DiagonalPriority: Prioritizes diagonal stick directions over straight directions\n\
-                                           # MostSustained: Captures the most sustained input pattern\n\
-                                           # LastStable: Captures the last stable input before release\n\
-                                           # Note: Compile with RUSTFLAGS=\"-C target-feature=+avx2\" for AVX2 optimizations\n\
-             xinput_stick_deadzone = {}    # Analog-stick deadzone applied to all XInput devices (0-32767)\n\
-             xinput_trigger_threshold = {} # Trigger activation threshold applied to all XInput devices (0-255)\n\
-             mouse_move_per_event_min_px = {} # Minimum per-event delta in pixels for a mouse event to count (noise floor)\n\
-             mouse_move_min_trigger_px = {}   # Accumulated pixels required to fire a directional trigger\n\
-             mouse_move_rearm_px = {}         # Reverse-direction distance in pixels before same direction can fire again\n\n\
-             # ─── Control Settings ───   \n\
-             switch_key = \"{}\"       # Reserved key to toggle SoraHK behavior\n\
-             sequence_finalize_key = \"{}\"  # Key that stops a sequence capture in Settings\n\n\
-             # ─── Process Whitelist ───\n\
-             # Process whitelist (empty = all processes enabled)\n\
-             # Only processes in this list will have turbo-fire enabled\n\
-             process_whitelist = {:?}      # Example: [\"notepad.exe\", \"game.exe\"]\n\n\
-             # ─── Input Mappings ───\n\
-             # Input mapping definitions (supports keyboard, mouse, and HID devices)\n\
-             # Supported mouse buttons: LBUTTON, RBUTTON, MBUTTON, XBUTTON1, XBUTTON2\n\
-             # Key combinations: Use '+' to separate keys (e.g., \"LALT+A\", \"RCTRL+RSHIFT+S\")\n\n\
-             # Turbo Mode Behavior:\n\
-             # - turbo_enabled = true: Auto-repeat with configurable interval (for rapid fire/continuous action)\n\
-             # - turbo_enabled = false: Press-to-press, release-to-release behavior\n\
-             #   * Keyboard targets: Supports Windows key repeat (holding trigger sends repeated key presses)\n\
-             #   * Mouse button targets: Pure follow mode (press follows trigger press, release follows trigger release)\n\
-             #   * Note: event_duration is ignored in non-turbo mode\n\n\
-             # ─── Rule Properties (per mapping, any target mode) ───\n\
-             # - hold_indices = [<u8>, ...]  # 0-based positions within target_keys that stay held after the body plays\n\
-             #   * target_mode = 0 / 1 (Single / Multi): body keys press simultaneously; marked indices remain held after duration\n\
-             #   * target_mode = 2 (Sequence): body keys play one-by-one; marked indices only press (no release), so they linger\n\
-             #   * Values >= target_keys.len() or >= 16 are ignored\n\
-             # - append_keys = [\"UP\", \"LSHIFT\", ...]  # extra keys pressed and held after the body\n\
-             #   * Keyboard / mouse-button names remain held until trigger release\n\
-             #   * Mouse-move / mouse-scroll names fire a single edge event per pass\n\
-             # Interaction with turbo_enabled:\n\
-             #   * turbo_enabled = false: body plays once, then hold_indices + append stay held until trigger release\n\
-             #   * turbo_enabled = true:  only the hold_indices subset + append is cycled at interval / duration; body is not replayed\n\n\
-             # ─── Key Combo Examples ───\n\
-             # Combo key mappings: Use '+' to separate keys\n\
-             # - Supports modifier keys: LSHIFT/RSHIFT, LCTRL/RCTRL, LALT/RALT, LWIN/RWIN\n\
-             # - Supports single modifier keys as triggers (e.g., \"LSHIFT\")\n\
-             # - Multiple combos with shared modifiers work simultaneously (e.g., LALT+1, RALT+2)\n\
-             # - Distinguishes left/right modifiers (e.g., \"LSHIFT+1\" only triggers with left Shift)\n\
-             # Uncomment to enable combo key mappings:\n\
-             # [[mappings]]\n\
-             # trigger_key = \"LALT+1\"      # Left ALT + 1 (won't trigger with right ALT)\n\
-             # target_keys = [\"F1\"]        # Auto-press F1\n\n\
-             # [[mappings]]\n\
-             # trigger_key = \"CTRL+SHIFT+F\"  # Multiple modifiers\n\
-             # target_keys = [\"LALT+F4\"]     # Output can also be combo\n\n\
-             # ─── Mouse Button Examples ───\n\
-             # Uncomment to enable mouse button mappings:\n\
-             # [[mappings]]\n\
-             # trigger_key = \"LBUTTON\"     # Left mouse button trigger\n\
-             # target_keys = [\"LBUTTON\"]   # Auto-click left button\n\n\
-             # [[mappings]]\n\
-             # trigger_key = \"RBUTTON\"     # Right mouse button trigger\n\
-             # target_keys = [\"SPACE\"]     # Press space when right-clicking\n\n\
-             # [[mappings]]\n\
-             # trigger_key = \"XBUTTON1\"    # Side button 1 trigger\n\
-             # target_keys = [\"F\"]         # Auto-press F key\n\n\
-             # ─── Mouse Movement Examples ───\n\
-             # [[mappings]]\n\
-             # trigger_key = \"W\"           # Trigger key\n\
-             # target_keys = [\"MOUSE_UP\"]  # Move cursor upward\n\
-             # move_speed = 5              # Speed in pixels (1-100)\n\
-             # interval = 5                # Update interval in ms\n\
-             # turbo_enabled = true        # Required for continuous movement\n\n\
-             # [[mappings]]\n\
-             # trigger_key = \"S\"\n\
-             # target_keys = [\"MOUSE_DOWN\"]\n\
-             # move_speed = 5\n\
-             # interval = 5\n\
-             # turbo_enabled = true\n\n\
-             # Multiple target keys for diagonal movement (simultaneous press):\n\
-             # [[mappings]]\n\
-             # trigger_key = \"Q\"\n\
-             # target_keys = [\"MOUSE_UP\", \"MOUSE_LEFT\"]  # Move diagonally up-left\n\
-             # move_speed = 5\n\
-             # interval = 5\n\
-             # turbo_enabled = true\n\n\
-             # Or use built-in diagonal directions:\n\
-             # Diagonal: MOUSE_UP_LEFT, MOUSE_UP_RIGHT, MOUSE_DOWN_LEFT, MOUSE_DOWN_RIGHT\n\n\
-             # ─── Mouse Scroll Examples ───\n\
-             # [[mappings]]\n\
-             # trigger_key = \"PAGEUP\"       # Trigger key\n\
-             # target_keys = [\"SCROLL_UP\"]  # Scroll wheel upward\n\
-             # move_speed = 120             # Wheel delta (120 = standard notch)\n\
-             # interval = 5                 # Repeat interval in ms\n\
-             # turbo_enabled = true         # true = continuous, false = Windows repeat\n\n\
-             # [[mappings]]\n\
-             # trigger_key = \"PAGEDOWN\"\n\
-             # target_keys = [\"SCROLL_DOWN\"]\n\
-             # move_speed = 240             # Wheel delta (240 = 2x)\n\
-             # interval = 5\n\
-             # turbo_enabled = true\n\n\
-             # ─── HID Device Examples (Gamepads, Joysticks, Custom Controllers) ───\n\
-             # Automatic support for any HID device via GUI capture!\n\
-             # \n\
-             # XInput Controllers (Xbox, compatible gamepads):\n\
-             #   Format: GAMEPAD_VID_ButtonName[+ButtonName...] (readable format)\n\
-             #   Buttons: A, B, X, Y, Start, Back, LB, RB, LS_Click, RS_Click, LT, RT\n\
-             #   D-Pad: DPad_Up, DPad_Down, DPad_Left, DPad_Right, DPad_UpLeft, DPad_UpRight, DPad_DownLeft, DPad_DownRight\n\
-             #   Left Stick: LS_Up, LS_Down, LS_Left, LS_Right, LS_LeftUp, LS_LeftDown, LS_RightUp, LS_RightDown\n\
-             #   Right Stick: RS_Up, RS_Down, RS_Left, RS_Right, RS_LeftUp, RS_LeftDown, RS_RightUp, RS_RightDown\n\
-             #   Combos: Use '+' to combine buttons (e.g., \"LS_RightUp+A+B\")\n\
-             #\n\
-             # Raw Input Devices (other gamepads, joysticks):\n\
-             #   Format: DEVICE_VID_PID_SERIAL_Bx.x (with serial) or DEVICE_VID_PID_DEVxxxxxxxx_Bx.x (without serial)\n\
-             #\n\
-             # How to configure:\n\
-             # 1. Connect your device (XInput controllers work automatically, others require activation)\n\
-             # 2. First-time use for non-XInput devices: Activation dialog will appear when you press any button\n\
-             #    - Follow instructions: press and release a single button to establish baseline\n\
-             #    - Device activation data is saved automatically\n\
-             # 3. Open settings dialog, click Capture button for trigger key\n\
-             # 4. Press button(s) on your device (supports single button or combo keys)\n\
-             # 5. Release all buttons to complete capture\n\
-             #\n\
-             # XInput Examples:\n\
-             # [[mappings]]\n\
-             # trigger_key = \"GAMEPAD_045E_A\"              # Xbox controller A button\n\
-             # target_keys = [\"SPACE\"]                     # Press space\n\
-             # turbo_enabled = true                        # Enable turbo mode\n\n\
-             # [[mappings]]\n\
-             # trigger_key = \"GAMEPAD_045E_LS_RightUp+A\"   # Left stick right-up + A button\n\
-             # target_keys = [\"LCTRL+C\"]                   # Press Ctrl+C\n\
-             # turbo_enabled = true                        # Enable turbo mode\n\n\
-             # [[mappings]]\n\
-             # trigger_key = \"GAMEPAD_045E_DPad_Up+B+X\"    # D-Pad up + B + X buttons\n\
-             # target_keys = [\"F\"]                         # Auto-press F\n\
-             # turbo_enabled = true                        # Enable turbo mode\n\n\
-             # Raw Input Examples:\n\
-             # [[mappings]]\n\
-             # trigger_key = \"GAMEPAD_045E_0B05_ABC123_B2.0\"  # Raw Input format (with serial)\n\
-             # target_keys = [\"SPACE\"]                        # Press space\n\
-             # turbo_enabled = true                           # Enable turbo mode\n\n\
-             # [[mappings]]\n\
-             # trigger_key = \"JOYSTICK_046D_C21D_B1.0\"        # Logitech joystick button\n\
-             # target_keys = [\"LBUTTON\"]                      # Left mouse click\n\
-             # turbo_enabled = true                           # Enable turbo mode\n\n\
-             # ─── Sequence Input/Output Examples (Fighting Game Combos) ───\n\
-             # Sequence input triggers: Execute commands using input sequences\n\
-             # Format: trigger_sequence = \"Key1,Key2,Key3,...\"\n\
-             # - Keys are comma-separated (e.g., \"DOWN,RIGHT,A\")\n\
-             # - Time window defines max time to complete the sequence (default: 500ms)\n\
-             # - Supports keyboard keys, mouse buttons, mouse movements, and XInput stick/buttons\n\
-             # - Smart transition tolerance: DOWN->LEFT matches DOWN->DOWNLEFT->LEFT\n\
-             # - XInput diagonal bidirectional: DOWN_LEFT matches both DOWN->LEFT and LEFT->DOWN\n\
-             #\n\
-             # Sequence output targets: Send key sequences in order\n\
-             # Format: target_keys = [\"Key1\", \"Key2\", \"Key3\"]\n\
-             # - Set target_mode = 2 for sequential output\n\
-             # - interval controls delay between keys\n\
-             # - turbo_enabled = true: repeat entire sequence\n\
-             # - turbo_enabled = false: execute once per trigger\n\
-             #\n\
-             # Example 1: Hadoken (Quarter Circle Forward + Punch)\n\
-             # [[mappings]]\n\
-             # trigger_sequence = \"LS_Down,LS_DownRight,LS_Right,A\"  # ↓↘→+A\n\
-             # target_keys = [\"J\"]                                    # Press J\n\
-             # sequence_window_ms = 500                               # Complete within 500ms\n\
-             # turbo_enabled = true                                   # Repeat on hold\n\
-             #\n\
-             # Example 2: Shoryuken (Dragon Punch)\n\
-             # [[mappings]]\n\
-             # trigger_sequence = \"LS_Right,LS_Down,LS_DownRight,B\"   # →↓↘+B\n\
-             # target_keys = [\"K\"]\n\
-             # sequence_window_ms = 400\n\
-             # turbo_enabled = false                                  # Single execution\n\
-             #\n\
-             # Example 3: Keyboard combo (e.g., \"WASD\" sequence)\n\
-             # [[mappings]]\n\
-             # trigger_sequence = \"W,A,S,D\"                           # Press W-A-S-D in order\n\
-             # target_keys = [\"F\"]                                    # Output F\n\
-             # sequence_window_ms = 800\n\
-             # turbo_enabled = true\n\
-             #\n\
-             # Example 4: Mouse movement sequence (double tap direction)\n\
-             # [[mappings]]\n\
-             # trigger_sequence = \"MOUSE_LEFT,MOUSE_LEFT\"             # Double tap left\n\
-             # target_keys = [\"LSHIFT\"]                               # Activate sprint\n\
-             # sequence_window_ms = 300\n\
-             # turbo_enabled = false\n\
-             #\n\
-             # Example 5: Sequence output (macro)\n\
-             # [[mappings]]\n\
-             # trigger_key = \"F5\"                                     # Single key trigger\n\
-             # target_keys = [\"H\", \"E\", \"L\", \"L\", \"O\"]                # Type \"HELLO\"\n\
-             # target_mode = 2                                        # Sequential mode\n\
-             # interval = 50                                          # 50ms between keys\n\
-             # turbo_enabled = false                                  # Single execution\n\
-             #\n\
-             # Example 6: Complex combo (multiple sticks + buttons)\n\
-             # [[mappings]]\n\
-             # trigger_sequence = \"LS_Left,LS_DownLeft,LS_Down,LS_DownRight,LS_Right,LB+RB\"\n\
-             # target_keys = [\"SPACE\"]\n\
-             # sequence_window_ms = 600\n\
-             # turbo_enabled = false\n\n\
-             # ─── HID Device Baselines (Auto-generated, Do Not Edit) ───\n\
-             # This section is managed automatically by the application\n\
-             # Device activation data for press/release detection\n\
-             # Format: VID:PID:Serial or VID:PID (without serial)\n\
-             # [[hid_baselines]]\n\
-             # device_id = \"045E:028E:1234567\"\n\
-             # baseline_data = [0, 255, 127, 255, 127, 0, 128, 0, 0, 0, 0]\n\n",
-            self.show_tray_icon,
-            self.show_notifications,
-            self.always_on_top,
-            self.dark_mode,
-            match self.language {
-                Language::English => "English",
-                Language::SimplifiedChinese => "SimplifiedChinese",
-                Language::TraditionalChinese => "TraditionalChinese",
-                Language::Japanese => "Japanese",
-                Language::Korean => "Korean",
-            },
-            self.input_timeout,
-            self.interval,
-            self.event_duration,
-            self.worker_count,
-            self.xinput_capture_mode,
-            self.xinput_stick_deadzone,
-            self.xinput_trigger_threshold,
-            self.mouse_move_per_event_min_px,
-            self.mouse_move_min_trigger_px,
-            self.mouse_move_rearm_px,
-            self.switch_key,
-            self.sequence_finalize_key,
-            self.process_whitelist
-        );
-
-        // Pre-allocate capacity for better performance
-        let mut result = String::with_capacity(header.len() + self.mappings.len() * 200);
-        result.push_str(&header);
-
-        // Append mappings efficiently
-        if self.mappings.is_empty() {
-            // Write empty array to ensure field exists
-            result.push_str("mappings = []\n");
-        } else {
-            for mapping in &self.mappings {
-                result.push_str("[[mappings]]\n");
-                result.push_str(&format!(
-                    "trigger_key = \"{}\"           # Physical key you press\n",
-                    mapping.trigger_key
-                ));
-
-                // Add trigger_sequence if present (for combo triggers)
-                if let Some(ref seq) = mapping.trigger_sequence {
-                    result.push_str(&format!(
-                        "trigger_sequence = \"{}\"    # Input sequence (e.g., \"A,S,D\")\n",
-                        seq
-                    ));
-                    result.push_str(&format!(
-                        "sequence_window_ms = {}       # Time window for sequence completion\n",
-                        mapping.sequence_window_ms
-                    ));
-                }
-
-                if mapping.target_keys.len() == 1 {
-                    result.push_str("target_keys = [\"");
-                    result.push_str(&mapping.target_keys[0]);
-                    result.push_str("\"]         # Key(s) that get repeatedly sent\n");
-                } else if mapping.target_keys.len() > 1 {
-                    result.push_str("target_keys = [");
-                    for (i, key) in mapping.target_keys.iter().enumerate() {
-                        if i > 0 {
-                            result.push_str(", ");
-                        }
-                        result.push('"');
-                        result.push_str(key);
-                        result.push('"');
-                    }
-                    result.push_str("]  # Multiple keys for simultaneous press\n");
-                } else {
-                    result
-                        .push_str("target_keys = []             # Keys that get repeatedly sent\n");
-                }
-
-                // Write target_mode only if not default (0 = Single)
-                if mapping.target_mode != 0 {
-                    result.push_str(&format!(
-                        "target_mode = {}              # 0=Single, 1=Multi, 2=Sequence\n",
-                        mapping.target_mode
-                    ));
-                }
-
-                if let Some(interval) = mapping.interval {
-                    result.push_str(&format!(
-                        "interval = {}                # Override global interval\n",
-                        interval
-                    ));
-                }
-                if let Some(duration) = mapping.event_duration {
-                    result.push_str(&format!(
-                        "event_duration = {}          # Override global press duration\n",
-                        duration
-                    ));
-                }
-                result.push_str(&format!(
-                    "move_speed = {}              # Speed (pixels for movement, wheel delta for scroll)\n",
-                    mapping.move_speed
-                ));
-                result.push_str(&format!(
-                    "turbo_enabled = {}        # Enable turbo mode (true = auto-repeat, false = follow trigger press/release)\n",
-                    mapping.turbo_enabled
-                ));
-
-                // Rule properties: only emit when non-empty. `None` and empty
-                // vectors keep the TOML clean so legacy configs stay lean.
-                if let Some(holds) = &mapping.hold_indices
-                    && !holds.is_empty()
-                {
-                    result.push_str("hold_indices = [");
-                    for (i, v) in holds.iter().enumerate() {
-                        if i > 0 {
-                            result.push_str(", ");
-                        }
-                        result.push_str(&v.to_string());
-                    }
-                    result
-                        .push_str("]          # Indices of target_keys to stay held after play\n");
-                }
-                if let Some(appends) = &mapping.append_keys
-                    && !appends.is_empty()
-                {
-                    result.push_str("append_keys = [");
-                    for (i, name) in appends.iter().enumerate() {
-                        if i > 0 {
-                            result.push_str(", ");
-                        }
-                        result.push('"');
-                        result.push_str(name);
-                        result.push('"');
-                    }
-                    result.push_str("]  # Extra keys pressed and held after play\n");
-                }
-
-                result.push('\n');
-            }
-        }
-
-        // Append HID device baselines
-        if !self.hid_baselines.is_empty() {
-            result.push_str("# ─── HID Device Baselines (Auto-managed) ───\n");
-            result.push_str("# Device activation data for press/release detection\n");
-            result.push_str("# Format: VID:PID:Serial or VID:PID (without serial)\n");
-            for baseline in &self.hid_baselines {
-                result.push_str("[[hid_baselines]]\n");
-                result.push_str(&format!("device_id = \"{}\"\n", baseline.device_id));
-                result.push_str(&format!("baseline_data = {:?}\n", baseline.baseline_data));
-                result.push('\n');
-            }
-        }
-
-        // Append device API preferences
-        if !self.device_api_preferences.is_empty() {
-            result.push_str("# ─── Device API Preferences (Auto-managed) ───\n");
-            result.push_str("# Preferred input API for each device (VID:PID format)\n");
-            result.push_str("# Options: \"Auto\" (default), \"XInput\", \"RawInput\"\n");
-            result.push_str("# Configure via Device Manager GUI window\n");
-            result.push_str("[device_api_preferences]\n");
-            for (device_key, preference) in &self.device_api_preferences {
-                let pref_str = match preference {
-                    DeviceApiPreference::Auto => "\"Auto\"",
-                    DeviceApiPreference::XInput => "\"XInput\"",
-                    DeviceApiPreference::RawInput => "\"RawInput\"",
-                };
-                result.push_str(&format!("\"{}\" = {}\n", device_key, pref_str));
-            }
-            result.push('\n');
-        }
-
-        fs::write(path, result)?;
-        Ok(())
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs;
-    use std::path::PathBuf;
-
-    fn get_test_config_path(name: &str) -> PathBuf {
-        use std::time::{SystemTime, UNIX_EPOCH};
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_nanos();
-        let mut path = std::env::temp_dir();
-        path.push(format!("sorahk_test_{}_{}.toml", name, timestamp));
-        path
-    }
-
-    fn cleanup_test_file(path: &PathBuf) {
-        let _ = fs::remove_file(path);
-    }
-
-    #[test]
-    fn test_default_config_creation() {
-        let config = AppConfig::default();
-
-        assert!(config.show_tray_icon);
-        assert!(config.show_notifications);
-        assert!(!config.always_on_top);
-        assert!(!config.dark_mode);
-        assert_eq!(config.switch_key, "DELETE");
-        assert_eq!(config.input_timeout, 5);
-        assert_eq!(config.interval, 5);
-        assert_eq!(config.event_duration, 5);
-        assert_eq!(config.worker_count, 0);
-        assert!(config.process_whitelist.is_empty());
-        assert_eq!(config.mappings.len(), 1);
-    }
-
-    #[test]
-    fn test_config_save_and_load() {
-        let path = get_test_config_path("save_and_load");
-        cleanup_test_file(&path); // Clean up before test
-
-        let mut config = AppConfig::default();
-        config.show_tray_icon = false;
-        config.show_notifications = false;
-        config.always_on_top = true;
-        config.dark_mode = true;
-        config.switch_key = "F12".to_string();
-        config.input_timeout = 20;
-        config.interval = 10;
-        config.event_duration = 15;
-        config.worker_count = 4;
-
-        config.save_to_file(&path).expect("Failed to save config");
-
-        let loaded_config = AppConfig::load_from_file(&path).expect("Failed to load config");
-
-        assert_eq!(loaded_config.show_tray_icon, config.show_tray_icon);
-        assert_eq!(loaded_config.show_notifications, config.show_notifications);
-        assert_eq!(loaded_config.always_on_top, config.always_on_top);
-        assert_eq!(loaded_config.dark_mode, config.dark_mode);
-        assert_eq!(loaded_config.switch_key, config.switch_key);
-        assert_eq!(loaded_config.input_timeout, config.input_timeout);
-        assert_eq!(loaded_config.interval, config.interval);
-        assert_eq!(loaded_config.event_duration, config.event_duration);
-        assert_eq!(loaded_config.worker_count, config.worker_count);
-
-        cleanup_test_file(&path);
-    }
-
-    #[test]
-    fn test_config_validation_input_timeout() {
-        let path = get_test_config_path("validation_timeout");
-        cleanup_test_file(&path);
-
-        let content = r#"
-            show_tray_icon = true
-            show_notifications = true
-            switch_key = "DELETE"
-            input_timeout = 1
-            interval = 5
-            event_duration = 5
-            worker_count = 0
-            process_whitelist = []
-            mappings = []
-        "#;
-
-        fs::write(&path, content).expect("Failed to write test config");
-
-        let config = AppConfig::load_from_file(&path).expect("Failed to load config");
-        assert!(
-            config.input_timeout >= 2,
-            "Input timeout should be clamped to minimum 2"
-        );
-
-        cleanup_test_file(&path);
-    }
-
-    #[test]
-    fn test_config_validation_interval() {
-        let path = get_test_config_path("validation_interval");
-        cleanup_test_file(&path);
-
-        let content = r#"
-            show_tray_icon = true
-            show_notifications = true
-            switch_key = "DELETE"
-            input_timeout = 10
-            interval = 2
-            event_duration = 5
-            worker_count = 0
-            process_whitelist = []
-            mappings = []
-        "#;
-
-        fs::write(&path, content).expect("Failed to write test config");
-
-        let config = AppConfig::load_from_file(&path).expect("Failed to load config");
-        assert!(
-            config.interval >= 5,
-            "Interval should be clamped to minimum 5"
-        );
-
-        cleanup_test_file(&path);
-    }
-
-    #[test]
-    fn test_config_validation_event_duration() {
-        let path = get_test_config_path("validation_duration");
-        cleanup_test_file(&path);
-
-        let content = r#"
-            show_tray_icon = true
-            show_notifications = true
-            switch_key = "DELETE"
-            input_timeout = 10
-            interval = 5
-            event_duration = 2
-            worker_count = 0
-            process_whitelist = []
-            mappings = []
-        "#;
-
-        fs::write(&path, content).expect("Failed to write test config");
-
-        let config = AppConfig::load_from_file(&path).expect("Failed to load config");
-        assert!(
-            config.event_duration >= 2,
-            "Event duration should be clamped to minimum 2"
-        );
-
-        cleanup_test_file(&path);
-    }
-
-    #[test]
-    fn test_load_or_create_missing_file() {
-        let path = get_test_config_path("missing_file");
-        cleanup_test_file(&path);
-
-        let config = AppConfig::load_or_create(&path).expect("Failed to load or create config");
-
-        assert!(path.exists(), "Config file should be created");
-        assert_eq!(config.switch_key, "DELETE");
-        assert_eq!(config.interval, 5);
-
-        cleanup_test_file(&path);
-    }
-
-    #[test]
-    fn test_load_or_create_existing_file() {
-        let path = get_test_config_path("existing_file");
-        cleanup_test_file(&path);
-
-        let mut config = AppConfig::default();
-        config.switch_key = "F11".to_string();
-        config.save_to_file(&path).expect("Failed to save config");
-
-        let loaded_config = AppConfig::load_or_create(&path).expect("Failed to load config");
-
-        assert_eq!(loaded_config.switch_key, "F11");
-
-        cleanup_test_file(&path);
-    }
-
-    #[test]
-    fn test_key_mapping_with_overrides() {
-        let mapping = KeyMapping {
-            trigger_key: "A".to_string(),
-            target_keys: SmallVec::from_vec(vec!["B".to_string()]),
-            interval: Some(10),
-            event_duration: Some(8),
-            turbo_enabled: true,
-            move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        };
-
-        assert_eq!(mapping.trigger_key, "A");
-        assert_eq!(mapping.target_keys.as_slice(), &["B".to_string()]);
-        assert_eq!(mapping.interval, Some(10));
-        assert_eq!(mapping.event_duration, Some(8));
-    }
-
-    #[test]
-    fn test_key_mapping_without_overrides() {
-        let mapping = KeyMapping {
-            trigger_key: "C".to_string(),
-            target_keys: SmallVec::from_vec(vec!["D".to_string()]),
-            interval: None,
-            event_duration: None,
-            turbo_enabled: true,
-            move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        };
-
-        assert_eq!(mapping.trigger_key, "C");
-        assert_eq!(mapping.target_keys.as_slice(), &["D".to_string()]);
-        assert_eq!(mapping.interval, None);
-        assert_eq!(mapping.event_duration, None);
-    }
-
-    #[test]
-    fn test_process_whitelist_serialization() {
-        let path = get_test_config_path("whitelist");
-        cleanup_test_file(&path);
-
-        let mut config = AppConfig::default();
-        config.process_whitelist = vec!["notepad.exe".to_string(), "chrome.exe".to_string()];
-
-        config.save_to_file(&path).expect("Failed to save config");
-
-        let loaded_config = AppConfig::load_from_file(&path).expect("Failed to load config");
-
-        assert_eq!(loaded_config.process_whitelist.len(), 2);
-        assert!(
-            loaded_config
-                .process_whitelist
-                .contains(&"notepad.exe".to_string())
-        );
-        assert!(
-            loaded_config
-                .process_whitelist
-                .contains(&"chrome.exe".to_string())
-        );
-
-        cleanup_test_file(&path);
-    }
-
-    #[test]
-    fn test_language_serialization() {
-        let languages = vec![
-            Language::English,
-            Language::SimplifiedChinese,
-            Language::TraditionalChinese,
-            Language::Japanese,
-        ];
-
-        for (idx, lang) in languages.iter().enumerate() {
-            let path = get_test_config_path(&format!("language_{}", idx));
-            cleanup_test_file(&path);
-
-            let mut config = AppConfig::default();
-            config.language = *lang;
-
-            config.save_to_file(&path).expect("Failed to save config");
-            let loaded_config = AppConfig::load_from_file(&path).expect("Failed to load config");
-
-            assert_eq!(loaded_config.language, *lang);
-
-            cleanup_test_file(&path);
-        }
-    }
-
-    #[test]
-    fn test_multiple_mappings_serialization() {
-        let path = get_test_config_path("multiple_mappings");
-        cleanup_test_file(&path);
-
-        let mut config = AppConfig::default();
-        config.mappings = vec![
-            KeyMapping {
-                trigger_key: "A".to_string(),
-                target_keys: SmallVec::from_vec(vec!["1".to_string()]),
-                interval: Some(10),
-                event_duration: Some(5),
-                turbo_enabled: true,
-                move_speed: 10,
-                target_mode: 0,
-                trigger_sequence: None,
-                sequence_window_ms: 500,
-                hold_indices: None,
-                append_keys: None,
-            },
-            KeyMapping {
-                trigger_key: "B".to_string(),
-                target_keys: SmallVec::from_vec(vec!["2".to_string()]),
-                interval: None,
-                event_duration: None,
-                turbo_enabled: true,
-                move_speed: 10,
-                target_mode: 0,
-                trigger_sequence: None,
-                sequence_window_ms: 500,
-                hold_indices: None,
-                append_keys: None,
-            },
-            KeyMapping {
-                trigger_key: "F1".to_string(),
-                target_keys: SmallVec::from_vec(vec!["SPACE".to_string()]),
-                interval: Some(20),
-                event_duration: Some(10),
-                turbo_enabled: true,
-                move_speed: 10,
-                target_mode: 0,
-                trigger_sequence: None,
-                sequence_window_ms: 500,
-                hold_indices: None,
-                append_keys: None,
-            },
-        ];
-
-        config.save_to_file(&path).expect("Failed to save config");
-        let loaded_config = AppConfig::load_from_file(&path).expect("Failed to load config");
-
-        assert_eq!(loaded_config.mappings.len(), 3);
-        assert_eq!(loaded_config.mappings[0].trigger_key, "A");
-        assert_eq!(
-            loaded_config.mappings[1].target_keys.as_slice(),
-            &["2".to_string()]
-        );
-        assert_eq!(loaded_config.mappings[2].interval, Some(20));
-
-        cleanup_test_file(&path);
-    }
-
-    #[test]
-    fn test_config_load_invalid_toml() {
-        let path = get_test_config_path("invalid_toml");
-        cleanup_test_file(&path);
-
-        // Write invalid TOML
-        std::fs::write(&path, "invalid [[ toml \n syntax").expect("Failed to write");
-
-        let result = AppConfig::load_from_file(&path);
-        assert!(result.is_err());
-
-        cleanup_test_file(&path);
-    }
-
-    #[test]
-    fn test_config_load_nonexistent_file() {
-        let path = get_test_config_path("nonexistent");
-
-        // Ensure file doesn't exist
-        let _ = std::fs::remove_file(&path);
-
-        let result = AppConfig::load_from_file(&path);
-        assert!(result.is_err());
-    }
-
-    #[test]
-    fn test_config_with_extreme_values() {
-        let path = get_test_config_path("extreme_values");
-        cleanup_test_file(&path);
-
-        let mut config = AppConfig::default();
-        config.interval = 1000; // Very large interval
-        config.event_duration = 500;
-        config.input_timeout = 10000;
-        config.worker_count = 64; // Large worker count
-
-        config.save_to_file(&path).expect("Failed to save");
-        let loaded = AppConfig::load_from_file(&path).expect("Failed to load");
-
-        assert_eq!(loaded.interval, 1000);
-        assert_eq!(loaded.event_duration, 500);
-        assert_eq!(loaded.input_timeout, 10000);
-        assert_eq!(loaded.worker_count, 64);
-
-        cleanup_test_file(&path);
-    }
-
-    #[test]
-    fn test_config_with_special_characters_in_process_name() {
-        let path = get_test_config_path("special_chars");
-        cleanup_test_file(&path);
-
-        let mut config = AppConfig::default();
-        config.process_whitelist = vec![
-            "app-name.exe".to_string(),
-            "app_name.exe".to_string(),
-            "app123.exe".to_string(),
-        ];
-
-        config.save_to_file(&path).expect("Failed to save");
-        let loaded = AppConfig::load_from_file(&path).expect("Failed to load");
-
-        assert_eq!(loaded.process_whitelist.len(), 3);
-        assert!(
-            loaded
-                .process_whitelist
-                .contains(&"app-name.exe".to_string())
-        );
-
-        cleanup_test_file(&path);
-    }
-
-    #[test]
-    fn test_config_save_to_readonly_path() {
-        // This test verifies error handling for readonly paths
-        // On Windows, we can't easily create readonly directories in tests
-        // so we test with an invalid path
-        let path = PathBuf::from("/nonexistent/invalid/path/config.toml");
-
-        let config = AppConfig::default();
-        let result = config.save_to_file(&path);
-
-        assert!(result.is_err());
-    }
-
-    #[test]
-    fn test_config_language_default() {
-        let config = AppConfig::default();
-        assert_eq!(config.language, Language::default());
-    }
-
-    #[test]
-    fn test_config_with_duplicate_process_names() {
-        let path = get_test_config_path("duplicate_processes");
-        cleanup_test_file(&path);
-
-        let mut config = AppConfig::default();
-        config.process_whitelist = vec![
-            "app.exe".to_string(),
-            "app.exe".to_string(), // Duplicate
-            "other.exe".to_string(),
-        ];
-
-        config.save_to_file(&path).expect("Failed to save");
-        let loaded = AppConfig::load_from_file(&path).expect("Failed to load");
-
-        assert_eq!(loaded.process_whitelist.len(), 2);
-
-        cleanup_test_file(&path);
-    }
-
-    #[test]
-    fn test_config_validation_clamps_negative_values() {
-        let path = get_test_config_path("negative_values");
-        cleanup_test_file(&path);
-
-        // Manually write config with "negative" (actually minimum) values
-        let content = r#"
-            show_tray_icon = true
-            show_notifications = true
-            switch_key = "DELETE"
-            input_timeout = 1
-            interval = 1
-            event_duration = 1
-            worker_count = 0
-            process_whitelist = []
-            mappings = []
-        "#;
-
-        std::fs::write(&path, content).expect("Failed to write");
-        let loaded = AppConfig::load_from_file(&path).expect("Failed to load");
-
-        // Values should be clamped to minimums
-        assert!(loaded.input_timeout >= 2);
-        assert!(loaded.interval >= 5);
-        assert!(loaded.event_duration >= 2);
-
-        cleanup_test_file(&path);
-    }
-
-    #[test]
-    fn test_config_deduplicates_process_whitelist() {
-        // Test that duplicate processes are automatically removed when loading config
-        let path = get_test_config_path("process_dedup");
-        cleanup_test_file(&path);
-
-        let content = r#"
-            show_tray_icon = true
-            show_notifications = true
-            switch_key = "DELETE"
-            input_timeout = 10
-            interval = 5
-            event_duration = 5
-            worker_count = 0
-            process_whitelist = ["chrome.exe", "notepad.exe", "chrome.exe", "firefox.exe", "notepad.exe"]
-            mappings = []
-        "#;
-
-        std::fs::write(&path, content).expect("Failed to write test config");
-        let loaded = AppConfig::load_from_file(&path).expect("Failed to load config");
-
-        // Should have exactly 3 unique processes after deduplication
-        assert_eq!(loaded.process_whitelist.len(), 3);
-        assert!(loaded.process_whitelist.contains(&"chrome.exe".to_string()));
-        assert!(
-            loaded
-                .process_whitelist
-                .contains(&"notepad.exe".to_string())
-        );
-        assert!(
-            loaded
-                .process_whitelist
-                .contains(&"firefox.exe".to_string())
-        );
-
-        cleanup_test_file(&path);
-    }
-
-    #[test]
-    fn test_multiple_target_keys_single() {
-        let mapping = KeyMapping {
-            trigger_key: "A".to_string(),
-            target_keys: SmallVec::from_vec(vec!["B".to_string()]),
-            interval: None,
-            event_duration: None,
-            turbo_enabled: true,
-            move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        };
-
-        assert_eq!(mapping.target_keys.len(), 1);
-        assert_eq!(mapping.target_keys_display(), "B");
-    }
-
-    #[test]
-    fn test_multiple_target_keys_multiple() {
-        let mapping = KeyMapping {
-            trigger_key: "A".to_string(),
-            target_keys: SmallVec::from_vec(vec!["MOUSE_UP".to_string(), "MOUSE_LEFT".to_string()]),
-            interval: None,
-            event_duration: None,
-            turbo_enabled: true,
-            move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        };
-
-        assert_eq!(mapping.target_keys.len(), 2);
-        assert_eq!(mapping.target_keys_display(), "MOUSE_UP, MOUSE_LEFT");
-    }
-
-    #[test]
-    fn test_multiple_target_keys_empty() {
-        let mapping = KeyMapping {
-            trigger_key: "A".to_string(),
-            target_keys: SmallVec::new(),
-            interval: None,
-            event_duration: None,
-            turbo_enabled: true,
-            move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        };
-
-        assert_eq!(mapping.target_keys.len(), 0);
-        assert_eq!(mapping.target_keys_display(), "");
-    }
-
-    #[test]
-    fn test_add_target_key() {
-        let mut mapping = KeyMapping {
-            trigger_key: "A".to_string(),
-            target_keys: SmallVec::from_vec(vec!["B".to_string()]),
-            interval: None,
-            event_duration: None,
-            turbo_enabled: true,
-            move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        };
-
-        mapping.add_target_key("C".to_string());
-        assert_eq!(mapping.target_keys.len(), 2);
-        assert_eq!(mapping.target_keys[1], "C");
-
-        // Adding duplicate should not increase count
-        mapping.add_target_key("B".to_string());
-        assert_eq!(mapping.target_keys.len(), 2);
-    }
-
-    #[test]
-    fn test_clear_target_keys() {
-        let mut mapping = KeyMapping {
-            trigger_key: "A".to_string(),
-            target_keys: SmallVec::from_vec(vec!["B".to_string(), "C".to_string()]),
-            interval: None,
-            event_duration: None,
-            turbo_enabled: true,
-            move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        };
-
-        mapping.clear_target_keys();
-        assert_eq!(mapping.target_keys.len(), 0);
-    }
-
-    #[test]
-    fn test_multiple_target_keys_serialization() {
-        let path = get_test_config_path("multi_target_keys");
-        cleanup_test_file(&path);
-
-        let mut config = AppConfig::default();
-        config.mappings = vec![
-            KeyMapping {
-                trigger_key: "Q".to_string(),
-                target_keys: SmallVec::from_vec(vec![
-                    "MOUSE_UP".to_string(),
-                    "MOUSE_LEFT".to_string(),
-                ]),
-                interval: Some(5),
-                event_duration: None,
-                turbo_enabled: true,
-                move_speed: 10,
-                target_mode: 0,
-                trigger_sequence: None,
-                sequence_window_ms: 500,
-                hold_indices: None,
-                append_keys: None,
-            },
-            KeyMapping {
-                trigger_key: "E".to_string(),
-                target_keys: SmallVec::from_vec(vec![
-                    "MOUSE_UP".to_string(),
-                    "MOUSE_RIGHT".to_string(),
-                ]),
-                interval: Some(5),
-                event_duration: None,
-                turbo_enabled: true,
-                move_speed: 10,
-                target_mode: 0,
-                trigger_sequence: None,
-                sequence_window_ms: 500,
-                hold_indices: None,
-                append_keys: None,
-            },
-        ];
-
-        config.save_to_file(&path).expect("Failed to save config");
-        let loaded_config = AppConfig::load_from_file(&path).expect("Failed to load config");
-
-        assert_eq!(loaded_config.mappings.len(), 2);
-        assert_eq!(loaded_config.mappings[0].target_keys.len(), 2);
-        assert_eq!(loaded_config.mappings[0].target_keys[0], "MOUSE_UP");
-        assert_eq!(loaded_config.mappings[0].target_keys[1], "MOUSE_LEFT");
-        assert_eq!(loaded_config.mappings[1].target_keys.len(), 2);
-        assert_eq!(loaded_config.mappings[1].target_keys[0], "MOUSE_UP");
-        assert_eq!(loaded_config.mappings[1].target_keys[1], "MOUSE_RIGHT");
-
-        cleanup_test_file(&path);
-    }
-
-    #[test]
-    fn test_smallvec_inline_capacity() {
-        // Test that SmallVec uses inline storage for small collections
-        let small_vec: SmallVec<[String; 4]> =
-            SmallVec::from_vec(vec!["A".to_string(), "B".to_string(), "C".to_string()]);
-
-        // Should be stored inline (capacity <= 4)
-        assert_eq!(small_vec.len(), 3);
-        assert!(small_vec.spilled() == false); // Not heap allocated
-
-        let mut large_vec: SmallVec<[String; 4]> = SmallVec::new();
-        for i in 0..6 {
-            large_vec.push(format!("KEY_{}", i));
-        }
-
-        // Should spill to heap (capacity > 4)
-        assert_eq!(large_vec.len(), 6);
-        assert!(large_vec.spilled()); // Heap allocated
-    }
-
-    #[test]
-    fn test_get_target_keys() {
-        let mapping = KeyMapping {
-            trigger_key: "A".to_string(),
-            target_keys: SmallVec::from_vec(vec!["B".to_string(), "C".to_string()]),
-            interval: None,
-            event_duration: None,
-            turbo_enabled: true,
-            move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        };
-
-        let keys = mapping.get_target_keys();
-        assert_eq!(keys.len(), 2);
-        assert_eq!(keys[0], "B");
-        assert_eq!(keys[1], "C");
-    }
-
-    #[test]
-    fn test_empty_target_keys_serialization() {
-        let path = get_test_config_path("empty_target_keys");
-        cleanup_test_file(&path);
-
-        let mut config = AppConfig::default();
-        config.mappings = vec![KeyMapping {
-            trigger_key: "A".to_string(),
-            target_keys: SmallVec::new(),
-            interval: None,
-            event_duration: None,
-            turbo_enabled: true,
-            move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        }];
-
-        config.save_to_file(&path).expect("Failed to save config");
-        let loaded_config = AppConfig::load_from_file(&path).expect("Failed to load config");
-
-        assert_eq!(loaded_config.mappings.len(), 1);
-        assert_eq!(loaded_config.mappings[0].target_keys.len(), 0);
-
-        cleanup_test_file(&path);
-    }
-
-    #[test]
-    fn test_many_target_keys_serialization() {
-        let path = get_test_config_path("many_target_keys");
-        cleanup_test_file(&path);
-
-        let mut config = AppConfig::default();
-        config.mappings = vec![KeyMapping {
-            trigger_key: "A".to_string(),
-            target_keys: SmallVec::from_vec(vec![
-                "1".to_string(),
-                "2".to_string(),
-                "3".to_string(),
-                "4".to_string(),
-                "5".to_string(),
-                "6".to_string(),
-            ]),
-            interval: None,
-            event_duration: None,
-            turbo_enabled: true,
-            move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        }];
-
-        config.save_to_file(&path).expect("Failed to save config");
-        let loaded_config = AppConfig::load_from_file(&path).expect("Failed to load config");
-
-        assert_eq!(loaded_config.mappings.len(), 1);
-        assert_eq!(loaded_config.mappings[0].target_keys.len(), 6);
-        assert_eq!(loaded_config.mappings[0].target_keys[5], "6");
-
-        cleanup_test_file(&path);
-    }
-
-    /// `hold_indices` and `append_keys` survive a
-    /// save/load cycle unchanged. Confirms serde keeps order and type.
-    #[test]
-    fn test_sequence_hold_fields_roundtrip() {
-        let path = get_test_config_path("sequence_hold_fields");
-        cleanup_test_file(&path);
-
-        let mut config = AppConfig::default();
-        config.mappings = vec![KeyMapping {
-            trigger_key: "F1".to_string(),
-            target_keys: SmallVec::from_vec(vec!["RIGHT".to_string(), "RIGHT".to_string()]),
-            interval: Some(5),
-            event_duration: Some(5),
-            turbo_enabled: false,
-            move_speed: 5,
-            target_mode: 2,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: Some(SmallVec::from_vec(vec![1u8])),
-            append_keys: Some(SmallVec::from_vec(vec![
-                "UP".to_string(),
-                "LSHIFT".to_string(),
-            ])),
-        }];
-
-        config.save_to_file(&path).expect("Failed to save config");
-        let loaded = AppConfig::load_from_file(&path).expect("Failed to load config");
-
-        assert_eq!(loaded.mappings.len(), 1);
-        let m = &loaded.mappings[0];
-        assert_eq!(
-            m.hold_indices.as_ref().map(|v| v.as_slice()),
-            Some([1u8].as_slice())
-        );
-        let append = m.append_keys.as_ref().unwrap();
-        assert_eq!(append.len(), 2);
-        assert_eq!(append[0], "UP");
-        assert_eq!(append[1], "LSHIFT");
-
-        cleanup_test_file(&path);
-    }
-
-    /// Omitted sequence hold / append fields round-trip as `None`, meaning
-    /// `skip_serializing_if` keeps legacy configs on the classic path.
-    #[test]
-    fn test_sequence_hold_fields_skip_when_none() {
-        let path = get_test_config_path("sequence_hold_fields_none");
-        cleanup_test_file(&path);
-
-        let mut config = AppConfig::default();
-        config.mappings = vec![KeyMapping {
-            trigger_key: "F1".to_string(),
-            target_keys: SmallVec::from_vec(vec!["A".to_string()]),
-            interval: None,
-            event_duration: None,
-            turbo_enabled: true,
-            move_speed: 5,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        }];
-
-        config.save_to_file(&path).expect("Failed to save config");
-        let raw_toml =
-            std::fs::read_to_string(&path).expect("Failed to read config file for inspection");
-        // The doc header references both legacy and new names in comments,
-        // so match the assignment pattern instead of the bare word.
-        let has_hold_assignment = raw_toml
-            .lines()
-            .any(|line| !line.trim_start().starts_with('#') && line.contains("hold_indices ="));
-        let has_append_assignment = raw_toml
-            .lines()
-            .any(|line| !line.trim_start().starts_with('#') && line.contains("append_keys ="));
-        assert!(
-            !has_hold_assignment,
-            "Missing skip_serializing_if: serialized TOML should omit hold_indices when None, got:\n{raw_toml}"
-        );
-        assert!(
-            !has_append_assignment,
-            "Missing skip_serializing_if: serialized TOML should omit append_keys when None, got:\n{raw_toml}"
-        );
-
-        let loaded = AppConfig::load_from_file(&path).expect("Failed to load config");
-        assert!(loaded.mappings[0].hold_indices.is_none());
-        assert!(loaded.mappings[0].append_keys.is_none());
-
-        cleanup_test_file(&path);
-    }
-
-    /// A malformed config file resets to defaults rather than failing.
-    /// The default config gets written back to disk so the next launch
-    /// reads a clean state.
-    #[test]
-    fn test_load_or_create_resets_on_malformed_file() {
-        let path = get_test_config_path("malformed_reset");
-        cleanup_test_file(&path);
-
-        std::fs::write(&path, "this is = not valid = toml\n[[mappings\n").unwrap();
-        let loaded = AppConfig::load_or_create(&path).expect("should recover to defaults");
-
-        // Defaults resurface.
-        let default_cfg = AppConfig::default();
-        assert_eq!(loaded.switch_key, default_cfg.switch_key);
-        assert_eq!(loaded.mappings.len(), default_cfg.mappings.len());
-
-        // The file on disk now parses cleanly on a second read.
-        let second = AppConfig::load_from_file(&path).expect("rewritten file should parse");
-        assert_eq!(second.switch_key, default_cfg.switch_key);
-
-        cleanup_test_file(&path);
-    }
-}
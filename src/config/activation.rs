@@ -0,0 +1,46 @@
+//! Latched activation.
+//!
+//! By default a mapping's output runs while its trigger is held. With
+//! `activation = "toggle"` the first press starts it and the next press
+//! stops it, so a long auto-click session needs no held button;
+//! `turbo_enabled` still decides between repeating and holding the
+//! output. `auto_stop_ms` stops a latched output on its own after that
+//! long. Pausing or leaving the process list stops it as well.
+
+use serde::{Deserialize, Serialize};
+
+use super::KeyMapping;
+
+/// When a mapping's output runs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Activation {
+    /// While the trigger is held.
+    #[default]
+    Hold,
+    /// From one press of the trigger to the next.
+    Toggle,
+}
+
+impl Activation {
+    pub(super) fn is_hold(&self) -> bool {
+        *self == Activation::Hold
+    }
+}
+
+/// Settings of a mapping with `activation = "toggle"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ToggleSettings {
+    /// Time after which a latched output stops by itself.
+    pub auto_stop_ms: Option<u64>,
+}
+
+impl KeyMapping {
+    /// Toggle settings, or `None` when the output runs while the trigger
+    /// is held.
+    pub fn toggle(&self) -> Option<ToggleSettings> {
+        (self.activation == Activation::Toggle).then_some(ToggleSettings {
+            auto_stop_ms: self.auto_stop_ms.filter(|&ms| ms > 0),
+        })
+    }
+}
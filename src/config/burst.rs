@@ -0,0 +1,29 @@
+//! Burst mode.
+//!
+//! `burst_count = 3` makes each press of the trigger send exactly three
+//! output cycles at the mapping's `interval` and `event_duration`, then
+//! stop even while the trigger stays down. A burst always runs as turbo
+//! cycles. Releasing the trigger early lets the burst finish unless
+//! `cancel_burst_on_release = true`.
+
+use super::KeyMapping;
+
+/// Burst settings of one mapping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BurstSettings {
+    /// Output cycles per press.
+    pub count: u32,
+    pub cancel_on_release: bool,
+}
+
+impl KeyMapping {
+    /// Burst settings, or `None` when the output runs for as long as the
+    /// trigger is active.
+    pub fn burst(&self) -> Option<BurstSettings> {
+        let count = self.burst_count.filter(|&count| count > 0)?;
+        Some(BurstSettings {
+            count,
+            cancel_on_release: self.cancel_burst_on_release,
+        })
+    }
+}
@@ -0,0 +1,96 @@
+//! Conditions on mappings that share a trigger.
+//!
+//! Any number of mappings may use the same trigger. On each press they are
+//! tried from the highest `priority` down; among equal priorities mappings
+//! on a `layer` come first, then later mappings before earlier ones. The
+//! first one whose conditions all hold fires. The conditions are the
+//! mapping's `processes` / `exclude_processes`, the `profiles` it belongs
+//! to, the lock-key states in `lock_keys` and its `layer`. A mapping
+//! without conditions always matches, so nothing tried after it ever
+//! fires.
+
+use serde::{Deserialize, Serialize};
+
+use super::{DEFAULT_PROFILE, KeyMapping};
+
+/// Caps Lock bit of a lock-key state.
+pub const CAPS_LOCK: u8 = 1 << 0;
+/// Num Lock bit of a lock-key state.
+pub const NUM_LOCK: u8 = 1 << 1;
+/// Scroll Lock bit of a lock-key state.
+pub const SCROLL_LOCK: u8 = 1 << 2;
+
+/// Lock-key states a mapping requires, e.g.
+/// `lock_keys = { capslock = true }`. Keys left out may be either on or
+/// off.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct LockKeys {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capslock: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub numlock: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scrolllock: Option<bool>,
+}
+
+impl LockKeys {
+    fn entries(&self) -> [(&'static str, Option<bool>, u8); 3] {
+        [
+            ("capslock", self.capslock, CAPS_LOCK),
+            ("numlock", self.numlock, NUM_LOCK),
+            ("scrolllock", self.scrolllock, SCROLL_LOCK),
+        ]
+    }
+
+    /// Whether no key is constrained.
+    pub fn is_empty(&self) -> bool {
+        self.entries().iter().all(|(_, want, _)| want.is_none())
+    }
+
+    /// Whether `state`, a combination of [`CAPS_LOCK`], [`NUM_LOCK`] and
+    /// [`SCROLL_LOCK`] bits for the keys that are on, satisfies every
+    /// constrained key.
+    #[inline]
+    pub fn matches(&self, state: u8) -> bool {
+        self.entries()
+            .iter()
+            .all(|&(_, want, bit)| want.is_none_or(|on| on == (state & bit != 0)))
+    }
+
+    /// Inline-table form used when writing the config.
+    pub(super) fn to_toml(self) -> String {
+        let fields: Vec<String> = self
+            .entries()
+            .iter()
+            .filter_map(|(name, want, _)| want.map(|on| format!("{name} = {on}")))
+            .collect();
+        format!("{{ {} }}", fields.join(", "))
+    }
+}
+
+impl KeyMapping {
+    /// Lock-key states the mapping requires, or `None` when it fires in
+    /// any state.
+    pub fn lock_key_condition(&self) -> Option<LockKeys> {
+        self.lock_keys.filter(|keys| !keys.is_empty())
+    }
+
+    /// Whether the mapping only fires under some condition, so a mapping
+    /// with the same trigger can still fire when the condition fails.
+    pub fn has_conditions(&self) -> bool {
+        self.process_scope().is_some()
+            || self.lock_key_condition().is_some()
+            || self.profiles.as_ref().is_some_and(|p| !p.is_empty())
+            || self.layer_condition().is_some()
+    }
+
+    /// Whether this top-level mapping belongs to profile `name`. Mappings
+    /// without `profiles` belong to [`DEFAULT_PROFILE`] only.
+    pub fn in_profile(&self, name: &str) -> bool {
+        match self.profiles.as_deref() {
+            None | Some([]) => name == DEFAULT_PROFILE,
+            Some(names) => names.iter().any(|n| n == name),
+        }
+    }
+}
@@ -0,0 +1,239 @@
+//! Format-preserving saves.
+//!
+//! Saving from the GUI must not throw away what users wrote into
+//! `Config.toml` by hand. Instead of regenerating the file, the config the
+//! file currently holds and the config being saved are both rendered
+//! through the normal template and compared table by table. Only values
+//! that differ are written into the existing document, keeping the
+//! comments around them; mapping tables are matched up in order so an
+//! added or removed entry touches only that entry.
+
+use toml_edit::{ArrayOfTables, Decor, DocumentMut, Item, RawString, Table, Value};
+
+use super::{AppConfig, CURRENT_CONFIG_VERSION};
+
+/// Applies `config` to the existing file text `existing` and returns the
+/// edited text. `None` when `existing` does not load as a current-layout
+/// config, in which case the caller writes the template instead.
+pub(super) fn update(existing: &str, config: &AppConfig) -> Option<String> {
+    let (on_disk, migration) = AppConfig::parse_str(existing).ok()?;
+    // A migration that rewrote keys leaves the old names in the document;
+    // editing around them would produce a file that mixes layouts.
+    if migration.as_ref().is_some_and(|m| !m.changes.is_empty()) {
+        return None;
+    }
+
+    let mut document = edit(
+        existing,
+        &on_disk.render_template(),
+        &config.render_template(),
+    )?;
+    if migration.is_some() {
+        document["config_version"] = toml_edit::value(i64::from(CURRENT_CONFIG_VERSION));
+    }
+    Some(document.to_string())
+}
+
+/// Applies the difference between the rendered files `old` and `new` to
+/// `existing`, which holds what `old` describes in the user's own layout.
+/// `None` when any of the three fails to parse.
+pub(super) fn edit(existing: &str, old: &str, new: &str) -> Option<DocumentMut> {
+    let mut document: DocumentMut = existing.parse().ok()?;
+    let old: DocumentMut = old.parse().ok()?;
+    let new: DocumentMut = new.parse().ok()?;
+    merge_table(document.as_table_mut(), old.as_table(), new.as_table());
+    Some(document)
+}
+
+/// Brings `target` from `old` to `new`. Keys whose rendered value did not
+/// change are left alone, as are keys `old` does not know about.
+fn merge_table(target: &mut Table, old: &Table, new: &Table) {
+    let removed: Vec<String> = old
+        .iter()
+        .filter(|(key, _)| !new.contains_key(key))
+        .map(|(key, _)| key.to_string())
+        .collect();
+    for key in removed {
+        target.remove(&key);
+    }
+
+    let empty = Table::new();
+    for (key, new_item) in new.iter() {
+        let old_item = old.get(key);
+        if old_item.is_some_and(|old_item| same_item(old_item, new_item)) {
+            continue;
+        }
+        match (target.get_mut(key), new_item) {
+            (Some(Item::Value(value)), Item::Value(new_value)) => {
+                let decor = value.decor().clone();
+                *value = new_value.clone();
+                *value.decor_mut() = decor;
+            }
+            (Some(Item::Table(table)), Item::Table(new_table)) => {
+                let old_table = old_item.and_then(Item::as_table).unwrap_or(&empty);
+                merge_table(table, old_table, new_table);
+            }
+            (Some(Item::ArrayOfTables(array)), Item::ArrayOfTables(new_array)) => {
+                match old_item.and_then(Item::as_array_of_tables) {
+                    Some(old_array) if old_array.len() == array.len() => {
+                        merge_array(array, old_array, new_array)
+                    }
+                    _ => *array = fresh_array(new_array),
+                }
+            }
+            _ => replace_item(target, key, new_item),
+        }
+    }
+}
+
+/// Lines up the tables of `old` and `new` by their longest common
+/// subsequence. Unchanged tables are kept as they are in `target`, a
+/// changed table is edited in place, and only genuinely added or removed
+/// tables are inserted or dropped. `target` must hold one table per
+/// entry of `old`.
+fn merge_array(target: &mut ArrayOfTables, old: &ArrayOfTables, new: &ArrayOfTables) {
+    let old: Vec<&Table> = old.iter().collect();
+    let new: Vec<&Table> = new.iter().collect();
+    let same: Vec<Vec<bool>> = old
+        .iter()
+        .map(|o| new.iter().map(|n| same_table(o, n)).collect())
+        .collect();
+    // lcs[i][j]: length of the longest common subsequence of old[i..]
+    // and new[j..].
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if same[i][j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut merged = ArrayOfTables::new();
+    // Comments above a removed table move to whatever follows it, so
+    // section headings survive deleting the entry below them.
+    let mut carried = String::new();
+    let mut push = |mut table: Table, carried: &mut String| {
+        if !carried.is_empty() {
+            let own = prefix(table.decor()).to_string();
+            table.decor_mut().set_prefix(std::mem::take(carried) + &own);
+        }
+        merged.push(table);
+    };
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && same[i][j] {
+            push(target.get(i).cloned().unwrap_or_default(), &mut carried);
+            i += 1;
+            j += 1;
+        } else if i < old.len() && j < new.len() && lcs[i + 1][j + 1] == lcs[i][j] {
+            let mut table = target.get(i).cloned().unwrap_or_default();
+            merge_table(&mut table, old[i], new[j]);
+            push(table, &mut carried);
+            i += 1;
+            j += 1;
+        } else if j < new.len() && (i == old.len() || lcs[i][j + 1] == lcs[i][j]) {
+            push(fresh_table(new[j]), &mut carried);
+            j += 1;
+        } else {
+            if let Some(table) = target.get(i) {
+                carried.push_str(prefix(table.decor()));
+            }
+            i += 1;
+        }
+    }
+    *target = merged;
+}
+
+/// Replaces `key` with an item of a different kind, e.g. `mappings = []`
+/// with the first `[[mappings]]` table or the other way round. Comments
+/// above the old item are moved above the new one.
+fn replace_item(target: &mut Table, key: &str, new_item: &Item) {
+    let comments = match target.get(key) {
+        Some(Item::Table(table)) => prefix(table.decor()).to_string(),
+        Some(Item::ArrayOfTables(array)) => array.iter().map(|t| prefix(t.decor())).collect(),
+        Some(Item::Value(_)) => target
+            .key(key)
+            .map(|k| prefix(k.leaf_decor()).to_string())
+            .unwrap_or_default(),
+        _ => String::new(),
+    };
+
+    let mut item = fresh_item(new_item);
+    if !comments.is_empty() {
+        match &mut item {
+            Item::Table(table) => table.decor_mut().set_prefix(comments.as_str()),
+            Item::ArrayOfTables(array) => {
+                if let Some(first) = array.get_mut(0) {
+                    first.decor_mut().set_prefix(comments.as_str());
+                }
+            }
+            _ => {}
+        }
+    }
+    let is_value = item.is_value();
+    target.insert(key, item);
+    if is_value
+        && !comments.is_empty()
+        && let Some(mut k) = target.key_mut(key)
+    {
+        k.leaf_decor_mut().set_prefix(comments);
+    }
+}
+
+/// A copy of a template item without its document positions, so new
+/// tables are laid out next to their siblings in the edited file rather
+/// than where they sat in the template.
+fn fresh_item(item: &Item) -> Item {
+    match item {
+        Item::Table(table) => Item::Table(fresh_table(table)),
+        Item::ArrayOfTables(array) => Item::ArrayOfTables(fresh_array(array)),
+        other => other.clone(),
+    }
+}
+
+fn fresh_table(source: &Table) -> Table {
+    let mut table = Table::new();
+    for (key, item) in source.iter() {
+        table.insert(key, fresh_item(item));
+    }
+    table
+}
+
+fn fresh_array(source: &ArrayOfTables) -> ArrayOfTables {
+    let mut array = ArrayOfTables::new();
+    for table in source.iter() {
+        array.push(fresh_table(table));
+    }
+    array
+}
+
+/// Whether two rendered items hold the same value, ignoring comments and
+/// whitespace around them.
+fn same_item(a: &Item, b: &Item) -> bool {
+    match (a, b) {
+        (Item::Value(a), Item::Value(b)) => plain(a) == plain(b),
+        (Item::Table(a), Item::Table(b)) => same_table(a, b),
+        (Item::ArrayOfTables(a), Item::ArrayOfTables(b)) => {
+            a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| same_table(a, b))
+        }
+        (Item::None, Item::None) => true,
+        _ => false,
+    }
+}
+
+fn same_table(a: &Table, b: &Table) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .all(|(key, item)| b.get(key).is_some_and(|other| same_item(item, other)))
+}
+
+fn plain(value: &Value) -> String {
+    value.clone().decorated("", "").to_string()
+}
+
+fn prefix(decor: &Decor) -> &str {
+    decor.prefix().and_then(RawString::as_str).unwrap_or("")
+}
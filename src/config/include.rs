@@ -0,0 +1,219 @@
+//! Include files.
+//!
+//! `include = ["packs/fighting.toml", "packs/fps.toml"]` loads the
+//! `[[mappings]]` of other files into the config so mapping sets can be
+//! shared between setups. A pack holds nothing but `mappings` and,
+//! optionally, an `include` list of its own. Paths are resolved relative
+//! to the file that names them.
+//!
+//! A file's includes come first, in the order listed, followed by its own
+//! mappings. When two mappings resolve to the same trigger the later one
+//! wins, exactly as within a single file, so a file overrides everything
+//! it includes and later packs override earlier ones. A pack reached a
+//! second time through another pack is loaded only once; a file that
+//! includes itself, directly or through other packs, is an error.
+//!
+//! Included mappings remember their file in `KeyMapping::source`, and
+//! saving writes each one back there.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use super::{AppConfig, KeyMapping, document, toml_strings, write_mapping, write_tracked};
+
+/// Contents of an included file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MappingPack {
+    #[serde(default)]
+    include: Vec<String>,
+    #[serde(default)]
+    mappings: Vec<KeyMapping>,
+}
+
+/// Where a mapping is written: the included file or profile it came
+/// from, if any, and its position among that file's or profile's
+/// mappings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MappingLocation {
+    /// `None` for the main config file.
+    pub file: Option<PathBuf>,
+    /// `[[profiles]]` entry the mapping belongs to; `None` for the
+    /// top-level mappings.
+    pub profile: Option<String>,
+    pub index: usize,
+}
+
+impl std::fmt::Display for MappingLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(profile) = &self.profile {
+            write!(f, "profiles[{profile}].")?;
+        }
+        write!(f, "mappings[{}]", self.index)?;
+        if let Some(file) = &self.file {
+            write!(f, " in {}", file.display())?;
+        }
+        Ok(())
+    }
+}
+
+/// One depth-first walk over the include graph.
+struct IncludeLoader {
+    /// Canonical and as-written path of every file currently being
+    /// loaded, outermost first.
+    stack: Vec<(PathBuf, PathBuf)>,
+    /// Canonical paths of every file loaded so far.
+    seen: HashSet<PathBuf>,
+    files: Vec<PathBuf>,
+    mappings: Vec<KeyMapping>,
+}
+
+impl IncludeLoader {
+    /// Loads the `entries` listed in the file at `from`.
+    fn load(&mut self, entries: &[String], from: &Path) -> anyhow::Result<()> {
+        let base = from.parent().unwrap_or(Path::new(""));
+        for entry in entries {
+            let path = base.join(entry);
+            let canonical = fs::canonicalize(&path).map_err(|e| {
+                anyhow::anyhow!(
+                    "{}: cannot open include \"{}\": {}",
+                    from.display(),
+                    entry,
+                    e
+                )
+            })?;
+            if self.stack.iter().any(|(c, _)| *c == canonical) {
+                let chain: Vec<String> = self
+                    .stack
+                    .iter()
+                    .map(|(_, p)| p.display().to_string())
+                    .chain(std::iter::once(path.display().to_string()))
+                    .collect();
+                anyhow::bail!("include cycle: {}", chain.join(" -> "));
+            }
+            if !self.seen.insert(canonical.clone()) {
+                continue;
+            }
+
+            let content = fs::read_to_string(&path)
+                .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+            let pack: MappingPack = toml::from_str(&content)
+                .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+
+            self.stack.push((canonical, path.clone()));
+            self.load(&pack.include, &path)?;
+            self.stack.pop();
+
+            self.mappings
+                .extend(pack.mappings.into_iter().map(|mut mapping| {
+                    mapping.source = Some(path.clone());
+                    mapping
+                }));
+            self.files.push(path);
+        }
+        Ok(())
+    }
+}
+
+impl AppConfig {
+    /// Loads the files named by `include`, recursively, and puts their
+    /// mappings ahead of the config's own. `path` is the file the config
+    /// was read from.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the file at fault when an include cannot
+    /// be read or parsed, or when the includes form a cycle.
+    pub(super) fn resolve_includes(&mut self, path: &Path) -> anyhow::Result<()> {
+        self.included_files.clear();
+        if self.include.is_empty() {
+            return Ok(());
+        }
+
+        let root = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let mut loader = IncludeLoader {
+            stack: vec![(root.clone(), path.to_path_buf())],
+            seen: HashSet::from([root]),
+            files: Vec::new(),
+            mappings: Vec::new(),
+        };
+        loader.load(&self.include, path)?;
+
+        let own = std::mem::take(&mut self.mappings);
+        self.mappings = loader.mappings;
+        self.mappings.extend(own);
+        self.included_files = loader.files;
+        Ok(())
+    }
+
+    /// Writes each included file's mappings back to it, editing the file
+    /// in place like the main config. Files whose mappings did not change
+    /// are not touched.
+    pub(super) fn save_includes(&self) -> anyhow::Result<()> {
+        for file in &self.included_files {
+            let mappings: Vec<&KeyMapping> = self
+                .mappings
+                .iter()
+                .filter(|m| m.source.as_ref() == Some(file))
+                .collect();
+            save_pack(file, &mappings)?;
+        }
+        Ok(())
+    }
+
+    /// Where mapping `idx` is written, for messages that point the user
+    /// at a file.
+    pub fn mapping_location(&self, idx: usize) -> MappingLocation {
+        let file = self.mappings[idx].source.clone();
+        let index = self.mappings[..idx]
+            .iter()
+            .filter(|m| m.source == file)
+            .count();
+        MappingLocation {
+            file,
+            profile: None,
+            index,
+        }
+    }
+}
+
+fn save_pack(path: &Path, mappings: &[&KeyMapping]) -> anyhow::Result<()> {
+    let existing = fs::read_to_string(path).ok();
+    let on_disk: Option<MappingPack> = existing.as_deref().and_then(|c| toml::from_str(c).ok());
+    let include = on_disk
+        .as_ref()
+        .map(|pack| pack.include.as_slice())
+        .unwrap_or_default();
+    let rendered = render_pack(include, mappings);
+
+    let result = match (&existing, &on_disk) {
+        (Some(existing), Some(on_disk)) => {
+            let old: Vec<&KeyMapping> = on_disk.mappings.iter().collect();
+            document::edit(existing, &render_pack(&on_disk.include, &old), &rendered)
+                .map(|document| document.to_string())
+                .unwrap_or(rendered)
+        }
+        _ => rendered,
+    };
+    if existing.as_deref() != Some(result.as_str()) {
+        write_tracked(path, &result)?;
+    }
+    Ok(())
+}
+
+fn render_pack(include: &[String], mappings: &[&KeyMapping]) -> String {
+    let mut result = String::new();
+    if !include.is_empty() {
+        result.push_str(&format!("include = {}\n\n", toml_strings(include)));
+    }
+    if mappings.is_empty() {
+        result.push_str("mappings = []\n");
+    }
+    for mapping in mappings {
+        write_mapping(&mut result, "[[mappings]]", mapping);
+    }
+    result
+}
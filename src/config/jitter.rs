@@ -0,0 +1,88 @@
+//! Timing jitter.
+//!
+//! Output at a fixed `interval` and `event_duration` is perfectly
+//! periodic, which some games flag. `interval_jitter_ms` and
+//! `duration_jitter_ms` move every turbo gap and every press hold by up
+//! to that many milliseconds either way, in turbo cycles and in
+//! sequence playback alike. `jitter_distribution` picks how the offsets
+//! spread: `"uniform"` (the default) over the whole range, or `"normal"`
+//! with most draws close to the configured time. Jittered times never go
+//! below [`MIN_INTERVAL_MS`] and [`MIN_EVENT_DURATION_MS`].
+
+use std::ops::RangeInclusive;
+
+use serde::{Deserialize, Serialize};
+
+use super::KeyMapping;
+
+/// Shortest interval the workers pace output at.
+pub const MIN_INTERVAL_MS: u64 = 5;
+
+/// Shortest time a simulated press is held.
+pub const MIN_EVENT_DURATION_MS: u64 = 2;
+
+/// How jitter offsets spread over their range.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JitterDistribution {
+    /// Every offset in the range is equally likely.
+    #[default]
+    Uniform,
+    /// Offsets cluster around zero, with a standard deviation of half the
+    /// range and the tails cut off at its ends.
+    Normal,
+}
+
+impl JitterDistribution {
+    pub(super) fn is_uniform(&self) -> bool {
+        *self == JitterDistribution::Uniform
+    }
+}
+
+/// Jitter settings of one mapping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JitterSettings {
+    /// Largest change to an interval, either way.
+    pub interval_ms: u64,
+    /// Largest change to a press duration, either way.
+    pub duration_ms: u64,
+    pub distribution: JitterDistribution,
+}
+
+impl KeyMapping {
+    /// Jitter settings, or `None` when the mapping keeps exact timing.
+    pub fn jitter(&self) -> Option<JitterSettings> {
+        let interval_ms = self.interval_jitter_ms.unwrap_or(0);
+        let duration_ms = self.duration_jitter_ms.unwrap_or(0);
+        (interval_ms > 0 || duration_ms > 0).then_some(JitterSettings {
+            interval_ms,
+            duration_ms,
+            distribution: self.jitter_distribution,
+        })
+    }
+
+    /// Intervals the output can run at, given the global `interval` for
+    /// mappings without their own.
+    pub fn interval_range(&self, default_interval: u64) -> RangeInclusive<u64> {
+        jitter_range(
+            self.interval.unwrap_or(default_interval),
+            self.interval_jitter_ms.unwrap_or(0),
+            MIN_INTERVAL_MS,
+        )
+    }
+
+    /// Press durations the output can use, given the global
+    /// `event_duration` for mappings without their own.
+    pub fn duration_range(&self, default_duration: u64) -> RangeInclusive<u64> {
+        jitter_range(
+            self.event_duration.unwrap_or(default_duration),
+            self.duration_jitter_ms.unwrap_or(0),
+            MIN_EVENT_DURATION_MS,
+        )
+    }
+}
+
+fn jitter_range(base: u64, spread: u64, floor: u64) -> RangeInclusive<u64> {
+    let base = base.max(floor);
+    base.saturating_sub(spread).max(floor)..=base.saturating_add(spread)
+}
@@ -0,0 +1,80 @@
+//! Mapping layers.
+//!
+//! `layer = "combat"` limits a mapping to the time the `combat` layer is
+//! active. A mapping with `activate_layer = "combat"` switches the layer
+//! instead of sending keys: with the default `layer_mode = "hold"` the
+//! layer is active while its trigger is held, with `"toggle"` each press
+//! turns it on or off. Several layers can be active at once.
+//!
+//! A layer is a condition like any other (see `condition`), except that
+//! at the same `priority` mappings on a layer are tried before mappings
+//! without one, so an active layer overrides the base mapping of a
+//! trigger without any priority set.
+
+use serde::{Deserialize, Serialize};
+
+use super::{AppConfig, KeyMapping};
+
+/// Most layers one config can use. Names past this are ignored, and
+/// mappings on them never fire.
+pub const MAX_LAYERS: usize = 64;
+
+/// How an `activate_layer` mapping switches its layer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LayerMode {
+    /// Active while the trigger is held.
+    #[default]
+    Hold,
+    /// Each press turns the layer on or off.
+    Toggle,
+}
+
+impl LayerMode {
+    pub(super) fn is_hold(&self) -> bool {
+        *self == LayerMode::Hold
+    }
+
+    pub(super) fn as_str(self) -> &'static str {
+        match self {
+            LayerMode::Hold => "hold",
+            LayerMode::Toggle => "toggle",
+        }
+    }
+}
+
+impl KeyMapping {
+    /// Layer the mapping is limited to, or `None` when it fires on any.
+    pub fn layer_condition(&self) -> Option<&str> {
+        self.layer.as_deref().filter(|name| !name.is_empty())
+    }
+
+    /// Layer this mapping switches and how, when it is a layer switch.
+    pub fn layer_switch(&self) -> Option<(&str, LayerMode)> {
+        self.activate_layer
+            .as_deref()
+            .filter(|name| !name.is_empty())
+            .map(|name| (name, self.layer_mode))
+    }
+}
+
+impl AppConfig {
+    /// Every layer the mappings name, in order of first use and at most
+    /// [`MAX_LAYERS`]. Bit `i` of a runtime layer mask stands for entry
+    /// `i`.
+    pub fn layer_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for mapping in &self.mappings {
+            let used = [
+                mapping.layer_condition(),
+                mapping.layer_switch().map(|(name, _)| name),
+            ];
+            for name in used.into_iter().flatten() {
+                if names.len() < MAX_LAYERS && !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+        names
+    }
+}
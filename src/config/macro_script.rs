@@ -0,0 +1,22 @@
+//! Macro script targets.
+//!
+//! `macro = """..."""` runs a small script instead of pressing
+//! `target_keys`: `down KEY`, `up KEY`, `tap KEY`, `wait MS`,
+//! `repeat N { ... }`, `move DX DY` and `scroll DELTA`, one command per
+//! line or separated by `;`, with `#` comments. Releasing the trigger
+//! stops the script and releases the keys it holds. With turbo on the
+//! script starts over every `interval` while the trigger is held;
+//! otherwise it runs once per press. See [`crate::state::Script`] for the
+//! parser.
+
+use super::KeyMapping;
+
+impl KeyMapping {
+    /// Script source, or `None` when the mapping has no script or only
+    /// whitespace in it.
+    pub fn macro_script(&self) -> Option<&str> {
+        self.macro_script
+            .as_deref()
+            .filter(|source| !source.trim().is_empty())
+    }
+}
@@ -0,0 +1,334 @@
+//! Config schema versioning.
+//!
+//! Every file written by this version of Sorahk carries `config_version`.
+//! Files without it are treated as version 1 (the 0.5.0 layout and
+//! everything before it). On load the raw TOML table is walked through
+//! [`MIGRATIONS`] one step at a time until it reaches
+//! [`CURRENT_CONFIG_VERSION`], before serde ever sees it, so renamed or
+//! relocated keys are carried over instead of silently falling back to
+//! their defaults.
+
+use std::path::PathBuf;
+
+use toml::{Table, Value};
+
+/// Schema version written by this build.
+pub const CURRENT_CONFIG_VERSION: u32 = 2;
+
+/// Version assumed for files that have no `config_version` key.
+const UNVERSIONED: u32 = 1;
+
+/// What a load-time upgrade changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigMigration {
+    pub from_version: u32,
+    pub to_version: u32,
+    /// Copy of the file as it was before the upgraded layout was written
+    /// back. `None` when nothing was rewritten.
+    pub backup_path: Option<PathBuf>,
+    /// One human-readable line per change, in the order applied. Empty
+    /// when the upgrade only stamped the version number.
+    pub changes: Vec<String>,
+}
+
+/// The file was written by a newer Sorahk than this one. Loading it would
+/// mean dropping settings we don't understand, so it is refused instead
+/// of being recovered or rewritten.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnsupportedConfigVersion {
+    pub found: u32,
+    pub supported: u32,
+}
+
+impl std::fmt::Display for UnsupportedConfigVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "config_version {} is newer than this build supports ({}); please update Sorahk",
+            self.found, self.supported
+        )
+    }
+}
+
+impl std::error::Error for UnsupportedConfigVersion {}
+
+type MigrationStep = fn(&mut Table, &mut Vec<String>);
+
+/// `MIGRATIONS[i]` upgrades a table from version `i + 1` to `i + 2`.
+const MIGRATIONS: &[MigrationStep] = &[migrate_v1_to_v2];
+
+const _: () = assert!(MIGRATIONS.len() as u32 == CURRENT_CONFIG_VERSION - UNVERSIONED);
+
+/// Reads `config_version` from a raw table. A missing key means
+/// [`UNVERSIONED`].
+///
+/// # Errors
+///
+/// Returns an error if the key is present but not a positive integer.
+pub(super) fn read_version(table: &Table) -> anyhow::Result<u32> {
+    match table.get("config_version") {
+        None => Ok(UNVERSIONED),
+        Some(Value::Integer(n)) if *n >= 1 && *n <= i64::from(u32::MAX) => Ok(*n as u32),
+        Some(other) => anyhow::bail!("config_version must be a positive integer, found {other}"),
+    }
+}
+
+/// Upgrades `table` in place to [`CURRENT_CONFIG_VERSION`].
+///
+/// Returns `None` when the table was already current.
+///
+/// # Errors
+///
+/// Returns [`UnsupportedConfigVersion`] for files from a newer build and
+/// an error for a malformed `config_version`.
+pub(super) fn migrate(table: &mut Table) -> anyhow::Result<Option<ConfigMigration>> {
+    let from_version = read_version(table)?;
+    if from_version > CURRENT_CONFIG_VERSION {
+        return Err(UnsupportedConfigVersion {
+            found: from_version,
+            supported: CURRENT_CONFIG_VERSION,
+        }
+        .into());
+    }
+    if from_version == CURRENT_CONFIG_VERSION {
+        return Ok(None);
+    }
+
+    let mut changes = Vec::new();
+    for step in &MIGRATIONS[(from_version - UNVERSIONED) as usize..] {
+        step(table, &mut changes);
+    }
+    table.insert(
+        "config_version".to_string(),
+        Value::Integer(i64::from(CURRENT_CONFIG_VERSION)),
+    );
+
+    Ok(Some(ConfigMigration {
+        from_version,
+        to_version: CURRENT_CONFIG_VERSION,
+        backup_path: None,
+        changes,
+    }))
+}
+
+/// Global keys that older writers could leave stranded under a table
+/// header, where TOML attaches them to that table instead of the root.
+const RELOCATED_GLOBALS: &[&str] = &["xinput_stick_deadzone", "xinput_trigger_threshold"];
+
+/// 0.5.0 and earlier → version 2.
+///
+/// - 0.3 `target_key = "X"` becomes `target_keys = ["X"]`.
+/// - `target_mode` given by name ("Single", "Multi", "Sequence") becomes
+///   its integer form.
+/// - Mappings from before `target_mode` existed that press several keys
+///   at once get `target_mode = 1` so the editor shows them as Multi.
+///   Single and Multi behave the same at runtime, so this is not listed
+///   as a change and never forces a rewrite of the file on its own.
+/// - XInput thresholds written below a `[[mappings]]` or other table
+///   header are moved back to the root; a root value wins if both exist.
+fn migrate_v1_to_v2(table: &mut Table, changes: &mut Vec<String>) {
+    let mut lifted: Vec<(String, Value, String)> = Vec::new();
+
+    if let Some(Value::Array(mappings)) = table.get_mut("mappings") {
+        for (idx, mapping) in mappings.iter_mut().enumerate() {
+            let Value::Table(mapping) = mapping else {
+                continue;
+            };
+            let location = format!("mappings[{idx}]");
+
+            if let Some(old) = mapping.remove("target_key") {
+                if mapping.contains_key("target_keys") {
+                    changes.push(format!(
+                        "{location}: removed obsolete target_key (target_keys is already set)"
+                    ));
+                } else {
+                    changes.push(format!(
+                        "{location}: target_key {old} → target_keys = [{old}]"
+                    ));
+                    mapping.insert("target_keys".to_string(), Value::Array(vec![old]));
+                }
+            }
+
+            match mapping.get("target_mode") {
+                Some(Value::String(name)) => {
+                    let name = name.clone();
+                    let mode = match name.to_ascii_lowercase().as_str() {
+                        "single" => Some(0),
+                        "multi" => Some(1),
+                        "sequence" => Some(2),
+                        _ => None,
+                    };
+                    if let Some(mode) = mode {
+                        changes.push(format!(
+                            "{location}: target_mode \"{name}\" → target_mode = {mode}"
+                        ));
+                        mapping.insert("target_mode".to_string(), Value::Integer(mode));
+                    }
+                }
+                None => {
+                    let multi = matches!(
+                        mapping.get("target_keys"),
+                        Some(Value::Array(keys)) if keys.len() > 1
+                    );
+                    if multi {
+                        mapping.insert("target_mode".to_string(), Value::Integer(1));
+                    }
+                }
+                Some(_) => {}
+            }
+
+            for key in RELOCATED_GLOBALS {
+                if let Some(value) = mapping.remove(*key) {
+                    lifted.push((key.to_string(), value, location.clone()));
+                }
+            }
+        }
+    }
+
+    for (name, value) in table.iter_mut() {
+        if name == "mappings" {
+            continue;
+        }
+        if let Value::Table(section) = value {
+            for key in RELOCATED_GLOBALS {
+                if let Some(stray) = section.remove(*key) {
+                    lifted.push((key.to_string(), stray, format!("[{name}]")));
+                }
+            }
+        }
+    }
+
+    for (key, value, from) in lifted {
+        if table.contains_key(&key) {
+            changes.push(format!(
+                "{from}: dropped stray {key} (the global value is kept)"
+            ));
+        } else {
+            changes.push(format!(
+                "{from}: moved {key} = {value} to the global section"
+            ));
+            table.insert(key, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> Table {
+        toml::from_str(content).unwrap()
+    }
+
+    #[test]
+    fn test_current_version_is_untouched() {
+        let mut table = parse(&format!(
+            "config_version = {CURRENT_CONFIG_VERSION}\nswitch_key = \"F1\"\n"
+        ));
+        let before = table.clone();
+        assert!(migrate(&mut table).unwrap().is_none());
+        assert_eq!(table, before);
+    }
+
+    #[test]
+    fn test_unversioned_file_is_stamped() {
+        let mut table = parse("switch_key = \"F1\"\n");
+        let migration = migrate(&mut table).unwrap().unwrap();
+        assert_eq!(migration.from_version, 1);
+        assert_eq!(migration.to_version, CURRENT_CONFIG_VERSION);
+        assert!(migration.changes.is_empty());
+        assert_eq!(
+            table.get("config_version"),
+            Some(&Value::Integer(i64::from(CURRENT_CONFIG_VERSION)))
+        );
+    }
+
+    #[test]
+    fn test_newer_version_is_refused() {
+        let mut table = parse("config_version = 999\n");
+        let err = migrate(&mut table).unwrap_err();
+        let err = err.downcast_ref::<UnsupportedConfigVersion>().unwrap();
+        assert_eq!(err.found, 999);
+    }
+
+    #[test]
+    fn test_malformed_version_is_an_error() {
+        let mut table = parse("config_version = \"two\"\n");
+        assert!(migrate(&mut table).is_err());
+        let mut table = parse("config_version = 0\n");
+        assert!(migrate(&mut table).is_err());
+    }
+
+    #[test]
+    fn test_v1_target_key_becomes_target_keys() {
+        let mut table = parse(
+            "[[mappings]]\ntrigger_key = \"A\"\ntarget_key = \"B\"\n\n\
+             [[mappings]]\ntrigger_key = \"C\"\ntarget_key = \"X\"\ntarget_keys = [\"D\"]\n",
+        );
+        let migration = migrate(&mut table).unwrap().unwrap();
+        let mappings = table["mappings"].as_array().unwrap();
+        assert_eq!(
+            mappings[0].get("target_keys"),
+            Some(&Value::Array(vec![Value::String("B".into())]))
+        );
+        assert!(mappings[0].get("target_key").is_none());
+        assert_eq!(
+            mappings[1].get("target_keys"),
+            Some(&Value::Array(vec![Value::String("D".into())]))
+        );
+        assert!(mappings[1].get("target_key").is_none());
+        assert_eq!(migration.changes.len(), 2);
+    }
+
+    #[test]
+    fn test_v1_target_mode_names_and_implicit_multi() {
+        let mut table = parse(
+            "[[mappings]]\ntrigger_key = \"A\"\ntarget_keys = [\"B\"]\ntarget_mode = \"Sequence\"\n\n\
+             [[mappings]]\ntrigger_key = \"C\"\ntarget_keys = [\"D\", \"E\"]\n\n\
+             [[mappings]]\ntrigger_key = \"F\"\ntarget_keys = [\"G\"]\n",
+        );
+        let migration = migrate(&mut table).unwrap().unwrap();
+        // Only the renamed mode counts; the inferred Multi is cosmetic.
+        assert_eq!(migration.changes.len(), 1);
+        let mappings = table["mappings"].as_array().unwrap();
+        assert_eq!(mappings[0].get("target_mode"), Some(&Value::Integer(2)));
+        assert_eq!(mappings[1].get("target_mode"), Some(&Value::Integer(1)));
+        assert!(mappings[2].get("target_mode").is_none());
+    }
+
+    #[test]
+    fn test_v1_stranded_xinput_keys_move_to_root() {
+        let mut table = parse(
+            "xinput_trigger_threshold = 40\n\n\
+             [[mappings]]\ntrigger_key = \"A\"\ntarget_keys = [\"B\"]\n\
+             xinput_stick_deadzone = 9000\nxinput_trigger_threshold = 10\n",
+        );
+        let migration = migrate(&mut table).unwrap().unwrap();
+        assert_eq!(
+            table.get("xinput_stick_deadzone"),
+            Some(&Value::Integer(9000))
+        );
+        assert_eq!(
+            table.get("xinput_trigger_threshold"),
+            Some(&Value::Integer(40))
+        );
+        let mapping = &table["mappings"].as_array().unwrap()[0];
+        assert!(mapping.get("xinput_stick_deadzone").is_none());
+        assert!(mapping.get("xinput_trigger_threshold").is_none());
+        assert_eq!(migration.changes.len(), 2);
+    }
+
+    #[test]
+    fn test_v1_stranded_xinput_keys_in_table_section() {
+        let mut table = parse(
+            "[device_api_preferences]\n\"045E:028E\" = \"XInput\"\nxinput_stick_deadzone = 8000\n",
+        );
+        migrate(&mut table).unwrap();
+        assert_eq!(
+            table.get("xinput_stick_deadzone"),
+            Some(&Value::Integer(8000))
+        );
+        let prefs = table["device_api_preferences"].as_table().unwrap();
+        assert_eq!(prefs.len(), 1);
+    }
+}
@@ -0,0 +1,180 @@
+//! Process list matching.
+//!
+//! Entries in `process_whitelist` and `[[profile_rules]]` are compared
+//! case-insensitively with the foreground executable. An entry may use
+//! the wildcards `*` (any run of characters) and `?` (one character), and
+//! an entry containing a path separator is matched against the full image
+//! path instead of the file name, so `C:\Games\A\*` and `C:\Games\B\*`
+//! tell apart two games that both ship a `game.exe`. A mapping's own
+//! `processes` and `exclude_processes` use the same syntax.
+
+use serde::{Deserialize, Serialize};
+
+use super::{AppConfig, KeyMapping};
+
+/// How `process_whitelist` is applied.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProcessListMode {
+    /// Mappings only run in the listed processes.
+    #[default]
+    Whitelist,
+    /// Mappings run everywhere except in the listed processes.
+    Blacklist,
+}
+
+impl ProcessListMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Whitelist => "whitelist",
+            Self::Blacklist => "blacklist",
+        }
+    }
+}
+
+/// One process list entry, lowercased and ready to match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessPattern {
+    pattern: String,
+    full_path: bool,
+    wildcard: bool,
+}
+
+impl ProcessPattern {
+    pub fn new(entry: &str) -> Self {
+        let pattern = entry.trim().to_lowercase().replace('/', "\\");
+        Self {
+            full_path: pattern.contains('\\'),
+            wildcard: pattern.contains(['*', '?']),
+            pattern,
+        }
+    }
+
+    /// Matches against the lowercase file `name` and full image `path` of
+    /// a process.
+    #[inline]
+    pub fn matches(&self, name: &str, path: &str) -> bool {
+        let text = if self.full_path { path } else { name };
+        if self.wildcard {
+            glob_match(&self.pattern, text)
+        } else {
+            self.pattern == text
+        }
+    }
+}
+
+/// A process list together with its mode, as the runtime consults it.
+#[derive(Debug, Clone, Default)]
+pub struct ProcessFilter {
+    mode: ProcessListMode,
+    patterns: Vec<ProcessPattern>,
+}
+
+impl ProcessFilter {
+    pub fn new(mode: ProcessListMode, entries: &[String]) -> Self {
+        Self {
+            mode,
+            patterns: entries.iter().map(|e| ProcessPattern::new(e)).collect(),
+        }
+    }
+
+    /// True when the list cannot exclude anything.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Whether mappings should run in the process with the given lowercase
+    /// file `name` and full image `path`. An empty list allows everything
+    /// in either mode.
+    #[inline]
+    pub fn allows(&self, name: &str, path: &str) -> bool {
+        if self.patterns.is_empty() {
+            return true;
+        }
+        let listed = self.patterns.iter().any(|p| p.matches(name, path));
+        match self.mode {
+            ProcessListMode::Whitelist => listed,
+            ProcessListMode::Blacklist => !listed,
+        }
+    }
+}
+
+/// A mapping's own `processes` / `exclude_processes` lists.
+#[derive(Debug, Clone)]
+pub struct ProcessScope {
+    include: ProcessFilter,
+    exclude: ProcessFilter,
+}
+
+impl ProcessScope {
+    /// Whether the mapping may fire in the process with the given
+    /// lowercase file `name` and full image `path`.
+    #[inline]
+    pub fn allows(&self, name: &str, path: &str) -> bool {
+        self.include.allows(name, path) && self.exclude.allows(name, path)
+    }
+}
+
+impl AppConfig {
+    /// `process_whitelist` compiled with its `process_list_mode`.
+    pub fn process_filter(&self) -> ProcessFilter {
+        ProcessFilter::new(self.process_list_mode, &self.process_whitelist)
+    }
+}
+
+impl KeyMapping {
+    /// The mapping's process lists compiled for matching, or `None` when
+    /// it applies in every process.
+    pub fn process_scope(&self) -> Option<ProcessScope> {
+        let include = self.processes.as_deref().unwrap_or_default();
+        let exclude = self.exclude_processes.as_deref().unwrap_or_default();
+        if include.is_empty() && exclude.is_empty() {
+            return None;
+        }
+        Some(ProcessScope {
+            include: ProcessFilter::new(ProcessListMode::Whitelist, include),
+            exclude: ProcessFilter::new(ProcessListMode::Blacklist, exclude),
+        })
+    }
+}
+
+/// Wildcard match of the whole of `text` against `pattern`. `*` matches
+/// any run of characters, `?` exactly one; everything else is literal.
+/// Backtracks only to the most recent `*`, which bounds the work at
+/// pattern length times text length.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let (p, t) = (pattern.as_bytes(), text.as_bytes());
+    let char_len = |i: usize| text[i..].chars().next().map_or(1, char::len_utf8);
+    let (mut pi, mut ti) = (0, 0);
+    // Position of the last `*` in the pattern and where in the text it
+    // currently stops.
+    let mut star: Option<(usize, usize)> = None;
+
+    while ti < t.len() {
+        match p.get(pi) {
+            Some(b'*') => {
+                star = Some((pi, ti));
+                pi += 1;
+            }
+            Some(b'?') => {
+                pi += 1;
+                ti += char_len(ti);
+            }
+            Some(&c) if c == t[ti] => {
+                pi += 1;
+                ti += 1;
+            }
+            _ => match star {
+                Some((sp, st)) => {
+                    let st = st + char_len(st);
+                    star = Some((sp, st));
+                    pi = sp + 1;
+                    ti = st;
+                }
+                None => return false,
+            },
+        }
+    }
+    p[pi..].iter().all(|&c| c == b'*')
+}
@@ -0,0 +1,143 @@
+//! Mapping profiles.
+//!
+//! The top-level `mappings`, `interval`, `event_duration` and
+//! `process_whitelist` form the built-in [`DEFAULT_PROFILE`]. Each
+//! `[[profiles]]` entry is an alternative set of mappings with its own
+//! optional timing defaults and whitelist; fields a profile leaves out are
+//! inherited from the top level. Only one profile is active at a time, and
+//! [`AppConfig::resolve_profile`] produces the flattened config the runtime
+//! builds its caches from.
+//!
+//! `[[profile_rules]]` pick the profile from the foreground executable;
+//! `fallback_profile` applies when no rule matches.
+
+use serde::{Deserialize, Serialize};
+
+use super::{AppConfig, KeyMapping, ProcessListMode, ProcessPattern};
+
+/// Name of the profile made of the top-level mappings. Always present,
+/// always first in cycling order.
+pub const DEFAULT_PROFILE: &str = "Default";
+
+/// One `[[profiles]]` entry.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Profile {
+    /// Unique name shown in the tray and main window.
+    pub name: String,
+    /// Mappings active while this profile is selected. They replace the
+    /// top-level mappings rather than adding to them; top-level mappings
+    /// can opt in with `profiles`.
+    #[serde(default)]
+    pub mappings: Vec<KeyMapping>,
+    /// Default repeat interval; the top-level `interval` when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval: Option<u64>,
+    /// Default press duration; the top-level `event_duration` when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_duration: Option<u64>,
+    /// Process list mode; the top-level `process_list_mode` when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub process_list_mode: Option<ProcessListMode>,
+    /// Process whitelist; the top-level `process_whitelist` when unset.
+    /// An explicit empty list enables the profile everywhere.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub process_whitelist: Option<Vec<String>>,
+}
+
+/// One `[[profile_rules]]` entry: activate `profile` while `process` has
+/// focus.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ProfileRule {
+    /// Executable name or pattern, matched like a `process_whitelist`
+    /// entry (e.g. `game_a.exe`, `*launcher*.exe`).
+    pub process: String,
+    /// Profile to activate.
+    pub profile: String,
+}
+
+pub(super) fn default_active_profile() -> String {
+    DEFAULT_PROFILE.to_string()
+}
+
+impl AppConfig {
+    /// Profile names in cycling order: [`DEFAULT_PROFILE`] followed by
+    /// every `[[profiles]]` entry. Later duplicates of a name are
+    /// unreachable and left out.
+    pub fn profile_names(&self) -> Vec<&str> {
+        let mut names = vec![DEFAULT_PROFILE];
+        for profile in &self.profiles {
+            if !names.contains(&profile.name.as_str()) {
+                names.push(&profile.name);
+            }
+        }
+        names
+    }
+
+    /// The `[[profiles]]` entry called `name`, if any. [`DEFAULT_PROFILE`]
+    /// has no entry.
+    pub fn profile(&self, name: &str) -> Option<&Profile> {
+        if name == DEFAULT_PROFILE {
+            return None;
+        }
+        self.profiles.iter().find(|p| p.name == name)
+    }
+
+    /// `active_profile` if it names a known profile, otherwise
+    /// [`DEFAULT_PROFILE`].
+    pub fn active_profile_name(&self) -> &str {
+        if self.active_profile == DEFAULT_PROFILE || self.profile(&self.active_profile).is_some() {
+            &self.active_profile
+        } else {
+            DEFAULT_PROFILE
+        }
+    }
+
+    /// Flattens profile `name` onto the global settings: the result has
+    /// the profile's mappings, timing defaults and whitelist in the
+    /// top-level fields and `active_profile` set to `name`. Top-level
+    /// mappings whose `profiles` name the profile come first, followed by
+    /// the profile's own. Returns `None` for an unknown name.
+    pub fn resolve_profile(&self, name: &str) -> Option<AppConfig> {
+        let mut resolved = self.clone();
+        resolved.mappings.retain(|mapping| mapping.in_profile(name));
+        if name != DEFAULT_PROFILE {
+            let profile = self.profile(name)?;
+            resolved.mappings.extend(profile.mappings.iter().cloned());
+            if let Some(interval) = profile.interval {
+                resolved.interval = interval;
+            }
+            if let Some(event_duration) = profile.event_duration {
+                resolved.event_duration = event_duration;
+            }
+            if let Some(mode) = profile.process_list_mode {
+                resolved.process_list_mode = mode;
+            }
+            if let Some(whitelist) = &profile.process_whitelist {
+                resolved.process_whitelist = whitelist.clone();
+            }
+        }
+        resolved.active_profile = name.to_string();
+        Some(resolved)
+    }
+
+    /// Profile that `profile_rules` select for the foreground executable
+    /// with the lowercase file `name` and image `path`: the first matching
+    /// rule, else `fallback_profile`. `None` means no rule applies and the
+    /// current profile should stay.
+    pub fn profile_for_process(&self, name: &str, path: &str) -> Option<&str> {
+        match self
+            .profile_rules
+            .iter()
+            .find(|r| ProcessPattern::new(&r.process).matches(name, path))
+        {
+            Some(rule) => Some(&rule.profile),
+            None => (!self.fallback_profile.is_empty()).then_some(self.fallback_profile.as_str()),
+        }
+    }
+
+    /// [`resolve_profile`](Self::resolve_profile) for the active profile.
+    pub fn effective_config(&self) -> AppConfig {
+        self.resolve_profile(self.active_profile_name())
+            .unwrap_or_else(|| self.clone())
+    }
+}
@@ -0,0 +1,645 @@
+//! Best-effort recovery for config files that fail to load.
+//!
+//! A single typo used to make `load_or_create` replace the whole file with
+//! defaults. Recovery instead copies the broken file aside, salvages every
+//! top-level setting and every `[[mappings]]` entry that still parses on
+//! its own, and reports what had to be dropped so the user can fix it.
+//!
+//! Salvage runs in two passes. The syntactic pass splits the text into
+//! table sections and key/value statements and parses each statement in
+//! isolation, so a syntax error only costs the statement it sits in. The
+//! semantic pass then deserializes each global setting and each mapping
+//! separately, so a value of the wrong type only costs that value.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use toml::{Table, Value};
+
+use super::migration::{self, ConfigMigration, UnsupportedConfigVersion};
+use super::{AppConfig, KeyMapping};
+
+/// One piece of a config file that was dropped during recovery.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RejectedConfigItem {
+    /// Where the item lives, e.g. `interval`, `mappings[2]` or
+    /// `mappings[2].interval`. Mapping indices count every `[[mappings]]`
+    /// entry in the file, starting at 0.
+    pub location: String,
+    /// 1-based line in the original file where the item starts, when known.
+    pub line: Option<usize>,
+    /// Why the item was rejected.
+    pub reason: String,
+}
+
+impl std::fmt::Display for RejectedConfigItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}: {}", line, self.location, self.reason),
+            None => write!(f, "{}: {}", self.location, self.reason),
+        }
+    }
+}
+
+/// Outcome of recovering from a config file that failed to load.
+#[derive(Debug, Clone)]
+pub struct ConfigRecovery {
+    /// Copy of the original file, taken before the recovered config was
+    /// written back.
+    pub backup_path: PathBuf,
+    /// The load error that triggered recovery.
+    pub error: String,
+    /// Everything that could not be salvaged, in file order.
+    pub rejected: Vec<RejectedConfigItem>,
+}
+
+/// Copies `path` next to itself as `<name>.<YYYYMMDD-HHMMSS>.bak` (UTC) and
+/// returns the backup path. A numeric suffix is added if a backup with the
+/// same timestamp already exists, so an earlier backup is never replaced.
+///
+/// # Errors
+///
+/// Returns an error if the copy fails. Callers must not rewrite the
+/// original in that case.
+pub(super) fn backup_file(path: &Path) -> anyhow::Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "Config.toml".to_string());
+    let stamp = utc_timestamp();
+
+    let mut candidate = path.with_file_name(format!("{file_name}.{stamp}.bak"));
+    let mut n = 1;
+    while candidate.exists() {
+        candidate = path.with_file_name(format!("{file_name}.{stamp}-{n}.bak"));
+        n += 1;
+    }
+
+    fs::copy(path, &candidate).map_err(|e| {
+        anyhow::anyhow!(
+            "Failed to back up {} to {}: {}",
+            path.display(),
+            candidate.display(),
+            e
+        )
+    })?;
+    Ok(candidate)
+}
+
+/// Formats the current UTC time as `YYYYMMDD-HHMMSS`.
+fn utc_timestamp() -> String {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    let (y, m, d) = civil_from_days(days);
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        y,
+        m,
+        d,
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60
+    )
+}
+
+/// Converts days since 1970-01-01 to a proleptic Gregorian date
+/// (Howard Hinnant's `civil_from_days`).
+fn civil_from_days(z: i64) -> (i64, u32, u32) {
+    let z = z + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    (y, m, d)
+}
+
+/// Salvages what it can from `content`, upgrading the salvaged layout to
+/// the current schema version first. In the worst case the result is
+/// `AppConfig::default()` with every statement listed as rejected. Timing
+/// clamps are *not* applied here; the caller runs the same post-processing
+/// as a normal load.
+///
+/// # Errors
+///
+/// Returns [`UnsupportedConfigVersion`] if the salvaged `config_version`
+/// is newer than this build. Nothing should be written back in that case.
+pub(super) fn salvage(
+    content: &str,
+) -> anyhow::Result<(AppConfig, Vec<RejectedConfigItem>, Option<ConfigMigration>)> {
+    let mut rejected = Vec::new();
+    let mut salvaged = salvage_syntax(content, &mut rejected);
+
+    let migration = match migration::migrate(&mut salvaged.root) {
+        Ok(migration) => migration,
+        Err(e) if e.is::<UnsupportedConfigVersion>() => return Err(e),
+        Err(e) => {
+            // A garbled version number is treated like a missing one.
+            rejected.push(RejectedConfigItem {
+                location: "config_version".to_string(),
+                line: salvaged.key_lines.get("config_version").copied(),
+                reason: e.to_string(),
+            });
+            salvaged.root.remove("config_version");
+            migration::migrate(&mut salvaged.root)?
+        }
+    };
+
+    let config = salvage_semantics(salvaged, &mut rejected);
+    rejected.sort_by_key(|item| item.line.unwrap_or(usize::MAX));
+    Ok((config, rejected, migration))
+}
+
+// ---------------------------------------------------------------------------
+// Syntactic pass
+// ---------------------------------------------------------------------------
+
+/// A key/value statement, possibly spanning several lines (multi-line
+/// arrays, inline tables and triple-quoted strings).
+struct Statement {
+    line: usize,
+    text: String,
+}
+
+enum SectionKind {
+    Root,
+    Table(Vec<String>),
+    ArrayOfTables(Vec<String>),
+    /// A header line that could not be parsed. Its statements cannot be
+    /// attributed to any table and are all rejected.
+    Malformed,
+}
+
+struct Section {
+    kind: SectionKind,
+    line: usize,
+    statements: Vec<Statement>,
+}
+
+/// Parsed-but-not-yet-typed config plus the line each piece came from.
+struct Salvaged {
+    root: Table,
+    /// First line of each top-level key.
+    key_lines: std::collections::HashMap<String, usize>,
+    /// Header line of each `[[mappings]]` entry, in order.
+    mapping_lines: Vec<usize>,
+}
+
+fn salvage_syntax(content: &str, rejected: &mut Vec<RejectedConfigItem>) -> Salvaged {
+    let mut out = Salvaged {
+        root: Table::new(),
+        key_lines: std::collections::HashMap::new(),
+        mapping_lines: Vec::new(),
+    };
+
+    for section in split_sections(content) {
+        let mut entries = Table::new();
+        let location = match &section.kind {
+            SectionKind::Root => String::new(),
+            SectionKind::Table(path) => path.join("."),
+            SectionKind::ArrayOfTables(path) => {
+                let name = path.join(".");
+                let index = if name == "mappings" {
+                    out.mapping_lines.push(section.line);
+                    out.mapping_lines.len() - 1
+                } else {
+                    array_len(&out.root, path)
+                };
+                format!("{name}[{index}]")
+            }
+            SectionKind::Malformed => {
+                rejected.push(RejectedConfigItem {
+                    location: "table header".to_string(),
+                    line: Some(section.line),
+                    reason: "malformed table header".to_string(),
+                });
+                for stmt in &section.statements {
+                    rejected.push(RejectedConfigItem {
+                        location: statement_key(&stmt.text),
+                        line: Some(stmt.line),
+                        reason: format!(
+                            "belongs to the malformed table header on line {}",
+                            section.line
+                        ),
+                    });
+                }
+                continue;
+            }
+        };
+
+        for stmt in &section.statements {
+            let item_location = |key: &str| {
+                if location.is_empty() {
+                    key.to_string()
+                } else {
+                    format!("{location}.{key}")
+                }
+            };
+            match toml::from_str::<Table>(&stmt.text) {
+                Ok(parsed) => {
+                    for (key, value) in parsed {
+                        if matches!(section.kind, SectionKind::Root) {
+                            out.key_lines.entry(key.clone()).or_insert(stmt.line);
+                        }
+                        if let Err(reason) = merge_value(&mut entries, key.clone(), value) {
+                            rejected.push(RejectedConfigItem {
+                                location: item_location(&key),
+                                line: Some(stmt.line),
+                                reason,
+                            });
+                        }
+                    }
+                }
+                Err(e) => rejected.push(RejectedConfigItem {
+                    location: item_location(&statement_key(&stmt.text)),
+                    line: Some(stmt.line),
+                    reason: first_line(&e.to_string()),
+                }),
+            }
+        }
+
+        let placed = match section.kind {
+            // The root section always comes first, so its keys cannot
+            // collide with anything already placed.
+            SectionKind::Root => {
+                out.root = entries;
+                Ok(())
+            }
+            SectionKind::Table(ref path) => {
+                if let Some(first) = path.first() {
+                    out.key_lines.entry(first.clone()).or_insert(section.line);
+                }
+                place_table(&mut out.root, path, entries)
+            }
+            SectionKind::ArrayOfTables(ref path) => {
+                if let Some(first) = path.first() {
+                    out.key_lines.entry(first.clone()).or_insert(section.line);
+                }
+                place_array_entry(&mut out.root, path, entries)
+            }
+            SectionKind::Malformed => unreachable!(),
+        };
+        if let Err(reason) = placed {
+            rejected.push(RejectedConfigItem {
+                location,
+                line: Some(section.line),
+                reason,
+            });
+        }
+    }
+
+    out
+}
+
+/// Splits `content` into sections at table headers and each section into
+/// statements. Blank lines and comments are dropped.
+fn split_sections(content: &str) -> Vec<Section> {
+    let mut sections = vec![Section {
+        kind: SectionKind::Root,
+        line: 1,
+        statements: Vec::new(),
+    }];
+    let mut current: Option<Statement> = None;
+
+    for (idx, raw_line) in content.lines().enumerate() {
+        let line_no = idx + 1;
+
+        if let Some(stmt) = current.as_mut()
+            && statement_is_open(&stmt.text)
+        {
+            stmt.text.push('\n');
+            stmt.text.push_str(raw_line);
+            continue;
+        }
+        if let Some(stmt) = current.take() {
+            sections.last_mut().unwrap().statements.push(stmt);
+        }
+
+        let trimmed = raw_line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if trimmed.starts_with('[') {
+            sections.push(Section {
+                kind: parse_header(trimmed),
+                line: line_no,
+                statements: Vec::new(),
+            });
+            continue;
+        }
+        current = Some(Statement {
+            line: line_no,
+            text: raw_line.to_string(),
+        });
+    }
+    if let Some(stmt) = current {
+        sections.last_mut().unwrap().statements.push(stmt);
+    }
+    sections
+}
+
+/// Returns true while `text` ends inside a multi-line construct: an open
+/// triple-quoted string or an unbalanced array / inline table.
+fn statement_is_open(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    let mut i = 0;
+    let mut depth: i32 = 0;
+    let mut in_ml_basic = false;
+    let mut in_ml_literal = false;
+    let mut in_basic = false;
+    let mut in_literal = false;
+
+    while i < bytes.len() {
+        let rest = &bytes[i..];
+        let b = bytes[i];
+        if in_ml_basic {
+            if b == b'\\' {
+                i += 2;
+                continue;
+            }
+            if rest.starts_with(b"\"\"\"") {
+                in_ml_basic = false;
+                i += 3;
+                continue;
+            }
+        } else if in_ml_literal {
+            if rest.starts_with(b"'''") {
+                in_ml_literal = false;
+                i += 3;
+                continue;
+            }
+        } else if in_basic {
+            if b == b'\\' {
+                i += 2;
+                continue;
+            }
+            if b == b'"' || b == b'\n' {
+                in_basic = false;
+            }
+        } else if in_literal {
+            if b == b'\'' || b == b'\n' {
+                in_literal = false;
+            }
+        } else if rest.starts_with(b"\"\"\"") {
+            in_ml_basic = true;
+            i += 3;
+            continue;
+        } else if rest.starts_with(b"'''") {
+            in_ml_literal = true;
+            i += 3;
+            continue;
+        } else {
+            match b {
+                b'"' => in_basic = true,
+                b'\'' => in_literal = true,
+                b'[' | b'{' => depth += 1,
+                b']' | b'}' => depth -= 1,
+                b'#' => {
+                    // Comment runs to end of line.
+                    while i < bytes.len() && bytes[i] != b'\n' {
+                        i += 1;
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        i += 1;
+    }
+    in_ml_basic || in_ml_literal || depth > 0
+}
+
+fn parse_header(trimmed: &str) -> SectionKind {
+    let without_comment = match trimmed.find('#') {
+        Some(pos) => trimmed[..pos].trim_end(),
+        None => trimmed,
+    };
+    let (inner, array) = if let Some(inner) = without_comment
+        .strip_prefix("[[")
+        .and_then(|s| s.strip_suffix("]]"))
+    {
+        (inner, true)
+    } else if let Some(inner) = without_comment
+        .strip_prefix('[')
+        .and_then(|s| s.strip_suffix(']'))
+    {
+        (inner, false)
+    } else {
+        return SectionKind::Malformed;
+    };
+
+    let path: Vec<String> = inner
+        .split('.')
+        .map(|part| part.trim().trim_matches('"').to_string())
+        .collect();
+    let valid = path.iter().all(|part| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == ':')
+    });
+    if !valid {
+        return SectionKind::Malformed;
+    }
+    if array {
+        SectionKind::ArrayOfTables(path)
+    } else {
+        SectionKind::Table(path)
+    }
+}
+
+/// Best guess at the key a statement assigns, for error locations.
+fn statement_key(text: &str) -> String {
+    let head = text.split('=').next().unwrap_or("").trim();
+    if head.is_empty() || head.contains('\n') {
+        "<unknown>".to_string()
+    } else {
+        head.trim_matches('"').to_string()
+    }
+}
+
+fn first_line(message: &str) -> String {
+    message.lines().next().unwrap_or(message).trim().to_string()
+}
+
+/// Inserts `key = value` into `table`, merging nested tables so dotted
+/// keys (`a.b = 1`, `a.c = 2`) combine. Any other repeat is a duplicate.
+fn merge_value(table: &mut Table, key: String, value: Value) -> Result<(), String> {
+    match (table.get_mut(&key), value) {
+        (None, value) => {
+            table.insert(key, value);
+            Ok(())
+        }
+        (Some(Value::Table(existing)), Value::Table(incoming)) => {
+            for (k, v) in incoming {
+                merge_value(existing, k, v)?;
+            }
+            Ok(())
+        }
+        (Some(_), _) => Err("duplicate key; the first definition was kept".to_string()),
+    }
+}
+
+/// Walks `path[..len-1]` from `root`, creating tables as needed. Returns
+/// `None` if the path runs into a non-table value.
+fn walk_to_parent<'a>(root: &'a mut Table, path: &[String]) -> Option<&'a mut Table> {
+    let mut table = root;
+    for part in &path[..path.len().saturating_sub(1)] {
+        let entry = table
+            .entry(part.clone())
+            .or_insert_with(|| Value::Table(Table::new()));
+        table = match entry {
+            Value::Table(t) => t,
+            // `[[a]]` followed by `[a.b]` targets the last array element.
+            Value::Array(items) => match items.last_mut() {
+                Some(Value::Table(t)) => t,
+                _ => return None,
+            },
+            _ => return None,
+        };
+    }
+    Some(table)
+}
+
+fn place_table(root: &mut Table, path: &[String], entries: Table) -> Result<(), String> {
+    let conflict = || "conflicts with an earlier definition of this table".to_string();
+    let parent = walk_to_parent(root, path).ok_or_else(conflict)?;
+    let name = path.last().cloned().unwrap_or_default();
+    merge_value(parent, name, Value::Table(entries)).map_err(|_| conflict())
+}
+
+fn place_array_entry(root: &mut Table, path: &[String], entries: Table) -> Result<(), String> {
+    let conflict = || "conflicts with an earlier non-array definition".to_string();
+    let parent = walk_to_parent(root, path).ok_or_else(conflict)?;
+    let name = path.last().cloned().unwrap_or_default();
+    match parent
+        .entry(name)
+        .or_insert_with(|| Value::Array(Vec::new()))
+    {
+        Value::Array(items) => {
+            items.push(Value::Table(entries));
+            Ok(())
+        }
+        _ => Err(conflict()),
+    }
+}
+
+fn array_len(root: &Table, path: &[String]) -> usize {
+    let mut table = root;
+    for part in &path[..path.len().saturating_sub(1)] {
+        match table.get(part) {
+            Some(Value::Table(t)) => table = t,
+            _ => return 0,
+        }
+    }
+    match path.last().and_then(|name| table.get(name)) {
+        Some(Value::Array(items)) => items.len(),
+        _ => 0,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Semantic pass
+// ---------------------------------------------------------------------------
+
+/// Top-level fields without a serde default. They are seeded from
+/// `AppConfig::default()` so a file that lost one of them still loads.
+fn required_field_defaults() -> Table {
+    let defaults = AppConfig::default();
+    let mut table = Table::new();
+    table.insert(
+        "show_tray_icon".to_string(),
+        Value::Boolean(defaults.show_tray_icon),
+    );
+    table.insert(
+        "show_notifications".to_string(),
+        Value::Boolean(defaults.show_notifications),
+    );
+    table.insert("switch_key".to_string(), Value::String(defaults.switch_key));
+    table.insert("mappings".to_string(), Value::Array(Vec::new()));
+    table
+}
+
+fn salvage_semantics(salvaged: Salvaged, rejected: &mut Vec<RejectedConfigItem>) -> AppConfig {
+    let Salvaged {
+        mut root,
+        key_lines,
+        mapping_lines,
+    } = salvaged;
+
+    let mut working = required_field_defaults();
+
+    // Mappings are vetted one entry at a time so a single bad entry does
+    // not take the rest down with it.
+    let mappings = root.remove("mappings");
+    let has_mappings = mappings.is_some();
+    let mut accepted_mappings = Vec::new();
+    match mappings {
+        Some(Value::Array(items)) => {
+            for (idx, item) in items.into_iter().enumerate() {
+                match item.clone().try_into::<KeyMapping>() {
+                    Ok(_) => accepted_mappings.push(item),
+                    Err(e) => rejected.push(RejectedConfigItem {
+                        location: format!("mappings[{idx}]"),
+                        line: mapping_lines
+                            .get(idx)
+                            .copied()
+                            .or_else(|| key_lines.get("mappings").copied()),
+                        reason: describe_mapping_error(&item, &e.to_string()),
+                    }),
+                }
+            }
+        }
+        Some(_) => rejected.push(RejectedConfigItem {
+            location: "mappings".to_string(),
+            line: key_lines.get("mappings").copied(),
+            reason: "expected a list of [[mappings]] tables".to_string(),
+        }),
+        None => {}
+    }
+
+    // Globals are applied one key at a time on top of what has already
+    // been accepted; a key that breaks deserialization is dropped.
+    let mut keys: Vec<(String, Value)> = root.into_iter().collect();
+    keys.sort_by_key(|(k, _)| key_lines.get(k).copied().unwrap_or(usize::MAX));
+    for (key, value) in keys {
+        let mut candidate = working.clone();
+        candidate.insert(key.clone(), value);
+        match Value::Table(candidate.clone()).try_into::<AppConfig>() {
+            Ok(_) => working = candidate,
+            Err(e) => rejected.push(RejectedConfigItem {
+                line: key_lines.get(&key).copied(),
+                location: key,
+                reason: first_line(&e.to_string()),
+            }),
+        }
+    }
+
+    working.insert("mappings".to_string(), Value::Array(accepted_mappings));
+    let mut config = Value::Table(working)
+        .try_into::<AppConfig>()
+        .unwrap_or_default();
+    if !has_mappings {
+        config.mappings = AppConfig::default().mappings;
+    }
+    config
+}
+
+/// Prefixes a mapping error with its trigger so the user can find it.
+fn describe_mapping_error(item: &Value, message: &str) -> String {
+    let trigger = item
+        .get("trigger_sequence")
+        .or_else(|| item.get("trigger_key"))
+        .and_then(Value::as_str);
+    match trigger {
+        Some(t) => format!("trigger \"{}\": {}", t, first_line(message)),
+        None => first_line(message),
+    }
+}
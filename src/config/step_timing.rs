@@ -0,0 +1,132 @@
+//! Per-step timing of sequence targets.
+//!
+//! With `target_mode = 2` every step of `target_keys` can wait and hold
+//! for its own time instead of the mapping's `interval` and
+//! `event_duration`. A step sets them inline as `"KEY@DELAY/DURATION"`
+//! (`"A@40/20"`, `"A@40"`, `"A@/20"`), or the mapping lists them in
+//! `step_delays_ms` / `step_durations_ms`, one entry per step. Inline
+//! values win over the lists. The delay is the wait after the step,
+//! before the next one starts.
+
+use super::KeyMapping;
+
+/// Separator between a target key name and its inline timing.
+pub const STEP_TIMING_SEPARATOR: char = '@';
+
+/// Timing of one sequence step. `None` falls back to the mapping's
+/// `interval` or `event_duration`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StepTiming {
+    /// Wait after the step, in milliseconds.
+    pub delay_ms: Option<u64>,
+    /// How long the step is held, in milliseconds.
+    pub duration_ms: Option<u64>,
+}
+
+impl StepTiming {
+    /// Parses the `DELAY/DURATION` part of an inline timing. Either side
+    /// may be empty, and `/DURATION` may be left out.
+    pub fn parse(spec: &str) -> Option<Self> {
+        let (delay, duration) = spec.split_once('/').unwrap_or((spec, ""));
+        let number = |text: &str| match text.trim() {
+            "" => Some(None),
+            text => text.parse().ok().map(Some),
+        };
+        Some(Self {
+            delay_ms: number(delay)?,
+            duration_ms: number(duration)?,
+        })
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.delay_ms.is_none() && self.duration_ms.is_none()
+    }
+
+    /// Inline form of the timing, without the separator; empty for the
+    /// default timing.
+    pub fn to_spec(self) -> String {
+        let number = |value: Option<u64>| value.map(|v| v.to_string()).unwrap_or_default();
+        match self.duration_ms {
+            Some(_) => format!("{}/{}", number(self.delay_ms), number(self.duration_ms)),
+            None => number(self.delay_ms),
+        }
+    }
+}
+
+/// Splits a target entry into its key name and its inline timing, if it
+/// has one.
+pub fn split_step_timing(entry: &str) -> (&str, Option<&str>) {
+    match entry.split_once(STEP_TIMING_SEPARATOR) {
+        Some((name, spec)) => (name, Some(spec)),
+        None => (entry, None),
+    }
+}
+
+/// Target entry for `name` with `timing` written inline.
+pub fn with_step_timing(name: &str, timing: StepTiming) -> String {
+    if timing.is_default() {
+        name.to_string()
+    } else {
+        format!("{name}{STEP_TIMING_SEPARATOR}{}", timing.to_spec())
+    }
+}
+
+impl KeyMapping {
+    /// Whether inline step timings in `target_keys` take effect: only a
+    /// sequence target without held or appended keys plays step by step.
+    pub fn accepts_step_timings(&self) -> bool {
+        self.target_mode == 2
+            && self.hold_indices.as_ref().is_none_or(|v| v.is_empty())
+            && self.append_keys.as_ref().is_none_or(|v| v.is_empty())
+    }
+
+    /// Drops the inline timings from `target_keys` when the mapping
+    /// cannot use them, which `validate` would otherwise reject. The
+    /// settings dialog calls this whenever the target mode or the held
+    /// and appended keys change.
+    pub fn strip_unused_step_timings(&mut self) {
+        if self.accepts_step_timings() {
+            return;
+        }
+        for entry in self.target_keys.iter_mut() {
+            if let Some(pos) = entry.find(STEP_TIMING_SEPARATOR) {
+                entry.truncate(pos);
+            }
+        }
+    }
+
+    /// Timing of every target step, or `None` when the mapping is not a
+    /// sequence or no step sets its own. An inline timing that does not
+    /// parse counts as unset.
+    pub fn step_timings(&self) -> Option<Vec<StepTiming>> {
+        if self.target_mode != 2 {
+            return None;
+        }
+        let listed = |list: &Option<Vec<u64>>, idx: usize| {
+            list.as_ref().and_then(|list| list.get(idx).copied())
+        };
+        let timings: Vec<StepTiming> = self
+            .target_keys
+            .iter()
+            .enumerate()
+            .map(|(idx, entry)| {
+                let inline = split_step_timing(entry)
+                    .1
+                    .and_then(StepTiming::parse)
+                    .unwrap_or_default();
+                StepTiming {
+                    delay_ms: inline
+                        .delay_ms
+                        .or_else(|| listed(&self.step_delays_ms, idx)),
+                    duration_ms: inline
+                        .duration_ms
+                        .or_else(|| listed(&self.step_durations_ms, idx)),
+                }
+            })
+            .collect();
+        timings
+            .iter()
+            .any(|timing| !timing.is_default())
+            .then_some(timings)
+    }
+}
@@ -0,0 +1,35 @@
+//! Tap-hold triggers.
+//!
+//! A mapping with `hold_target_keys` sends `target_keys` when its trigger
+//! is tapped and holds `hold_target_keys` instead once the trigger stays
+//! down for `tapping_term_ms`, e.g. Caps Lock as Escape on a tap and Left
+//! Ctrl while held. With `permissive_hold = true` pressing any other input
+//! before the tapping term ends picks the hold right away, so a quick
+//! Caps Lock + C still sends Ctrl+C. A tap always fires once;
+//! `turbo_enabled` does not apply.
+
+use super::KeyMapping;
+
+/// Tapping term used when a tap-hold mapping sets none.
+pub const DEFAULT_TAPPING_TERM_MS: u64 = 200;
+
+/// Tap-hold settings of one mapping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TapHoldSettings<'a> {
+    /// Outputs held while the trigger is held.
+    pub hold_keys: &'a [String],
+    pub tapping_term_ms: u64,
+    pub permissive_hold: bool,
+}
+
+impl KeyMapping {
+    /// Tap-hold settings, or `None` when the mapping fires on press.
+    pub fn tap_hold(&self) -> Option<TapHoldSettings<'_>> {
+        let hold_keys = self.hold_target_keys.as_deref().filter(|k| !k.is_empty())?;
+        Some(TapHoldSettings {
+            hold_keys,
+            tapping_term_ms: self.tapping_term_ms.unwrap_or(DEFAULT_TAPPING_TERM_MS),
+            permissive_hold: self.permissive_hold,
+        })
+    }
+}
@@ -0,0 +1,116 @@
+//! Multi-tap and long-press triggers.
+//!
+//! `trigger_kind = "double_tap"` or `"triple_tap"` fires a mapping when
+//! its `trigger_key` is pressed that many times, each press starting
+//! within `tap_gap_ms` of the previous release. `"long_press"` fires once
+//! the trigger stays down for `long_press_ms`. The output is held until
+//! the trigger is released, so double-tapping W can hold Shift+W.
+//!
+//! Plain presses of the same trigger still fire its `"press"` mapping, or
+//! the trigger itself when it has none. `single_tap` decides when:
+//! `"immediate"` sends every press right away, `"deferred"` waits until
+//! the press turned out not to be part of a gesture, and `"suppressed"`
+//! drops it. The strictest setting among a trigger's gesture mappings
+//! applies.
+
+use serde::{Deserialize, Serialize};
+
+use super::KeyMapping;
+
+/// Tap gap used when a multi-tap mapping sets none.
+pub const DEFAULT_TAP_GAP_MS: u64 = 250;
+/// Hold time used when a long-press mapping sets none.
+pub const DEFAULT_LONG_PRESS_MS: u64 = 500;
+
+/// How the trigger of a mapping must be pressed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerKind {
+    /// Every press.
+    #[default]
+    Press,
+    /// Two presses in a row.
+    DoubleTap,
+    /// Three presses in a row.
+    TripleTap,
+    /// One press held for `long_press_ms`.
+    LongPress,
+}
+
+impl TriggerKind {
+    pub(super) fn is_press(&self) -> bool {
+        *self == TriggerKind::Press
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TriggerKind::Press => "press",
+            TriggerKind::DoubleTap => "double_tap",
+            TriggerKind::TripleTap => "triple_tap",
+            TriggerKind::LongPress => "long_press",
+        }
+    }
+
+    /// Presses in a row the kind needs.
+    pub fn taps(self) -> u8 {
+        match self {
+            TriggerKind::DoubleTap => 2,
+            TriggerKind::TripleTap => 3,
+            TriggerKind::Press | TriggerKind::LongPress => 1,
+        }
+    }
+}
+
+/// What happens to a plain press of a trigger that also has gestures.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum SingleTap {
+    /// Sent as soon as the trigger goes down.
+    #[default]
+    Immediate,
+    /// Sent once no gesture can match it any more.
+    Deferred,
+    /// Never sent.
+    Suppressed,
+}
+
+impl SingleTap {
+    pub(super) fn is_immediate(&self) -> bool {
+        *self == SingleTap::Immediate
+    }
+
+    pub(super) fn as_str(self) -> &'static str {
+        match self {
+            SingleTap::Immediate => "immediate",
+            SingleTap::Deferred => "deferred",
+            SingleTap::Suppressed => "suppressed",
+        }
+    }
+}
+
+/// Gesture settings of one mapping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GestureSettings {
+    pub kind: TriggerKind,
+    pub tap_gap_ms: u64,
+    pub long_press_ms: u64,
+    pub single_tap: SingleTap,
+}
+
+impl KeyMapping {
+    /// Gesture settings, or `None` when the mapping fires on every press.
+    /// Sequence triggers have no gestures.
+    pub fn gesture(&self) -> Option<GestureSettings> {
+        if self.trigger_kind.is_press() || self.is_sequence_trigger() {
+            return None;
+        }
+        Some(GestureSettings {
+            kind: self.trigger_kind,
+            tap_gap_ms: self.tap_gap_ms.unwrap_or(DEFAULT_TAP_GAP_MS),
+            long_press_ms: self.long_press_ms.unwrap_or(DEFAULT_LONG_PRESS_MS),
+            single_tap: self.single_tap,
+        })
+    }
+}
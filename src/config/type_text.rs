@@ -0,0 +1,31 @@
+//! Text typing targets.
+//!
+//! `type_text = "gg wp, 你好"` types a phrase instead of pressing
+//! `target_keys`. Characters found on the keyboard are sent as their key,
+//! with Shift where needed; everything else, CJK text included, is sent as
+//! Unicode input. `char_delay_ms` waits between characters. A text types
+//! once per press of the trigger, or `burst_count` times.
+
+use super::KeyMapping;
+
+/// Delay between typed characters when a mapping sets none.
+pub const DEFAULT_CHAR_DELAY_MS: u64 = 10;
+
+/// Text typing settings of one mapping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TypeTextSettings<'a> {
+    pub text: &'a str,
+    pub char_delay_ms: u64,
+}
+
+impl KeyMapping {
+    /// Text typing settings, or `None` when the mapping presses
+    /// `target_keys`.
+    pub fn type_text(&self) -> Option<TypeTextSettings<'_>> {
+        let text = self.type_text.as_deref().filter(|text| !text.is_empty())?;
+        Some(TypeTextSettings {
+            text,
+            char_delay_ms: self.char_delay_ms.unwrap_or(DEFAULT_CHAR_DELAY_MS),
+        })
+    }
+}
@@ -0,0 +1,786 @@
+//! Config validation.
+//!
+//! [`AppConfig::validate`] runs every mapping through the same parsers the
+//! runtime uses and reports each problem it finds, instead of stopping at
+//! the first bad name the way building the input mappings does. Callers
+//! decide what to do with the result: `create_input_mappings` refuses to
+//! start on errors, the settings dialog lists them next to the save
+//! button.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::sequence_matcher::{self, MAX_SEQUENCE_LENGTH};
+use crate::state::parsing;
+use crate::state::{InputDevice, OutputAction, Script};
+
+use super::{
+    AppConfig, DEFAULT_PROFILE, MAX_LAYERS, MappingLocation, SingleTap, StepTiming, TriggerKind,
+    split_step_timing,
+};
+
+/// How serious a [`ConfigDiagnostic`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DiagnosticSeverity {
+    /// The config loads, but part of it is ignored or will not behave as
+    /// written.
+    Warning,
+    /// The config cannot be applied as written.
+    Error,
+}
+
+impl DiagnosticSeverity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+}
+
+/// A single problem found by [`AppConfig::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigDiagnostic {
+    /// Index into `AppConfig::mappings`, or into the profile's `mappings`
+    /// when `location` names a profile; `None` for a global setting.
+    pub mapping_index: Option<usize>,
+    /// Where that mapping is written, which differs from `mapping_index`
+    /// once `include` pulls in other files.
+    pub location: Option<MappingLocation>,
+    /// Config key the problem belongs to, e.g. `target_keys`.
+    pub field: &'static str,
+    pub severity: DiagnosticSeverity,
+    pub message: String,
+}
+
+impl ConfigDiagnostic {
+    fn global(field: &'static str, severity: DiagnosticSeverity, message: String) -> Self {
+        Self {
+            mapping_index: None,
+            location: None,
+            field,
+            severity,
+            message,
+        }
+    }
+
+    fn mapping(
+        index: usize,
+        field: &'static str,
+        severity: DiagnosticSeverity,
+        message: String,
+    ) -> Self {
+        Self {
+            mapping_index: Some(index),
+            location: None,
+            field,
+            severity,
+            message,
+        }
+    }
+
+    #[inline]
+    pub fn is_error(&self) -> bool {
+        self.severity == DiagnosticSeverity::Error
+    }
+}
+
+impl std::fmt::Display for ConfigDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.location, self.mapping_index) {
+            (Some(location), _) => {
+                if let Some(file) = &location.file {
+                    write!(f, "{}: ", file.display())?;
+                }
+                if let Some(profile) = &location.profile {
+                    write!(f, "profiles[{profile}].")?;
+                }
+                write!(f, "mappings[{}].{}", location.index, self.field)?
+            }
+            (None, Some(idx)) => write!(f, "mappings[{}].{}", idx, self.field)?,
+            (None, None) => write!(f, "{}", self.field)?,
+        }
+        write!(f, ": {}: {}", self.severity.as_str(), self.message)
+    }
+}
+
+/// Hold positions are tracked in a 16-bit mask at runtime.
+const MAX_HOLD_INDEX: usize = 16;
+
+impl AppConfig {
+    /// Checks the config against the runtime parsers and returns every
+    /// problem found, globals first, then the top-level mappings in order,
+    /// then each profile's own mappings. An empty list means the config
+    /// applies exactly as written, whichever profile is active.
+    ///
+    /// Covered: unknown key names in `switch_key`, the profile hotkeys,
+    /// triggers, targets and `append_keys`; duplicate profile names and
+    /// references to unknown profiles; sequences that are too long or fail
+    /// to parse; `hold_indices` past the end of `target_keys`; `profiles`
+    /// naming unknown profiles; layers nothing activates or past
+    /// [`MAX_LAYERS`]; `hold_target_keys` that cannot be held; gesture,
+    /// toggle, burst, jitter and text settings without the mode they
+    /// belong to; macros that fail to parse; target keys a `macro` or
+    /// `type_text` replaces; step timings that do not parse or that a
+    /// mapping ignores; and mappings that never fire because a mapping
+    /// without conditions on the same trigger is tried first, including
+    /// across `include`d files.
+    pub fn validate(&self) -> Vec<ConfigDiagnostic> {
+        use DiagnosticSeverity::{Error, Warning};
+
+        let mut out = Vec::new();
+
+        if parsing::input_name_to_device(&self.switch_key).is_none() {
+            out.push(ConfigDiagnostic::global(
+                "switch_key",
+                Error,
+                format!("unknown key name \"{}\"", self.switch_key),
+            ));
+        }
+
+        for (field, name) in [
+            ("next_profile_key", &self.next_profile_key),
+            ("prev_profile_key", &self.prev_profile_key),
+        ] {
+            if !name.is_empty() && parsing::input_name_to_device(name).is_none() {
+                out.push(ConfigDiagnostic::global(
+                    field,
+                    Error,
+                    format!("unknown key name \"{name}\""),
+                ));
+            }
+        }
+
+        let mut profile_names = vec![DEFAULT_PROFILE];
+        for profile in &self.profiles {
+            if profile_names.contains(&profile.name.as_str()) {
+                out.push(ConfigDiagnostic::global(
+                    "profiles",
+                    Warning,
+                    format!(
+                        "profile name \"{}\" is already taken; this profile is unreachable",
+                        profile.name
+                    ),
+                ));
+            } else {
+                profile_names.push(&profile.name);
+            }
+        }
+        if !profile_names.contains(&self.active_profile.as_str()) {
+            out.push(ConfigDiagnostic::global(
+                "active_profile",
+                Warning,
+                format!(
+                    "unknown profile \"{}\"; \"{DEFAULT_PROFILE}\" is used instead",
+                    self.active_profile
+                ),
+            ));
+        }
+
+        for rule in &self.profile_rules {
+            if !profile_names.contains(&rule.profile.as_str()) {
+                out.push(ConfigDiagnostic::global(
+                    "profile_rules",
+                    Warning,
+                    format!(
+                        "rule for \"{}\" names unknown profile \"{}\"; it is ignored",
+                        rule.process, rule.profile
+                    ),
+                ));
+            }
+        }
+        if !self.fallback_profile.is_empty()
+            && !profile_names.contains(&self.fallback_profile.as_str())
+        {
+            out.push(ConfigDiagnostic::global(
+                "fallback_profile",
+                Warning,
+                format!(
+                    "unknown profile \"{}\"; it is ignored",
+                    self.fallback_profile
+                ),
+            ));
+        }
+
+        out.extend(self.mapping_diagnostics(&profile_names, &|idx| self.mapping_location(idx)));
+
+        // A profile's own mappings run alongside the top-level ones that
+        // opt into it, so they are checked in that company.
+        for &name in &profile_names[1..] {
+            let Some(resolved) = self.resolve_profile(name) else {
+                continue;
+            };
+            let inherited: Vec<usize> = (0..self.mappings.len())
+                .filter(|&idx| self.mappings[idx].in_profile(name))
+                .collect();
+            let locate = |idx: usize| match inherited.get(idx) {
+                Some(&idx) => self.mapping_location(idx),
+                None => MappingLocation {
+                    file: None,
+                    profile: Some(name.to_string()),
+                    index: idx - inherited.len(),
+                },
+            };
+            // Inherited mappings were reported above.
+            out.extend(
+                resolved
+                    .mapping_diagnostics(&profile_names, &locate)
+                    .into_iter()
+                    .filter_map(|mut diagnostic| {
+                        let idx = diagnostic.mapping_index?.checked_sub(inherited.len())?;
+                        diagnostic.mapping_index = Some(idx);
+                        Some(diagnostic)
+                    }),
+            );
+        }
+        out
+    }
+
+    /// The per-mapping part of [`validate`](Self::validate) over
+    /// `self.mappings`. `locate` names where mapping `idx` is written.
+    fn mapping_diagnostics(
+        &self,
+        profile_names: &[&str],
+        locate: &dyn Fn(usize) -> MappingLocation,
+    ) -> Vec<ConfigDiagnostic> {
+        use DiagnosticSeverity::{Error, Warning};
+
+        let mut out = Vec::new();
+
+        // Trigger device -> every mapping registered for it, as in
+        // `create_input_mappings`.
+        let mut candidates: HashMap<InputDevice, Vec<usize>> = HashMap::new();
+        let layer_names = self.layer_names();
+        let activated_layers: Vec<&str> = self
+            .mappings
+            .iter()
+            .filter_map(|m| m.layer_switch().map(|(name, _)| name))
+            .collect();
+
+        for (idx, mapping) in self.mappings.iter().enumerate() {
+            let trigger_field = if mapping.is_sequence_trigger() {
+                "trigger_sequence"
+            } else {
+                "trigger_key"
+            };
+
+            let trigger = match &mapping.trigger_sequence {
+                Some(seq) => {
+                    let parts: Vec<&str> = seq.split(',').map(str::trim).collect();
+                    // Sequence mappings are keyed by the whole sequence.
+                    let sequence = if parts.len() > MAX_SEQUENCE_LENGTH {
+                        out.push(ConfigDiagnostic::mapping(
+                            idx,
+                            trigger_field,
+                            Warning,
+                            format!(
+                                "sequence has {} inputs but at most {} are supported; it never fires",
+                                parts.len(),
+                                MAX_SEQUENCE_LENGTH
+                            ),
+                        ));
+                        None
+                    } else {
+                        match sequence_matcher::parse_sequence_string(seq, None) {
+                            Ok(sequence) => Some(sequence.identity()),
+                            Err(e) => {
+                                out.push(ConfigDiagnostic::mapping(
+                                    idx,
+                                    trigger_field,
+                                    Warning,
+                                    format!("sequence is not registered: {e}"),
+                                ));
+                                None
+                            }
+                        }
+                    };
+                    let last = parts.last().copied().unwrap_or_default();
+                    if parsing::input_name_to_device(last).is_none() {
+                        out.push(ConfigDiagnostic::mapping(
+                            idx,
+                            trigger_field,
+                            Error,
+                            format!("unknown key name \"{last}\" at the end of the sequence"),
+                        ));
+                    }
+                    sequence
+                }
+                None => {
+                    let device = parsing::input_name_to_device(&mapping.trigger_key);
+                    if device.is_none() {
+                        out.push(ConfigDiagnostic::mapping(
+                            idx,
+                            trigger_field,
+                            Error,
+                            format!("unknown key name \"{}\"", mapping.trigger_key),
+                        ));
+                    }
+                    device
+                }
+            };
+
+            let mut target_keys = mapping.get_target_keys();
+            let layer_switch = mapping.layer_switch();
+            if layer_switch.is_some() && !target_keys.is_empty() {
+                out.push(ConfigDiagnostic::mapping(
+                    idx,
+                    "target_keys",
+                    Warning,
+                    "ignored on a mapping with activate_layer".to_string(),
+                ));
+            }
+            // A layer switch wins over a macro, and a macro over a text.
+            let script = mapping.macro_script().filter(|_| layer_switch.is_none());
+            let text = mapping
+                .type_text()
+                .filter(|_| layer_switch.is_none() && script.is_none());
+            for (field, set, kept) in [
+                ("macro", mapping.macro_script().is_some(), script.is_some()),
+                ("type_text", mapping.type_text().is_some(), text.is_some()),
+            ] {
+                if set && !kept {
+                    let by = if layer_switch.is_some() {
+                        "activate_layer"
+                    } else {
+                        "macro"
+                    };
+                    out.push(ConfigDiagnostic::mapping(
+                        idx,
+                        field,
+                        Warning,
+                        format!("ignored on a mapping with {by}"),
+                    ));
+                }
+            }
+            let replaced_by = if script.is_some() {
+                Some("macro")
+            } else if text.is_some() {
+                Some("type_text")
+            } else {
+                None
+            };
+            if let Some(by) = replaced_by
+                && !target_keys.is_empty()
+            {
+                out.push(ConfigDiagnostic::mapping(
+                    idx,
+                    "target_keys",
+                    Warning,
+                    format!("ignored on a mapping with {by}"),
+                ));
+                // The runtime never parses them.
+                target_keys = &[];
+            }
+            if let Some(source) = script
+                && let Err(err) = Script::parse(source)
+            {
+                out.push(ConfigDiagnostic::mapping(
+                    idx,
+                    "macro",
+                    Error,
+                    err.to_string(),
+                ));
+            }
+            if mapping.char_delay_ms.is_some() && mapping.type_text().is_none() {
+                out.push(ConfigDiagnostic::mapping(
+                    idx,
+                    "char_delay_ms",
+                    Warning,
+                    "ignored without type_text".to_string(),
+                ));
+            }
+            if target_keys.is_empty() && layer_switch.is_none() && replaced_by.is_none() {
+                out.push(ConfigDiagnostic::mapping(
+                    idx,
+                    "target_keys",
+                    Warning,
+                    "no target keys; the mapping is ignored".to_string(),
+                ));
+            }
+            let mut has_inline_timing = false;
+            for entry in target_keys {
+                let (name, spec) = split_step_timing(entry);
+                if parsing::input_name_to_output(name).is_none() {
+                    out.push(ConfigDiagnostic::mapping(
+                        idx,
+                        "target_keys",
+                        Error,
+                        format!("unknown key name \"{name}\""),
+                    ));
+                }
+                if let Some(spec) = spec {
+                    has_inline_timing = true;
+                    if StepTiming::parse(spec).is_none() {
+                        out.push(ConfigDiagnostic::mapping(
+                            idx,
+                            "target_keys",
+                            Error,
+                            format!(
+                                "invalid step timing in \"{entry}\"; expected KEY@DELAY/DURATION"
+                            ),
+                        ));
+                    }
+                }
+            }
+            // Step timings only shape a plain sequence target.
+            let timing_ignored_by = if layer_switch.is_some() {
+                Some("on a mapping with activate_layer".to_string())
+            } else if let Some(by) = replaced_by {
+                Some(format!("on a mapping with {by}"))
+            } else if mapping.target_mode != 2 {
+                Some("unless target_mode = 2".to_string())
+            } else if mapping.hold_indices.as_ref().is_some_and(|v| !v.is_empty())
+                || mapping.append_keys.as_ref().is_some_and(|v| !v.is_empty())
+            {
+                Some("on a mapping with hold_indices or append_keys".to_string())
+            } else {
+                None
+            };
+            if let Some(by) = &timing_ignored_by
+                && has_inline_timing
+            {
+                // Without a layer switch, macro or text the target keys
+                // are sent, so a timing on them must not be dropped
+                // silently.
+                if layer_switch.is_none() && replaced_by.is_none() {
+                    out.push(ConfigDiagnostic::mapping(
+                        idx,
+                        "target_keys",
+                        Error,
+                        format!("step timings are not allowed {by}"),
+                    ));
+                } else {
+                    out.push(ConfigDiagnostic::mapping(
+                        idx,
+                        "target_keys",
+                        Warning,
+                        format!("step timings are ignored {by}"),
+                    ));
+                }
+            }
+            for (field, list) in [
+                ("step_delays_ms", &mapping.step_delays_ms),
+                ("step_durations_ms", &mapping.step_durations_ms),
+            ] {
+                let Some(list) = list else { continue };
+                if let Some(by) = &timing_ignored_by {
+                    out.push(ConfigDiagnostic::mapping(
+                        idx,
+                        field,
+                        Warning,
+                        format!("ignored {by}"),
+                    ));
+                } else if list.len() > target_keys.len() {
+                    out.push(ConfigDiagnostic::mapping(
+                        idx,
+                        field,
+                        Warning,
+                        format!(
+                            "{} entries for {} target keys; the extra ones are ignored",
+                            list.len(),
+                            target_keys.len()
+                        ),
+                    ));
+                }
+            }
+
+            if let Some(indices) = &mapping.hold_indices {
+                let limit = target_keys.len().min(MAX_HOLD_INDEX);
+                for &i in indices {
+                    if usize::from(i) >= limit {
+                        out.push(ConfigDiagnostic::mapping(
+                            idx,
+                            "hold_indices",
+                            Warning,
+                            format!(
+                                "index {} is out of range (valid: 0..{}); it is ignored",
+                                i, limit
+                            ),
+                        ));
+                    }
+                }
+            }
+
+            if let Some(names) = &mapping.append_keys {
+                for name in names {
+                    if parsing::input_name_to_output(name).is_none() {
+                        out.push(ConfigDiagnostic::mapping(
+                            idx,
+                            "append_keys",
+                            Warning,
+                            format!("unknown key name \"{name}\"; it is ignored"),
+                        ));
+                    }
+                }
+            }
+
+            match mapping.tap_hold() {
+                Some(hold) => {
+                    for name in hold.hold_keys {
+                        let message = match parsing::input_name_to_output(name) {
+                            None => format!("unknown key name \"{name}\""),
+                            Some(
+                                OutputAction::KeyboardKey(_)
+                                | OutputAction::KeyCombo(_)
+                                | OutputAction::MouseButton(_),
+                            ) => continue,
+                            Some(_) => {
+                                format!("\"{name}\" cannot be held; use keys or mouse buttons")
+                            }
+                        };
+                        out.push(ConfigDiagnostic::mapping(
+                            idx,
+                            "hold_target_keys",
+                            Error,
+                            message,
+                        ));
+                    }
+                    if layer_switch.is_some() {
+                        out.push(ConfigDiagnostic::mapping(
+                            idx,
+                            "hold_target_keys",
+                            Warning,
+                            "ignored on a mapping with activate_layer".to_string(),
+                        ));
+                    }
+                }
+                None => {
+                    for (field, set) in [
+                        ("tapping_term_ms", mapping.tapping_term_ms.is_some()),
+                        ("permissive_hold", mapping.permissive_hold),
+                    ] {
+                        if set {
+                            out.push(ConfigDiagnostic::mapping(
+                                idx,
+                                field,
+                                Warning,
+                                "ignored without hold_target_keys".to_string(),
+                            ));
+                        }
+                    }
+                }
+            }
+
+            let gesture = mapping.gesture();
+            if mapping.is_sequence_trigger() && mapping.trigger_kind != TriggerKind::Press {
+                out.push(ConfigDiagnostic::mapping(
+                    idx,
+                    "trigger_kind",
+                    Warning,
+                    "ignored on a sequence trigger".to_string(),
+                ));
+            }
+            // A wheel notch is pressed and released at once.
+            let wheel_trigger = trigger.as_ref().is_some_and(InputDevice::is_wheel_trigger);
+            if wheel_trigger {
+                for (field, set) in [
+                    ("trigger_kind", gesture.is_some()),
+                    ("hold_target_keys", mapping.tap_hold().is_some()),
+                ] {
+                    if set {
+                        out.push(ConfigDiagnostic::mapping(
+                            idx,
+                            field,
+                            Warning,
+                            "ignored on a wheel trigger".to_string(),
+                        ));
+                    }
+                }
+            } else if gesture.is_some() && mapping.tap_hold().is_some() {
+                out.push(ConfigDiagnostic::mapping(
+                    idx,
+                    "hold_target_keys",
+                    Warning,
+                    "ignored on a mapping with trigger_kind".to_string(),
+                ));
+            }
+            let kind = gesture.map(|g| g.kind);
+            for (field, set, message) in [
+                (
+                    "tap_gap_ms",
+                    mapping.tap_gap_ms.is_some() && kind.is_none_or(|k| k.taps() < 2),
+                    "ignored unless trigger_kind is \"double_tap\" or \"triple_tap\"",
+                ),
+                (
+                    "long_press_ms",
+                    mapping.long_press_ms.is_some() && kind != Some(TriggerKind::LongPress),
+                    "ignored unless trigger_kind is \"long_press\"",
+                ),
+                (
+                    "single_tap",
+                    mapping.single_tap != SingleTap::Immediate && kind.is_none(),
+                    "ignored without trigger_kind",
+                ),
+            ] {
+                if set {
+                    out.push(ConfigDiagnostic::mapping(
+                        idx,
+                        field,
+                        Warning,
+                        message.to_string(),
+                    ));
+                }
+            }
+
+            if mapping.toggle().is_some() && layer_switch.is_some() {
+                out.push(ConfigDiagnostic::mapping(
+                    idx,
+                    "activation",
+                    Warning,
+                    "ignored on a mapping with activate_layer; use layer_mode".to_string(),
+                ));
+            }
+            if mapping.auto_stop_ms.is_some() && mapping.toggle().is_none() {
+                out.push(ConfigDiagnostic::mapping(
+                    idx,
+                    "auto_stop_ms",
+                    Warning,
+                    "ignored unless activation is \"toggle\"".to_string(),
+                ));
+            }
+
+            if mapping.burst().is_some() {
+                let conflict = if layer_switch.is_some() {
+                    Some("activate_layer")
+                } else if mapping.tap_hold().is_some() {
+                    Some("hold_target_keys")
+                } else {
+                    None
+                };
+                if let Some(conflict) = conflict {
+                    out.push(ConfigDiagnostic::mapping(
+                        idx,
+                        "burst_count",
+                        Warning,
+                        format!("ignored on a mapping with {}", conflict),
+                    ));
+                } else if mapping.toggle().is_some() {
+                    out.push(ConfigDiagnostic::mapping(
+                        idx,
+                        "activation",
+                        Warning,
+                        "ignored with burst_count; a burst stops by itself".to_string(),
+                    ));
+                }
+            } else if mapping.burst_count == Some(0) {
+                out.push(ConfigDiagnostic::mapping(
+                    idx,
+                    "burst_count",
+                    Warning,
+                    "0 sends no burst; the output runs while the trigger is held".to_string(),
+                ));
+            } else if mapping.cancel_burst_on_release {
+                out.push(ConfigDiagnostic::mapping(
+                    idx,
+                    "cancel_burst_on_release",
+                    Warning,
+                    "ignored without burst_count".to_string(),
+                ));
+            }
+
+            if mapping.jitter().is_none() && mapping.jitter_distribution != Default::default() {
+                out.push(ConfigDiagnostic::mapping(
+                    idx,
+                    "jitter_distribution",
+                    Warning,
+                    "ignored without interval_jitter_ms or duration_jitter_ms".to_string(),
+                ));
+            }
+
+            if let Some(names) = &mapping.profiles {
+                for name in names {
+                    if !profile_names.contains(&name.as_str()) {
+                        out.push(ConfigDiagnostic::mapping(
+                            idx,
+                            "profiles",
+                            Warning,
+                            format!("unknown profile \"{name}\"; it is ignored"),
+                        ));
+                    }
+                }
+            }
+
+            for (field, name) in [
+                ("layer", mapping.layer_condition()),
+                ("activate_layer", layer_switch.map(|(name, _)| name)),
+            ] {
+                let Some(name) = name else { continue };
+                if !layer_names.contains(&name) {
+                    out.push(ConfigDiagnostic::mapping(
+                        idx,
+                        field,
+                        Warning,
+                        format!(
+                            "more than {MAX_LAYERS} layers; \"{name}\" is ignored and the mapping never fires"
+                        ),
+                    ));
+                } else if field == "layer" && !activated_layers.contains(&name) {
+                    out.push(ConfigDiagnostic::mapping(
+                        idx,
+                        field,
+                        Warning,
+                        format!("no mapping activates layer \"{name}\"; the mapping never fires"),
+                    ));
+                }
+            }
+
+            // Mappings without targets are skipped before registration,
+            // so they cannot take a trigger from anything.
+            if let Some(device) = trigger
+                && (!target_keys.is_empty() || layer_switch.is_some() || replaced_by.is_some())
+            {
+                // Gestures are keyed apart from plain presses, as at
+                // runtime.
+                let device = match kind {
+                    Some(kind) => InputDevice::Gesture(Arc::new(device), kind),
+                    None => device,
+                };
+                candidates.entry(device).or_default().push(idx);
+            }
+        }
+
+        // Candidates are tried in the runtime's order; one without
+        // conditions always matches and hides everything after it.
+        for indices in candidates.values_mut() {
+            indices.sort_by_key(|&idx| {
+                let mapping = &self.mappings[idx];
+                (
+                    Reverse(mapping.priority),
+                    Reverse(mapping.layer_condition().is_some()),
+                    Reverse(idx),
+                )
+            });
+            let Some(first) = indices
+                .iter()
+                .position(|&idx| !self.mappings[idx].has_conditions())
+            else {
+                continue;
+            };
+            for &shadowed in &indices[first + 1..] {
+                let field = if self.mappings[shadowed].is_sequence_trigger() {
+                    "trigger_sequence"
+                } else {
+                    "trigger_key"
+                };
+                out.push(ConfigDiagnostic::mapping(
+                    shadowed,
+                    field,
+                    Warning,
+                    format!(
+                        "never fires: {} resolves to the same trigger, is tried first and has no conditions",
+                        locate(indices[first])
+                    ),
+                ));
+            }
+        }
+
+        for diagnostic in &mut out {
+            diagnostic.location = diagnostic.mapping_index.map(locate);
+        }
+        // Shadowing is reported after all mappings are seen; keep the
+        // output in mapping order.
+        out.sort_by_key(|d| d.mapping_index);
+        out
+    }
+}
@@ -0,0 +1,289 @@
+//! Hot reload of `Config.toml` after external edits.
+//!
+//! A background thread polls the size and modification time of the file
+//! and of every file it `include`s. Once a change has settled for
+//! [`DEBOUNCE`], the config is loaded and validated like a normal start-up
+//! load and handed to [`AppState::reload_config`]. A config that fails to
+//! load or validate is reported through a notification and the running
+//! config stays live. Content identical to the app's own last save is
+//! skipped.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::config::AppConfig;
+use crate::state::{AppState, NotificationEvent};
+
+/// How often the file's metadata is checked.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// How long the file must stay unchanged before it is reloaded. Editors
+/// often write in several steps (truncate, write, rename).
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Size and modification time; `None` while the file is missing.
+type FileStamp = Option<(u64, SystemTime)>;
+
+#[derive(Debug, PartialEq)]
+enum ReloadOutcome {
+    Reloaded,
+    /// The file holds what the app itself last saved.
+    OwnWrite,
+    Failed(String),
+}
+
+pub struct ConfigWatcher {
+    state: Arc<AppState>,
+    path: PathBuf,
+    /// `path` followed by every file it includes, with the stamp each had
+    /// when last checked.
+    files: Vec<(PathBuf, FileStamp)>,
+    /// Files changed since the last reload attempt.
+    changed: Vec<PathBuf>,
+    /// When the current unsettled change was first seen.
+    pending_since: Option<Instant>,
+}
+
+impl ConfigWatcher {
+    pub fn new(state: Arc<AppState>, path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let files = watched_files(&path, &state.current_config().included_files);
+        Self {
+            state,
+            path,
+            files,
+            changed: Vec::new(),
+            pending_since: None,
+        }
+    }
+
+    /// Runs the watcher on its own thread until the app exits.
+    pub fn spawn(mut self) -> JoinHandle<()> {
+        thread::spawn(move || {
+            while !self.state.should_exit() {
+                thread::sleep(POLL_INTERVAL);
+                self.poll();
+            }
+        })
+    }
+
+    /// One polling step. Returns the outcome when a reload was attempted.
+    fn poll(&mut self) -> Option<ReloadOutcome> {
+        let now = self.state.clock().now();
+        let (mut touched, mut missing) = (false, false);
+        for (path, stamp) in &mut self.files {
+            let current = file_stamp(path);
+            if current != *stamp {
+                *stamp = current;
+                touched = true;
+                missing |= current.is_none();
+                if !self.changed.contains(path) {
+                    self.changed.push(path.clone());
+                }
+            }
+        }
+        if touched {
+            // A missing file is usually an editor mid-save; wait for it
+            // to come back rather than reporting an error.
+            self.pending_since = (!missing).then_some(now);
+            return None;
+        }
+        let since = self.pending_since?;
+        if now.duration_since(since) < DEBOUNCE {
+            return None;
+        }
+        self.pending_since = None;
+
+        let outcome = self.reload();
+        if let Some(sender) = self.state.get_notification_sender() {
+            match &outcome {
+                ReloadOutcome::Reloaded => {
+                    let _ =
+                        sender.send(NotificationEvent::Info("Config.toml reloaded".to_string()));
+                }
+                ReloadOutcome::Failed(e) => {
+                    let _ = sender.send(NotificationEvent::Error(format!(
+                        "Config.toml not reloaded, keeping the current config: {}",
+                        e
+                    )));
+                }
+                ReloadOutcome::OwnWrite => {}
+            }
+        }
+        Some(outcome)
+    }
+
+    fn reload(&mut self) -> ReloadOutcome {
+        let changed = std::mem::take(&mut self.changed);
+        if changed.iter().all(|path| {
+            fs::read_to_string(path).is_ok_and(|content| AppConfig::is_own_write(path, &content))
+        }) {
+            return ReloadOutcome::OwnWrite;
+        }
+
+        let mut config = match AppConfig::load_from_file(&self.path) {
+            Ok(config) => config,
+            Err(e) => return ReloadOutcome::Failed(e.to_string()),
+        };
+        let errors: Vec<String> = config
+            .validate()
+            .into_iter()
+            .filter(|d| d.is_error())
+            .map(|d| d.to_string())
+            .collect();
+        if !errors.is_empty() {
+            return ReloadOutcome::Failed(errors.join("\n"));
+        }
+
+        // Stay on the profile in use if the new file still has it.
+        let active = self.state.active_profile_name();
+        if config.profile_names().contains(&active.as_str()) {
+            config.active_profile = active;
+        }
+
+        let files = watched_files(&self.path, &config.included_files);
+        match self.state.reload_config(config) {
+            Ok(()) => {
+                self.files = files;
+                ReloadOutcome::Reloaded
+            }
+            Err(e) => ReloadOutcome::Failed(e.to_string()),
+        }
+    }
+}
+
+fn watched_files(path: &Path, included: &[PathBuf]) -> Vec<(PathBuf, FileStamp)> {
+    std::iter::once(path)
+        .chain(included.iter().map(PathBuf::as_path))
+        .map(|p| (p.to_path_buf(), file_stamp(p)))
+        .collect()
+}
+
+fn file_stamp(path: &Path) -> FileStamp {
+    let meta = fs::metadata(path).ok()?;
+    Some((meta.len(), meta.modified().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::VirtualClock;
+    use crate::state::output::RecordingOutputSink;
+
+    fn test_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("sorahk_watch_{}_{}.toml", name, std::process::id()))
+    }
+
+    fn watcher(name: &str) -> (ConfigWatcher, Arc<VirtualClock>, PathBuf) {
+        let path = test_path(name);
+        AppConfig::default().save_to_file(&path).unwrap();
+        let clock = Arc::new(VirtualClock::new());
+        let state = AppState::with_backends(
+            AppConfig::default(),
+            Arc::new(RecordingOutputSink::new()),
+            clock.clone(),
+        )
+        .unwrap();
+        (ConfigWatcher::new(Arc::new(state), &path), clock, path)
+    }
+
+    #[test]
+    fn test_external_edit_is_reloaded_after_debounce() {
+        let (mut watcher, clock, path) = watcher("reload");
+        let config = AppConfig {
+            switch_key: "F12".to_string(),
+            ..Default::default()
+        };
+        config.save_to_file(&path).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        // The size changes, so the edit is seen even when the
+        // modification time has not ticked.
+        fs::write(&path, format!("{content}\n# edited by hand\n")).unwrap();
+
+        assert_eq!(watcher.poll(), None);
+        clock.advance(DEBOUNCE / 2);
+        assert_eq!(watcher.poll(), None);
+        clock.advance(DEBOUNCE);
+        assert_eq!(watcher.poll(), Some(ReloadOutcome::Reloaded));
+        assert_eq!(watcher.state.config_generation(), 1);
+        assert_eq!(watcher.state.current_config().switch_key, "F12");
+        assert_eq!(watcher.poll(), None);
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_invalid_edit_keeps_current_config() {
+        let (mut watcher, clock, path) = watcher("invalid");
+        fs::write(&path, "switch_key = \"NOT_A_KEY\"\nmappings = []\n").unwrap();
+
+        assert_eq!(watcher.poll(), None);
+        clock.advance(DEBOUNCE);
+        assert!(matches!(watcher.poll(), Some(ReloadOutcome::Failed(_))));
+        assert_eq!(watcher.state.config_generation(), 0);
+
+        fs::write(&path, "broken = = 1\n").unwrap();
+        assert_eq!(watcher.poll(), None);
+        clock.advance(DEBOUNCE);
+        assert!(matches!(watcher.poll(), Some(ReloadOutcome::Failed(_))));
+        assert_eq!(watcher.state.config_generation(), 0);
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_own_save_is_ignored() {
+        let (mut watcher, clock, path) = watcher("own_write");
+        let mut config = AppConfig::default();
+        config.dark_mode = !config.dark_mode;
+        config.mappings.clear();
+        config.save_to_file(&path).unwrap();
+
+        assert_eq!(watcher.poll(), None);
+        clock.advance(DEBOUNCE);
+        assert_eq!(watcher.poll(), Some(ReloadOutcome::OwnWrite));
+        assert_eq!(watcher.state.config_generation(), 0);
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_included_file_edit_is_reloaded() {
+        let path = test_path("include_main");
+        let pack = test_path("include_pack");
+        fs::write(
+            &pack,
+            "[[mappings]]\ntrigger_key = \"F1\"\ntarget_keys = [\"A\"]\n",
+        )
+        .unwrap();
+        let mut config = AppConfig::default();
+        config.mappings.clear();
+        config.include = vec![pack.file_name().unwrap().to_string_lossy().into_owned()];
+        config.save_to_file(&path).unwrap();
+
+        let config = AppConfig::load_from_file(&path).unwrap();
+        let clock = Arc::new(VirtualClock::new());
+        let state =
+            AppState::with_backends(config, Arc::new(RecordingOutputSink::new()), clock.clone())
+                .unwrap();
+        let mut watcher = ConfigWatcher::new(Arc::new(state), &path);
+
+        fs::write(
+            &pack,
+            "[[mappings]]\ntrigger_key = \"F12\"\ntarget_keys = [\"A\"]\n",
+        )
+        .unwrap();
+        assert_eq!(watcher.poll(), None);
+        clock.advance(DEBOUNCE);
+        assert_eq!(watcher.poll(), Some(ReloadOutcome::Reloaded));
+        assert_eq!(
+            watcher.state.current_config().mappings[0].trigger_key,
+            "F12"
+        );
+
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(&pack);
+    }
+}
@@ -1,5 +1,7 @@
-//! Error dialog for displaying critical errors.
+//! Error dialogs: the standalone window for fatal startup errors and the
+//! in-app notice shown after the config was recovered or upgraded.
 
+use crate::config::ConfigLoadReport;
 use crate::gui::theme::{self, ThemeCache};
 use crate::gui::widgets::{self, ButtonKind, text_size};
 use crate::gui::fonts;
@@ -120,3 +122,194 @@ pub fn show_error(error_msg: &str) -> anyhow::Result<()> {
     )
     .map_err(|e| anyhow::anyhow!("Failed to show error dialog: {}", e))
 }
+
+/// Renders the in-app notice shown after the config file was recovered or
+/// upgraded at startup.
+///
+/// Unlike [`show_error`] this runs inside the main window: neither case
+/// blocks startup, the dialog only tells the user where the original file
+/// went and what was dropped or changed. Closing the dialog clears `report`.
+pub fn render_config_load_dialog(
+    ctx: &egui::Context,
+    dark_mode: bool,
+    report: &mut Option<ConfigLoadReport>,
+    translations: &CachedTranslations,
+) {
+    let Some(loaded) = report.as_ref() else {
+        return;
+    };
+    let t = translations;
+    let c = theme::colors(dark_mode);
+    let mut close = false;
+
+    let migration = loaded.migration.as_ref().filter(|m| !m.changes.is_empty());
+    let title = if loaded.recovery.is_some() {
+        t.config_recovery_title()
+    } else {
+        t.config_migration_title()
+    };
+
+    egui::Window::new("config_load_notice")
+        .id(egui::Id::new("config_load_dialog_window"))
+        .title_bar(false)
+        .collapsible(false)
+        .resizable(false)
+        .fixed_size([520.0, 420.0])
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .frame(
+            egui::Frame::window(&ctx.style())
+                .fill(c.bg_card)
+                .corner_radius(egui::CornerRadius::same(widgets::radius::DIALOG))
+                .stroke(egui::Stroke::NONE)
+                .shadow(egui::epaint::Shadow {
+                    offset: [0, 5],
+                    blur: 22,
+                    spread: 2,
+                    color: theme::overlay::SHADOW_HEAVY,
+                }),
+        )
+        .show(ctx, |ui| {
+            ui.add_space(15.0);
+            ui.vertical_centered(|ui| {
+                ui.label(
+                    egui::RichText::new(title)
+                        .size(text_size::TITLE)
+                        .color(c.accent_warning)
+                        .strong(),
+                );
+            });
+            ui.add_space(12.0);
+
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .show(ui, |ui| {
+                    if let Some(recovery) = &loaded.recovery {
+                        render_notice_section(
+                            ui,
+                            dark_mode,
+                            t.config_recovery_intro(),
+                            &recovery.backup_path,
+                            t,
+                            |ui| {
+                                notice_entry(ui, dark_mode, recovery.error.trim_end());
+                                ui.add_space(6.0);
+                                if recovery.rejected.is_empty() {
+                                    notice_text(ui, dark_mode, t.config_recovery_nothing_skipped());
+                                    return;
+                                }
+                                notice_label(ui, dark_mode, t.config_recovery_skipped_label());
+                                for item in &recovery.rejected {
+                                    notice_entry(ui, dark_mode, &item.to_string());
+                                }
+                            },
+                        );
+                    }
+
+                    if let Some(migration) = migration {
+                        if loaded.recovery.is_some() {
+                            ui.add_space(12.0);
+                        }
+                        let backup = migration
+                            .backup_path
+                            .as_deref()
+                            .or(loaded.recovery.as_ref().map(|r| r.backup_path.as_path()));
+                        let intro = t.config_migration_intro();
+                        let body = |ui: &mut egui::Ui| {
+                            notice_label(ui, dark_mode, t.config_migration_changes_label());
+                            notice_entry(
+                                ui,
+                                dark_mode,
+                                &format!(
+                                    "config_version {} → {}",
+                                    migration.from_version, migration.to_version
+                                ),
+                            );
+                            for change in &migration.changes {
+                                notice_entry(ui, dark_mode, change);
+                            }
+                        };
+                        match backup {
+                            // The recovery section above already shows the
+                            // backup when both happened.
+                            Some(path) if loaded.recovery.is_none() => {
+                                render_notice_section(ui, dark_mode, intro, path, t, body)
+                            }
+                            _ => {
+                                notice_text(ui, dark_mode, intro);
+                                ui.add_space(10.0);
+                                notice_card(ui, dark_mode, body);
+                            }
+                        }
+                    }
+                });
+
+            ui.add_space(15.0);
+            ui.vertical_centered(|ui| {
+                let btn =
+                    widgets::themed_button(t.error_close_button(), ButtonKind::Pink, dark_mode);
+                if ui.add_sized([120.0, 36.0], btn).clicked() {
+                    close = true;
+                }
+            });
+            ui.add_space(10.0);
+        });
+
+    if close {
+        *report = None;
+    }
+}
+
+/// Intro text, backup location and a card holding `body`.
+fn render_notice_section(
+    ui: &mut egui::Ui,
+    dark_mode: bool,
+    intro: &str,
+    backup_path: &std::path::Path,
+    t: &CachedTranslations,
+    body: impl FnOnce(&mut egui::Ui),
+) {
+    notice_text(ui, dark_mode, intro);
+    ui.add_space(8.0);
+    notice_label(ui, dark_mode, t.config_recovery_backup_label());
+    notice_entry(ui, dark_mode, &backup_path.display().to_string());
+    ui.add_space(10.0);
+    notice_card(ui, dark_mode, body);
+}
+
+fn notice_card(ui: &mut egui::Ui, dark_mode: bool, body: impl FnOnce(&mut egui::Ui)) {
+    let c = theme::colors(dark_mode);
+    widgets::card_frame(dark_mode)
+        .fill(c.bg_card_hover)
+        .show(ui, |ui| {
+            ui.set_width(ui.available_width());
+            body(ui);
+        });
+}
+
+fn notice_text(ui: &mut egui::Ui, dark_mode: bool, text: &str) {
+    let c = theme::colors(dark_mode);
+    ui.label(
+        egui::RichText::new(text)
+            .size(text_size::NORMAL)
+            .color(c.fg_primary),
+    );
+}
+
+fn notice_label(ui: &mut egui::Ui, dark_mode: bool, text: &str) {
+    let c = theme::colors(dark_mode);
+    ui.label(
+        egui::RichText::new(text)
+            .size(text_size::NORMAL)
+            .color(c.fg_muted),
+    );
+}
+
+fn notice_entry(ui: &mut egui::Ui, dark_mode: bool, text: &str) {
+    let c = theme::colors(dark_mode);
+    ui.label(
+        egui::RichText::new(text)
+            .size(text_size::COMPACT)
+            .monospace()
+            .color(c.fg_primary),
+    );
+}
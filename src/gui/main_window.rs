@@ -1,8 +1,10 @@
 //! Main window implementation and rendering logic.
 
+use crate::config::split_step_timing;
 use crate::gui::SorahkGui;
 use crate::gui::about_dialog::render_about_dialog;
-use crate::gui::utils::{is_mouse_move_target, is_mouse_scroll_target};
+use crate::gui::error_dialog::render_config_load_dialog;
+use crate::gui::utils::{format_ms_range, is_mouse_move_target, is_mouse_scroll_target};
 use crate::gui::theme;
 use crate::gui::widgets::{arrow_separator_width, estimate_pill_width_display};
 use crate::state::NotificationEvent;
@@ -12,6 +14,11 @@ use eframe::egui;
 struct FrameState {
     is_paused: bool,
     worker_count: usize,
+    /// `None` when the config defines no profiles besides the default.
+    active_profile: Option<String>,
+    /// Active layer names joined for display; `None` when no mapping
+    /// switches a layer.
+    active_layers: Option<String>,
     should_exit: bool,
 }
 
@@ -21,6 +28,12 @@ impl eframe::App for SorahkGui {
         let frame_state = FrameState {
             is_paused: self.app_state.is_paused(),
             worker_count: self.app_state.get_actual_worker_count(),
+            active_profile: (!self.config.profiles.is_empty())
+                .then(|| self.app_state.active_profile_name()),
+            active_layers: self
+                .app_state
+                .has_layer_switches()
+                .then(|| self.app_state.active_layer_names().join(", ")),
             should_exit: self.app_state.should_exit(),
         };
 
@@ -30,6 +43,15 @@ impl eframe::App for SorahkGui {
             return;
         }
 
+        // Adopt configs applied outside the GUI, e.g. by the file watcher.
+        // An open settings dialog keeps its draft; its save wins.
+        if self.temp_config.is_none()
+            && self.app_state.config_generation() != self.config_generation
+        {
+            let config = self.app_state.current_config();
+            self.adopt_config(ctx, config);
+        }
+
         // Check for HID device activation requests
         if self.hid_activation_dialog.is_none() {
             let requests = self.app_state.poll_hid_activation_requests();
@@ -152,6 +174,15 @@ impl eframe::App for SorahkGui {
             );
         }
 
+        if self.config_load_report.is_some() {
+            render_config_load_dialog(
+                ctx,
+                self.dark_mode,
+                &mut self.config_load_report,
+                &self.translations,
+            );
+        }
+
         // Show device manager dialog
         if self.show_device_manager {
             if self.device_manager_dialog.is_none() {
@@ -299,6 +330,7 @@ impl eframe::App for SorahkGui {
                             } else {
                                 Some(result.append_keys)
                             };
+                            mapping.strip_unused_step_timings();
                         }
                     } else {
                         // New-mapping path: park the result in the GUI's
@@ -740,6 +772,36 @@ impl SorahkGui {
                     ui.label(egui::RichText::new(icon).size(18.0).color(color));
                     ui.label(egui::RichText::new(text).size(15.0).color(color).strong());
 
+                    if let Some(profile) = &frame_state.active_profile {
+                        ui.add_space(10.0);
+                        ui.label(
+                            egui::RichText::new(t.active_profile_label())
+                                .size(13.0)
+                                .color(c.fg_muted),
+                        );
+                        ui.label(
+                            egui::RichText::new(profile)
+                                .size(14.0)
+                                .color(c.accent_primary)
+                                .strong(),
+                        );
+                    }
+
+                    if let Some(layers) = &frame_state.active_layers {
+                        ui.add_space(10.0);
+                        ui.label(
+                            egui::RichText::new(t.active_layers_label())
+                                .size(13.0)
+                                .color(c.fg_muted),
+                        );
+                        let (text, color) = if layers.is_empty() {
+                            (t.no_active_layer(), c.fg_muted)
+                        } else {
+                            (layers.as_str(), c.accent_primary)
+                        };
+                        ui.label(egui::RichText::new(text).size(14.0).color(color).strong());
+                    }
+
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                         if frame_state.worker_count > 0 {
                             ui.label(
@@ -1202,7 +1264,43 @@ impl SorahkGui {
 
                 // Target keys with proper wrapping
                 let target_keys = mapping.get_target_keys();
-                if !target_keys.is_empty() {
+                if let Some(source) = mapping.macro_script() {
+                    // A macro replaces the keys; show its commands on one
+                    // line.
+                    let commands: Vec<&str> = source
+                        .lines()
+                        .map(str::trim)
+                        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                        .collect();
+                    egui::Frame::NONE
+                        .fill(c.pill_keyboard)
+                        .corner_radius(egui::CornerRadius::same(10))
+                        .inner_margin(egui::Margin::symmetric(10, 5))
+                        .show(ui, |ui| {
+                            ui.label(
+                                egui::RichText::new(commands.join("; "))
+                                    .size(13.0)
+                                    .color(c.fg_primary)
+                                    .strong(),
+                            );
+                        });
+                    ui.add_space(4.0);
+                } else if let Some(text) = mapping.type_text() {
+                    // A typed text replaces the keys; show it as one pill.
+                    egui::Frame::NONE
+                        .fill(c.pill_target)
+                        .corner_radius(egui::CornerRadius::same(10))
+                        .inner_margin(egui::Margin::symmetric(10, 5))
+                        .show(ui, |ui| {
+                            ui.label(
+                                egui::RichText::new(format!("\"{}\"", text.text))
+                                    .size(13.0)
+                                    .color(c.fg_primary)
+                                    .strong(),
+                            );
+                        });
+                    ui.add_space(4.0);
+                } else if !target_keys.is_empty() {
                     let available_width = ui.available_width();
                     let (sep_char, sep_width) = if target_mode == 2 {
                         ("→", arrow_separator_width())
@@ -1289,16 +1387,16 @@ impl SorahkGui {
                     let first_target = mapping
                         .get_target_keys()
                         .first()
-                        .map(|s| s.as_str())
+                        .map(|s| split_step_timing(s).0)
                         .unwrap_or("");
                     let is_mouse_move = is_mouse_move_target(first_target);
                     let is_mouse_scroll = is_mouse_scroll_target(first_target);
 
-                    // Interval
+                    // Interval, as the range jitter can move it over
                     ui.label(
                         egui::RichText::new(format!(
-                            "⏱ {} ms",
-                            mapping.interval.unwrap_or(self.config.interval)
+                            "⏱ {}",
+                            format_ms_range(&mapping.interval_range(self.config.interval))
                         ))
                         .size(11.0)
                         .color(detail_color),
@@ -1309,8 +1407,10 @@ impl SorahkGui {
                         ui.add_space(8.0);
                         ui.label(
                             egui::RichText::new(format!(
-                                "⏳ {} ms",
-                                mapping.event_duration.unwrap_or(self.config.event_duration)
+                                "⏳ {}",
+                                format_ms_range(
+                                    &mapping.duration_range(self.config.event_duration)
+                                )
                             ))
                             .size(11.0)
                             .color(detail_color),
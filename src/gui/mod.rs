@@ -19,7 +19,7 @@ mod types;
 mod utils;
 mod widgets;
 
-use crate::config::AppConfig;
+use crate::config::{AppConfig, ConfigLoadReport};
 use crate::gui::types::KeyCaptureMode;
 use crate::i18n::CachedTranslations;
 use crate::state::AppState;
@@ -53,6 +53,8 @@ pub struct SorahkGui {
     app_state: Arc<AppState>,
     /// Application configuration
     config: AppConfig,
+    /// `AppState::config_generation` that `config` was last synced with.
+    config_generation: u64,
     /// Cached translations for rendering
     translations: CachedTranslations,
     /// Close confirmation dialog visibility
@@ -91,6 +93,9 @@ pub struct SorahkGui {
     /// Append keys captured by the rule properties dialog for the new
     /// mapping in progress.
     new_mapping_append_keys: Vec<String>,
+    /// Target step of the new sequence mapping whose timing is being
+    /// edited.
+    new_mapping_timing_step: Option<usize>,
     /// Index of mapping being edited for mouse direction (None for new mapping)
     mouse_direction_mapping_idx: Option<usize>,
     /// Index of mapping being edited for mouse scroll (None for new mapping)
@@ -153,25 +158,36 @@ pub struct SorahkGui {
     dialog_highlight_until: Option<std::time::Instant>,
     /// Pause state before entering settings
     was_paused_before_settings: Option<bool>,
-    /// Error message for duplicate mapping
-    duplicate_mapping_error: Option<String>,
+    /// Hint that a new mapping hides one on the same trigger
+    duplicate_mapping_hint: Option<String>,
     /// Error message for duplicate process
     duplicate_process_error: Option<String>,
+    /// Validation problems that blocked the last settings save.
+    settings_diagnostics: Vec<crate::config::ConfigDiagnostic>,
     /// Pre-computed dark/light theme visuals.
     theme_cache: theme::ThemeCache,
+    /// Startup config recovery/upgrade report, shown until dismissed.
+    /// `None` when there was nothing worth telling the user.
+    config_load_report: Option<ConfigLoadReport>,
 }
 
 impl SorahkGui {
     /// Creates a new GUI instance with the given state and configuration.
-    pub fn new(app_state: Arc<AppState>, config: AppConfig) -> Self {
+    pub fn new(
+        app_state: Arc<AppState>,
+        config: AppConfig,
+        config_load_report: ConfigLoadReport,
+    ) -> Self {
         let dark_mode = config.dark_mode;
         let translations = CachedTranslations::new(config.language);
         let theme_cache = theme::ThemeCache::new();
         let parsed_switch_key = Self::parse_switch_key(&config.switch_key);
+        let config_generation = app_state.config_generation();
 
         Self {
             app_state,
             config,
+            config_generation,
             translations,
             show_close_dialog: false,
             show_settings_dialog: false,
@@ -187,6 +203,7 @@ impl SorahkGui {
             rule_props_editing_idx: None,
             new_mapping_hold_indices: Vec::new(),
             new_mapping_append_keys: Vec::new(),
+            new_mapping_timing_step: None,
             mouse_direction_mapping_idx: None,
             mouse_scroll_mapping_idx: None,
             minimize_on_close: true,
@@ -218,9 +235,13 @@ impl SorahkGui {
             parsed_switch_key,
             last_vk_state: 0,
             was_paused_before_settings: None,
-            duplicate_mapping_error: None,
+            duplicate_mapping_hint: None,
             duplicate_process_error: None,
+            settings_diagnostics: Vec::new(),
             theme_cache,
+            config_load_report: config_load_report
+                .needs_attention()
+                .then_some(config_load_report),
         }
     }
 
@@ -229,6 +250,40 @@ impl SorahkGui {
         self.translations = CachedTranslations::new(language);
     }
 
+    /// Makes `config` the GUI's config after it has been applied to
+    /// `AppState`, refreshing everything the GUI derives from it.
+    fn adopt_config(&mut self, ctx: &egui::Context, config: AppConfig) {
+        let always_on_top_changed = config.always_on_top != self.config.always_on_top;
+        let dark_mode_changed = config.dark_mode != self.config.dark_mode;
+        let language_changed = config.language != self.config.language;
+        self.config = config;
+        self.config_generation = self.app_state.config_generation();
+
+        // The GUI polls the switch key via GetAsyncKeyState
+        // using a cached parse result. Rebuild it from the new
+        // config so the polling loop picks up the new hotkey
+        // without an app restart.
+        self.parsed_switch_key = Self::parse_switch_key(&self.config.switch_key);
+
+        if dark_mode_changed {
+            self.dark_mode = self.config.dark_mode;
+        }
+
+        if language_changed {
+            self.update_translations(self.config.language);
+            crate::gui::fonts::load_fonts(ctx, self.config.language);
+        }
+
+        if always_on_top_changed {
+            let level = if self.config.always_on_top {
+                egui::WindowLevel::AlwaysOnTop
+            } else {
+                egui::WindowLevel::Normal
+            };
+            ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(level));
+        }
+    }
+
     /// Parse switch key configuration during initialization.
     fn parse_switch_key(switch_key: &str) -> ParsedSwitchKey {
         use crate::gui::utils::string_to_vk;
@@ -266,12 +321,18 @@ impl SorahkGui {
         ParsedSwitchKey::Combo { modifiers, keys }
     }
 
-    /// Launches the GUI application.
+    /// Launches the GUI application. `config_load_report` describes any
+    /// recovery or upgrade done while loading the config; it is shown as a
+    /// dialog once the window opens.
     ///
     /// # Errors
     ///
     /// Returns an error if the GUI framework fails to initialize or run.
-    pub fn run(app_state: Arc<AppState>, config: AppConfig) -> anyhow::Result<()> {
+    pub fn run(
+        app_state: Arc<AppState>,
+        config: AppConfig,
+        config_load_report: ConfigLoadReport,
+    ) -> anyhow::Result<()> {
         let icon = crate::gui::utils::create_icon();
 
         let mut viewport = egui::ViewportBuilder::default()
@@ -298,7 +359,11 @@ impl SorahkGui {
             options,
             Box::new(move |cc| {
                 fonts::load_fonts(&cc.egui_ctx, language);
-                Ok(Box::new(SorahkGui::new(app_state, config)))
+                Ok(Box::new(SorahkGui::new(
+                    app_state,
+                    config,
+                    config_load_report,
+                )))
             }),
         )
         .map_err(|e| anyhow::anyhow!("Failed to run GUI: {}", e))
@@ -49,7 +49,7 @@ impl MouseScrollDialog {
             .title_bar(false)
             .collapsible(false)
             .resizable(false)
-            .fixed_size([320.0, 380.0])
+            .fixed_size([320.0, 500.0])
             .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
             .frame(
                 egui::Frame::window(&ctx.style())
@@ -126,6 +126,40 @@ impl MouseScrollDialog {
                         should_close = true;
                     }
 
+                    ui.add_space(8.0);
+
+                    // Scroll Left button
+                    if render_scroll_button(
+                        ui,
+                        t.mouse_scroll_left(),
+                        dark_mode,
+                        button_bg,
+                        button_hover_bg,
+                        c.fg_primary,
+                    )
+                    .clicked()
+                    {
+                        self.selected_direction = Some("SCROLL_LEFT".to_string());
+                        should_close = true;
+                    }
+
+                    ui.add_space(8.0);
+
+                    // Scroll Right button
+                    if render_scroll_button(
+                        ui,
+                        t.mouse_scroll_right(),
+                        dark_mode,
+                        button_bg,
+                        button_hover_bg,
+                        c.fg_primary,
+                    )
+                    .clicked()
+                    {
+                        self.selected_direction = Some("SCROLL_RIGHT".to_string());
+                        should_close = true;
+                    }
+
                     ui.add_space(20.0);
 
                     // Cancel button.
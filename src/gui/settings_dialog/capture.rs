@@ -86,6 +86,7 @@ impl SorahkGui {
                                     m.hold_indices = None;
                                 }
                             }
+                            m.strip_unused_step_timings();
                             self.editing_target_seq_list.clear();
                             self.editing_target_seq_idx = None;
                         }
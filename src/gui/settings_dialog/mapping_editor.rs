@@ -4,7 +4,7 @@
 //! `SorahkGui` fields disjointly with the parent scroll-area closure.
 
 use super::helpers::{BUTTON_TEXT_MAX_CHARS, truncate_text_safe};
-use crate::config::{AppConfig, KeyMapping};
+use crate::config::{AppConfig, KeyMapping, StepTiming, split_step_timing, with_step_timing};
 use crate::gui::mouse_direction_dialog::MouseDirectionDialog;
 use crate::gui::mouse_scroll_dialog::MouseScrollDialog;
 use crate::gui::rule_properties_dialog::RulePropertiesDialog;
@@ -27,7 +27,7 @@ pub(super) fn render_mapping_editor_section(
     capture_initial_pressed: &mut HashSet<u32>,
     capture_pressed_keys: &mut HashSet<u32>,
     just_captured_input: &mut bool,
-    duplicate_mapping_error: &mut Option<String>,
+    duplicate_mapping_hint: &mut Option<String>,
     sequence_last_mouse_pos: &mut Option<egui::Pos2>,
     sequence_last_mouse_direction: &mut Option<String>,
     sequence_mouse_delta: &mut egui::Vec2,
@@ -51,6 +51,7 @@ pub(super) fn render_mapping_editor_section(
     new_mapping_turbo: &mut bool,
     new_mapping_hold_indices: &mut Vec<u8>,
     new_mapping_append_keys: &mut Vec<String>,
+    new_mapping_timing_step: &mut Option<usize>,
     app_state: &Arc<AppState>,
     dark_mode: bool,
     translations: CachedTranslations,
@@ -306,7 +307,7 @@ pub(super) fn render_mapping_editor_section(
                         crate::gui::SorahkGui::poll_all_pressed_keys();
                     app_state.set_raw_input_capture_mode(true);
                     *just_captured_input = true;
-                    *duplicate_mapping_error = None;
+                    *duplicate_mapping_hint = None;
                 }
                 // Display full trigger text with wrapping for long device names
                 if !is_capturing_new_trigger && !(*new_mapping_trigger).is_empty() && (*new_mapping_trigger).chars().count() > BUTTON_TEXT_MAX_CHARS {
@@ -486,7 +487,7 @@ pub(super) fn render_mapping_editor_section(
                         *capture_initial_pressed = crate::gui::SorahkGui::poll_all_pressed_keys();
                         app_state.set_raw_input_capture_mode(true);
                         *just_captured_input = true;
-                        *duplicate_mapping_error = None;
+                        *duplicate_mapping_hint = None;
                     }
                 }
                 // Display captured sequence keys with a horizontal flow layout.
@@ -1078,6 +1079,7 @@ pub(super) fn render_mapping_editor_section(
             let keys_list: Vec<_> = display_keys.to_vec();
             let keys_count = keys_list.len();
             let mut target_key_to_remove: Option<usize> = None;
+            let mut timing_step_to_toggle: Option<usize> = None;
             if target_mode > 0 && keys_count > 0 {
                 ui.add_space(8.0);
                 let full_width = ui.available_width();
@@ -1138,7 +1140,9 @@ pub(super) fn render_mapping_editor_section(
                                 let mut current_width = 0.0f32;
 
                                 for (key_idx, key) in keys_list.iter().enumerate() {
-                                    let pill_width = widgets::estimate_pill_width_editor(key);
+                                    // Sequence pills carry a timing button.
+                                    let timing_width = if target_mode == 2 { 16.0 } else { 0.0 };
+                                    let pill_width = widgets::estimate_pill_width_editor(key) + timing_width;
                                     let s_width = if key_idx < keys_list.len() - 1 { sep_width } else { 0.0 };
                                     let total_width = pill_width + s_width;
                                     if current_width + total_width > available_width && !current_row.is_empty() {
@@ -1156,9 +1160,11 @@ pub(super) fn render_mapping_editor_section(
                                         ui.spacing_mut().item_spacing = egui::vec2(4.0, 0.0);
                                         for &key_idx in row {
                                             let key = &keys_list[key_idx];
+                                            // Inline step timing is shown apart from the key name.
+                                            let (key_name, timing_spec) = split_step_timing(key);
                                             // Sequence mode uses trigger-style colors
                                             let (tag_color, text_color) = if target_mode == 2 {
-                                                let color = widgets::pill_color(key, dark_mode);
+                                                let color = widgets::pill_color(key_name, dark_mode);
                                                 let text = if dark_mode {
                                                     egui::Color32::from_rgb(40, 30, 50)
                                                 } else {
@@ -1176,7 +1182,7 @@ pub(super) fn render_mapping_editor_section(
                                             };
                                             // Get display name for sequence mode
                                             let display_name = if target_mode == 2 {
-                                                let (_, name) = widgets::pill_icon_and_label(key);
+                                                let (_, name) = widgets::pill_icon_and_label(key_name);
                                                 name
                                             } else {
                                                 key.clone()
@@ -1191,6 +1197,16 @@ pub(super) fn render_mapping_editor_section(
                                                         ui.label(egui::RichText::new(format!("{}", key_idx + 1)).size(9.0).strong().color(text_color));
                                                         let short_name = if display_name.len() > 15 { format!("{}...", &display_name[..12]) } else { display_name };
                                                         ui.label(egui::RichText::new(&short_name).size(11.0).color(text_color));
+                                                        if target_mode == 2 {
+                                                            if let Some(spec) = timing_spec.filter(|spec| !spec.is_empty()) {
+                                                                ui.label(egui::RichText::new(spec).size(9.0).italics().color(text_color));
+                                                            }
+                                                            let selected = *new_mapping_timing_step == Some(key_idx);
+                                                            let timing_btn = ui.add(egui::Button::new(egui::RichText::new("⏱").size(11.0).color(
+                                                                if selected { egui::Color32::from_rgb(255, 120, 150) } else { text_color }
+                                                            )).fill(egui::Color32::TRANSPARENT).frame(false).corner_radius(8.0));
+                                                            if timing_btn.clicked() { timing_step_to_toggle = Some(key_idx); }
+                                                        }
                                                         let del_btn = ui.add(egui::Button::new(egui::RichText::new("🗑").size(11.0).color(
                                                             if dark_mode { egui::Color32::from_rgb(180, 80, 100) }
                                                             else { egui::Color32::from_rgb(200, 60, 80) }
@@ -1215,8 +1231,81 @@ pub(super) fn render_mapping_editor_section(
                             });
                     });
             }
+            if let Some(idx) = timing_step_to_toggle {
+                *new_mapping_timing_step = if *new_mapping_timing_step == Some(idx) {
+                    None
+                } else {
+                    Some(idx)
+                };
+            }
+            // Per-step timing of the selected sequence pill, written back
+            // into its entry as `KEY@DELAY/DURATION`.
+            if target_mode == 2
+                && let Some(step) = *new_mapping_timing_step
+                && let Some(entry) = target_sequence_capture_list.get(step).cloned()
+            {
+                let (name, spec) = split_step_timing(&entry);
+                let timing = spec.and_then(StepTiming::parse).unwrap_or_default();
+                let mut delay = timing.delay_ms.map(|v| v.to_string()).unwrap_or_default();
+                let mut duration = timing.duration_ms.map(|v| v.to_string()).unwrap_or_default();
+                let label_color = if dark_mode {
+                    egui::Color32::from_rgb(170, 170, 190)
+                } else {
+                    egui::Color32::from_rgb(100, 100, 120)
+                };
+                let field_bg = if dark_mode {
+                    egui::Color32::from_rgb(60, 62, 72)
+                } else {
+                    egui::Color32::from_rgb(240, 240, 245)
+                };
+                let mut changed = false;
+                ui.add_space(6.0);
+                ui.horizontal(|ui| {
+                    ui.label(
+                        egui::RichText::new(format!("{} {}", step + 1, name))
+                            .size(12.0)
+                            .strong()
+                            .color(label_color),
+                    );
+                    ui.add_space(8.0);
+                    // Empty fields fall back to the mapping's values.
+                    for (label, value, fallback) in [
+                        (t.interval_short(), &mut delay, new_mapping_interval.as_str()),
+                        (t.duration_short(), &mut duration, new_mapping_duration.as_str()),
+                    ] {
+                        let hint = if fallback.is_empty() { "5" } else { fallback };
+                        ui.label(egui::RichText::new(label).size(12.0).color(label_color));
+                        let edit = egui::TextEdit::singleline(value)
+                            .background_color(field_bg)
+                            .hint_text(hint)
+                            .desired_width(55.0)
+                            .font(egui::TextStyle::Button);
+                        changed |= ui.add_sized([55.0, 28.0], edit).changed();
+                        ui.add_space(12.0);
+                    }
+                });
+                if changed {
+                    let number = |text: &str| {
+                        let digits: String = text.chars().filter(char::is_ascii_digit).collect();
+                        digits.parse::<u64>().ok()
+                    };
+                    let timing = StepTiming {
+                        delay_ms: number(&delay),
+                        duration_ms: number(&duration),
+                    };
+                    target_sequence_capture_list[step] = with_step_timing(name, timing);
+                    *new_mapping_target_keys = target_sequence_capture_list.clone();
+                    *new_mapping_target = new_mapping_target_keys[0].clone();
+                }
+            }
             // Apply deferred target key deletion outside the inner closures.
             if let Some(idx) = target_key_to_remove {
+                // The timing editor follows its pill, or closes with it.
+                *new_mapping_timing_step = match *new_mapping_timing_step {
+                    Some(step) if step > idx => Some(step - 1),
+                    Some(step) if step == idx => None,
+                    other => other,
+                };
                 if target_mode == 2 {
                     target_sequence_capture_list.remove(idx);
                     *new_mapping_target_keys = target_sequence_capture_list.clone();
@@ -1247,6 +1336,7 @@ pub(super) fn render_mapping_editor_section(
                 capture_pressed_keys.clear();
             }
             if should_clear_target_seq {
+                *new_mapping_timing_step = None;
                 target_sequence_capture_list.clear();
                 new_mapping_target_keys.clear();
                 (*new_mapping_target).clear();
@@ -1541,19 +1631,20 @@ pub(super) fn render_mapping_editor_section(
                             ((*new_mapping_trigger).to_uppercase(), None)
                         };
 
-                        // Check for duplicate trigger key
-                        let is_duplicate = temp_config
-                            .mappings
-                            .iter()
-                            .any(|m| m.trigger_key == trigger_key);
+                        // Several mappings may share a trigger, but without
+                        // priority or conditions only the newest one fires.
+                        // Sequences compare as a whole.
+                        let shadows_existing = temp_config.mappings.iter().any(|m| {
+                            let same_trigger = match (&m.trigger_sequence, &trigger_sequence) {
+                                (Some(existing), Some(new)) => existing == new,
+                                (None, None) => m.trigger_key == trigger_key,
+                                _ => false,
+                            };
+                            same_trigger && m.priority == 0 && !m.has_conditions()
+                        });
+                        *duplicate_mapping_hint = shadows_existing
+                            .then(|| t.duplicate_trigger_hint().to_string());
 
-                        if is_duplicate {
-                *duplicate_mapping_error = Some(
-                    t.duplicate_trigger_error().to_string(),
-                );
-            } else {
-                // Clear any previous error
-                *duplicate_mapping_error = None;
 
                 let interval = new_mapping_interval
                     .parse::<u64>()
@@ -1563,10 +1654,13 @@ pub(super) fn render_mapping_editor_section(
                     .parse::<u64>()
                     .ok()
                     .map(|v| v.max(2));
+                // Scroll speeds are wheel deltas, 120 per notch.
+                let scrolls = new_mapping_target_keys.iter().any(|k| is_mouse_scroll_target(k));
+                let (default_speed, max_speed) = if scrolls { (120, 1200) } else { (5, 100) };
                 let move_speed = new_mapping_move_speed
                     .parse::<i32>()
-                    .unwrap_or(5)
-                    .clamp(1, 100);
+                    .unwrap_or(default_speed)
+                    .clamp(1, max_speed);
                 let sequence_window = new_mapping_sequence_window
                     .parse::<u64>()
                     .unwrap_or(300)
@@ -1610,7 +1704,7 @@ pub(super) fn render_mapping_editor_section(
                         (None, None)
                     };
 
-                temp_config.mappings.push(KeyMapping {
+                let mut mapping = KeyMapping {
                     trigger_key,
                     trigger_sequence,
                     sequence_window_ms: sequence_window,
@@ -1624,7 +1718,10 @@ pub(super) fn render_mapping_editor_section(
                     target_mode: (*new_mapping_target_mode),
                     hold_indices: new_hold,
                     append_keys: new_append,
-                });
+                    ..Default::default()
+                };
+                mapping.strip_unused_step_timings();
+                temp_config.mappings.push(mapping);
 
                 // Clear input fields
                 (*new_mapping_trigger).clear();
@@ -1641,21 +1738,19 @@ pub(super) fn render_mapping_editor_section(
                 target_sequence_capture_list.clear();
                 new_mapping_hold_indices.clear();
                 new_mapping_append_keys.clear();
+                *new_mapping_timing_step = None;
                 *sequence_last_mouse_pos = None;
                 *sequence_last_mouse_direction = None;
                 *sequence_mouse_delta = egui::Vec2::ZERO;
-            }
         }
     });
 
-            // Display duplicate trigger error if exists
-            if let Some(ref error_msg) = *duplicate_mapping_error {
+            // Hint that the new mapping hides an existing one
+            if let Some(ref hint) = *duplicate_mapping_hint {
                 ui.add_space(8.0);
                 ui.label(
-                    egui::RichText::new(error_msg)
-                        .color(egui::Color32::from_rgb(
-                            255, 100, 100,
-                        ))
+                    egui::RichText::new(hint)
+                        .color(crate::gui::theme::colors(dark_mode).accent_warning)
                         .size(13.0),
                 );
             }
@@ -191,6 +191,15 @@ pub(super) fn render_mapping_list_section(
                                     .color(badge_color_text),
                             );
                         });
+                    // Mappings from an include file name it
+                    if let Some(source) = &mapping.source {
+                        ui.add_space(6.0);
+                        let file_name = source
+                            .file_name()
+                            .map_or_else(|| source.display().to_string(), |n| n.to_string_lossy().into_owned());
+                        ui.label(egui::RichText::new(file_name).size(11.0).weak())
+                            .on_hover_text(t.mapping_source_hint());
+                    }
                 });
                 ui.add_space(10.0);
 
@@ -708,6 +717,7 @@ pub(super) fn render_mapping_list_section(
                     }).corner_radius(10.0);
                     if ui.add(single_btn).clicked() && target_mode != 0 {
                         mapping.target_mode = 0;
+                        mapping.strip_unused_step_timings();
                         if mapping.target_keys.len() > 1 {
                             let first = mapping.target_keys[0].clone();
                             mapping.target_keys.clear();
@@ -747,6 +757,7 @@ pub(super) fn render_mapping_list_section(
                     }).corner_radius(10.0);
                     if ui.add(multi_btn).clicked() && target_mode != 1 {
                         mapping.target_mode = 1;
+                        mapping.strip_unused_step_timings();
                         // Deduplicate target keys when switching from Sequence to Multi
                         if target_mode == 2 {
                             let mut seen = std::collections::HashSet::new();
@@ -1033,6 +1044,7 @@ pub(super) fn render_mapping_list_section(
                                         None;
                                 }
                             }
+                            mapping.strip_unused_step_timings();
                             editing_target_seq_list.clear();
                             *editing_target_seq_idx = None;
                             {
@@ -6,6 +6,7 @@ mod mapping_editor;
 mod mapping_list;
 mod process_list;
 
+use crate::config::ConfigDiagnostic;
 use crate::gui::SorahkGui;
 use crate::gui::types::KeyCaptureMode;
 use eframe::egui;
@@ -194,7 +195,7 @@ impl SorahkGui {
                                                 &mut self.capture_initial_pressed,
                                                 &mut self.capture_pressed_keys,
                                                 &mut self.just_captured_input,
-                                                &mut self.duplicate_mapping_error,
+                                                &mut self.duplicate_mapping_hint,
                                                 &mut self.sequence_last_mouse_pos,
                                                 &mut self.sequence_last_mouse_direction,
                                                 &mut self.sequence_mouse_delta,
@@ -218,6 +219,7 @@ impl SorahkGui {
                                                 &mut self.new_mapping_turbo,
                                                 &mut self.new_mapping_hold_indices,
                                                 &mut self.new_mapping_append_keys,
+                                                &mut self.new_mapping_timing_step,
                                                 &self.app_state,
                                                 self.dark_mode,
                                                 self.translations,
@@ -238,6 +240,36 @@ impl SorahkGui {
 
                     ui.separator();
 
+                    // Problems that blocked the last save attempt.
+                    if !self.settings_diagnostics.is_empty() {
+                        let c = crate::gui::theme::colors(self.dark_mode);
+                        ui.label(
+                            egui::RichText::new(t.settings_validation_failed())
+                                .size(13.0)
+                                .color(c.accent_danger)
+                                .strong(),
+                        );
+                        egui::ScrollArea::vertical()
+                            .id_salt("settings_diagnostics_scroll")
+                            .max_height(80.0)
+                            .show(ui, |ui| {
+                                for diagnostic in &self.settings_diagnostics {
+                                    let color = if diagnostic.is_error() {
+                                        c.accent_danger
+                                    } else {
+                                        c.accent_warning
+                                    };
+                                    ui.label(
+                                        egui::RichText::new(diagnostic.to_string())
+                                            .size(12.0)
+                                            .monospace()
+                                            .color(color),
+                                    );
+                                }
+                            });
+                        ui.separator();
+                    }
+
                     // Action buttons. Pinned to the bottom outside the scroll area.
                     ui.vertical_centered(|ui| {
                         ui.horizontal(|ui| {
@@ -296,64 +328,46 @@ impl SorahkGui {
                 }); // End of ui.push_id
             }); // End of egui::Window
 
+        // Keep whichever profile the hotkeys or the tray switched to, so
+        // saving does not jump back to the one the file started with.
+        if should_save && let Some(temp_config) = &mut self.temp_config {
+            temp_config.active_profile = self.app_state.active_profile_name();
+        }
+
+        // Refuse to save a config the runtime would reject, and list every
+        // problem at once so they can all be fixed in one pass.
+        if should_save && let Some(temp_config) = &self.temp_config {
+            let diagnostics = temp_config.validate();
+            if diagnostics.iter().any(ConfigDiagnostic::is_error) {
+                self.settings_diagnostics = diagnostics;
+                should_save = false;
+            }
+        }
+
         // Handle save/cancel outside the window closure
         if should_save {
-            if let Some(temp_config) = &self.temp_config {
-                // Check if always_on_top changed
-                let always_on_top_changed = temp_config.always_on_top != self.config.always_on_top;
-                // Check if dark_mode changed
-                let dark_mode_changed = temp_config.dark_mode != self.config.dark_mode;
-                // Check if language changed
-                let language_changed = temp_config.language != self.config.language;
-
+            if let Some(temp_config) = self.temp_config.clone() {
                 // Save to file
                 if temp_config.save_to_file("Config.toml").is_ok() {
                     // Reload configuration into AppState. Takes effect immediately.
                     let _ = self.app_state.reload_config(temp_config.clone());
 
                     // Update GUI's config
-                    self.config = temp_config.clone();
-
-                    // The GUI polls the switch key via GetAsyncKeyState
-                    // using a cached parse result. Rebuild it from the new
-                    // config so the polling loop picks up the new hotkey
-                    // without an app restart.
-                    self.parsed_switch_key = Self::parse_switch_key(&self.config.switch_key);
-
-                    // Apply theme change immediately
-                    if dark_mode_changed {
-                        self.dark_mode = self.config.dark_mode;
-                    }
-
-                    if language_changed {
-                        self.update_translations(self.config.language);
-                        crate::gui::fonts::load_fonts(ctx, self.config.language);
-                    }
-
-                    // Apply always_on_top change immediately
-                    if always_on_top_changed {
-                        if self.config.always_on_top {
-                            ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(
-                                egui::WindowLevel::AlwaysOnTop,
-                            ));
-                        } else {
-                            ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(
-                                egui::WindowLevel::Normal,
-                            ));
-                        }
-                    }
+                    self.adopt_config(ctx, temp_config);
                 }
             }
             self.show_settings_dialog = false;
             self.temp_config = None;
             self.key_capture_mode = KeyCaptureMode::None;
-            self.duplicate_mapping_error = None;
+            self.duplicate_mapping_hint = None;
             self.duplicate_process_error = None;
+            self.settings_diagnostics.clear();
             self.app_state.set_raw_input_capture_mode(false);
             // Clear rule-properties draft so a leftover selection from a
             // previous session doesn't auto-apply to the next draft.
             self.new_mapping_hold_indices.clear();
             self.new_mapping_append_keys.clear();
+            self.new_mapping_timing_step = None;
             // Drop any in-flight rule-properties dialog so it can't outlive
             // the settings session and write into a stale temp_config.
             self.rule_properties_dialog = None;
@@ -372,8 +386,9 @@ impl SorahkGui {
             self.show_settings_dialog = false;
             self.temp_config = None;
             self.key_capture_mode = KeyCaptureMode::None;
-            self.duplicate_mapping_error = None;
+            self.duplicate_mapping_hint = None;
             self.duplicate_process_error = None;
+            self.settings_diagnostics.clear();
             self.app_state.set_raw_input_capture_mode(false);
             // Clear input fields
             self.new_mapping_trigger.clear();
@@ -389,6 +404,7 @@ impl SorahkGui {
             self.editing_target_seq_idx = None;
             self.new_mapping_hold_indices.clear();
             self.new_mapping_append_keys.clear();
+            self.new_mapping_timing_step = None;
             self.rule_properties_dialog = None;
             self.rule_props_editing_idx = None;
             self.sequence_last_mouse_pos = None;
@@ -1,13 +1,14 @@
-//! Settings dialog "Process Whitelist" section. Implemented as a free
+//! Settings dialog "Process Whitelist" section, also used for the
+//! blacklist mode. Implemented as a free
 //! function so the caller can split-borrow `SorahkGui` fields disjointly
 //! with the parent scroll-area closure.
 
-use crate::config::AppConfig;
+use crate::config::{AppConfig, ProcessListMode};
 use crate::i18n::CachedTranslations;
 use eframe::egui;
 
-/// Renders the Process Whitelist card with hint text, entry list, and
-/// the input row for adding a new entry.
+/// Renders the Process Whitelist card with the list mode selector, hint
+/// text, entry list, and the input row for adding a new entry.
 #[allow(clippy::too_many_arguments)]
 pub(super) fn render_process_list_section(
     ui: &mut egui::Ui,
@@ -32,8 +33,12 @@ pub(super) fn render_process_list_section(
         .inner_margin(egui::Margin::same(16))
         .show(ui, |ui| {
             ui.set_min_width(ui.available_width());
+            let title = match temp_config.process_list_mode {
+                ProcessListMode::Whitelist => t.process_whitelist_hint(),
+                ProcessListMode::Blacklist => t.process_blacklist_hint(),
+            };
             ui.label(
-                egui::RichText::new(t.process_whitelist_hint())
+                egui::RichText::new(title)
                     .size(16.0)
                     .strong()
                     .color(if dark_mode {
@@ -44,6 +49,37 @@ pub(super) fn render_process_list_section(
             );
             ui.add_space(6.0);
 
+            // List mode
+            ui.horizontal(|ui| {
+                ui.label(t.process_list_mode_label());
+                let mode_name = |mode| match mode {
+                    ProcessListMode::Whitelist => t.process_mode_whitelist(),
+                    ProcessListMode::Blacklist => t.process_mode_blacklist(),
+                };
+                egui::ComboBox::from_id_salt("process_list_mode")
+                    .selected_text(mode_name(temp_config.process_list_mode))
+                    .width(120.0)
+                    .show_ui(ui, |ui| {
+                        for mode in [ProcessListMode::Whitelist, ProcessListMode::Blacklist] {
+                            ui.selectable_value(
+                                &mut temp_config.process_list_mode,
+                                mode,
+                                mode_name(mode),
+                            );
+                        }
+                    });
+            });
+            ui.label(
+                egui::RichText::new(t.process_pattern_hint())
+                    .size(12.0)
+                    .color(if dark_mode {
+                        egui::Color32::from_rgb(160, 160, 180)
+                    } else {
+                        egui::Color32::from_rgb(120, 120, 140)
+                    }),
+            );
+            ui.add_space(6.0);
+
             // Process list
             egui::ScrollArea::vertical().max_height(80.0).show(
                 ui,
@@ -1,5 +1,6 @@
 //! GUI utility functions.
 
+use crate::config::split_step_timing;
 use eframe::egui;
 
 /// Converts key name string to Windows VK code.
@@ -114,10 +115,11 @@ pub fn create_icon() -> egui::IconData {
     }
 }
 
-/// Returns true if the target key names a mouse movement action.
+/// Returns true if the target key names a mouse movement action. An
+/// inline step timing (`MOUSE_UP@40`) is ignored.
 #[inline]
 pub fn is_mouse_move_target(target: &str) -> bool {
-    let upper = target.to_uppercase();
+    let upper = split_step_timing(target).0.to_uppercase();
     matches!(
         upper.as_str(),
         "MOUSE_UP"
@@ -151,10 +153,11 @@ pub fn is_mouse_move_target(target: &str) -> bool {
     )
 }
 
-/// Returns true if the target key names a mouse scroll action.
+/// Returns true if the target key names a mouse scroll action. An
+/// inline step timing (`SCROLL_UP@40`) is ignored.
 #[inline]
 pub fn is_mouse_scroll_target(target: &str) -> bool {
-    let upper = target.to_uppercase();
+    let upper = split_step_timing(target).0.to_uppercase();
     matches!(
         upper.as_str(),
         "SCROLL_UP"
@@ -165,6 +168,14 @@ pub fn is_mouse_scroll_target(target: &str) -> bool {
             | "SCROLLDOWN"
             | "WHEEL_DOWN"
             | "WHEELDOWN"
+            | "SCROLL_LEFT"
+            | "SCROLLLEFT"
+            | "WHEEL_LEFT"
+            | "WHEELLEFT"
+            | "SCROLL_RIGHT"
+            | "SCROLLRIGHT"
+            | "WHEEL_RIGHT"
+            | "WHEELRIGHT"
     )
 }
 
@@ -198,6 +209,16 @@ pub fn mouse_delta_to_direction(delta: egui::Vec2, threshold: f32) -> Option<&'s
     })
 }
 
+/// Formats a range of milliseconds as `"50 ms"`, or `"45-55 ms"` when
+/// it spans more than one value.
+pub fn format_ms_range(range: &std::ops::RangeInclusive<u64>) -> String {
+    if range.start() == range.end() {
+        format!("{} ms", range.start())
+    } else {
+        format!("{}-{} ms", range.start(), range.end())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -279,6 +300,7 @@ mod tests {
                   "MOUSE_UP_LEFT", "MOUSE_UP_RIGHT", "MOUSE_DOWN_LEFT", "MOUSE_DOWN_RIGHT"] {
             assert!(is_mouse_move_target(k), "{k} should be move target");
             assert!(is_mouse_move_target(&k.to_lowercase()), "lowercase {k}");
+            assert!(is_mouse_move_target(&format!("{k}@40/20")), "timed {k}");
         }
     }
 
@@ -291,9 +313,19 @@ mod tests {
 
     #[test]
     fn is_mouse_scroll_target_recognizes_canonical() {
-        for k in ["SCROLL_UP", "SCROLL_DOWN", "WHEEL_UP", "WHEEL_DOWN"] {
+        for k in [
+            "SCROLL_UP",
+            "SCROLL_DOWN",
+            "SCROLL_LEFT",
+            "SCROLL_RIGHT",
+            "WHEEL_UP",
+            "WHEEL_DOWN",
+            "WHEEL_LEFT",
+            "WHEEL_RIGHT",
+        ] {
             assert!(is_mouse_scroll_target(k));
             assert!(is_mouse_scroll_target(&k.to_lowercase()));
+            assert!(is_mouse_scroll_target(&format!("{k}@40")));
         }
         assert!(!is_mouse_scroll_target("MOUSE_UP"));
         assert!(!is_mouse_scroll_target("A"));
@@ -324,4 +356,10 @@ mod tests {
             assert_eq!(mouse_delta_to_direction(v, 30.0), Some(expected), "angle {angle}");
         }
     }
+
+    #[test]
+    fn test_format_ms_range() {
+        assert_eq!(format_ms_range(&(50..=50)), "50 ms");
+        assert_eq!(format_ms_range(&(45..=55)), "45-55 ms");
+    }
 }
@@ -171,8 +171,8 @@ impl CachedTranslations {
     pub fn error_close_button(&self) -> &str {
         self.inner.error_close_button
     }
-    pub fn duplicate_trigger_error(&self) -> &str {
-        self.inner.duplicate_trigger_error
+    pub fn duplicate_trigger_hint(&self) -> &str {
+        self.inner.duplicate_trigger_hint
     }
 
     pub fn duplicate_process_error(&self) -> &str {
@@ -308,6 +308,12 @@ impl CachedTranslations {
     pub fn mouse_scroll_down(&self) -> &str {
         self.inner.mouse_scroll_down
     }
+    pub fn mouse_scroll_left(&self) -> &str {
+        self.inner.mouse_scroll_left
+    }
+    pub fn mouse_scroll_right(&self) -> &str {
+        self.inner.mouse_scroll_right
+    }
     pub fn mouse_middle_button(&self) -> &str {
         self.inner.mouse_middle_button
     }
@@ -687,6 +693,77 @@ impl CachedTranslations {
         self.inner.rule_props_cancel
     }
 
+    // Config Recovery Dialog
+    pub fn config_recovery_title(&self) -> &str {
+        self.inner.config_recovery_title
+    }
+    pub fn config_recovery_intro(&self) -> &str {
+        self.inner.config_recovery_intro
+    }
+    pub fn config_recovery_backup_label(&self) -> &str {
+        self.inner.config_recovery_backup_label
+    }
+    pub fn config_recovery_skipped_label(&self) -> &str {
+        self.inner.config_recovery_skipped_label
+    }
+    pub fn config_recovery_nothing_skipped(&self) -> &str {
+        self.inner.config_recovery_nothing_skipped
+    }
+
+    // Config Migration Dialog
+    pub fn config_migration_title(&self) -> &str {
+        self.inner.config_migration_title
+    }
+    pub fn config_migration_intro(&self) -> &str {
+        self.inner.config_migration_intro
+    }
+    pub fn config_migration_changes_label(&self) -> &str {
+        self.inner.config_migration_changes_label
+    }
+
+    // Config Validation
+    pub fn settings_validation_failed(&self) -> &str {
+        self.inner.settings_validation_failed
+    }
+
+    // Profiles
+    pub fn active_profile_label(&self) -> &str {
+        self.inner.active_profile_label
+    }
+    pub fn tray_profiles(&self) -> &str {
+        self.inner.tray_profiles
+    }
+
+    // Process List Mode
+    pub fn process_blacklist_hint(&self) -> &str {
+        self.inner.process_blacklist_hint
+    }
+    pub fn process_list_mode_label(&self) -> &str {
+        self.inner.process_list_mode_label
+    }
+    pub fn process_mode_whitelist(&self) -> &str {
+        self.inner.process_mode_whitelist
+    }
+    pub fn process_mode_blacklist(&self) -> &str {
+        self.inner.process_mode_blacklist
+    }
+    pub fn process_pattern_hint(&self) -> &str {
+        self.inner.process_pattern_hint
+    }
+
+    // Include Files
+    pub fn mapping_source_hint(&self) -> &str {
+        self.inner.mapping_source_hint
+    }
+
+    // Layers
+    pub fn active_layers_label(&self) -> &str {
+        self.inner.active_layers_label
+    }
+    pub fn no_active_layer(&self) -> &str {
+        self.inner.no_active_layer
+    }
+
     /// Format keys count with localized text
     /// Optimized to minimize allocations with pre-sized capacity
     #[inline]
@@ -86,7 +86,7 @@ pub(super) const fn translate(key: RawKey) -> &'static str {
         RawKey::ExitProgramBtn => "🚪  Exit Program",
         RawKey::CancelCloseBtn => "↩  Cancel",
         RawKey::ErrorTitle => "❌ Configuration Error",
-        RawKey::DuplicateTriggerError => "⚠ This trigger key already exists!",
+        RawKey::DuplicateTriggerHint => "⚠ This trigger already has a mapping. Without priority or conditions, only the newest one fires.",
         RawKey::DuplicateProcessError => "⚠ This process already exists in the whitelist!",
         RawKey::AboutInspired => "🌸 Inspired by Kasugano Sora",
         RawKey::TurboOnHover => "Turbo ON - Auto-repeat enabled",
@@ -119,6 +119,8 @@ pub(super) const fn translate(key: RawKey) -> &'static str {
         RawKey::MouseScrollDirectionLabel => "🎡 Scroll Direction 🎡",
         RawKey::MouseScrollUp => "⬆ Scroll Up",
         RawKey::MouseScrollDown => "⬇ Scroll Down",
+        RawKey::MouseScrollLeft => "⬅ Scroll Left",
+        RawKey::MouseScrollRight => "➡ Scroll Right",
         RawKey::MouseMiddleButton => "🖱 Middle Button",
         RawKey::SetMouseScrollDirectionHover => "Set mouse scroll direction",
         RawKey::SpeedLabel => "🚀 Speed:",
@@ -224,5 +226,34 @@ pub(super) const fn translate(key: RawKey) -> &'static str {
         RawKey::RulePropsAppendPlaceholder => "click to capture a key",
         RawKey::RulePropsSave => "Save",
         RawKey::RulePropsCancel => "Cancel",
+        RawKey::ConfigRecoveryTitle => "⚠ Config Recovered",
+        RawKey::ConfigRecoveryIntro => {
+            "Config.toml could not be loaded. Everything that was still valid has been kept; the entries below were skipped."
+        }
+        RawKey::ConfigRecoveryBackupLabel => "Original file saved as:",
+        RawKey::ConfigRecoverySkippedLabel => "Skipped entries:",
+        RawKey::ConfigRecoveryNothingSkipped => {
+            "No entries had to be skipped; missing settings were filled in with defaults."
+        }
+        RawKey::ConfigMigrationTitle => "⚙ Config Upgraded",
+        RawKey::ConfigMigrationIntro => {
+            "Config.toml used an older layout and has been upgraded to the current one."
+        }
+        RawKey::ConfigMigrationChangesLabel => "Changes:",
+        RawKey::SettingsValidationFailed => "⚠ Cannot save, please fix these problems first:",
+        RawKey::ActiveProfileLabel => "Profile:",
+        RawKey::TrayProfiles => "Profiles",
+        RawKey::ProcessBlacklistHint => "Process Blacklist (Empty = All Enabled)",
+        RawKey::ProcessListModeLabel => "Mode:",
+        RawKey::ProcessModeWhitelist => "Whitelist",
+        RawKey::ProcessModeBlacklist => "Blacklist",
+        RawKey::ProcessPatternHint => {
+            "Supports * and ? wildcards; include a folder to match the full path"
+        }
+        RawKey::MappingSourceHint => {
+            "Loaded from an include file. Changes are saved back to that file."
+        }
+        RawKey::ActiveLayersLabel => "Layers:",
+        RawKey::NoActiveLayer => "Base",
     }
 }
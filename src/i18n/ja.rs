@@ -82,7 +82,7 @@ pub(super) const fn translate(key: RawKey) -> &'static str {
         RawKey::ExitProgramBtn => "🚪  プログラムを終了",
         RawKey::CancelCloseBtn => "↩  キャンセル",
         RawKey::ErrorTitle => "❌ 設定エラー",
-        RawKey::DuplicateTriggerError => "⚠ この起動キーは既に存在します！",
+        RawKey::DuplicateTriggerHint => "⚠ この起動キーには既にマッピングがあります。優先度や条件がない場合、最新のマッピングのみが動作します。",
         RawKey::DuplicateProcessError => "⚠ このプロセスは既にホワイトリストに存在します！",
         RawKey::AboutInspired => "🌸 インスパイア: かすがのそら",
         RawKey::TurboOnHover => "連打オン - 自動連打",
@@ -115,6 +115,8 @@ pub(super) const fn translate(key: RawKey) -> &'static str {
         RawKey::MouseScrollDirectionLabel => "🎡 スクロール方向 🎡",
         RawKey::MouseScrollUp => "⬆ 上にスクロール",
         RawKey::MouseScrollDown => "⬇ 下にスクロール",
+        RawKey::MouseScrollLeft => "⬅ 左にスクロール",
+        RawKey::MouseScrollRight => "➡ 右にスクロール",
         RawKey::MouseMiddleButton => "🖱 ミドルボタン",
         RawKey::SetMouseScrollDirectionHover => "マウススクロール方向を設定",
         RawKey::SpeedLabel => "🚀 速度:",
@@ -214,5 +216,34 @@ pub(super) const fn translate(key: RawKey) -> &'static str {
         RawKey::RulePropsAppendPlaceholder => "クリックしてキーを記録",
         RawKey::RulePropsSave => "保存",
         RawKey::RulePropsCancel => "キャンセル",
+        RawKey::ConfigRecoveryTitle => "⚠ 設定を復元しました",
+        RawKey::ConfigRecoveryIntro => {
+            "Config.toml を読み込めませんでした。有効な設定はすべて保持され、以下の項目はスキップされました。"
+        }
+        RawKey::ConfigRecoveryBackupLabel => "元のファイルの保存先：",
+        RawKey::ConfigRecoverySkippedLabel => "スキップされた項目：",
+        RawKey::ConfigRecoveryNothingSkipped => {
+            "スキップされた項目はありません。不足していた設定は既定値で補いました。"
+        }
+        RawKey::ConfigMigrationTitle => "⚙ 設定を更新しました",
+        RawKey::ConfigMigrationIntro => {
+            "Config.toml は古い形式だったため、現在の形式に更新しました。"
+        }
+        RawKey::ConfigMigrationChangesLabel => "変更内容：",
+        RawKey::SettingsValidationFailed => "⚠ 保存できません。先に次の問題を修正してください：",
+        RawKey::ActiveProfileLabel => "プロファイル：",
+        RawKey::TrayProfiles => "プロファイル",
+        RawKey::ProcessBlacklistHint => "プロセスブラックリスト（空=全て有効）",
+        RawKey::ProcessListModeLabel => "モード：",
+        RawKey::ProcessModeWhitelist => "ホワイトリスト",
+        RawKey::ProcessModeBlacklist => "ブラックリスト",
+        RawKey::ProcessPatternHint => {
+            "* と ? のワイルドカードに対応。フォルダを含めるとフルパスで照合します"
+        }
+        RawKey::MappingSourceHint => {
+            "インクルードファイルから読み込まれたマッピングです。変更はそのファイルに保存されます。"
+        }
+        RawKey::ActiveLayersLabel => "レイヤー：",
+        RawKey::NoActiveLayer => "ベース",
     }
 }
@@ -88,7 +88,7 @@ pub(super) const fn translate(key: RawKey) -> &'static str {
         RawKey::ExitProgramBtn => "🚪  프로그램 종료",
         RawKey::CancelCloseBtn => "↩  취소",
         RawKey::ErrorTitle => "❌ 설정 오류",
-        RawKey::DuplicateTriggerError => "⚠ 이 트리거 키는 이미 존재합니다!",
+        RawKey::DuplicateTriggerHint => "⚠ 이 트리거 키에는 이미 매핑이 있습니다. 우선순위나 조건이 없으면 가장 최근 매핑만 작동합니다.",
         RawKey::DuplicateProcessError => "⚠ 이 프로세스는 이미 허용 목록에 있습니다!",
         RawKey::AboutInspired => "🌸 Kasugano Sora에서 영감을 받았습니다",
         RawKey::TurboOnHover => "터보 켜짐 - 자동 연타 활성화",
@@ -121,6 +121,8 @@ pub(super) const fn translate(key: RawKey) -> &'static str {
         RawKey::MouseScrollDirectionLabel => "🎡 스크롤 방향 🎡",
         RawKey::MouseScrollUp => "⬆ 위로 스크롤",
         RawKey::MouseScrollDown => "⬇ 아래로 스크롤",
+        RawKey::MouseScrollLeft => "⬅ 왼쪽으로 스크롤",
+        RawKey::MouseScrollRight => "➡ 오른쪽으로 스크롤",
         RawKey::MouseMiddleButton => "🖱 가운데 버튼",
         RawKey::SetMouseScrollDirectionHover => "마우스 스크롤 방향 설정",
         RawKey::SpeedLabel => "🚀 속도:",
@@ -224,5 +226,34 @@ pub(super) const fn translate(key: RawKey) -> &'static str {
         RawKey::RulePropsAppendPlaceholder => "클릭하여 키를 입력하세요",
         RawKey::RulePropsSave => "저장",
         RawKey::RulePropsCancel => "취소",
+        RawKey::ConfigRecoveryTitle => "⚠ 설정이 복구되었습니다",
+        RawKey::ConfigRecoveryIntro => {
+            "Config.toml을 불러올 수 없습니다. 유효한 항목은 모두 유지되었으며 아래 항목은 건너뛰었습니다."
+        }
+        RawKey::ConfigRecoveryBackupLabel => "원본 파일 저장 위치:",
+        RawKey::ConfigRecoverySkippedLabel => "건너뛴 항목:",
+        RawKey::ConfigRecoveryNothingSkipped => {
+            "건너뛴 항목이 없으며, 누락된 설정은 기본값으로 채웠습니다."
+        }
+        RawKey::ConfigMigrationTitle => "⚙ 설정이 업그레이드되었습니다",
+        RawKey::ConfigMigrationIntro => {
+            "Config.toml이 이전 형식이어서 현재 형식으로 업그레이드했습니다."
+        }
+        RawKey::ConfigMigrationChangesLabel => "변경 사항:",
+        RawKey::SettingsValidationFailed => "⚠ 저장할 수 없습니다. 먼저 다음 문제를 해결하세요:",
+        RawKey::ActiveProfileLabel => "프로필:",
+        RawKey::TrayProfiles => "프로필",
+        RawKey::ProcessBlacklistHint => "프로세스 차단 목록 (비어 있으면 전체 허용)",
+        RawKey::ProcessListModeLabel => "모드:",
+        RawKey::ProcessModeWhitelist => "허용 목록",
+        RawKey::ProcessModeBlacklist => "차단 목록",
+        RawKey::ProcessPatternHint => {
+            "* 및 ? 와일드카드 지원, 폴더를 포함하면 전체 경로로 비교합니다"
+        }
+        RawKey::MappingSourceHint => {
+            "포함 파일에서 불러온 매핑입니다. 변경 사항은 해당 파일에 저장됩니다."
+        }
+        RawKey::ActiveLayersLabel => "레이어:",
+        RawKey::NoActiveLayer => "기본",
     }
 }
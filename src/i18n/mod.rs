@@ -163,7 +163,7 @@ struct TranslationCache {
     cancel_close_button: &'static str,
     error_title: &'static str,
     error_close_button: &'static str,
-    duplicate_trigger_error: &'static str,
+    duplicate_trigger_hint: &'static str,
     duplicate_process_error: &'static str,
     about_version: &'static str,
     about_description_line1: &'static str,
@@ -205,6 +205,8 @@ struct TranslationCache {
     mouse_scroll_direction_label: &'static str,
     mouse_scroll_up: &'static str,
     mouse_scroll_down: &'static str,
+    mouse_scroll_left: &'static str,
+    mouse_scroll_right: &'static str,
     mouse_middle_button: &'static str,
     set_mouse_scroll_direction_hover: &'static str,
     speed_label: &'static str,
@@ -312,6 +314,39 @@ struct TranslationCache {
     rule_props_append_placeholder: &'static str,
     rule_props_save: &'static str,
     rule_props_cancel: &'static str,
+
+    // Config Recovery Dialog
+    config_recovery_title: &'static str,
+    config_recovery_intro: &'static str,
+    config_recovery_backup_label: &'static str,
+    config_recovery_skipped_label: &'static str,
+    config_recovery_nothing_skipped: &'static str,
+
+    // Config Migration Dialog
+    config_migration_title: &'static str,
+    config_migration_intro: &'static str,
+    config_migration_changes_label: &'static str,
+
+    // Config Validation
+    settings_validation_failed: &'static str,
+
+    // Profiles
+    active_profile_label: &'static str,
+    tray_profiles: &'static str,
+
+    // Process List Mode
+    process_blacklist_hint: &'static str,
+    process_list_mode_label: &'static str,
+    process_mode_whitelist: &'static str,
+    process_mode_blacklist: &'static str,
+    process_pattern_hint: &'static str,
+
+    // Include Files
+    mapping_source_hint: &'static str,
+
+    // Layers
+    active_layers_label: &'static str,
+    no_active_layer: &'static str,
 }
 
 /// Pre-built translation tables, one per supported language. Built at
@@ -433,7 +468,7 @@ impl TranslationCache {
             // Error Dialog
             error_title: get_raw_translation(lang, RawKey::ErrorTitle),
             error_close_button: get_raw_translation(lang, RawKey::Close),
-            duplicate_trigger_error: get_raw_translation(lang, RawKey::DuplicateTriggerError),
+            duplicate_trigger_hint: get_raw_translation(lang, RawKey::DuplicateTriggerHint),
             duplicate_process_error: get_raw_translation(lang, RawKey::DuplicateProcessError),
 
             // About Dialog
@@ -509,6 +544,8 @@ impl TranslationCache {
             ),
             mouse_scroll_up: get_raw_translation(lang, RawKey::MouseScrollUp),
             mouse_scroll_down: get_raw_translation(lang, RawKey::MouseScrollDown),
+            mouse_scroll_left: get_raw_translation(lang, RawKey::MouseScrollLeft),
+            mouse_scroll_right: get_raw_translation(lang, RawKey::MouseScrollRight),
             mouse_middle_button: get_raw_translation(lang, RawKey::MouseMiddleButton),
 
             // Hover hints
@@ -666,6 +703,51 @@ impl TranslationCache {
             ),
             rule_props_save: get_raw_translation(lang, RawKey::RulePropsSave),
             rule_props_cancel: get_raw_translation(lang, RawKey::RulePropsCancel),
+
+            // Config Recovery Dialog
+            config_recovery_title: get_raw_translation(lang, RawKey::ConfigRecoveryTitle),
+            config_recovery_intro: get_raw_translation(lang, RawKey::ConfigRecoveryIntro),
+            config_recovery_backup_label: get_raw_translation(
+                lang,
+                RawKey::ConfigRecoveryBackupLabel,
+            ),
+            config_recovery_skipped_label: get_raw_translation(
+                lang,
+                RawKey::ConfigRecoverySkippedLabel,
+            ),
+            config_recovery_nothing_skipped: get_raw_translation(
+                lang,
+                RawKey::ConfigRecoveryNothingSkipped,
+            ),
+
+            // Config Migration Dialog
+            config_migration_title: get_raw_translation(lang, RawKey::ConfigMigrationTitle),
+            config_migration_intro: get_raw_translation(lang, RawKey::ConfigMigrationIntro),
+            config_migration_changes_label: get_raw_translation(
+                lang,
+                RawKey::ConfigMigrationChangesLabel,
+            ),
+
+            // Config Validation
+            settings_validation_failed: get_raw_translation(lang, RawKey::SettingsValidationFailed),
+
+            // Profiles
+            active_profile_label: get_raw_translation(lang, RawKey::ActiveProfileLabel),
+            tray_profiles: get_raw_translation(lang, RawKey::TrayProfiles),
+
+            // Process List Mode
+            process_blacklist_hint: get_raw_translation(lang, RawKey::ProcessBlacklistHint),
+            process_list_mode_label: get_raw_translation(lang, RawKey::ProcessListModeLabel),
+            process_mode_whitelist: get_raw_translation(lang, RawKey::ProcessModeWhitelist),
+            process_mode_blacklist: get_raw_translation(lang, RawKey::ProcessModeBlacklist),
+            process_pattern_hint: get_raw_translation(lang, RawKey::ProcessPatternHint),
+
+            // Include Files
+            mapping_source_hint: get_raw_translation(lang, RawKey::MappingSourceHint),
+
+            // Layers
+            active_layers_label: get_raw_translation(lang, RawKey::ActiveLayersLabel),
+            no_active_layer: get_raw_translation(lang, RawKey::NoActiveLayer),
         }
     }
 }
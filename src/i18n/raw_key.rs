@@ -81,7 +81,7 @@ pub(super) enum RawKey {
     ExitProgramBtn,
     CancelCloseBtn,
     ErrorTitle,
-    DuplicateTriggerError,
+    DuplicateTriggerHint,
     DuplicateProcessError,
     AboutInspired,
     TurboOnHover,
@@ -114,6 +114,8 @@ pub(super) enum RawKey {
     MouseScrollDirectionLabel,
     MouseScrollUp,
     MouseScrollDown,
+    MouseScrollLeft,
+    MouseScrollRight,
     MouseMiddleButton,
     SetMouseScrollDirectionHover,
     SpeedLabel,
@@ -218,6 +220,39 @@ pub(super) enum RawKey {
     RulePropsAppendPlaceholder,
     RulePropsSave,
     RulePropsCancel,
+
+    // Config Recovery Dialog
+    ConfigRecoveryTitle,
+    ConfigRecoveryIntro,
+    ConfigRecoveryBackupLabel,
+    ConfigRecoverySkippedLabel,
+    ConfigRecoveryNothingSkipped,
+
+    // Config Migration Dialog
+    ConfigMigrationTitle,
+    ConfigMigrationIntro,
+    ConfigMigrationChangesLabel,
+
+    // Config Validation
+    SettingsValidationFailed,
+
+    // Profiles
+    ActiveProfileLabel,
+    TrayProfiles,
+
+    // Process List Mode
+    ProcessBlacklistHint,
+    ProcessListModeLabel,
+    ProcessModeWhitelist,
+    ProcessModeBlacklist,
+    ProcessPatternHint,
+
+    // Include Files
+    MappingSourceHint,
+
+    // Layers
+    ActiveLayersLabel,
+    NoActiveLayer,
 }
 
 /// Returns the translation for `key` in `lang`, resolved to a compile-time
@@ -134,8 +134,8 @@ fn test_error_message_translations() {
     let translations = CachedTranslations::new(Language::English);
 
     assert_eq!(
-        translations.duplicate_trigger_error(),
-        "⚠ This trigger key already exists!"
+        translations.duplicate_trigger_hint(),
+        "⚠ This trigger already has a mapping. Without priority or conditions, only the newest one fires."
     );
 }
 
@@ -247,6 +247,7 @@ fn test_translation_consistency_across_languages() {
 }
 
 #[test]
+#[allow(clippy::clone_on_copy)]
 fn test_cached_translations_cloning() {
     let original = CachedTranslations::new(Language::English);
     let cloned = original.clone();
@@ -80,7 +80,7 @@ pub(super) const fn translate(key: RawKey) -> &'static str {
         RawKey::ExitProgramBtn => "🚪  退出程序",
         RawKey::CancelCloseBtn => "↩  取消",
         RawKey::ErrorTitle => "❌ 配置错误",
-        RawKey::DuplicateTriggerError => "⚠ 该触发键已存在！",
+        RawKey::DuplicateTriggerHint => "⚠ 该触发键已有映射。未设置优先级或条件时，只有最新的映射生效。",
         RawKey::DuplicateProcessError => "⚠ 该进程已在白名单中！",
         RawKey::AboutInspired => "🌸 灵感来源: 春日野穹",
         RawKey::TurboOnHover => "连发开启 - 自动重复输入",
@@ -113,6 +113,8 @@ pub(super) const fn translate(key: RawKey) -> &'static str {
         RawKey::MouseScrollDirectionLabel => "🎡 滚动方向 🎡",
         RawKey::MouseScrollUp => "⬆ 向上滚动",
         RawKey::MouseScrollDown => "⬇ 向下滚动",
+        RawKey::MouseScrollLeft => "⬅ 向左滚动",
+        RawKey::MouseScrollRight => "➡ 向右滚动",
         RawKey::MouseMiddleButton => "🖱 鼠标中键",
         RawKey::SetMouseScrollDirectionHover => "设置鼠标滚动方向",
         RawKey::SpeedLabel => "🚀 速度:",
@@ -212,5 +214,26 @@ pub(super) const fn translate(key: RawKey) -> &'static str {
         RawKey::RulePropsAppendPlaceholder => "点击录制按键",
         RawKey::RulePropsSave => "保存",
         RawKey::RulePropsCancel => "取消",
+        RawKey::ConfigRecoveryTitle => "⚠ 配置已恢复",
+        RawKey::ConfigRecoveryIntro => {
+            "无法加载 Config.toml。仍然有效的内容已保留，以下条目已被跳过。"
+        }
+        RawKey::ConfigRecoveryBackupLabel => "原文件已保存为：",
+        RawKey::ConfigRecoverySkippedLabel => "已跳过的条目：",
+        RawKey::ConfigRecoveryNothingSkipped => "没有条目被跳过，缺失的设置已使用默认值补全。",
+        RawKey::ConfigMigrationTitle => "⚙ 配置已升级",
+        RawKey::ConfigMigrationIntro => "Config.toml 使用的是旧版格式，已升级为当前格式。",
+        RawKey::ConfigMigrationChangesLabel => "变更内容：",
+        RawKey::SettingsValidationFailed => "⚠ 无法保存，请先修正以下问题：",
+        RawKey::ActiveProfileLabel => "配置方案：",
+        RawKey::TrayProfiles => "配置方案",
+        RawKey::ProcessBlacklistHint => "进程黑名单（空=全部启用）",
+        RawKey::ProcessListModeLabel => "模式：",
+        RawKey::ProcessModeWhitelist => "白名单",
+        RawKey::ProcessModeBlacklist => "黑名单",
+        RawKey::ProcessPatternHint => "支持 * 和 ? 通配符；包含文件夹路径时匹配完整路径",
+        RawKey::MappingSourceHint => "来自包含文件的映射。修改会保存回该文件。",
+        RawKey::ActiveLayersLabel => "层：",
+        RawKey::NoActiveLayer => "基础",
     }
 }
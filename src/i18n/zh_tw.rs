@@ -80,7 +80,7 @@ pub(super) const fn translate(key: RawKey) -> &'static str {
         RawKey::ExitProgramBtn => "🚪  退出程式",
         RawKey::CancelCloseBtn => "↩  取消",
         RawKey::ErrorTitle => "❌ 配置錯誤",
-        RawKey::DuplicateTriggerError => "⚠ 該觸發鍵已存在！",
+        RawKey::DuplicateTriggerHint => "⚠ 該觸發鍵已有映射。未設定優先級或條件時，只有最新的映射生效。",
         RawKey::DuplicateProcessError => "⚠ 該進程已在白名單中！",
         RawKey::AboutInspired => "🌸 靈感來源: 春日野穹",
         RawKey::TurboOnHover => "連發開啟 - 自動重複輸入",
@@ -113,6 +113,8 @@ pub(super) const fn translate(key: RawKey) -> &'static str {
         RawKey::MouseScrollDirectionLabel => "🎡 滾動方向 🎡",
         RawKey::MouseScrollUp => "⬆ 向上滾動",
         RawKey::MouseScrollDown => "⬇ 向下滾動",
+        RawKey::MouseScrollLeft => "⬅ 向左滾動",
+        RawKey::MouseScrollRight => "➡ 向右滾動",
         RawKey::MouseMiddleButton => "🖱 滑鼠中鍵",
         RawKey::SetMouseScrollDirectionHover => "設定滑鼠滾動方向",
         RawKey::SpeedLabel => "🚀 速度:",
@@ -212,5 +214,26 @@ pub(super) const fn translate(key: RawKey) -> &'static str {
         RawKey::RulePropsAppendPlaceholder => "點擊錄製按鍵",
         RawKey::RulePropsSave => "儲存",
         RawKey::RulePropsCancel => "取消",
+        RawKey::ConfigRecoveryTitle => "⚠ 設定已復原",
+        RawKey::ConfigRecoveryIntro => {
+            "無法載入 Config.toml。仍然有效的內容已保留，以下項目已被略過。"
+        }
+        RawKey::ConfigRecoveryBackupLabel => "原始檔案已儲存為：",
+        RawKey::ConfigRecoverySkippedLabel => "已略過的項目：",
+        RawKey::ConfigRecoveryNothingSkipped => "沒有項目被略過，缺少的設定已使用預設值補齊。",
+        RawKey::ConfigMigrationTitle => "⚙ 設定已升級",
+        RawKey::ConfigMigrationIntro => "Config.toml 使用的是舊版格式，已升級為目前格式。",
+        RawKey::ConfigMigrationChangesLabel => "變更內容：",
+        RawKey::SettingsValidationFailed => "⚠ 無法儲存，請先修正以下問題：",
+        RawKey::ActiveProfileLabel => "設定檔：",
+        RawKey::TrayProfiles => "設定檔",
+        RawKey::ProcessBlacklistHint => "程序黑名單（空=全部啟用）",
+        RawKey::ProcessListModeLabel => "模式：",
+        RawKey::ProcessModeWhitelist => "白名單",
+        RawKey::ProcessModeBlacklist => "黑名單",
+        RawKey::ProcessPatternHint => "支援 * 和 ? 萬用字元；包含資料夾路徑時比對完整路徑",
+        RawKey::MappingSourceHint => "來自包含檔案的映射。修改會儲存回該檔案。",
+        RawKey::ActiveLayersLabel => "層：",
+        RawKey::NoActiveLayer => "基礎",
     }
 }
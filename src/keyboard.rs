@@ -10,133 +10,11 @@ use windows::Win32::Foundation::{LPARAM, LRESULT, WPARAM};
 use windows::Win32::System::Threading::GetCurrentThreadId;
 use windows::Win32::UI::WindowsAndMessaging::*;
 
-use crate::state::{AppState, InputDevice, InputEvent};
+use crate::state::{
+    AppState, GestureOutcome, InputDevice, InputEvent, TapHoldOutcome, TurboWorker,
+};
 use crate::util::{likely, unlikely};
 
-/// Per-device simulation state held by a turbo-worker's local cache.
-/// `synthetic_repeat` marks triggers that do not produce Windows key-repeat
-/// events such as mouse, XInput, and Raw Input. For those the worker drives
-/// its own repeat pump using `first_press` and the system keyboard-repeat
-/// settings. `is_holdable` is cached at insert time so the hot path skips
-/// rescanning `MultipleActions` on every Pressed event and synthetic-repeat
-/// tick. `is_sequential` marks targets that must fire a full `simulate_action`
-/// cycle per repeat tick rather than a `simulate_repeat_press`, so sequential
-/// targets loop their whole key chain on every Windows repeat.
-struct DeviceSimState<A> {
-    last_time: Instant,
-    interval: u64,
-    event_duration: u64,
-    target_action: A,
-    turbo_enabled: bool,
-    first_press: Instant,
-    synthetic_repeat: bool,
-    is_holdable: bool,
-    is_sequential: bool,
-    /// True when the target is `OutputAction::MappingHold`. The first
-    /// press plays the whole sequence once and leaves the held subset
-    /// pressed; subsequent auto-repeat ticks drive `simulate_hold_repeat`
-    /// instead of replaying the sequence, and release drops the held set.
-    is_sequential_hold: bool,
-}
-
-impl<A> DeviceSimState<A> {
-    /// Creates a turbo entry. Turbo entries never drive the synthetic
-    /// repeat pump because `simulate_action` already paces the output
-    /// cycle itself.
-    ///
-    /// `is_sequential_hold` must be set per-target so the worker's turbo
-    /// dispatch in `handle_input_event` and `handle_timeout` routes the
-    /// recurring tick to `simulate_hold_cycle` for `MappingHold` targets
-    /// rather than falling through to `simulate_action`. The latter
-    /// presses every body action without releasing the append list,
-    /// leaving the append refcount permanently above zero after the
-    /// second tick.
-    #[inline(always)]
-    fn new_turbo(
-        target_action: A,
-        interval: u64,
-        event_duration: u64,
-        now: Instant,
-        is_sequential_hold: bool,
-    ) -> Self {
-        Self {
-            last_time: now,
-            interval,
-            event_duration,
-            target_action,
-            turbo_enabled: true,
-            first_press: now,
-            synthetic_repeat: false,
-            is_holdable: false,
-            is_sequential: false,
-            is_sequential_hold,
-        }
-    }
-
-    /// Creates a non-turbo entry. The caller must have already classified
-    /// the trigger and the target action.
-    #[inline(always)]
-    #[allow(clippy::too_many_arguments)]
-    fn new_non_turbo(
-        target_action: A,
-        interval: u64,
-        event_duration: u64,
-        now: Instant,
-        synthetic_repeat: bool,
-        is_holdable: bool,
-        is_sequential: bool,
-        is_sequential_hold: bool,
-    ) -> Self {
-        Self {
-            last_time: now,
-            interval,
-            event_duration,
-            target_action,
-            turbo_enabled: false,
-            first_press: now,
-            synthetic_repeat,
-            is_holdable,
-            is_sequential,
-            is_sequential_hold,
-        }
-    }
-}
-
-/// Returns true for output actions that have a meaningful held state:
-/// keyboard keys, key combos, mouse buttons, and recursive compositions of
-/// those. Movement, scroll, and sequential actions return false because
-/// `simulate_initial_press` cannot represent their held semantics.
-#[inline(always)]
-fn is_holdable_action(action: &crate::state::OutputAction) -> bool {
-    use crate::state::OutputAction;
-    match action {
-        OutputAction::KeyboardKey(_) | OutputAction::KeyCombo(_) | OutputAction::MouseButton(_) => {
-            true
-        }
-        OutputAction::MultipleActions(actions) => actions.iter().all(is_holdable_action),
-        _ => false,
-    }
-}
-
-/// Returns true for targets that must loop a full `simulate_action` pass
-/// on each repeat tick. Only the top-level `SequentialActions` qualifies;
-/// nested uses inside `MultipleActions` are not supported because the
-/// worker can only drive one simulation cursor per device.
-#[inline(always)]
-fn is_sequential_action(action: &crate::state::OutputAction) -> bool {
-    matches!(action, crate::state::OutputAction::SequentialActions(..))
-}
-
-/// Returns true for targets that play the sequence once and then hold a
-/// chosen subset until release. Classified separately from
-/// `is_sequential_action` so the worker routes them to
-/// `simulate_hold_repeat` / `simulate_hold_release` instead of replaying
-/// the sequence on every auto-repeat tick.
-#[inline(always)]
-fn is_sequential_hold_action(action: &crate::state::OutputAction) -> bool {
-    matches!(action, crate::state::OutputAction::MappingHold { .. })
-}
-
 unsafe impl Send for KeyboardHook {}
 
 // Multi-worker dispatcher supporting both keyboard and mouse
@@ -182,11 +60,73 @@ impl crate::state::EventDispatcher for WorkerPool {
 
     /// Dispatch events using pre-computed lookup (hot path)
     #[inline(always)]
-    fn dispatch(&self, event: InputEvent) {
+    fn dispatch(&self, event: InputEvent) -> bool {
         if unlikely(self.workers.is_empty()) {
-            return;
+            return true;
+        }
+
+        // Gestures are recognized before anything else sees the presses
+        // they are made of.
+        let event = if unlikely(self.state.has_gestures()) {
+            match self.state.handle_gesture_event(&event) {
+                GestureOutcome::Forward => event,
+                GestureOutcome::PassThrough => return false,
+                GestureOutcome::Consumed => return true,
+                GestureOutcome::Replace(replacement) => replacement,
+                GestureOutcome::Tap => {
+                    let (InputEvent::Pressed(device)
+                    | InputEvent::Released(device)
+                    | InputEvent::RetapHold(device)) = event;
+                    self.dispatch_tap(device);
+                    return true;
+                }
+            }
+        } else {
+            event
+        };
+
+        // Layer switches change state on the hook thread, so a key
+        // pressed right after one already sees the new layer.
+        if unlikely(self.state.has_layer_switches()) && self.state.handle_layer_event(&event) {
+            return true;
         }
 
+        if unlikely(self.state.has_tap_holds()) {
+            match self.state.handle_tap_hold_event(&event) {
+                TapHoldOutcome::Forward => {}
+                TapHoldOutcome::Consumed => return true,
+                TapHoldOutcome::Tap => {
+                    let (InputEvent::Pressed(device)
+                    | InputEvent::Released(device)
+                    | InputEvent::RetapHold(device)) = event;
+                    self.route(InputEvent::Pressed(device.clone()));
+                    self.route(InputEvent::Released(device));
+                    return true;
+                }
+            }
+        }
+
+        self.route(event);
+        true
+    }
+
+    fn dispatch_tap(&self, device: InputDevice) {
+        for event in [
+            InputEvent::Pressed(device.clone()),
+            InputEvent::Released(device),
+        ] {
+            if unlikely(self.state.has_layer_switches()) && self.state.handle_layer_event(&event) {
+                continue;
+            }
+            self.route(event);
+        }
+    }
+}
+
+impl WorkerPool {
+    /// Sends `event` to the worker that owns its device.
+    #[inline(always)]
+    fn route(&self, event: InputEvent) {
         let device = match &event {
             InputEvent::Pressed(d) | InputEvent::Released(d) | InputEvent::RetapHold(d) => d,
         };
@@ -200,11 +140,16 @@ impl crate::state::EventDispatcher for WorkerPool {
                 // Cache hit
                 (cached & 0x01) != 0
             } else {
-                // Cache miss - query and update cache
+                // Cache miss - query and update cache. Reads the table
+                // directly so a mapping scoped to another process is still
+                // routed (and cached) correctly. Only triggers whose every
+                // mapping moves or scrolls go to the mouse worker, so press
+                // and release always reach the same worker.
                 let is_action = self
                     .state
-                    .get_input_mapping(device)
-                    .is_some_and(|mapping| Self::is_mouse_action(&mapping.target_action));
+                    .input_mappings
+                    .read_sync(device, |_, mapping| Self::all_mouse_actions(mapping))
+                    .unwrap_or(false);
                 self.mouse_action_cache[*vk as usize]
                     .store(if is_action { 0x01 } else { 0x00 }, Ordering::Relaxed);
                 is_action
@@ -212,8 +157,9 @@ impl crate::state::EventDispatcher for WorkerPool {
         } else {
             // Slow path: mouse buttons, combos, and generic devices
             self.state
-                .get_input_mapping(device)
-                .is_some_and(|mapping| Self::is_mouse_action(&mapping.target_action))
+                .input_mappings
+                .read_sync(device, |_, mapping| Self::all_mouse_actions(mapping))
+                .unwrap_or(false)
         };
 
         // Route to dedicated mouse move worker or distribute normally
@@ -221,32 +167,45 @@ impl crate::state::EventDispatcher for WorkerPool {
             let _ = self.mouse_move_worker.send(event);
         } else {
             // Load-balanced distribution across worker threads
-            let worker_idx = match device {
-                InputDevice::Keyboard(vk) => (*vk as usize) % self.worker_count,
-                InputDevice::Mouse(button) => (*button as usize) % self.worker_count,
-                InputDevice::MouseMove(direction) => (*direction as usize) % self.worker_count,
-                InputDevice::KeyCombo(keys) => {
-                    keys.last().map_or(0, |&k| (k as usize) % self.worker_count)
-                }
-                InputDevice::XInputCombo {
-                    device_type,
-                    button_ids,
-                } => {
-                    let first_button_id = button_ids.first().copied().unwrap_or(0);
-                    Self::hash_generic_device(device_type, first_button_id as u64)
-                        % self.worker_count
-                }
-                InputDevice::GenericDevice {
-                    device_type,
-                    button_id,
-                } => Self::hash_generic_device(device_type, *button_id) % self.worker_count,
-            };
+            let worker_idx = Self::device_slot(device) % self.worker_count;
             let _ = self.workers[worker_idx].send(event);
         }
     }
-}
 
-impl WorkerPool {
+    /// Stable per-device value the worker index is derived from, so every
+    /// event of a device reaches the same worker.
+    #[inline]
+    fn device_slot(device: &InputDevice) -> usize {
+        match device {
+            InputDevice::Keyboard(vk) => *vk as usize,
+            InputDevice::Mouse(button) => *button as usize,
+            InputDevice::MouseMove(direction) => *direction as usize,
+            InputDevice::Wheel(direction) | InputDevice::WheelCombo(_, direction) => {
+                *direction as usize
+            }
+            InputDevice::KeyCombo(keys) => keys.last().map_or(0, |&k| k as usize),
+            InputDevice::XInputCombo {
+                device_type,
+                button_ids,
+            } => {
+                let first_button_id = button_ids.first().copied().unwrap_or(0);
+                Self::hash_generic_device(device_type, first_button_id as u64)
+            }
+            InputDevice::GenericDevice {
+                device_type,
+                button_id,
+            } => Self::hash_generic_device(device_type, *button_id),
+            InputDevice::Sequence(inputs) => inputs
+                .iter()
+                .fold(crate::util::fnv64::OFFSET_BASIS, |hash, input| {
+                    crate::util::fnv1a_hash_u64(hash, Self::device_slot(input) as u64)
+                }) as usize,
+            // With the input's own slot, a gesture and the plain press
+            // it interrupts are handled in order.
+            InputDevice::Gesture(input, _) => Self::device_slot(input),
+        }
+    }
+
     /// Check if OutputAction contains mouse movement or scroll
     #[inline(always)]
     fn is_mouse_action(action: &crate::state::OutputAction) -> bool {
@@ -254,7 +213,7 @@ impl WorkerPool {
         match action {
             OutputAction::MouseMove(_, _) | OutputAction::MouseScroll(_, _) => true,
             OutputAction::MultipleActions(actions) => actions.iter().any(Self::is_mouse_action),
-            OutputAction::SequentialActions(_, _) => false,
+            OutputAction::SequentialActions(_, _) | OutputAction::TimedSequence(_) => false,
             // MappingHold is always routed to turbo_worker so the
             // worker can own both the synchronous body playback and the
             // held-subset lifecycle without contending with the
@@ -264,6 +223,18 @@ impl WorkerPool {
         }
     }
 
+    /// Whether every mapping on a trigger moves or scrolls the mouse while
+    /// the trigger is held.
+    #[inline(always)]
+    fn all_mouse_actions(mapping: &crate::state::InputMappingInfo) -> bool {
+        // Latched outputs and bursts need the turbo workers' state.
+        mapping.candidates().all(|candidate| {
+            Self::is_mouse_action(&candidate.target_action)
+                && candidate.toggle.is_none()
+                && candidate.burst.is_none()
+        })
+    }
+
     /// Hash generic device for worker distribution using FNV-1a.
     #[inline(always)]
     fn hash_generic_device(device_type: &crate::state::DeviceType, button_id: u64) -> usize {
@@ -407,13 +378,17 @@ impl KeyboardHook {
         }
 
         let kb_struct = unsafe { &*(l_param.0 as *mut KBDLLHOOKSTRUCT) };
+        let state = crate::state::get_global_state();
+        if let Some(state) = state {
+            state.track_lock_key(w_param.0 as u32, kb_struct.vkCode);
+        }
 
         // Skip simulated key events
         if kb_struct.dwExtraInfo == crate::state::SIMULATED_EVENT_MARKER {
             return unsafe { CallNextHookEx(None, code, w_param, l_param) };
         }
 
-        if let Some(state) = crate::state::get_global_state() {
+        if let Some(state) = state {
             let should_block = state.handle_key_event(w_param.0 as u32, kb_struct.vkCode);
             if should_block {
                 return LRESULT(1); // block raw key event
@@ -424,9 +399,7 @@ impl KeyboardHook {
     }
 
     fn turbo_worker(_worker_id: usize, state: Arc<AppState>, event_rx: Receiver<InputEvent>) {
-        use crate::state::OutputAction;
-        let mut device_states: HashMap<InputDevice, DeviceSimState<OutputAction>> =
-            HashMap::with_capacity(16);
+        let mut worker = TurboWorker::new();
 
         let timeout_duration = Duration::from_millis(state.input_timeout());
 
@@ -436,252 +409,31 @@ impl KeyboardHook {
             // paths never call `is_process_whitelisted` themselves). The
             // inner 50ms cache keeps the real syscall rate bounded.
             let _ = state.is_process_whitelisted();
+            // Profiles the rules picked on the hook threads are built here.
+            state.apply_pending_profile_switch();
 
             if unlikely(state.is_paused() || !state.last_was_whitelisted()) {
                 // Either user-paused or the foreground app left the
                 // whitelist. Drop worker-local state so pending turbo
                 // cycles stop, and drain the channel so stale events
                 // don't replay after the idle period.
-                if !device_states.is_empty() {
-                    device_states.clear();
+                if !worker.is_empty() {
+                    worker.clear();
                 }
                 while event_rx.try_recv().is_ok() {}
-                thread::sleep(Duration::from_millis(50));
+                state.clock().sleep(Duration::from_millis(50));
                 continue;
             }
 
-            match event_rx.recv_timeout(timeout_duration) {
-                Ok(event) => Self::handle_input_event(&state, &mut device_states, event),
-                Err(_) => Self::handle_timeout(&state, &mut device_states),
-            }
-        }
-    }
-
-    /// Returns true for trigger devices that do not produce native Windows
-    /// key-repeat events. Such triggers need a worker-driven synthetic
-    /// repeat to emulate a held key in non-turbo mode. The match uses a
-    /// positive whitelist so any future `InputDevice` variant forces a
-    /// compile error here instead of silently inheriting synthetic repeat.
-    #[inline(always)]
-    fn needs_synthetic_repeat(device: &InputDevice) -> bool {
-        match device {
-            InputDevice::Keyboard(_) | InputDevice::KeyCombo(_) => false,
-            InputDevice::Mouse(_)
-            | InputDevice::MouseMove(_)
-            | InputDevice::XInputCombo { .. }
-            | InputDevice::GenericDevice { .. } => true,
-        }
-    }
-
-    /// Processes a single input event, applying turbo pacing or passing
-    /// through a press/release for non-turbo mappings.
-    #[inline]
-    fn handle_input_event(
-        state: &AppState,
-        device_states: &mut HashMap<InputDevice, DeviceSimState<crate::state::OutputAction>>,
-        event: InputEvent,
-    ) {
-        // Re-check the pause / whitelist state before firing any ref-counted
-        // press. Closes the narrow race where the handler thread toggled
-        // `is_paused` between its own top-level guard and `pool.dispatch`,
-        // letting a stale Pressed slip past early-return. Dropping the
-        // event here avoids `simulate_initial_press` leaking a ref count
-        // that no matching Released will arrive to balance.
-        if unlikely(state.is_paused() || !state.last_was_whitelisted()) {
-            return;
-        }
-
-        match event {
-            InputEvent::Pressed(device) => {
-                let now = Instant::now();
-
-                if let Some(sim) = device_states.get_mut(&device) {
-                    if sim.turbo_enabled {
-                        if likely(
-                            now.duration_since(sim.last_time)
-                                >= Duration::from_millis(sim.interval),
-                        ) {
-                            if sim.is_sequential_hold {
-                                // Turbo + MappingHold cycles only the
-                                // user-chosen hold subset + append keys.
-                                state.simulate_hold_cycle(&sim.target_action, sim.event_duration);
-                            } else {
-                                state
-                                    .simulate_action(sim.target_action.clone(), sim.event_duration);
-                            }
-                            sim.last_time = now;
-                        }
-                    } else if !sim.synthetic_repeat {
-                        // Keyboard-like trigger rides Windows' own key-repeat
-                        // stream. Each arriving Pressed maps to one WM_KEYDOWN
-                        // delivery at Windows pacing.
-                        if sim.is_sequential_hold {
-                            // Sequence already played; refresh KEYDOWN on
-                            // the held subset so the OS auto-repeat stream
-                            // stays warm without re-triggering the body.
-                            state.simulate_hold_repeat(&sim.target_action);
-                            sim.last_time = now;
-                        } else if sim.is_sequential {
-                            // Sequential target runs a full press/release
-                            // cycle per tick so the whole key chain loops.
-                            state.simulate_action(sim.target_action.clone(), sim.event_duration);
-                            sim.last_time = now;
-                        } else if sim.is_holdable {
-                            // Ref-neutral repeat: the key is already held
-                            // (refcount >= 1 from the initial press), so we
-                            // only need to re-emit KEYDOWN to keep Windows'
-                            // auto-repeat stream alive.
-                            state.simulate_repeat_press(&sim.target_action);
-                            sim.last_time = now;
-                        }
-                        // Non-holdable non-sequential targets (MouseMove /
-                        // MouseScroll) have no repeat semantics; drop the tick.
-                    }
-                    // Non-keyboard triggers arrive at event-source rate which
-                    // is far faster than Windows key repeat. Fall through
-                    // without firing so handle_timeout can drive the synthetic
-                    // repeat at the real OS delay and rate.
-                } else if let Some(mapping) = state.get_input_mapping(&device) {
-                    let target_action_clone = mapping.target_action.clone();
-                    let turbo_enabled = mapping.turbo_enabled;
-                    let is_sequential = is_sequential_action(&mapping.target_action);
-                    let is_sequential_hold = is_sequential_hold_action(&mapping.target_action);
-
-                    let sim = if turbo_enabled {
-                        DeviceSimState::new_turbo(
-                            mapping.target_action,
-                            mapping.interval,
-                            mapping.event_duration,
-                            now,
-                            is_sequential_hold,
-                        )
-                    } else {
-                        let synthetic_repeat = Self::needs_synthetic_repeat(&device);
-                        let is_holdable = is_holdable_action(&mapping.target_action);
-                        DeviceSimState::new_non_turbo(
-                            mapping.target_action,
-                            mapping.interval,
-                            mapping.event_duration,
-                            now,
-                            synthetic_repeat,
-                            is_holdable,
-                            is_sequential,
-                            is_sequential_hold,
-                        )
-                    };
-                    device_states.insert(device, sim);
-
-                    if turbo_enabled {
-                        if is_sequential_hold {
-                            // Turbo's first shot only needs to pulse the
-                            // sequence-properties subset; the full body
-                            // is not played at all in turbo mode.
-                            state.simulate_hold_cycle(&target_action_clone, mapping.event_duration);
-                        } else {
-                            state.simulate_action(target_action_clone, mapping.event_duration);
-                        }
-                    } else if is_sequential || is_sequential_hold {
-                        // First fire of a sequential (loop or hold) target
-                        // runs the whole chain. For MappingHold, the
-                        // simulate_action arm leaves the held subset and
-                        // append list pressed for follow-up repeats.
-                        state.simulate_action(target_action_clone, mapping.event_duration);
-                    } else {
-                        // Initial press increments per-key ref counts so
-                        // overlapping triggers (e.g. diagonal mouse-move
-                        // merge) can share a held key without racing.
-                        state.simulate_initial_press(&target_action_clone);
-                    }
-                }
-            }
-            InputEvent::Released(device) => {
-                if let Some(sim) = device_states.get(&device)
-                    && !sim.turbo_enabled
-                {
-                    if sim.is_sequential_hold {
-                        // Drop the held subset + append list. Sequential
-                        // body actions already self-balanced inside
-                        // simulate_action, so only the pending hold set
-                        // needs decrement + KEYUP.
-                        state.simulate_hold_release(&sim.target_action);
-                    } else if !sim.is_sequential {
-                        // Pure SequentialActions self-balances inside each
-                        // simulate_action pass, so no extra release is
-                        // needed. Everything else decrements here.
-                        state.simulate_release(&sim.target_action);
-                    }
-                }
-                device_states.remove(&device);
-            }
-            InputEvent::RetapHold(device) => {
-                // Same-direction re-arm: release the current hold, wait the
-                // mapping's interval, then press again. Polling-based games
-                // can observe the intermediate keyup so double-tap detection
-                // fires. Delay is per-mapping so the user tunes it together
-                // with turbo rate.
-                if let Some(sim) = device_states.get_mut(&device) {
-                    let delay_ms = sim.interval;
-                    let target = sim.target_action.clone();
-                    state.simulate_release(&target);
-                    if delay_ms > 0 {
-                        thread::sleep(Duration::from_millis(delay_ms));
-                    }
-                    // Re-seed the refcount-backed press so the target is
-                    // treated as freshly held after the retap window.
-                    state.simulate_initial_press(&target);
-                    let now = Instant::now();
-                    sim.first_press = now;
-                    sim.last_time = now;
-                }
-                // If the device is not currently held, RetapHold is a no-op.
-            }
-        }
-    }
-
-    /// Fires pending turbo events and non-turbo synthetic repeats.
-    #[inline]
-    fn handle_timeout(
-        state: &AppState,
-        device_states: &mut HashMap<InputDevice, DeviceSimState<crate::state::OutputAction>>,
-    ) {
-        if unlikely(device_states.is_empty()) {
-            return;
-        }
-
-        let now = Instant::now();
-        let repeat_delay = Duration::from_millis(state.kb_repeat_delay_ms());
-        let repeat_interval = Duration::from_millis(state.kb_repeat_interval_ms());
-
-        for sim in device_states.values_mut() {
-            if sim.turbo_enabled {
-                if likely(now.duration_since(sim.last_time) >= Duration::from_millis(sim.interval))
-                {
-                    if sim.is_sequential_hold {
-                        state.simulate_hold_cycle(&sim.target_action, sim.event_duration);
-                    } else {
-                        state.simulate_action(sim.target_action.clone(), sim.event_duration);
-                    }
-                    sim.last_time = now;
-                }
-            } else if sim.synthetic_repeat
-                && (sim.is_holdable || sim.is_sequential || sim.is_sequential_hold)
-                && now.duration_since(sim.first_press) >= repeat_delay
-                && now.duration_since(sim.last_time) >= repeat_interval
-            {
-                if sim.is_sequential_hold {
-                    // Drive the OS auto-repeat for the held subset without
-                    // replaying the sequence body.
-                    state.simulate_hold_repeat(&sim.target_action);
-                } else if sim.is_sequential {
-                    state.simulate_action(sim.target_action.clone(), sim.event_duration);
-                } else {
-                    // Synthetic repeat keeps the OS auto-repeat stream
-                    // warm. The key is already ref-counted from the
-                    // initial press, so only the KEYDOWN signal goes out.
-                    state.simulate_repeat_press(&sim.target_action);
-                }
-                sim.last_time = now;
+            match crate::clock::recv_timeout(state.clock(), &event_rx, timeout_duration) {
+                Ok(event) => worker.handle_input_event(&state, event),
+                Err(_) => worker.handle_timeout(&state),
             }
+            // Tap-hold triggers get no events while they are held still,
+            // so their tapping terms run out on the workers' ticks, as do
+            // tap gaps and long presses.
+            state.expire_tap_holds();
+            state.expire_gestures();
         }
     }
 
@@ -694,7 +446,7 @@ impl KeyboardHook {
         let mut direction_devices: [Option<InputDevice>; 8] =
             [None, None, None, None, None, None, None, None];
         let mut direction_intervals: [u64; 8] = [0; 8];
-        let mut direction_last_times: [Instant; 8] = [Instant::now(); 8];
+        let mut direction_last_times: [Instant; 8] = [state.clock().now(); 8];
         let mut direction_turbo: [bool; 8] = [false; 8];
 
         // Pre-computed normalized direction vectors
@@ -733,7 +485,7 @@ impl KeyboardHook {
         ];
         let mut scroll_speeds: [i32; 4] = [1; 4];
         let mut scroll_intervals: [u64; 4] = [50; 4];
-        let mut scroll_last_times: [Instant; 4] = [Instant::now(); 4];
+        let mut scroll_last_times: [Instant; 4] = [state.clock().now(); 4];
         let mut scroll_turbo: [bool; 4] = [false; 4];
 
         // Local cache: maps device to action type
@@ -758,11 +510,11 @@ impl KeyboardHook {
                 first_speed = 5;
                 mapping_cache.clear();
                 while event_rx.try_recv().is_ok() {}
-                thread::sleep(Duration::from_millis(50));
+                state.clock().sleep(Duration::from_millis(50));
                 continue;
             }
 
-            match event_rx.recv_timeout(timeout_duration) {
+            match crate::clock::recv_timeout(state.clock(), &event_rx, timeout_duration) {
                 Ok(event) => {
                     Self::handle_mouse_action_event(
                         &state,
@@ -789,6 +541,7 @@ impl KeyboardHook {
                     // Process timeout for turbo-enabled movements
                     if active_directions != 0 {
                         Self::execute_movement_bitflags(
+                            &state,
                             active_directions,
                             &direction_intervals,
                             &mut direction_last_times,
@@ -895,13 +648,14 @@ impl KeyboardHook {
 
         if !move_directions.is_empty() {
             Self::execute_movement_immediate_bitflags(
+                state,
                 *active_directions,
                 direction_vectors,
                 move_speed,
             );
             // Sync all active turbo directions using current time to prevent
             // premature turbo firing due to stale timestamp
-            let sync_time = Instant::now();
+            let sync_time = state.clock().now();
             for i in 0..8 {
                 if (*active_directions & (1 << i)) != 0 && direction_turbo[i] {
                     direction_last_times[i] = sync_time;
@@ -963,7 +717,7 @@ impl KeyboardHook {
             return;
         }
 
-        let now = Instant::now();
+        let now = state.clock().now();
 
         match event {
             InputEvent::Pressed(device) => {
@@ -1025,11 +779,12 @@ impl KeyboardHook {
                     if !direction_turbo[idx] {
                         direction_last_times[idx] = now;
                         Self::execute_movement_immediate_bitflags(
+                            state,
                             *active_directions,
                             direction_vectors,
                             *first_speed,
                         );
-                        let sync_time = Instant::now();
+                        let sync_time = state.clock().now();
                         for i in 0..8 {
                             if (*active_directions & (1 << i)) != 0 && direction_turbo[i] {
                                 direction_last_times[i] = sync_time;
@@ -1056,11 +811,12 @@ impl KeyboardHook {
                     direction_turbo[dir_idx] = turbo_enabled;
 
                     Self::execute_movement_immediate_bitflags(
+                        state,
                         *active_directions,
                         direction_vectors,
                         *first_speed,
                     );
-                    let sync_time = Instant::now();
+                    let sync_time = state.clock().now();
                     for i in 0..8 {
                         if (*active_directions & (1 << i)) != 0 && direction_turbo[i] {
                             direction_last_times[i] = sync_time;
@@ -1075,10 +831,14 @@ impl KeyboardHook {
                         OutputAction::MouseMove(direction, speed) => {
                             let dir_idx = *direction as usize;
 
-                            mapping_cache.insert(
-                                device.clone(),
-                                (dir_idx, *speed, mapping.interval, mapping.turbo_enabled),
-                            );
+                            // Which mapping a conditional trigger resolves
+                            // to can change, so only fixed ones are cached.
+                            if !mapping.is_conditional {
+                                mapping_cache.insert(
+                                    device.clone(),
+                                    (dir_idx, *speed, mapping.interval, mapping.turbo_enabled),
+                                );
+                            }
 
                             if !*has_first_speed {
                                 *first_speed = *speed;
@@ -1092,13 +852,14 @@ impl KeyboardHook {
                             direction_turbo[dir_idx] = mapping.turbo_enabled;
 
                             Self::execute_movement_immediate_bitflags(
+                                state,
                                 *active_directions,
                                 direction_vectors,
                                 *first_speed,
                             );
                             // Sync all active turbo directions using current time to prevent
                             // premature turbo firing due to stale timestamp
-                            let sync_time = Instant::now();
+                            let sync_time = state.clock().now();
                             for i in 0..8 {
                                 if (*active_directions & (1 << i)) != 0 && direction_turbo[i] {
                                     direction_last_times[i] = sync_time;
@@ -1192,7 +953,7 @@ impl KeyboardHook {
         scroll_turbo: &[bool; 4],
     ) {
         use crate::state::OutputAction;
-        let now = Instant::now();
+        let now = state.clock().now();
 
         // Check each scroll slot bit
         #[allow(clippy::needless_range_loop)]
@@ -1222,6 +983,7 @@ impl KeyboardHook {
     /// and synchronizes their last_times to ensure smooth diagonal movement.
     #[inline(always)]
     fn execute_movement_bitflags(
+        state: &AppState,
         active_directions: u8,
         direction_intervals: &[u64; 8],
         direction_last_times: &mut [Instant; 8],
@@ -1229,7 +991,7 @@ impl KeyboardHook {
         direction_vectors: &[(f32, f32); 8],
         speed: i32,
     ) {
-        let now = Instant::now();
+        let now = state.clock().now();
 
         // First pass: check if any turbo direction is ready
         let mut any_ready = false;
@@ -1272,12 +1034,13 @@ impl KeyboardHook {
             direction_last_times[i] = now;
         }
 
-        Self::send_movement_normalized(total_dx, total_dy, speed);
+        Self::send_movement_normalized(state, total_dx, total_dy, speed);
     }
 
     /// Process immediate movements from key press
     #[inline(always)]
     fn execute_movement_immediate_bitflags(
+        state: &AppState,
         active_directions: u8,
         direction_vectors: &[(f32, f32); 8],
         speed: i32,
@@ -1331,14 +1094,12 @@ impl KeyboardHook {
             total_dy += dy;
         }
 
-        Self::send_movement_normalized(total_dx, total_dy, speed);
+        Self::send_movement_normalized(state, total_dx, total_dy, speed);
     }
 
     /// Normalize and send mouse movement
     #[inline(always)]
-    fn send_movement_normalized(dx: f32, dy: f32, speed: i32) {
-        use crate::state::SIMULATED_EVENT_MARKER;
-
+    fn send_movement_normalized(state: &AppState, dx: f32, dy: f32, speed: i32) {
         if dx == 0.0 && dy == 0.0 {
             return;
         }
@@ -1363,24 +1124,7 @@ impl KeyboardHook {
             return;
         }
 
-        unsafe {
-            use windows::Win32::UI::Input::KeyboardAndMouse::*;
-
-            let input = INPUT {
-                r#type: INPUT_MOUSE,
-                Anonymous: INPUT_0 {
-                    mi: MOUSEINPUT {
-                        dx: final_dx,
-                        dy: final_dy,
-                        mouseData: 0,
-                        dwFlags: MOUSEEVENTF_MOVE,
-                        time: 0,
-                        dwExtraInfo: SIMULATED_EVENT_MARKER,
-                    },
-                },
-            };
-            SendInput(&[input], std::mem::size_of::<INPUT>() as i32);
-        }
+        state.send_mouse_move_relative(final_dx, final_dy);
     }
 }
 
@@ -1437,112 +1181,6 @@ mod tests {
         }
     }
 
-    #[test]
-    fn test_is_sequential_hold_action_classification() {
-        use crate::state::OutputAction;
-        use smallvec::SmallVec;
-        use std::sync::Arc;
-
-        // Only MappingHold counts as sequential-hold. The turbo dispatch
-        // relies on this matrix to route MappingHold targets through
-        // simulate_hold_cycle instead of simulate_action.
-        let mapping_hold = OutputAction::MappingHold {
-            actions: Arc::new(SmallVec::new()),
-            interval_ms: 0,
-            hold_mask: 0,
-            append: Arc::new(SmallVec::new()),
-            sequential: false,
-        };
-        assert!(is_sequential_hold_action(&mapping_hold));
-
-        assert!(!is_sequential_hold_action(&OutputAction::KeyboardKey(0x41)));
-        assert!(!is_sequential_hold_action(&OutputAction::KeyCombo(
-            Arc::from([0x11u16, 0x41u16].as_slice())
-        )));
-        assert!(!is_sequential_hold_action(&OutputAction::MouseButton(
-            crate::state::MouseButton::Left
-        )));
-        assert!(!is_sequential_hold_action(&OutputAction::MultipleActions(
-            Arc::new(SmallVec::new())
-        )));
-        assert!(!is_sequential_hold_action(&OutputAction::SequentialActions(
-            Arc::new(SmallVec::new()),
-            0
-        )));
-    }
-
-    #[test]
-    fn test_new_turbo_propagates_sequential_hold() {
-        use crate::state::OutputAction;
-        use smallvec::SmallVec;
-        use std::sync::Arc;
-
-        let now = Instant::now();
-        let mapping_hold = OutputAction::MappingHold {
-            actions: Arc::new(SmallVec::new()),
-            interval_ms: 0,
-            hold_mask: 0,
-            append: Arc::new(SmallVec::new()),
-            sequential: false,
-        };
-        let plain_key = OutputAction::KeyboardKey(0x41);
-
-        // Construct turbo state through the same field the worker reads.
-        // Compute the flag exactly as handle_input_event does.
-        let sim_held = DeviceSimState::new_turbo(
-            mapping_hold.clone(),
-            10,
-            5,
-            now,
-            is_sequential_hold_action(&mapping_hold),
-        );
-        assert!(
-            sim_held.is_sequential_hold,
-            "turbo entry for MappingHold must record the flag so recurring \
-             ticks route to simulate_hold_cycle"
-        );
-
-        let sim_plain = DeviceSimState::new_turbo(
-            plain_key.clone(),
-            10,
-            5,
-            now,
-            is_sequential_hold_action(&plain_key),
-        );
-        assert!(
-            !sim_plain.is_sequential_hold,
-            "non-MappingHold turbo targets must keep the flag false so \
-             recurring ticks stay on simulate_action"
-        );
-    }
-
-    #[test]
-    fn test_new_non_turbo_propagates_sequential_hold() {
-        use crate::state::OutputAction;
-        use smallvec::SmallVec;
-        use std::sync::Arc;
-
-        let now = Instant::now();
-        let mapping_hold = OutputAction::MappingHold {
-            actions: Arc::new(SmallVec::new()),
-            interval_ms: 0,
-            hold_mask: 0,
-            append: Arc::new(SmallVec::new()),
-            sequential: false,
-        };
-        let sim = DeviceSimState::new_non_turbo(
-            mapping_hold,
-            10,
-            5,
-            now,
-            false,
-            false,
-            false,
-            true,
-        );
-        assert!(sim.is_sequential_hold);
-    }
-
     #[test]
     fn test_mapping_cache_retrieval() {
         use crate::config::KeyMapping;
@@ -1554,13 +1192,8 @@ mod tests {
             target_keys: SmallVec::from_vec(vec!["B".to_string()]),
             interval: Some(10),
             event_duration: Some(5),
-            turbo_enabled: true,
             move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
+            ..Default::default()
         }];
 
         let state = Arc::new(AppState::new(config).unwrap());
@@ -3,15 +3,21 @@
 //! This library exposes internal modules for testing purposes.
 //! It is not intended for external use as a library.
 
+pub mod clock;
 pub mod config;
+pub mod config_watcher;
+#[cfg(windows)]
 pub mod gui;
 pub mod i18n;
+#[cfg(windows)]
 pub mod input_manager;
 pub mod input_ownership;
+#[cfg(windows)]
 pub mod rawinput;
 pub mod sequence_matcher;
 pub mod state;
 pub mod util;
+#[cfg(windows)]
 pub mod xinput;
 
 // Re-export types for test modules
@@ -1,43 +1,64 @@
 // Hide console window in release mode
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+#[cfg(windows)]
+mod clock;
+#[cfg(windows)]
 mod config;
+#[cfg(windows)]
+mod config_watcher;
+#[cfg(windows)]
 mod gui;
+#[cfg(windows)]
 mod i18n;
+#[cfg(windows)]
 mod input_manager;
+#[cfg(windows)]
 mod input_ownership;
+#[cfg(windows)]
 mod keyboard;
+#[cfg(windows)]
 mod mouse;
+#[cfg(windows)]
 mod rawinput;
+#[cfg(windows)]
 mod sequence_matcher;
+#[cfg(windows)]
 mod signal;
+#[cfg(windows)]
 mod state;
+#[cfg(windows)]
 mod tray;
+#[cfg(windows)]
 mod util;
+#[cfg(windows)]
 mod xinput;
 
-use std::sync::Arc;
-use std::thread;
+#[cfg(windows)]
+fn main() -> anyhow::Result<()> {
+    use std::sync::Arc;
+    use std::thread;
 
-use anyhow::Result;
-use config::AppConfig;
-use gui::{SorahkGui, show_error};
-use input_manager::InputManager;
-use keyboard::KeyboardHook;
-use mouse::MouseHook;
-use state::AppState;
-use tray::TrayIcon;
-use windows::Win32::Media::timeBeginPeriod;
+    use config::AppConfig;
+    use config_watcher::ConfigWatcher;
+    use gui::{SorahkGui, show_error};
+    use input_manager::InputManager;
+    use keyboard::KeyboardHook;
+    use mouse::MouseHook;
+    use state::AppState;
+    use tray::TrayIcon;
+    use windows::Win32::Media::timeBeginPeriod;
 
-fn main() -> Result<()> {
     // Request 1ms timer resolution for precise timing in input processing
     unsafe { timeBeginPeriod(1) };
 
     signal::set_control_ctrl_handler()?;
 
-    // Load config or create default if not exists
-    let config = match AppConfig::load_or_create("Config.toml") {
-        Ok(cfg) => cfg,
+    // Load config or create default if not exists. Outdated files are
+    // upgraded and malformed ones partially recovered, with a backup of
+    // the original either way; the report is shown in the GUI.
+    let (config, config_load_report) = match AppConfig::load_or_recover("Config.toml") {
+        Ok(loaded) => loaded,
         Err(e) => {
             let error_msg = format!("Failed to load configuration: {}", e);
             return show_error(&error_msg);
@@ -81,6 +102,9 @@ fn main() -> Result<()> {
         }
     };
 
+    // Pick up edits made to Config.toml outside the app
+    ConfigWatcher::new(app_state.clone(), "Config.toml").spawn();
+
     // Give hooks and input managers time to initialize
     thread::sleep(std::time::Duration::from_millis(200));
 
@@ -98,5 +122,11 @@ fn main() -> Result<()> {
         });
     }
 
-    SorahkGui::run(app_state.clone(), config)
+    SorahkGui::run(app_state.clone(), config, config_load_report)
+}
+
+#[cfg(not(windows))]
+fn main() {
+    eprintln!("Sorahk only runs on Windows.");
+    std::process::exit(1);
 }
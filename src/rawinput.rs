@@ -38,7 +38,7 @@ use windows::Win32::UI::Input::*;
 use windows::Win32::UI::WindowsAndMessaging::*;
 use windows::core::PCWSTR;
 
-use crate::state::{AppState, DeviceType, InputDevice, InputEvent};
+use crate::state::{AppState, DeviceDisplayInfo, DeviceType, InputDevice, InputEvent};
 use crate::util::{
     fnv1a_hash_bytes, fnv1a_hash_u32, fnv1a_hash_u64, fnv32, fnv64, likely, unlikely,
 };
@@ -147,14 +147,6 @@ static RAW_INPUT_HANDLER: OnceLock<RawInputHandler> = OnceLock::new();
 /// Global cache for device display information.
 static DEVICE_DISPLAY_INFO: OnceLock<scc::HashMap<u64, DeviceDisplayInfo>> = OnceLock::new();
 
-/// Display information for HID devices.
-#[derive(Debug, Clone)]
-pub struct DeviceDisplayInfo {
-    pub vendor_id: u16,
-    pub product_id: u16,
-    pub serial_number: Option<String>,
-}
-
 /// Cached device information
 #[derive(Debug, Clone)]
 struct CachedDeviceInfo {
@@ -1109,7 +1101,7 @@ impl RawInputHandler {
 
                     if likely(self.state.get_input_mapping(&device).is_some()) {
                         if is_pressed {
-                            let now = Instant::now();
+                            let now = self.state.clock().now();
                             let sequence_match_result =
                                 self.state.record_and_match_sequence(device.clone(), now);
 
@@ -1135,6 +1127,14 @@ impl RawInputHandler {
 
                                     pool.dispatch(InputEvent::Pressed(matched_device));
                                     continue;
+                                } else if self
+                                    .state
+                                    .get_input_mapping(&device)
+                                    .is_some_and(|mapping_info| mapping_info.is_sequence)
+                                {
+                                    // The sequence's mapping does not apply
+                                    // here; a sequence-only input fires nothing
+                                    continue;
                                 }
                             } else {
                                 // No sequence matched - check if holding sequence trigger button
@@ -1182,28 +1182,12 @@ impl RawInputHandler {
                         if is_pressed {
                             pool.dispatch(InputEvent::Pressed(device));
                         } else {
-                            // Check if released device is the last input in an active sequence
-                            use scc::Guard;
-                            let guard = Guard::new();
-                            let last_seq_inputs = self
-                                .state
-                                .last_sequence_inputs
-                                .load(Ordering::Acquire, &guard);
-
-                            if let Some(seq_inputs) = last_seq_inputs.as_ref()
-                                && let Some(last_input) = seq_inputs.last()
-                                && last_input == &device
+                            // Releasing the last input of the active sequence
+                            // releases the sequence mapping too
+                            if let Some(sequence) =
+                                self.state.end_sequence_on_release(|last| last == &device)
                             {
-                                // Clear sequence state when last input is released
-                                use scc::Tag;
-                                let _ = self
-                                    .state
-                                    .last_sequence_device
-                                    .swap((None, Tag::None), Ordering::Release);
-                                let _ = self
-                                    .state
-                                    .last_sequence_inputs
-                                    .swap((None, Tag::None), Ordering::Release);
+                                pool.dispatch(InputEvent::Released(sequence));
                             }
 
                             pool.dispatch(InputEvent::Released(device));
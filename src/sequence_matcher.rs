@@ -1,13 +1,14 @@
 //! Sequence matching for fighting game-style input combos.
 
-use crate::state::InputDevice;
+use crate::state::{InputDevice, SequenceInputs};
 use crate::util::unlikely;
 use smallvec::SmallVec;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicPtr, AtomicU64, Ordering};
 use std::time::{Duration, Instant};
 
-const MAX_SEQUENCE_LENGTH: usize = 16;
+/// Longest trigger sequence the matcher accepts.
+pub const MAX_SEQUENCE_LENGTH: usize = 16;
 const DEFAULT_SEQUENCE_WINDOW_MS: u64 = 500;
 const HISTORY_BUFFER_SIZE: usize = 32;
 const HISTORY_BUFFER_MASK: usize = HISTORY_BUFFER_SIZE - 1;
@@ -23,7 +24,7 @@ struct TimedInput {
 #[allow(clippy::len_without_is_empty)]
 #[derive(Clone, Debug)]
 pub struct InputSequence {
-    inputs: Arc<SmallVec<[InputDevice; MAX_SEQUENCE_LENGTH]>>,
+    inputs: SequenceInputs,
     window_ms: u64,
 }
 
@@ -47,10 +48,17 @@ impl InputSequence {
     }
 
     #[inline(always)]
-    pub fn inputs_arc(&self) -> Arc<SmallVec<[InputDevice; MAX_SEQUENCE_LENGTH]>> {
+    pub fn inputs_arc(&self) -> SequenceInputs {
         Arc::clone(&self.inputs)
     }
 
+    /// Identity the matcher reports when this sequence completes; equal
+    /// for sequences with the same inputs.
+    #[inline]
+    pub fn identity(&self) -> InputDevice {
+        InputDevice::Sequence(self.inputs_arc())
+    }
+
     #[inline(always)]
     pub fn window_ms(&self) -> u64 {
         self.window_ms
@@ -108,6 +116,7 @@ impl Drop for HistorySlot {
 pub struct SequenceMatcher {
     head: AtomicU64,
     history: [HistorySlot; HISTORY_BUFFER_SIZE],
+    /// Registered sequences with their identities, longest first.
     sequences: AtomicPtr<Arc<Vec<(InputSequence, InputDevice)>>>,
 }
 
@@ -123,7 +132,8 @@ impl SequenceMatcher {
 
     #[inline]
     pub fn register_sequence(&self, sequence: InputSequence) {
-        if let Some(last_device) = sequence.inputs().last().cloned() {
+        if !sequence.inputs().is_empty() {
+            let identity = sequence.identity();
             loop {
                 let current_ptr = self.sequences.load(Ordering::Acquire);
                 let mut new_seqs = if current_ptr.is_null() {
@@ -132,7 +142,7 @@ impl SequenceMatcher {
                     unsafe { (**current_ptr).clone() }
                 };
 
-                new_seqs.push((sequence.clone(), last_device.clone()));
+                new_seqs.push((sequence.clone(), identity.clone()));
                 new_seqs.sort_unstable_by_key(|entry| std::cmp::Reverse(entry.0.len()));
 
                 let new_ptr = Box::into_raw(Box::new(Arc::new(new_seqs)));
@@ -186,13 +196,11 @@ impl SequenceMatcher {
         self.history[idx].store(Some(TimedInput { device, timestamp }));
     }
 
+    /// Returns the identity of the longest registered sequence the
+    /// recent inputs complete, see [`InputSequence::identity`], together
+    /// with its inputs.
     #[inline(always)]
-    pub fn try_match_with_sequence(
-        &self,
-    ) -> Option<(
-        InputDevice,
-        Arc<SmallVec<[InputDevice; MAX_SEQUENCE_LENGTH]>>,
-    )> {
+    pub fn try_match_with_sequence(&self) -> Option<(InputDevice, SequenceInputs)> {
         let head = self.head.load(Ordering::Acquire);
         if unlikely(head == 0) {
             return None;
@@ -205,9 +213,9 @@ impl SequenceMatcher {
 
         let sequences = unsafe { &**sequences_ptr };
 
-        for (sequence, last_device) in sequences.iter() {
+        for (sequence, identity) in sequences.iter() {
             if self.match_sequence(sequence, head) {
-                return Some((last_device.clone(), sequence.inputs_arc()));
+                return Some((identity.clone(), sequence.inputs_arc()));
             }
         }
         None
@@ -381,6 +389,10 @@ impl SequenceMatcher {
             (InputDevice::Keyboard(k1), InputDevice::Keyboard(k2)) => k1 == k2,
             (InputDevice::Mouse(m1), InputDevice::Mouse(m2)) => m1 == m2,
             (InputDevice::MouseMove(d1), InputDevice::MouseMove(d2)) => d1 == d2,
+            (InputDevice::Wheel(d1), InputDevice::Wheel(d2)) => d1 == d2,
+            (InputDevice::WheelCombo(k1, d1), InputDevice::WheelCombo(k2, d2)) => {
+                k1 == k2 && d1 == d2
+            }
             (InputDevice::KeyCombo(c1), InputDevice::KeyCombo(c2)) => c1 == c2,
             (
                 InputDevice::XInputCombo {
@@ -423,6 +435,8 @@ impl SequenceMatcher {
                     button_id: bi2,
                 },
             ) => dt1 == dt2 && bi1 == bi2,
+            (InputDevice::Sequence(s1), InputDevice::Sequence(s2)) => s1 == s2,
+            (InputDevice::Gesture(i1, k1), InputDevice::Gesture(i2, k2)) => i1 == i2 && k1 == k2,
             _ => unsafe { std::hint::unreachable_unchecked() },
         }
     }
@@ -529,7 +543,7 @@ pub fn parse_sequence_string(s: &str, window_ms: Option<u64>) -> Result<InputSeq
 }
 
 fn parse_input_device(s: &str) -> Result<InputDevice, String> {
-    use crate::state::{AppState, MouseButton, MouseMoveDirection};
+    use crate::state::{AppState, MouseButton, MouseMoveDirection, MouseScrollDirection};
 
     let upper = s.to_uppercase();
 
@@ -559,6 +573,18 @@ fn parse_input_device(s: &str) -> Result<InputDevice, String> {
         "MOUSE_DOWN_RIGHT" | "MOUSE_DOWNRIGHT" => {
             return Ok(InputDevice::MouseMove(MouseMoveDirection::DownRight));
         }
+        "WHEEL_UP" | "WHEELUP" | "SCROLL_UP" | "SCROLLUP" => {
+            return Ok(InputDevice::Wheel(MouseScrollDirection::Up));
+        }
+        "WHEEL_DOWN" | "WHEELDOWN" | "SCROLL_DOWN" | "SCROLLDOWN" => {
+            return Ok(InputDevice::Wheel(MouseScrollDirection::Down));
+        }
+        "WHEEL_LEFT" | "WHEELLEFT" | "SCROLL_LEFT" | "SCROLLLEFT" => {
+            return Ok(InputDevice::Wheel(MouseScrollDirection::Left));
+        }
+        "WHEEL_RIGHT" | "WHEELRIGHT" | "SCROLL_RIGHT" | "SCROLLRIGHT" => {
+            return Ok(InputDevice::Wheel(MouseScrollDirection::Right));
+        }
         "LBUTTON" | "LMB" | "LEFT_MOUSE" => return Ok(InputDevice::Mouse(MouseButton::Left)),
         "RBUTTON" | "RMB" | "RIGHT_MOUSE" => return Ok(InputDevice::Mouse(MouseButton::Right)),
         "MBUTTON" | "MMB" | "MIDDLE_MOUSE" => return Ok(InputDevice::Mouse(MouseButton::Middle)),
@@ -660,8 +686,8 @@ mod tests {
 
         let result = matcher.try_match_with_sequence();
         assert!(result.is_some());
-        let (last_device, sequence_inputs) = result.unwrap();
-        assert_eq!(last_device, InputDevice::Keyboard(0x41));
+        let (identity, sequence_inputs) = result.unwrap();
+        assert_eq!(identity, InputDevice::Sequence(sequence_inputs.clone()));
         assert_eq!(sequence_inputs.len(), 3);
     }
 
@@ -757,8 +783,11 @@ mod tests {
 
         let result = matcher.try_match_with_sequence();
         assert!(result.is_some());
-        let (last_device, _) = result.unwrap();
-        assert_eq!(last_device, InputDevice::Mouse(MouseButton::Middle));
+        let (_, sequence_inputs) = result.unwrap();
+        assert_eq!(
+            sequence_inputs.last(),
+            Some(&InputDevice::Mouse(MouseButton::Middle))
+        );
     }
 
     #[test]
@@ -790,6 +819,32 @@ mod tests {
         assert!(matcher.try_match_with_sequence().is_some());
     }
 
+    #[test]
+    fn test_wheel_tilt_sequence() {
+        use crate::state::MouseScrollDirection;
+        let matcher = SequenceMatcher::new();
+
+        let sequence = parse_sequence_string("RBUTTON,WHEEL_LEFT", Some(300)).unwrap();
+        let tilt = InputDevice::Wheel(MouseScrollDirection::Left);
+        assert_eq!(sequence.inputs().last(), Some(&tilt));
+        matcher.register_sequence(sequence);
+
+        let now = Instant::now();
+        matcher.record_input(InputDevice::Mouse(MouseButton::Right), now);
+        matcher.record_input(
+            InputDevice::Wheel(MouseScrollDirection::Right),
+            now + Duration::from_millis(50),
+        );
+        assert!(matcher.try_match_with_sequence().is_none());
+
+        matcher.record_input(
+            InputDevice::Mouse(MouseButton::Right),
+            now + Duration::from_millis(100),
+        );
+        matcher.record_input(tilt, now + Duration::from_millis(150));
+        assert!(matcher.try_match_with_sequence().is_some());
+    }
+
     #[test]
     fn test_multiple_sequences() {
         let matcher = SequenceMatcher::new();
@@ -811,8 +866,8 @@ mod tests {
 
         let result = matcher.try_match_with_sequence();
         assert!(result.is_some());
-        let (last_device, _) = result.unwrap();
-        assert_eq!(last_device, InputDevice::Keyboard(0x41));
+        let (_, sequence_inputs) = result.unwrap();
+        assert_eq!(sequence_inputs.last(), Some(&InputDevice::Keyboard(0x41)));
 
         matcher.clear_history();
         matcher.record_input(InputDevice::Keyboard(0x26), now);
@@ -820,8 +875,39 @@ mod tests {
 
         let result = matcher.try_match_with_sequence();
         assert!(result.is_some());
-        let (last_device, _) = result.unwrap();
-        assert_eq!(last_device, InputDevice::Keyboard(0x42));
+        let (_, sequence_inputs) = result.unwrap();
+        assert_eq!(sequence_inputs.last(), Some(&InputDevice::Keyboard(0x42)));
+    }
+
+    #[test]
+    fn test_sequences_with_same_last_input_are_distinct() {
+        let matcher = SequenceMatcher::new();
+
+        let quarter_circle = parse_sequence_string("DOWN,RIGHT,A", Some(500)).unwrap();
+        let dragon_punch = parse_sequence_string("RIGHT,DOWN,A", Some(500)).unwrap();
+        matcher.register_sequence(quarter_circle.clone());
+        matcher.register_sequence(dragon_punch.clone());
+        assert_ne!(quarter_circle.identity(), dragon_punch.identity());
+
+        let now = Instant::now();
+        matcher.record_input(InputDevice::Keyboard(0x28), now);
+        matcher.record_input(InputDevice::Keyboard(0x27), now + Duration::from_millis(50));
+        matcher.record_input(
+            InputDevice::Keyboard(0x41),
+            now + Duration::from_millis(100),
+        );
+        let (identity, _) = matcher.try_match_with_sequence().unwrap();
+        assert_eq!(identity, quarter_circle.identity());
+
+        matcher.clear_history();
+        matcher.record_input(InputDevice::Keyboard(0x27), now);
+        matcher.record_input(InputDevice::Keyboard(0x28), now + Duration::from_millis(50));
+        matcher.record_input(
+            InputDevice::Keyboard(0x41),
+            now + Duration::from_millis(100),
+        );
+        let (identity, _) = matcher.try_match_with_sequence().unwrap();
+        assert_eq!(identity, dragon_punch.identity());
     }
 
     #[test]
@@ -1031,4 +1117,56 @@ mod tests {
             "Should match sequence with multiple transition tolerances"
         );
     }
+
+    #[test]
+    fn test_dedup_threshold_boundary_with_virtual_clock() {
+        use crate::clock::{Clock, VirtualClock};
+
+        let clock = VirtualClock::new();
+        let matcher = SequenceMatcher::new();
+
+        matcher.record_input(InputDevice::Keyboard(0x41), clock.now());
+        clock.advance(Duration::from_millis(DEDUP_THRESHOLD_MS - 1));
+        matcher.record_input(InputDevice::Keyboard(0x41), clock.now());
+        assert_eq!(matcher.head(), 1, "Repeat inside the threshold is dropped");
+
+        // The threshold is measured from the last *recorded* input, so the
+        // dropped repeat does not extend it.
+        clock.advance(Duration::from_millis(1));
+        matcher.record_input(InputDevice::Keyboard(0x41), clock.now());
+        assert_eq!(matcher.head(), 2, "Repeat at the threshold is recorded");
+    }
+
+    #[test]
+    fn test_sequence_window_boundary_with_virtual_clock() {
+        use crate::clock::{Clock, VirtualClock};
+
+        let window_ms = 100;
+        let sequence = InputSequence::new(
+            vec![InputDevice::Keyboard(0x28), InputDevice::Keyboard(0x27)],
+            Some(window_ms),
+        );
+
+        let clock = VirtualClock::new();
+        let matcher = SequenceMatcher::new();
+        matcher.register_sequence(sequence.clone());
+        matcher.record_input(InputDevice::Keyboard(0x28), clock.now());
+        clock.advance(Duration::from_millis(window_ms));
+        matcher.record_input(InputDevice::Keyboard(0x27), clock.now());
+        assert!(
+            matcher.try_match_with_sequence().is_some(),
+            "Sequence completed exactly at the window edge should match"
+        );
+
+        let clock = VirtualClock::new();
+        let matcher = SequenceMatcher::new();
+        matcher.register_sequence(sequence);
+        matcher.record_input(InputDevice::Keyboard(0x28), clock.now());
+        clock.advance(Duration::from_millis(window_ms + 1));
+        matcher.record_input(InputDevice::Keyboard(0x27), clock.now());
+        assert!(
+            matcher.try_match_with_sequence().is_none(),
+            "Sequence completed past the window should expire"
+        );
+    }
 }
@@ -0,0 +1,440 @@
+//! Multi-tap and long-press decisions.
+//!
+//! Presses of a trigger with gesture mappings are counted on the thread
+//! that dispatches them. A press that completes the longest multi-tap of
+//! the trigger fires it right away; a shorter run fires once the tap gap
+//! passes without another press. A first press held for the long-press
+//! time fires the long press, and any other press held past the tap gap
+//! ends the run. A fired gesture is dispatched with its
+//! `InputDevice::Gesture` identity and stays pressed until the trigger is
+//! released.
+//!
+//! Plain presses go through, wait for the run to end or are dropped, as
+//! the trigger's `single_tap` says. A press that goes through reaches the
+//! OS as it is unless a mapping of the trigger applies; only deferred
+//! presses are sent again. Runs that end on a worker tick are dispatched
+//! from there.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+use scc::Guard;
+use smallvec::SmallVec;
+
+use super::AppState;
+use super::parsing;
+use super::types::*;
+use crate::config::{AppConfig, SingleTap, TriggerKind};
+
+/// Gestures configured on one trigger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct GestureTrigger {
+    /// Most presses in a row any multi-tap of the trigger counts; 1
+    /// without one.
+    max_taps: u8,
+    /// Longest pause between two presses of a run, in milliseconds.
+    tap_gap: u64,
+    /// Hold time of the long press, when the trigger has one.
+    long_press: Option<u64>,
+    single_tap: SingleTap,
+}
+
+/// Run of presses of a gesture trigger that has not ended yet.
+#[derive(Debug, Clone)]
+pub(crate) struct GesturePress {
+    /// Presses counted in the run; 0 once the run is decided.
+    taps: u8,
+    down: bool,
+    /// Time of the last press or release.
+    since: Instant,
+    /// Whether the plain press went through and is not released yet.
+    passed: bool,
+    /// Whether the press that went through was a deferred one sent again
+    /// by a worker tick, rather than the original input.
+    replayed: bool,
+    /// Gesture the trigger fired, released along with it.
+    fired: Option<InputDevice>,
+}
+
+/// What the dispatcher does with an event after the gesture check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum GestureOutcome {
+    /// Not part of a gesture, or a plain press let through; dispatch it
+    /// as usual.
+    Forward,
+    /// A plain press let through that no mapping applies to; let the
+    /// original input reach the OS.
+    PassThrough,
+    /// Handled here; dispatch nothing.
+    Consumed,
+    /// Dispatch this event instead.
+    Replace(InputEvent),
+    /// A deferred plain press; dispatch a tap of the trigger.
+    Tap,
+}
+
+/// Decision a worker tick made about a run.
+enum GestureStep {
+    /// Press a gesture and keep it pressed.
+    Press(InputDevice),
+    /// Press and release a gesture.
+    Tap(InputDevice),
+    /// Send deferred plain presses: `taps` taps, then a press that stays
+    /// down when `hold` is set.
+    Plain {
+        device: InputDevice,
+        taps: u8,
+        hold: bool,
+    },
+}
+
+impl GesturePress {
+    fn new(now: Instant) -> Self {
+        Self {
+            taps: 0,
+            down: false,
+            since: now,
+            passed: false,
+            replayed: false,
+            fired: None,
+        }
+    }
+}
+
+/// Plain presses of a run of `taps` that did not go through when they
+/// were pressed.
+fn unsent_taps(trigger: &GestureTrigger, taps: u8) -> u8 {
+    match trigger.single_tap {
+        // Only the first press of a run goes through right away.
+        SingleTap::Immediate => taps.saturating_sub(1),
+        SingleTap::Deferred => taps,
+        SingleTap::Suppressed => 0,
+    }
+}
+
+impl AppState {
+    /// Gesture triggers of `config`, keyed by the input they count.
+    pub(super) fn collect_gesture_triggers(
+        config: &AppConfig,
+    ) -> HashMap<InputDevice, GestureTrigger> {
+        let mut triggers: HashMap<InputDevice, GestureTrigger> = HashMap::new();
+        for mapping in &config.mappings {
+            let Some(gesture) = mapping.gesture() else {
+                continue;
+            };
+            if mapping.get_target_keys().is_empty()
+                && mapping.layer_switch().is_none()
+                && mapping.type_text().is_none()
+                && mapping.macro_script().is_none()
+            {
+                continue;
+            }
+            // Wheel notches cannot make gestures.
+            let Some(device) = parsing::input_name_to_device(&mapping.trigger_key)
+                .filter(|device| !device.is_wheel_trigger())
+            else {
+                continue;
+            };
+            let trigger = triggers.entry(device).or_insert(GestureTrigger {
+                max_taps: 1,
+                tap_gap: 0,
+                long_press: None,
+                single_tap: SingleTap::Immediate,
+            });
+            match gesture.kind {
+                TriggerKind::LongPress => {
+                    trigger.long_press = Some(
+                        trigger
+                            .long_press
+                            .map_or(gesture.long_press_ms, |ms| ms.min(gesture.long_press_ms)),
+                    );
+                }
+                kind => {
+                    trigger.max_taps = trigger.max_taps.max(kind.taps());
+                    trigger.tap_gap = trigger.tap_gap.max(gesture.tap_gap_ms);
+                }
+            }
+            trigger.single_tap = trigger.single_tap.max(gesture.single_tap);
+        }
+        // Long presses alone still need a gap to tell when a deferred
+        // tap is over.
+        for trigger in triggers.values_mut() {
+            if trigger.tap_gap == 0 {
+                trigger.tap_gap = crate::config::DEFAULT_TAP_GAP_MS;
+            }
+        }
+        triggers
+    }
+
+    /// Whether any installed mapping has a `trigger_kind`. Lets the
+    /// dispatcher skip the gesture lookup for configs without one.
+    #[inline(always)]
+    pub(crate) fn has_gestures(&self) -> bool {
+        self.has_gestures.load(Ordering::Relaxed)
+    }
+
+    fn gesture_trigger(&self, device: &InputDevice) -> Option<GestureTrigger> {
+        let guard = Guard::new();
+        self.gesture_triggers
+            .load(Ordering::Acquire, &guard)
+            .as_ref()
+            .and_then(|triggers| triggers.get(device).copied())
+    }
+
+    /// Identity of the `kind` gesture of `device`, when a mapping for it
+    /// applies right now.
+    fn gesture_identity(&self, device: &InputDevice, kind: TriggerKind) -> Option<InputDevice> {
+        let identity = InputDevice::Gesture(Arc::new(device.clone()), kind);
+        self.get_input_mapping(&identity).map(|_| identity)
+    }
+
+    /// Gesture a run of `taps` presses makes, if it makes one.
+    fn gesture_for_taps(&self, device: &InputDevice, taps: u8) -> Option<InputDevice> {
+        let kind = match taps {
+            2 => TriggerKind::DoubleTap,
+            3 => TriggerKind::TripleTap,
+            _ => return None,
+        };
+        self.gesture_identity(device, kind)
+    }
+
+    /// Whether a press of `device` can still turn into a gesture.
+    fn any_gesture_applies(&self, device: &InputDevice, trigger: &GestureTrigger) -> bool {
+        (trigger.long_press.is_some()
+            && self
+                .gesture_identity(device, TriggerKind::LongPress)
+                .is_some())
+            || (2..=trigger.max_taps).any(|taps| self.gesture_for_taps(device, taps).is_some())
+    }
+
+    /// Outcome of a plain press, repeat or release that went through.
+    fn passed_outcome(&self, device: &InputDevice, press: &GesturePress) -> GestureOutcome {
+        if !press.replayed
+            && self
+                .get_input_mapping(device)
+                .is_some_and(|info| info.pass_through)
+        {
+            GestureOutcome::PassThrough
+        } else {
+            GestureOutcome::Forward
+        }
+    }
+
+    /// Runs `event` through the gesture state.
+    #[cfg_attr(not(windows), allow(dead_code))]
+    pub(crate) fn handle_gesture_event(&self, event: &InputEvent) -> GestureOutcome {
+        let (InputEvent::Pressed(device)
+        | InputEvent::Released(device)
+        | InputEvent::RetapHold(device)) = event;
+        let Some(trigger) = self.gesture_trigger(device) else {
+            return GestureOutcome::Forward;
+        };
+        let now = self.clock().now();
+
+        match event {
+            InputEvent::Pressed(_) => {
+                let _ = self
+                    .gesture_presses
+                    .insert_sync(device.clone(), GesturePress::new(now));
+                self.gesture_presses
+                    .update_sync(device, |_, press| {
+                        self.gesture_press(device, &trigger, press, now)
+                    })
+                    .unwrap_or(GestureOutcome::Forward)
+            }
+            InputEvent::Released(_) => self
+                .gesture_presses
+                .update_sync(device, |_, press| {
+                    if !press.down {
+                        return GestureOutcome::Forward;
+                    }
+                    press.down = false;
+                    press.since = now;
+                    let outcome = if let Some(identity) = press.fired.take() {
+                        press.taps = 0;
+                        GestureOutcome::Replace(InputEvent::Released(identity))
+                    } else if press.passed {
+                        let outcome = self.passed_outcome(device, press);
+                        press.passed = false;
+                        press.replayed = false;
+                        outcome
+                    } else if press.taps == 1
+                        && trigger.max_taps == 1
+                        && trigger.single_tap == SingleTap::Deferred
+                    {
+                        // Only a long press was possible, and it was not.
+                        GestureOutcome::Tap
+                    } else {
+                        GestureOutcome::Consumed
+                    };
+                    if trigger.max_taps == 1 {
+                        press.taps = 0;
+                    }
+                    outcome
+                })
+                .unwrap_or(GestureOutcome::Forward),
+            InputEvent::RetapHold(_) => self
+                .gesture_presses
+                .read_sync(device, |_, press| match &press.fired {
+                    Some(identity) => {
+                        GestureOutcome::Replace(InputEvent::RetapHold(identity.clone()))
+                    }
+                    None if press.passed => self.passed_outcome(device, press),
+                    None => GestureOutcome::Consumed,
+                })
+                .unwrap_or(GestureOutcome::Forward),
+        }
+    }
+
+    /// Counts a press of `device` into its run.
+    fn gesture_press(
+        &self,
+        device: &InputDevice,
+        trigger: &GestureTrigger,
+        press: &mut GesturePress,
+        now: Instant,
+    ) -> GestureOutcome {
+        if press.down {
+            // Auto-repeat follows whatever the first press started.
+            return match &press.fired {
+                Some(identity) => GestureOutcome::Replace(InputEvent::Pressed(identity.clone())),
+                None if press.passed => self.passed_outcome(device, press),
+                None => GestureOutcome::Consumed,
+            };
+        }
+        let gap = Duration::from_millis(trigger.tap_gap);
+        if press.taps == 0 || now.duration_since(press.since) > gap {
+            press.taps = 1;
+        } else {
+            press.taps += 1;
+        }
+        press.down = true;
+        press.since = now;
+
+        if press.taps == 1 {
+            if trigger.single_tap == SingleTap::Immediate {
+                press.passed = true;
+                return self.passed_outcome(device, press);
+            }
+            if !self.any_gesture_applies(device, trigger) {
+                press.taps = 0;
+                press.passed = true;
+                return self.passed_outcome(device, press);
+            }
+            return GestureOutcome::Consumed;
+        }
+        if press.taps < trigger.max_taps {
+            return GestureOutcome::Consumed;
+        }
+        // The longest multi-tap is complete.
+        let taps = press.taps;
+        press.taps = 0;
+        match self.gesture_for_taps(device, taps) {
+            Some(identity) => {
+                press.fired = Some(identity.clone());
+                GestureOutcome::Replace(InputEvent::Pressed(identity))
+            }
+            None if trigger.single_tap == SingleTap::Suppressed => GestureOutcome::Consumed,
+            None => {
+                press.passed = true;
+                self.passed_outcome(device, press)
+            }
+        }
+    }
+
+    /// Ends the runs whose tap gap or long-press time passed. Called from
+    /// the worker loop.
+    #[cfg_attr(not(windows), allow(dead_code))]
+    pub(crate) fn expire_gestures(&self) {
+        if !self.has_gestures() || self.gesture_presses.is_empty() {
+            return;
+        }
+        let Some(pool) = self.worker_pool.get() else {
+            return;
+        };
+        let now = self.clock().now();
+        let mut steps: SmallVec<[GestureStep; 2]> = SmallVec::new();
+        self.gesture_presses.retain_sync(|device, press| {
+            let Some(trigger) = self.gesture_trigger(device) else {
+                return false;
+            };
+            let elapsed = now.duration_since(press.since);
+            let gap = Duration::from_millis(trigger.tap_gap);
+
+            if !press.down {
+                if elapsed < gap {
+                    return true;
+                }
+                match self.gesture_for_taps(device, press.taps) {
+                    Some(identity) => steps.push(GestureStep::Tap(identity)),
+                    None => steps.push(GestureStep::Plain {
+                        device: device.clone(),
+                        taps: unsent_taps(&trigger, press.taps),
+                        hold: false,
+                    }),
+                }
+                return false;
+            }
+
+            if press.taps == 0 || press.fired.is_some() {
+                return true;
+            }
+            let long_press = trigger.long_press.filter(|_| press.taps == 1);
+            let limit = long_press.map_or(gap, Duration::from_millis);
+            if elapsed < limit {
+                return true;
+            }
+            let identity = match long_press {
+                Some(_) => self.gesture_identity(device, TriggerKind::LongPress),
+                None => self.gesture_for_taps(device, press.taps),
+            };
+            match identity {
+                Some(identity) => {
+                    press.fired = Some(identity.clone());
+                    steps.push(GestureStep::Press(identity));
+                }
+                None => {
+                    let hold = !press.passed && trigger.single_tap != SingleTap::Suppressed;
+                    press.passed |= hold;
+                    press.replayed |= hold;
+                    steps.push(GestureStep::Plain {
+                        device: device.clone(),
+                        taps: unsent_taps(&trigger, press.taps - 1),
+                        hold,
+                    });
+                }
+            }
+            press.taps = 0;
+            true
+        });
+
+        for step in steps {
+            match step {
+                GestureStep::Press(identity) => {
+                    pool.dispatch(InputEvent::Pressed(identity));
+                }
+                GestureStep::Tap(identity) => {
+                    pool.dispatch(InputEvent::Pressed(identity.clone()));
+                    pool.dispatch(InputEvent::Released(identity));
+                }
+                GestureStep::Plain { device, taps, hold } => {
+                    for _ in 0..taps {
+                        pool.dispatch_tap(device.clone());
+                    }
+                    // The run is marked replayed, so the press goes to
+                    // a worker like the trigger's auto-repeat.
+                    if hold {
+                        pool.dispatch(InputEvent::Pressed(device));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Forgets every run. Fired gestures are released by the caller.
+    pub(super) fn reset_gestures(&self) {
+        self.gesture_presses.clear_sync();
+    }
+}
@@ -1,14 +1,12 @@
-use std::time::Instant;
-
 use scc::{Guard, Shared, Tag};
 use smallvec::SmallVec;
 
-use windows::Win32::UI::WindowsAndMessaging::*;
-
 use crate::util::{likely, unlikely};
 
 use super::AppState;
+use super::output::OutputEvent;
 use super::types::*;
+use super::win32::*;
 
 /// Returns how much of a mouse-move delta lands in the half-plane opposite
 /// to the given direction. Screen coordinates have Y pointing down. Only
@@ -35,6 +33,28 @@ fn opposite_projection(dx: i32, dy: i32, dir: MouseMoveDirection) -> f32 {
 }
 
 impl AppState {
+    /// Whether a keydown of `vk_code` completes the keyboard hotkey held
+    /// in `cache`: either the single key itself or the last missing key
+    /// of a combo. Must run after `vk_code` is added to `pressed_keys`.
+    #[inline(always)]
+    fn is_hotkey_press(&self, cache: &SwitchKeyCache, vk_code: u32) -> bool {
+        use std::sync::atomic::Ordering;
+
+        let kb_vk = cache.keyboard_vk.load(Ordering::Relaxed);
+        if kb_vk != 0 {
+            return vk_code == kb_vk;
+        }
+
+        let guard = Guard::new();
+        let device_ptr = cache.full_device.load(Ordering::Acquire, &guard);
+        if let Some(InputDevice::KeyCombo(keys)) = device_ptr.as_ref()
+            && keys.contains(&vk_code)
+        {
+            return keys.iter().all(|k| self.pressed_keys.contains_sync(k));
+        }
+        false
+    }
+
     #[allow(non_snake_case)]
     #[inline(always)]
     pub fn handle_key_event(&self, message: u32, vk_code: u32) -> bool {
@@ -45,36 +65,19 @@ impl AppState {
         if matches!(message, WM_KEYDOWN | WM_SYSKEYDOWN) {
             let _ = self.pressed_keys.insert_sync(vk_code);
 
-            let kb_vk = self.switch_key_cache.keyboard_vk.load(Ordering::Relaxed);
-
-            if unlikely(kb_vk != 0 && vk_code == kb_vk) {
+            if unlikely(self.is_hotkey_press(&self.switch_key_cache, vk_code)) {
                 self.handle_switch_key_toggle();
                 return true;
             }
 
-            if unlikely(kb_vk == 0) {
-                let guard = Guard::new();
-                let device_ptr = self
-                    .switch_key_cache
-                    .full_device
-                    .load(Ordering::Acquire, &guard);
-                if let Some(device) = device_ptr.as_ref()
-                    && let InputDevice::KeyCombo(keys) = device
-                    && keys.contains(&vk_code)
-                {
-                    let mut all_pressed = true;
-                    for k in keys.iter() {
-                        if !self.pressed_keys.contains_sync(k) {
-                            all_pressed = false;
-                            break;
-                        }
-                    }
+            if unlikely(self.is_hotkey_press(&self.next_profile_key_cache, vk_code)) {
+                self.cycle_profile(true);
+                return true;
+            }
 
-                    if all_pressed {
-                        self.handle_switch_key_toggle();
-                        return true;
-                    }
-                }
+            if unlikely(self.is_hotkey_press(&self.prev_profile_key_cache, vk_code)) {
+                self.cycle_profile(false);
+                return true;
             }
         }
 
@@ -144,7 +147,7 @@ impl AppState {
                     .or(Some(InputDevice::Keyboard(vk_code)));
 
                 if let Some(device) = matched_device {
-                    let now = Instant::now();
+                    let now = self.clock().now();
                     let sequence_match_result = self.record_and_match_sequence(device.clone(), now);
 
                     if let Some((matched_device, sequence_inputs)) = sequence_match_result {
@@ -198,6 +201,14 @@ impl AppState {
                         }
                     }
                 }
+
+                // A key passed through must not beat the hold output of a
+                // tap-hold trigger it interrupts, so it is sent again
+                // after it.
+                let scancode = super::parsing::vk_to_scancode(vk_code);
+                if !should_block && scancode != 0 {
+                    should_block = self.hold_before_passthrough(OutputEvent::KeyDown(scancode));
+                }
             }
 
             WM_KEYUP | WM_SYSKEYUP => {
@@ -216,23 +227,11 @@ impl AppState {
                 if let Some(dev) = device
                     && let Some(pool) = self.worker_pool.get()
                 {
-                    let guard = Guard::new();
-                    let last_seq_inputs = self.last_sequence_inputs.load(Ordering::Acquire, &guard);
-
-                    if let Some(seq_inputs) = last_seq_inputs.as_ref()
-                        && let Some(last_input) = seq_inputs.last()
-                        && last_input == &dev
-                    {
-                        let _ = self
-                            .last_sequence_device
-                            .swap((None, Tag::None), Ordering::Release);
-                        let _ = self
-                            .last_sequence_inputs
-                            .swap((None, Tag::None), Ordering::Release);
+                    if let Some(sequence) = self.end_sequence_on_release(|last| last == &dev) {
+                        pool.dispatch(InputEvent::Released(sequence));
                     }
 
-                    pool.dispatch(InputEvent::Released(dev));
-                    should_block = true;
+                    should_block |= pool.dispatch(InputEvent::Released(dev));
                 }
             }
 
@@ -255,8 +254,7 @@ impl AppState {
         }
 
         if let Some(pool) = self.worker_pool.get() {
-            pool.dispatch(InputEvent::Pressed(device.clone()));
-            return true;
+            return pool.dispatch(InputEvent::Pressed(device.clone()));
         }
         false
     }
@@ -412,11 +410,15 @@ impl AppState {
                         // then presses again. That intermediate keyup is
                         // what a polling-based game samples to register the
                         // double-tap. Skip the record/dispatch path below
-                        // because `last_sequence_device` is already set to
-                        // this direction and the worker will restart its
+                        // because the sequence ending in this direction is
+                        // still active and the worker will restart its
                         // hold cycle.
                         self.mouse_opp_distance_px.store(0, Ordering::Release);
-                        rearm_dispatch = Some(InputDevice::MouseMove(direction));
+                        let moved = InputDevice::MouseMove(direction);
+                        rearm_dispatch = Some(
+                            self.active_sequence_ending(|last| last == &moved)
+                                .unwrap_or(moved),
+                        );
                         self.sequence_matcher.clear_history();
                         (false, false)
                     } else {
@@ -439,25 +441,14 @@ impl AppState {
                     // transition (smooth cardinal → diagonal handoff).
                     let mut deferred_release: Option<InputDevice> = None;
                     if should_stop_previous {
-                        let guard = Guard::new();
-                        let last_seq = self.last_sequence_device.load(Ordering::Acquire, &guard);
-                        if let Some(seq_device) = last_seq.as_ref().map(|device| device.clone())
-                            && matches!(seq_device, InputDevice::MouseMove(_))
-                        {
-                            let _ = self
-                                .last_sequence_device
-                                .swap((None, Tag::None), Ordering::Release);
-                            let _ = self
-                                .last_sequence_inputs
-                                .swap((None, Tag::None), Ordering::Release);
-
-                            deferred_release = Some(seq_device);
-                        }
+                        deferred_release = self.end_sequence_on_release(|last| {
+                            matches!(last, InputDevice::MouseMove(_))
+                        });
                     }
 
                     if should_record {
                         let device = InputDevice::MouseMove(direction);
-                        let now = Instant::now();
+                        let now = self.clock().now();
 
                         let sequence_match_result =
                             self.record_and_match_sequence(device.clone(), now);
@@ -577,7 +568,7 @@ impl AppState {
                 WM_LBUTTONDOWN | WM_RBUTTONDOWN | WM_MBUTTONDOWN | WM_XBUTTONDOWN
                     if !self.is_paused() && self.is_process_whitelisted() =>
                 {
-                    let now = Instant::now();
+                    let now = self.clock().now();
 
                     let sequence_match_result = self.record_and_match_sequence(device.clone(), now);
 
@@ -626,8 +617,7 @@ impl AppState {
                                 if !mapping_info.is_sequence
                                     && let Some(pool) = self.worker_pool.get()
                                 {
-                                    pool.dispatch(InputEvent::Pressed(device));
-                                    should_block = true;
+                                    should_block = pool.dispatch(InputEvent::Pressed(device));
                                 }
                             }
                         } else if let Some(mapping_info) = self.get_input_mapping(&device) {
@@ -635,56 +625,37 @@ impl AppState {
                             if !mapping_info.is_sequence
                                 && let Some(pool) = self.worker_pool.get()
                             {
-                                pool.dispatch(InputEvent::Pressed(device));
-                                should_block = true;
+                                should_block = pool.dispatch(InputEvent::Pressed(device));
                             }
                         }
                     }
+
+                    if !should_block {
+                        should_block = self.hold_before_passthrough(OutputEvent::MouseDown(button));
+                    }
                 }
                 WM_LBUTTONUP | WM_RBUTTONUP | WM_MBUTTONUP | WM_XBUTTONUP => {
-                    let guard = Guard::new();
-                    let last_seq_inputs = self.last_sequence_inputs.load(Ordering::Acquire, &guard);
-
-                    let is_last_sequence_input = if let Some(seq_inputs) = last_seq_inputs.as_ref()
+                    // Clear sequence state when last input is released
+                    if let Some(sequence) = self.end_sequence_on_release(|last| last == &device)
+                        && let Some(pool) = self.worker_pool.get()
                     {
-                        if let Some(last_input) = seq_inputs.last() {
-                            matches!(last_input, InputDevice::Mouse(btn) if *btn == button)
-                        } else {
-                            false
-                        }
-                    } else {
-                        false
-                    };
-
-                    if is_last_sequence_input {
-                        // Clear sequence state when last input is released
-                        let _ = self
-                            .last_sequence_device
-                            .swap((None, Tag::None), Ordering::Release);
-                        let _ = self
-                            .last_sequence_inputs
-                            .swap((None, Tag::None), Ordering::Release);
+                        pool.dispatch(InputEvent::Released(sequence));
+                    }
 
-                        if let Some(pool) = self.worker_pool.get() {
-                            pool.dispatch(InputEvent::Released(device.clone()));
-                        }
-                    } else {
-                        // Not a sequence device
-                        if let Some(mapping_info) = self.get_input_mapping(&device) {
-                            // Has mapping - check if it's sequence-only
-                            if !mapping_info.is_sequence {
-                                // Normal mapping (non-sequence)
-                                if let Some(pool) = self.worker_pool.get() {
-                                    pool.dispatch(InputEvent::Released(device));
-                                }
-                            }
-                            // Else: sequence-only mapping - don't dispatch release
-                        } else {
-                            // No mapping configured - dispatch as normal input
+                    if let Some(mapping_info) = self.get_input_mapping(&device) {
+                        // Has mapping - check if it's sequence-only
+                        if !mapping_info.is_sequence {
+                            // Normal mapping (non-sequence)
                             if let Some(pool) = self.worker_pool.get() {
                                 pool.dispatch(InputEvent::Released(device));
                             }
                         }
+                        // Else: sequence-only mapping - don't dispatch release
+                    } else {
+                        // No mapping configured - dispatch as normal input
+                        if let Some(pool) = self.worker_pool.get() {
+                            pool.dispatch(InputEvent::Released(device));
+                        }
                     }
                     should_block = false;
                 }
@@ -692,13 +663,95 @@ impl AppState {
             }
         }
 
-        // Handle mouse wheel scroll
-        const WM_MOUSEWHEEL: u32 = 0x020A;
-        if message == WM_MOUSEWHEEL {
-            // Mouse wheel is not intercepted by default - always return false
+        // Wheel notches and tilts: the high word of mouseData is the
+        // signed delta, positive away from the user or to the right.
+        if message == WM_MOUSEWHEEL || message == WM_MOUSEHWHEEL {
+            let delta = (mouse_data >> 16) as u16 as i16 as i32;
+            return self.handle_wheel_event(delta, message == WM_MOUSEHWHEEL);
+        }
+
+        should_block
+    }
+
+    /// Taps the trigger of every whole notch in a wheel message. Wheels
+    /// that report fractions of a notch add them up until they make
+    /// one; turning the other way drops what was left. Returns whether
+    /// the message is blocked, which it is whenever the notch has a
+    /// mapping of its own, even if it completes no notch yet.
+    fn handle_wheel_event(&self, delta: i32, horizontal: bool) -> bool {
+        use std::sync::atomic::Ordering;
+        const WHEEL_DELTA: i32 = 120;
+
+        let Some(pool) = self.worker_pool.get() else {
+            return false;
+        };
+        if delta == 0 {
             return false;
         }
+        let direction = match (horizontal, delta > 0) {
+            (false, true) => MouseScrollDirection::Up,
+            (false, false) => MouseScrollDirection::Down,
+            (true, true) => MouseScrollDirection::Right,
+            (true, false) => MouseScrollDirection::Left,
+        };
+
+        let accum = if horizontal {
+            &self.hwheel_accum
+        } else {
+            &self.wheel_accum
+        };
+        let left = accum.load(Ordering::Acquire);
+        let total = if (left > 0) == (delta > 0) {
+            left + delta
+        } else {
+            delta
+        };
+        accum.store(total % WHEEL_DELTA, Ordering::Release);
 
+        // Notches no mapping or sequence uses stay out of the sequence
+        // history, so scrolling does not break up other sequences.
+        let device = self.find_wheel_trigger(direction);
+        let Some(info) = self.get_input_mapping(&device) else {
+            return false;
+        };
+        let mapped = !info.is_sequence;
+        let mut should_block = mapped;
+        for _ in 0..(total / WHEEL_DELTA).unsigned_abs() {
+            let now = self.clock().now();
+            if let Some((matched_device, _)) = self.record_and_match_sequence(device.clone(), now)
+                && self
+                    .get_input_mapping(&matched_device)
+                    .is_some_and(|info| info.is_sequence)
+            {
+                self.sequence_matcher.clear_history();
+                pool.dispatch_tap(matched_device);
+                should_block = true;
+            } else if mapped {
+                pool.dispatch_tap(device.clone());
+            }
+        }
         should_block
     }
+
+    /// Wheel combo of `direction` whose keys are all held, the one with
+    /// the most keys if several are, or else the plain notch.
+    fn find_wheel_trigger(&self, direction: MouseScrollDirection) -> InputDevice {
+        self.cached_wheel_combos
+            .read_sync(&direction, |_, combos| {
+                combos
+                    .iter()
+                    .filter_map(|device| match device {
+                        InputDevice::WheelCombo(keys, _)
+                            if keys.iter().all(|k| self.pressed_keys.contains_sync(k)) =>
+                        {
+                            Some((keys.len(), device))
+                        }
+                        _ => None,
+                    })
+                    .max_by_key(|(len, _)| *len)
+                    .map(|(_, device)| device.clone())
+            })
+            .flatten()
+            .unwrap_or(InputDevice::Wheel(direction))
+    }
 }
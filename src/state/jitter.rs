@@ -0,0 +1,127 @@
+//! Random timing jitter.
+//!
+//! Mappings with `interval_jitter_ms` or `duration_jitter_ms` draw every
+//! turbo gap, sequence step gap and press hold from a range around the
+//! configured time. Each thread draws from a small generator of its own, so
+//! the workers never contend for one; tests, including the integration
+//! tests, reseed the calling thread's with `AppState::seed_jitter` to get
+//! the same timings on every run.
+
+use std::cell::Cell;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+use super::AppState;
+use super::types::Jitter;
+use crate::config::{JitterDistribution, MIN_EVENT_DURATION_MS, MIN_INTERVAL_MS};
+
+thread_local! {
+    static JITTER_RNG: JitterRng = JitterRng::new();
+}
+
+/// SplitMix64 generator. Good enough to break up a periodic pattern, and
+/// cheap enough for the worker hot path.
+struct JitterRng {
+    state: Cell<u64>,
+}
+
+impl JitterRng {
+    const GAMMA: u64 = 0x9E37_79B9_7F4A_7C15;
+
+    /// Seeds from the OS-randomized hasher keys, so runs differ.
+    fn new() -> Self {
+        Self {
+            state: Cell::new(RandomState::new().hash_one(0u64)),
+        }
+    }
+
+    fn next_u64(&self) -> u64 {
+        let mut z = self.state.get().wrapping_add(Self::GAMMA);
+        self.state.set(z);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform draw from `[0, 1)`.
+    fn next_f64(&self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Offset in `-spread..=spread` drawn from `distribution`.
+    fn offset(&self, spread: u64, distribution: JitterDistribution) -> i64 {
+        let spread = spread.min(i64::MAX as u64) as i64;
+        match distribution {
+            JitterDistribution::Uniform => {
+                let span = spread as u64 * 2 + 1;
+                (self.next_u64() % span) as i64 - spread
+            }
+            JitterDistribution::Normal => {
+                // Box-Muller, with the standard deviation at half the
+                // spread and the tails clamped to its ends.
+                let u1 = 1.0 - self.next_f64();
+                let u2 = self.next_f64();
+                let z = (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos();
+                ((z * spread as f64 / 2.0).round() as i64).clamp(-spread, spread)
+            }
+        }
+    }
+
+    fn jittered(
+        &self,
+        base: u64,
+        spread: u64,
+        distribution: JitterDistribution,
+        floor: u64,
+    ) -> u64 {
+        if spread == 0 {
+            return base;
+        }
+        base.saturating_add_signed(self.offset(spread, distribution))
+            .max(floor)
+    }
+}
+
+impl AppState {
+    /// Restarts the calling thread's jitter draws from `seed`, so the
+    /// timings it draws next repeat from run to run. Other threads keep
+    /// their own sequences. Meant for tests and debugging.
+    #[doc(hidden)]
+    pub fn seed_jitter(&self, seed: u64) {
+        JITTER_RNG.with(|rng| rng.state.set(seed));
+    }
+
+    /// Interval to wait before the next output, `interval` moved by the
+    /// mapping's interval jitter.
+    #[inline]
+    pub fn jitter_interval(&self, interval: u64, jitter: Option<Jitter>) -> u64 {
+        match jitter {
+            Some(jitter) => JITTER_RNG.with(|rng| {
+                rng.jittered(
+                    interval,
+                    jitter.interval,
+                    jitter.distribution,
+                    MIN_INTERVAL_MS,
+                )
+            }),
+            None => interval,
+        }
+    }
+
+    /// Time to hold the next press, `duration` moved by the mapping's
+    /// duration jitter.
+    #[inline]
+    pub fn jitter_duration(&self, duration: u64, jitter: Option<Jitter>) -> u64 {
+        match jitter {
+            Some(jitter) => JITTER_RNG.with(|rng| {
+                rng.jittered(
+                    duration,
+                    jitter.duration,
+                    jitter.distribution,
+                    MIN_EVENT_DURATION_MS,
+                )
+            }),
+            None => duration,
+        }
+    }
+}
@@ -0,0 +1,126 @@
+//! Runtime state of mapping layers.
+//!
+//! Each layer of the installed config owns one bit. A layer is active
+//! while its bit is set in either the held mask, kept by the switches
+//! whose trigger is down, or the toggled mask. `get_input_mapping` skips
+//! mappings whose layer is inactive.
+
+use std::sync::atomic::Ordering;
+
+use scc::Guard;
+
+use super::AppState;
+use super::types::*;
+use crate::config::{AppConfig, LayerMode};
+
+impl AppState {
+    pub(super) fn owned_layer_names(config: &AppConfig) -> Vec<String> {
+        config
+            .layer_names()
+            .into_iter()
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Whether any installed mapping switches a layer. Lets the dispatcher
+    /// skip the layer lookup for configs without layers.
+    #[inline(always)]
+    #[cfg_attr(not(windows), allow(dead_code))]
+    pub(crate) fn has_layer_switches(&self) -> bool {
+        self.has_layer_switches.load(Ordering::Relaxed)
+    }
+
+    /// Applies `event` to the layer state when its trigger is a layer
+    /// switch. Returns true when the event was consumed and must not
+    /// reach a worker.
+    ///
+    /// The first press turns a held layer on or flips a toggled one;
+    /// auto-repeat presses only get swallowed. A held layer stays on
+    /// until the last held switch for it is released.
+    #[cfg_attr(not(windows), allow(dead_code))]
+    pub(crate) fn handle_layer_event(&self, event: &InputEvent) -> bool {
+        match event {
+            InputEvent::Pressed(device) => {
+                if self.pressed_layer_switches.contains_sync(device) {
+                    return true;
+                }
+                // Inputs of a sequence carry its action but only the
+                // whole sequence switches the layer.
+                let Some(InputMappingInfo {
+                    target_action: OutputAction::SwitchLayer(switch),
+                    is_sequence,
+                    ..
+                }) = self.get_input_mapping(device)
+                else {
+                    return false;
+                };
+                if is_sequence && !matches!(device, InputDevice::Sequence(_)) {
+                    return false;
+                }
+                let _ = self
+                    .pressed_layer_switches
+                    .insert_sync(device.clone(), switch);
+                if switch.mode == LayerMode::Hold {
+                    self.held_layers.fetch_or(switch.layer, Ordering::Release);
+                } else {
+                    self.toggled_layers
+                        .fetch_xor(switch.layer, Ordering::Release);
+                }
+                true
+            }
+            InputEvent::Released(device) => {
+                let Some((_, switch)) = self.pressed_layer_switches.remove_sync(device) else {
+                    return false;
+                };
+                if switch.mode == LayerMode::Hold {
+                    let mut still_held = 0;
+                    self.pressed_layer_switches.iter_sync(|_, other| {
+                        if other.mode == LayerMode::Hold {
+                            still_held |= other.layer;
+                        }
+                        true
+                    });
+                    self.held_layers
+                        .fetch_and(!switch.layer | still_held, Ordering::Release);
+                }
+                true
+            }
+            InputEvent::RetapHold(device) => self.pressed_layer_switches.contains_sync(device),
+        }
+    }
+
+    /// Bits of every active layer.
+    #[inline(always)]
+    pub fn active_layers(&self) -> u64 {
+        self.held_layers.load(Ordering::Acquire) | self.toggled_layers.load(Ordering::Acquire)
+    }
+
+    /// Names of the active layers, in config order. Empty when only the
+    /// base mappings apply.
+    pub fn active_layer_names(&self) -> Vec<String> {
+        let active = self.active_layers();
+        self.layer_names()
+            .into_iter()
+            .enumerate()
+            .filter(|(bit, _)| active & (1u64 << bit) != 0)
+            .map(|(_, name)| name)
+            .collect()
+    }
+
+    /// Every layer of the installed config, in bit order.
+    pub fn layer_names(&self) -> Vec<String> {
+        let guard = Guard::new();
+        self.layer_names
+            .load(Ordering::Acquire, &guard)
+            .as_ref()
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Turns every layer off.
+    pub(super) fn reset_layers(&self) {
+        self.pressed_layer_switches.clear_sync();
+        self.held_layers.store(0, Ordering::Release);
+        self.toggled_layers.store(0, Ordering::Release);
+    }
+}
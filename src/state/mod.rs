@@ -1,12 +1,22 @@
 //! Application state management.
 
+mod gesture;
 pub mod handlers;
+mod jitter;
+mod layers;
+pub mod output;
 pub mod parsing;
+pub mod script;
 pub mod simulation;
+mod tap_hold;
 #[cfg(test)]
 mod tests;
+pub mod text;
 pub mod types;
+mod win32;
+mod worker;
 
+use std::cmp::Reverse;
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, AtomicU8, AtomicU32, AtomicU64, Ordering};
 use std::sync::{Arc, OnceLock};
@@ -16,20 +26,29 @@ use crossbeam_channel::{Receiver, Sender};
 use scc::{AtomicShared, Guard, Shared, Tag};
 use smallvec::SmallVec;
 
-use windows::Win32::Foundation::MAX_PATH;
-use windows::Win32::System::Threading::{
-    OpenProcess, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION, QueryFullProcessImageNameW,
-};
-use windows::Win32::UI::WindowsAndMessaging::*;
-use windows::core::PWSTR;
-
 use std::str::FromStr;
 
-use crate::config::AppConfig;
+use crate::clock::{Clock, SystemClock};
+use crate::config::{
+    AppConfig, CAPS_LOCK, DEFAULT_PROFILE, MIN_EVENT_DURATION_MS, MIN_INTERVAL_MS, NUM_LOCK,
+    ProcessFilter, ProcessScope, SCROLL_LOCK, split_step_timing,
+};
 use crate::i18n::Language;
 use crate::util::likely;
 
+// Both outcomes are matched on by the Windows keyboard hook.
+#[cfg_attr(not(windows), allow(unused_imports))]
+pub(crate) use gesture::GestureOutcome;
+pub use output::{OutputEvent, OutputSink, RecordingOutputSink};
+pub use script::{Script, ScriptError, ScriptOp, ScriptRun};
+#[cfg_attr(not(windows), allow(unused_imports))]
+pub(crate) use tap_hold::TapHoldOutcome;
+pub use text::{LayoutKeys, TextPlan, TextStroke};
 pub use types::*;
+#[cfg(windows)]
+pub use win32::Win32OutputSink;
+pub(crate) use win32::{WM_KEYDOWN, WM_KEYUP, WM_SYSKEYDOWN, WM_SYSKEYUP};
+pub use worker::TurboWorker;
 
 static GLOBAL_STATE: OnceLock<Arc<AppState>> = OnceLock::new();
 
@@ -40,6 +59,11 @@ const DEFAULT_KB_REPEAT_DELAY_MS: u64 = 500;
 /// Default repeat interval in ms used if `SPI_GETKEYBOARDSPEED` fails.
 const DEFAULT_KB_REPEAT_INTERVAL_MS: u64 = 33;
 
+/// Toggle-state bits of `AppState::lock_keys`.
+const LOCK_KEY_MASK: u8 = CAPS_LOCK | NUM_LOCK | SCROLL_LOCK;
+/// Shift from a lock key's toggle bit to its held bit.
+const LOCK_KEY_HELD_SHIFT: u8 = 4;
+
 /// Reads the system's keyboard repeat settings. Returns a tuple of the
 /// initial delay in ms and the repeat interval in ms. Falls back to
 /// `DEFAULT_KB_REPEAT_DELAY_MS` and `DEFAULT_KB_REPEAT_INTERVAL_MS` when
@@ -47,24 +71,7 @@ const DEFAULT_KB_REPEAT_INTERVAL_MS: u64 = 33;
 fn read_system_key_repeat() -> (u64, u64) {
     let mut delay_val: u32 = 1;
     let mut speed_val: u32 = 31;
-    // SAFETY: `delay_val` and `speed_val` are live `u32` locals with no
-    // aliasing borrows for the duration of each FFI call. Failure leaves
-    // the initial fallback values in place, and the `.max 1` guards below
-    // still accept them.
-    unsafe {
-        let _ = SystemParametersInfoW(
-            SPI_GETKEYBOARDDELAY,
-            0,
-            Some(&mut delay_val as *mut _ as *mut core::ffi::c_void),
-            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
-        );
-        let _ = SystemParametersInfoW(
-            SPI_GETKEYBOARDSPEED,
-            0,
-            Some(&mut speed_val as *mut _ as *mut core::ffi::c_void),
-            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
-        );
-    }
+    win32::read_keyboard_repeat(&mut delay_val, &mut speed_val);
     // SPI_GETKEYBOARDDELAY returns 0..=3 and delay in ms is value+1 times 250.
     let delay_ms = (delay_val.min(3) as u64 + 1) * 250;
     // SPI_GETKEYBOARDSPEED returns 0..=31. Microsoft maps 0 to about 2.5 Hz
@@ -78,6 +85,8 @@ fn read_system_key_repeat() -> (u64, u64) {
 #[derive(Debug, Clone)]
 pub(crate) struct ProcessInfo {
     pub name: Option<String>,
+    /// Lowercase full image path; `None` exactly when `name` is.
+    pub path: Option<String>,
     pub timestamp: Instant,
 }
 
@@ -86,12 +95,65 @@ pub struct AppState {
     show_tray_icon: AtomicBool,
     show_notifications: AtomicBool,
     pub switch_key_cache: SwitchKeyCache,
+    /// Hotkeys that cycle through the profiles. Cleared when unset.
+    pub(crate) next_profile_key_cache: SwitchKeyCache,
+    pub(crate) prev_profile_key_cache: SwitchKeyCache,
+    /// Full config as last loaded, including every profile, so a profile
+    /// switch can resolve its mappings without going back to the file.
+    profile_config: AtomicShared<AppConfig>,
+    /// Bumped by every successful `reload_config`, so the GUI can notice
+    /// configs applied behind its back (e.g. by the file watcher).
+    config_generation: AtomicU64,
+    /// Name of the profile whose mappings are currently installed.
+    active_profile: AtomicShared<String>,
+    /// Whether the config has any `profile_rules`; lets the hot path skip
+    /// the foreground-process lookup entirely when it does not.
+    has_profile_rules: AtomicBool,
+    /// Whether any installed mapping has `processes` or
+    /// `exclude_processes`, so held keys must be released whenever the
+    /// foreground process changes.
+    has_scoped_mappings: AtomicBool,
+    /// Caps / Num / Scroll Lock state as seen by the keyboard hook: the
+    /// low bits are the toggle states, the bits above
+    /// `LOCK_KEY_HELD_SHIFT` whether each key is held down.
+    lock_keys: AtomicU8,
+    /// Image path of the foreground process last seen by
+    /// `track_foreground_process`. Profile rules only run again once this
+    /// changes, so a manual switch sticks until focus moves to another
+    /// process.
+    foreground_path: AtomicShared<String>,
+    /// Profiles picked by `profile_rules` on the hook thread, waiting for
+    /// a worker to switch to them.
+    profile_switch_sender: Sender<String>,
+    profile_switch_receiver: Receiver<String>,
     /// Virtual-key code used to finalize a sequence capture in the Settings
     /// dialog. Mirrors `AppConfig::sequence_finalize_key`. Stored as an
     /// atomic so the capture helpers can read it without locking.
     pub sequence_finalize_vk: std::sync::atomic::AtomicU32,
     pub should_exit: Arc<AtomicBool>,
     is_paused: AtomicBool,
+    /// Layers active while an `activate_layer` trigger is held, one bit
+    /// per entry of `layer_names`.
+    held_layers: AtomicU64,
+    /// Layers turned on by a `layer_mode = "toggle"` trigger.
+    toggled_layers: AtomicU64,
+    /// Whether any installed mapping has `activate_layer`.
+    has_layer_switches: AtomicBool,
+    /// Layer names of the installed mappings, by bit.
+    layer_names: AtomicShared<Vec<String>>,
+    /// Layer switches whose trigger is down. Key auto-repeat presses of
+    /// these are swallowed, and the release ends a held layer.
+    pressed_layer_switches: scc::HashMap<InputDevice, LayerSwitch>,
+    /// Whether any installed mapping has `hold_target_keys`.
+    has_tap_holds: AtomicBool,
+    /// Tap-hold triggers that are down, deciding or held.
+    tap_holds: scc::HashMap<InputDevice, tap_hold::TapHoldPress>,
+    /// Whether any installed mapping has a `trigger_kind`.
+    has_gestures: AtomicBool,
+    /// Gestures of the installed mappings, by the input they count.
+    gesture_triggers: AtomicShared<HashMap<InputDevice, gesture::GestureTrigger>>,
+    /// Runs of gesture trigger presses that have not ended.
+    gesture_presses: scc::HashMap<InputDevice, gesture::GesturePress>,
     /// Cached result of the last `is_process_whitelisted` check. Workers
     /// read this atomically to decide whether to idle and drain their
     /// queues. Also used to detect the true → false transition so we can
@@ -116,7 +178,7 @@ pub struct AppState {
     xinput_stick_deadzone: std::sync::atomic::AtomicI16,
     /// XInput trigger activation threshold, snapshotted the same way.
     xinput_trigger_threshold: AtomicU8,
-    process_whitelist: AtomicShared<Vec<String>>,
+    process_filter: AtomicShared<ProcessFilter>,
     pub(crate) cached_process_info: AtomicShared<ProcessInfo>,
     pub(crate) pressed_keys: scc::HashSet<u32>,
     active_combo_triggers: scc::HashMap<InputDevice, SmallVec<[u32; 8]>>,
@@ -124,6 +186,9 @@ pub struct AppState {
     cached_turbo_other: scc::HashMap<InputDevice, bool>,
     pub(crate) cached_combo_index: scc::HashMap<u32, Vec<InputDevice>>,
     cached_xinput_combos: scc::HashMap<DeviceType, Vec<Vec<u32>>>,
+    /// Wheel combos of each direction, for finding the one whose keys
+    /// are held when a notch comes in.
+    cached_wheel_combos: scc::HashMap<MouseScrollDirection, Vec<InputDevice>>,
     raw_input_capture_sender: Sender<InputDevice>,
     raw_input_capture_receiver: Receiver<InputDevice>,
     is_capturing_raw_input: AtomicBool,
@@ -152,6 +217,11 @@ pub struct AppState {
     pub(crate) mouse_accum_dx: std::sync::atomic::AtomicI32,
     /// Accumulated Y delta counterpart.
     pub(crate) mouse_accum_dy: std::sync::atomic::AtomicI32,
+    /// Wheel delta left over after the last whole notch, for wheels that
+    /// report fractions of a notch.
+    pub(crate) wheel_accum: std::sync::atomic::AtomicI32,
+    /// Horizontal counterpart of `wheel_accum`.
+    pub(crate) hwheel_accum: std::sync::atomic::AtomicI32,
     /// Minimum squared single-event delta for a mouse event to count
     /// toward the motion accumulator. Events below this threshold are
     /// dropped as noise and do not contribute to the accumulator or the
@@ -178,12 +248,56 @@ pub struct AppState {
     /// Counterpart for mouse buttons. Indexed by `mouse_button_index`:
     /// 0=Left, 1=Right, 2=Middle, 3=X1, 4=X2.
     pub(crate) held_mouse_buttons: [std::sync::atomic::AtomicI32; 5],
+    /// Destination for every synthesized key / mouse event. The Win32
+    /// backend in production; tests swap in a `RecordingOutputSink` to
+    /// observe the exact event stream.
+    output_sink: Arc<dyn OutputSink>,
+    /// Time source for worker pacing, sequence timestamps and the
+    /// foreground-process cache. `SystemClock` in production.
+    clock: Arc<dyn Clock>,
 }
 
 impl AppState {
     pub fn new(config: AppConfig) -> anyhow::Result<Self> {
+        #[cfg(windows)]
+        let output_sink: Arc<dyn OutputSink> = Arc::new(Win32OutputSink);
+        // No injection backend exists off Windows; keep the output in memory.
+        #[cfg(not(windows))]
+        let output_sink: Arc<dyn OutputSink> = Arc::new(RecordingOutputSink::new());
+        let state = Self::with_backends(config, output_sink, Arc::new(SystemClock))?;
+        state.sync_lock_keys();
+        Ok(state)
+    }
+
+    /// Builds the state with a caller-supplied output backend instead of
+    /// `SendInput`. The sink is fixed for the lifetime of the state.
+    #[allow(dead_code)]
+    pub fn with_output_sink(
+        config: AppConfig,
+        output_sink: Arc<dyn OutputSink>,
+    ) -> anyhow::Result<Self> {
+        Self::with_backends(config, output_sink, Arc::new(SystemClock))
+    }
+
+    /// Builds the state with both the output backend and the time source
+    /// supplied by the caller. Tests pair a `RecordingOutputSink` with a
+    /// `VirtualClock` to check timing-dependent behavior deterministically.
+    pub fn with_backends(
+        config: AppConfig,
+        output_sink: Arc<dyn OutputSink>,
+        clock: Arc<dyn Clock>,
+    ) -> anyhow::Result<Self> {
         let switch_key_cache = SwitchKeyCache::new();
         Self::update_switch_key_cache(&switch_key_cache, &config.switch_key)?;
+        let next_profile_key_cache = SwitchKeyCache::new();
+        Self::update_profile_key_cache(&next_profile_key_cache, &config.next_profile_key)?;
+        let prev_profile_key_cache = SwitchKeyCache::new();
+        Self::update_profile_key_cache(&prev_profile_key_cache, &config.prev_profile_key)?;
+
+        // Everything below works on the active profile flattened onto the
+        // global settings; the full config is kept for later switches.
+        let profile_config = config;
+        let config = profile_config.effective_config();
         let sequence_finalize_vk = std::sync::atomic::AtomicU32::new(Self::parse_finalize_key(
             &config.sequence_finalize_key,
         ));
@@ -200,9 +314,11 @@ impl AppState {
         let cached_turbo_other = scc::HashMap::new();
         let cached_combo_index: scc::HashMap<u32, Vec<InputDevice>> = scc::HashMap::new();
         let cached_xinput_combos: scc::HashMap<DeviceType, Vec<Vec<u32>>> = scc::HashMap::new();
+        let cached_wheel_combos = scc::HashMap::new();
 
         for mapping in config.mappings.iter() {
             if let Some(device) = parsing::input_name_to_device(&mapping.trigger_key) {
+                Self::index_wheel_combo(&cached_wheel_combos, &device);
                 match &device {
                     InputDevice::Keyboard(vk) if *vk < 256 => {
                         cached_turbo_keyboard[*vk as usize]
@@ -248,6 +364,7 @@ impl AppState {
                 for part in parts {
                     let part_trimmed = part.trim();
                     if let Some(device) = parsing::input_name_to_device(part_trimmed) {
+                        Self::index_wheel_combo(&cached_wheel_combos, &device);
                         match device {
                             InputDevice::XInputCombo {
                                 device_type,
@@ -285,6 +402,7 @@ impl AppState {
 
         let (raw_input_capture_sender, raw_input_capture_receiver) = crossbeam_channel::unbounded();
         let (hid_activation_sender, hid_activation_receiver) = crossbeam_channel::unbounded();
+        let (profile_switch_sender, profile_switch_receiver) = crossbeam_channel::unbounded();
         let (hid_activation_data_sender, hid_activation_data_receiver) =
             crossbeam_channel::unbounded();
 
@@ -306,15 +424,42 @@ impl AppState {
             show_tray_icon: AtomicBool::new(config.show_tray_icon),
             show_notifications: AtomicBool::new(config.show_notifications),
             switch_key_cache,
+            next_profile_key_cache,
+            prev_profile_key_cache,
+            active_profile: AtomicShared::from(Shared::new(config.active_profile.clone())),
+            has_profile_rules: AtomicBool::new(!profile_config.profile_rules.is_empty()),
+            has_scoped_mappings: AtomicBool::new(
+                config.mappings.iter().any(|m| m.process_scope().is_some()),
+            ),
+            lock_keys: AtomicU8::new(0),
+            foreground_path: AtomicShared::null(),
+            profile_switch_sender,
+            profile_switch_receiver,
+            profile_config: AtomicShared::from(Shared::new(profile_config)),
+            config_generation: AtomicU64::new(0),
             sequence_finalize_vk,
             should_exit: Arc::new(AtomicBool::new(false)),
             is_paused: AtomicBool::new(false),
+            held_layers: AtomicU64::new(0),
+            toggled_layers: AtomicU64::new(0),
+            has_layer_switches: AtomicBool::new(
+                config.mappings.iter().any(|m| m.layer_switch().is_some()),
+            ),
+            layer_names: AtomicShared::from(Shared::new(Self::owned_layer_names(&config))),
+            pressed_layer_switches: scc::HashMap::new(),
+            has_tap_holds: AtomicBool::new(config.mappings.iter().any(|m| m.tap_hold().is_some())),
+            tap_holds: scc::HashMap::new(),
+            has_gestures: AtomicBool::new(config.mappings.iter().any(|m| m.gesture().is_some())),
+            gesture_triggers: AtomicShared::from(Shared::new(Self::collect_gesture_triggers(
+                &config,
+            ))),
+            gesture_presses: scc::HashMap::new(),
             prev_whitelisted: AtomicBool::new(true),
             show_window_requested: AtomicBool::new(false),
             show_about_requested: AtomicBool::new(false),
             input_timeout: AtomicU64::new(config.input_timeout),
             worker_count: AtomicU64::new(0),
-            process_whitelist: AtomicShared::from(Shared::new(config.process_whitelist.clone())),
+            process_filter: AtomicShared::from(Shared::new(config.process_filter())),
             configured_worker_count: config.worker_count,
             input_mappings,
             worker_pool: OnceLock::new(),
@@ -325,7 +470,8 @@ impl AppState {
             xinput_trigger_threshold: AtomicU8::new(config.xinput_trigger_threshold),
             cached_process_info: AtomicShared::from(Shared::new(ProcessInfo {
                 name: None,
-                timestamp: Instant::now(),
+                path: None,
+                timestamp: clock.now(),
             })),
             pressed_keys: scc::HashSet::new(),
             active_combo_triggers: scc::HashMap::new(),
@@ -333,6 +479,7 @@ impl AppState {
             cached_turbo_other,
             cached_combo_index,
             cached_xinput_combos,
+            cached_wheel_combos,
             raw_input_capture_sender,
             raw_input_capture_receiver,
             is_capturing_raw_input: AtomicBool::new(false),
@@ -357,6 +504,8 @@ impl AppState {
             mouse_opp_distance_px: std::sync::atomic::AtomicU32::new(0),
             mouse_accum_dx: std::sync::atomic::AtomicI32::new(0),
             mouse_accum_dy: std::sync::atomic::AtomicI32::new(0),
+            wheel_accum: std::sync::atomic::AtomicI32::new(0),
+            hwheel_accum: std::sync::atomic::AtomicI32::new(0),
             mouse_move_per_event_min_sq: AtomicU32::new(
                 config
                     .mouse_move_per_event_min_px
@@ -375,11 +524,15 @@ impl AppState {
                 std::sync::atomic::AtomicI32::new(0)
             })),
             held_mouse_buttons: std::array::from_fn(|_| std::sync::atomic::AtomicI32::new(0)),
+            output_sink,
+            clock,
         })
     }
 
     pub fn reload_config(&self, config: AppConfig) -> anyhow::Result<()> {
         Self::update_switch_key_cache(&self.switch_key_cache, &config.switch_key)?;
+        Self::update_profile_key_cache(&self.next_profile_key_cache, &config.next_profile_key)?;
+        Self::update_profile_key_cache(&self.prev_profile_key_cache, &config.prev_profile_key)?;
         self.sequence_finalize_vk.store(
             Self::parse_finalize_key(&config.sequence_finalize_key),
             Ordering::Relaxed,
@@ -427,21 +580,80 @@ impl AppState {
         let _ = self
             .xinput_capture_mode
             .swap((Some(new_xinput_mode), Tag::None), Ordering::Release);
-        let new_input_mappings = Self::create_input_mappings(&config)?;
+        let effective = config.effective_config();
+        let new_input_mappings = Self::create_input_mappings(&effective)?;
+        self.install_mappings(&effective, new_input_mappings);
+        let _ = self.active_profile.swap(
+            (Some(Shared::new(effective.active_profile)), Tag::None),
+            Ordering::Release,
+        );
+        let new_cache = Shared::new(ProcessInfo {
+            name: None,
+            path: None,
+            timestamp: self.clock.now(),
+        });
+        let _ = self
+            .cached_process_info
+            .swap((Some(new_cache), Tag::None), Ordering::Release);
+        self.has_profile_rules
+            .store(!config.profile_rules.is_empty(), Ordering::Relaxed);
+        let _ = self
+            .foreground_path
+            .swap((None, Tag::None), Ordering::Release);
+        let _ = self
+            .profile_config
+            .swap((Some(Shared::new(config)), Tag::None), Ordering::Release);
+        self.config_generation.fetch_add(1, Ordering::AcqRel);
+
+        Ok(())
+    }
+
+    /// Number of successful `reload_config` calls so far.
+    pub fn config_generation(&self) -> u64 {
+        self.config_generation.load(Ordering::Acquire)
+    }
+
+    /// The full config last passed to `reload_config` (or the
+    /// constructor), with every profile.
+    pub fn current_config(&self) -> AppConfig {
+        let guard = Guard::new();
+        self.profile_config
+            .load(Ordering::Acquire, &guard)
+            .as_ref()
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Replaces the trigger table and every cache derived from the
+    /// mappings with those of `config`, which must already be resolved to
+    /// a single profile. Hooks and workers keep running; in-flight combo
+    /// and sequence state is dropped.
+    fn install_mappings(
+        &self,
+        config: &AppConfig,
+        new_input_mappings: HashMap<InputDevice, InputMappingInfo>,
+    ) {
         self.input_mappings.clear_sync();
         for (k, v) in new_input_mappings {
             let _ = self.input_mappings.insert_sync(k, v);
         }
 
+        self.has_scoped_mappings.store(
+            config.mappings.iter().any(|m| m.process_scope().is_some()),
+            Ordering::Relaxed,
+        );
+
         self.cached_turbo_other.clear_sync();
         self.cached_combo_index.clear_sync();
         self.cached_xinput_combos.clear_sync();
+        self.cached_wheel_combos.clear_sync();
         for i in 0..256 {
             self.cached_turbo_keyboard[i].store(true, Ordering::Relaxed);
         }
 
         for mapping in config.mappings.iter() {
             if let Some(device) = parsing::input_name_to_device(&mapping.trigger_key) {
+                Self::index_wheel_combo(&self.cached_wheel_combos, &device);
                 match &device {
                     InputDevice::Keyboard(vk) if *vk < 256 => {
                         self.cached_turbo_keyboard[*vk as usize]
@@ -493,6 +705,7 @@ impl AppState {
                 for part in parts {
                     let part_trimmed = part.trim();
                     if let Some(device) = parsing::input_name_to_device(part_trimmed) {
+                        Self::index_wheel_combo(&self.cached_wheel_combos, &device);
                         match device {
                             InputDevice::XInputCombo {
                                 device_type,
@@ -532,20 +745,44 @@ impl AppState {
             }
         }
 
-        let new_whitelist = Shared::new(config.process_whitelist.clone());
+        let new_filter = Shared::new(config.process_filter());
         let _ = self
-            .process_whitelist
-            .swap((Some(new_whitelist), Tag::None), Ordering::Release);
-        let new_cache = Shared::new(ProcessInfo {
-            name: None,
-            timestamp: Instant::now(),
-        });
-        let _ = self
-            .cached_process_info
-            .swap((Some(new_cache), Tag::None), Ordering::Release);
+            .process_filter
+            .swap((Some(new_filter), Tag::None), Ordering::Release);
         self.pressed_keys.clear_sync();
         self.active_combo_triggers.clear_sync();
 
+        // Layer bits are assigned per config, so old ones mean nothing.
+        self.has_layer_switches.store(
+            config.mappings.iter().any(|m| m.layer_switch().is_some()),
+            Ordering::Relaxed,
+        );
+        let _ = self.layer_names.swap(
+            (
+                Some(Shared::new(Self::owned_layer_names(config))),
+                Tag::None,
+            ),
+            Ordering::Release,
+        );
+        self.reset_layers();
+        self.has_tap_holds.store(
+            config.mappings.iter().any(|m| m.tap_hold().is_some()),
+            Ordering::Relaxed,
+        );
+        self.reset_tap_holds();
+        self.has_gestures.store(
+            config.mappings.iter().any(|m| m.gesture().is_some()),
+            Ordering::Relaxed,
+        );
+        let _ = self.gesture_triggers.swap(
+            (
+                Some(Shared::new(Self::collect_gesture_triggers(config))),
+                Tag::None,
+            ),
+            Ordering::Release,
+        );
+        self.reset_gestures();
+
         self.sequence_matcher.clear_sequences();
         self.sequence_matcher.clear_history();
         for mapping in config.mappings.iter() {
@@ -563,10 +800,90 @@ impl AppState {
             pool.clear_cache();
         }
         self.xinput_cache_invalid.store(true, Ordering::Release);
+    }
 
+    /// Name of the profile whose mappings are currently installed.
+    pub fn active_profile_name(&self) -> String {
+        let guard = Guard::new();
+        self.active_profile
+            .load(Ordering::Acquire, &guard)
+            .as_ref()
+            .cloned()
+            .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+    }
+
+    /// Every selectable profile, in the order the profile hotkeys cycle
+    /// through them.
+    pub fn profile_names(&self) -> Vec<String> {
+        let guard = Guard::new();
+        self.profile_config
+            .load(Ordering::Acquire, &guard)
+            .as_ref()
+            .map(|config| {
+                config
+                    .profile_names()
+                    .into_iter()
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Activates profile `name`: releases held keys, then rebuilds the
+    /// mapping caches the same way `reload_config` does. Hooks and
+    /// workers are left running.
+    ///
+    /// # Errors
+    ///
+    /// Fails for an unknown profile or one whose mappings do not build;
+    /// the current profile then stays active.
+    pub fn switch_profile(&self, name: &str) -> anyhow::Result<()> {
+        let resolved = {
+            let guard = Guard::new();
+            self.profile_config
+                .load(Ordering::Acquire, &guard)
+                .as_ref()
+                .and_then(|config| config.resolve_profile(name))
+        }
+        .ok_or_else(|| anyhow::anyhow!("Unknown profile: {}", name))?;
+        let input_mappings = Self::create_input_mappings(&resolved)
+            .map_err(|e| anyhow::anyhow!("Profile \"{}\": {}", name, e))?;
+
+        self.release_all_held_keys();
+        self.reset_transient_state();
+        self.install_mappings(&resolved, input_mappings);
+        let _ = self.active_profile.swap(
+            (Some(Shared::new(name.to_string())), Tag::None),
+            Ordering::Release,
+        );
+
+        if let Some(sender) = self.notification_sender.get() {
+            let _ = sender.send(NotificationEvent::Info(format!("Profile: {}", name)));
+        }
         Ok(())
     }
 
+    /// Switches to the next (or previous) profile, wrapping around. Does
+    /// nothing when only the default profile exists.
+    pub fn cycle_profile(&self, forward: bool) {
+        let names = self.profile_names();
+        if names.len() < 2 {
+            return;
+        }
+        let current = self.active_profile_name();
+        let idx = names.iter().position(|n| *n == current).unwrap_or(0);
+        let next = if forward {
+            (idx + 1) % names.len()
+        } else {
+            (idx + names.len() - 1) % names.len()
+        };
+        if let Err(e) = self.switch_profile(&names[next])
+            && let Some(sender) = self.notification_sender.get()
+        {
+            let _ = sender.send(NotificationEvent::Error(e.to_string()));
+        }
+    }
+
     pub fn set_worker_pool(&self, pool: Arc<dyn EventDispatcher>) {
         let _ = self.worker_pool.set(pool);
     }
@@ -612,12 +929,16 @@ impl AppState {
 
         if enabled {
             while self.raw_input_capture_receiver.try_recv().is_ok() {}
-            crate::rawinput::clear_device_display_info_cache();
-            crate::rawinput::reset_hid_device_states();
+            #[cfg(windows)]
+            {
+                crate::rawinput::clear_device_display_info_cache();
+                crate::rawinput::reset_hid_device_states();
+            }
         }
     }
 
     /// Clears activation baseline for device.
+    #[cfg(windows)]
     #[inline]
     pub fn clear_device_baseline(&self, vid: u16, pid: u16) {
         crate::rawinput::clear_device_baseline(vid, pid);
@@ -813,10 +1134,94 @@ impl AppState {
         self.worker_count.store(count as u64, Ordering::Relaxed);
     }
 
-    /// Fast mapping lookup using lock-free read
+    /// Fast mapping lookup using lock-free read. Returns the first of the
+    /// trigger's mappings whose conditions hold: its layer is active, the
+    /// foreground process is in its scope and the lock keys are in the
+    /// required state.
     #[inline(always)]
     pub fn get_input_mapping(&self, device: &InputDevice) -> Option<InputMappingInfo> {
-        self.input_mappings.read_sync(device, |_, v| v.clone())
+        let info = self.input_mappings.read_sync(device, |_, v| v.clone())?;
+        if likely(!info.is_conditional) {
+            return Some(info);
+        }
+        info.candidates()
+            .find(|candidate| self.conditions_hold(candidate))
+            .cloned()
+    }
+
+    #[inline]
+    fn conditions_hold(&self, info: &InputMappingInfo) -> bool {
+        if info.layer != 0 && self.active_layers() & info.layer == 0 {
+            return false;
+        }
+        if let Some(lock_keys) = &info.lock_keys
+            && !lock_keys.matches(self.lock_key_state())
+        {
+            return false;
+        }
+        info.process_scope
+            .as_ref()
+            .is_none_or(|scope| self.foreground_in_scope(scope))
+    }
+
+    /// Caps / Num / Scroll Lock bits that are currently on.
+    #[inline(always)]
+    pub fn lock_key_state(&self) -> u8 {
+        self.lock_keys.load(Ordering::Relaxed) & LOCK_KEY_MASK
+    }
+
+    /// Follows the lock keys through a keyboard hook event. Called for
+    /// every event, simulated ones included, since those toggle the lock
+    /// keys too. A lock key toggles when it goes down, not on the repeats
+    /// while it is held.
+    #[inline(always)]
+    pub fn track_lock_key(&self, message: u32, vk_code: u32) {
+        let bit = match vk_code {
+            0x14 => CAPS_LOCK,
+            0x90 => NUM_LOCK,
+            0x91 => SCROLL_LOCK,
+            _ => return,
+        };
+        let held = bit << LOCK_KEY_HELD_SHIFT;
+        match message {
+            WM_KEYDOWN | WM_SYSKEYDOWN => {
+                let _ =
+                    self.lock_keys
+                        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |state| {
+                            (state & held == 0).then_some((state | held) ^ bit)
+                        });
+            }
+            WM_KEYUP | WM_SYSKEYUP => {
+                self.lock_keys.fetch_and(!held, Ordering::Relaxed);
+            }
+            _ => {}
+        }
+    }
+
+    /// Reads the lock-key states from the system, for startup.
+    fn sync_lock_keys(&self) {
+        let mut state = 0;
+        for (vk, bit) in [(0x14, CAPS_LOCK), (0x90, NUM_LOCK), (0x91, SCROLL_LOCK)] {
+            if win32::lock_key_toggled(vk) {
+                state |= bit;
+            }
+        }
+        self.lock_keys.store(state, Ordering::Relaxed);
+    }
+
+    /// Checks a mapping's process scope against the cached foreground
+    /// process. An unknown foreground process is in every scope.
+    #[inline]
+    fn foreground_in_scope(&self, scope: &ProcessScope) -> bool {
+        let guard = Guard::new();
+        match self.foreground_process(&guard) {
+            Some(ProcessInfo {
+                name: Some(name),
+                path: Some(path),
+                ..
+            }) => scope.allows(name, path),
+            _ => true,
+        }
     }
 
     /// Gets all XInputCombo button_ids for a specific device type
@@ -873,13 +1278,11 @@ impl AppState {
     /// the app transitions into an inactive state (pause, process left the
     /// whitelist) so downstream apps don't see a stuck key.
     pub fn release_all_held_keys(&self) {
-        let mut inputs: smallvec::SmallVec<
-            [windows::Win32::UI::Input::KeyboardAndMouse::INPUT; 32],
-        > = smallvec::SmallVec::new();
+        let mut events: SmallVec<[OutputEvent; 32]> = SmallVec::new();
         for (idx, cell) in self.held_scancodes.iter().enumerate() {
             let prev = cell.swap(0, Ordering::AcqRel);
             if prev > 0 {
-                inputs.push(Self::build_key_input(idx as u16, true));
+                events.push(OutputEvent::KeyUp(idx as u16));
             }
         }
         for (idx, cell) in self.held_mouse_buttons.iter().enumerate() {
@@ -892,18 +1295,10 @@ impl AppState {
                     3 => MouseButton::X1,
                     _ => MouseButton::X2,
                 };
-                inputs.push(Self::build_mouse_input(btn, true));
-            }
-        }
-        if !inputs.is_empty() {
-            unsafe {
-                windows::Win32::UI::Input::KeyboardAndMouse::SendInput(
-                    &inputs,
-                    std::mem::size_of::<windows::Win32::UI::Input::KeyboardAndMouse::INPUT>()
-                        as i32,
-                );
+                events.push(OutputEvent::MouseUp(btn));
             }
         }
+        self.emit(&events);
     }
 
     /// Resets every piece of short-lived state the handler accumulates
@@ -922,8 +1317,20 @@ impl AppState {
         self.mouse_opp_distance_px.store(0, Ordering::Release);
         self.mouse_accum_dx.store(0, Ordering::Release);
         self.mouse_accum_dy.store(0, Ordering::Release);
+        self.wheel_accum.store(0, Ordering::Release);
+        self.hwheel_accum.store(0, Ordering::Release);
         self.sequence_matcher.clear_history();
         self.pressed_keys.clear_sync();
+        self.reset_layers();
+        self.reset_tap_holds();
+        self.reset_gestures();
+    }
+
+    /// Time source shared by the hook handlers, workers and simulation
+    /// layer.
+    #[inline(always)]
+    pub fn clock(&self) -> &dyn Clock {
+        &*self.clock
     }
 
     /// Cheap atomic read used by worker threads to decide whether they
@@ -945,6 +1352,42 @@ impl AppState {
             .try_match_with_sequence()
             .map(|(device, arc)| (device, arc.to_vec()))
     }
+
+    /// Identity of the sequence mapping that last fired, provided its
+    /// final input satisfies `ends`.
+    #[inline]
+    pub(crate) fn active_sequence_ending(
+        &self,
+        ends: impl Fn(&InputDevice) -> bool,
+    ) -> Option<InputDevice> {
+        let guard = Guard::new();
+        let inputs = self.last_sequence_inputs.load(Ordering::Acquire, &guard);
+        if !inputs.as_ref()?.last().is_some_and(ends) {
+            return None;
+        }
+        self.last_sequence_device
+            .load(Ordering::Acquire, &guard)
+            .as_ref()
+            .cloned()
+    }
+
+    /// Clears the active sequence when its final input satisfies `ends`,
+    /// i.e. when that input was just released, and returns its identity
+    /// so the caller can release the sequence mapping.
+    #[inline]
+    pub(crate) fn end_sequence_on_release(
+        &self,
+        ends: impl Fn(&InputDevice) -> bool,
+    ) -> Option<InputDevice> {
+        let sequence = self.active_sequence_ending(ends)?;
+        let _ = self
+            .last_sequence_device
+            .swap((None, Tag::None), Ordering::Release);
+        let _ = self
+            .last_sequence_inputs
+            .swap((None, Tag::None), Ordering::Release);
+        Some(sequence)
+    }
     #[inline(always)]
     pub(crate) fn is_in_active_combo(&self, vk_code: u32) -> bool {
         self.active_combo_triggers.any_sync(|combo_device, _| {
@@ -1016,42 +1459,8 @@ impl AppState {
     }
 
     /// Get the process name of the foreground window
-    fn get_foreground_process_name() -> Option<String> {
-        unsafe {
-            let hwnd = GetForegroundWindow();
-            if hwnd.0.is_null() {
-                return None;
-            }
-
-            let mut process_id: u32 = 0;
-            GetWindowThreadProcessId(hwnd, Some(&mut process_id as *mut u32));
-            if process_id == 0 {
-                return None;
-            }
-
-            let process_handle =
-                match OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, process_id) {
-                    Ok(handle) => handle,
-                    Err(_) => return None,
-                };
-
-            let mut buffer = [0u16; MAX_PATH as usize];
-            let mut size = buffer.len() as u32;
-
-            match QueryFullProcessImageNameW(
-                process_handle,
-                PROCESS_NAME_WIN32,
-                PWSTR(buffer.as_mut_ptr()),
-                &mut size,
-            ) {
-                Ok(_) => {
-                    let path = String::from_utf16_lossy(&buffer[..size as usize]);
-                    // Extract filename from full path
-                    path.split('\\').next_back().map(|s| s.to_lowercase())
-                }
-                Err(_) => None,
-            }
-        }
+    fn get_foreground_process_path() -> Option<String> {
+        win32::foreground_process_path()
     }
 
     /// Check if current foreground process is in whitelist (empty whitelist = all allowed)
@@ -1060,6 +1469,7 @@ impl AppState {
     /// previous app don't linger after the foreground window changes out.
     #[inline(always)]
     pub(crate) fn is_process_whitelisted(&self) -> bool {
+        self.track_foreground_process();
         let current = self.compute_whitelisted();
         let prev = self.prev_whitelisted.swap(current, Ordering::AcqRel);
         if prev && !current {
@@ -1073,114 +1483,267 @@ impl AppState {
         current
     }
 
+    /// Reacts to the foreground process changing since the last call:
+    /// releases held keys when mappings are scoped to processes, then
+    /// queues the profile `profile_rules` pick for
+    /// `apply_pending_profile_switch`. Rebuilding the mappings is too slow
+    /// for the hooks, so the switch itself waits for a worker's next tick.
+    /// An unknown foreground process changes nothing.
+    #[inline(always)]
+    fn track_foreground_process(&self) {
+        let has_rules = self.has_profile_rules.load(Ordering::Relaxed);
+        let has_scoped = self.has_scoped_mappings.load(Ordering::Relaxed);
+        if likely(!has_rules && !has_scoped) {
+            return;
+        }
+        let guard = Guard::new();
+        let Some((name, path)) = self
+            .foreground_process(&guard)
+            .and_then(|info| Some((info.name.as_deref()?, info.path.as_deref()?)))
+        else {
+            return;
+        };
+        if self
+            .foreground_path
+            .load(Ordering::Acquire, &guard)
+            .as_ref()
+            .is_some_and(|last| last == path)
+        {
+            return;
+        }
+
+        let target = if has_rules {
+            self.profile_config
+                .load(Ordering::Acquire, &guard)
+                .as_ref()
+                .and_then(|config| config.profile_for_process(name, path))
+                .map(str::to_string)
+        } else {
+            None
+        };
+        let _ = self.foreground_path.swap(
+            (Some(Shared::new(path.to_string())), Tag::None),
+            Ordering::Release,
+        );
+        drop(guard);
+
+        if has_scoped {
+            // A held mapping may not apply in the new process, and its
+            // release would no longer resolve to it.
+            self.release_all_held_keys();
+            self.reset_transient_state();
+        }
+
+        if let Some(target) = target {
+            let _ = self.profile_switch_sender.send(target);
+        }
+    }
+
+    /// Switches to the profile `profile_rules` picked last, if it is not
+    /// already active. Called from the worker loop, off the hook threads.
+    pub fn apply_pending_profile_switch(&self) {
+        let Some(target) = self.profile_switch_receiver.try_iter().last() else {
+            return;
+        };
+        if target != self.active_profile_name()
+            && let Err(e) = self.switch_profile(&target)
+            && let Some(sender) = self.notification_sender.get()
+        {
+            let _ = sender.send(NotificationEvent::Error(e.to_string()));
+        }
+    }
+
     /// Raw check without transition side effects. Used internally by
     /// `is_process_whitelisted` and by `handle_switch_key_toggle` to
     /// decide worker activity on unpause.
     #[inline(always)]
     fn compute_whitelisted(&self) -> bool {
         let guard = Guard::new();
-        let whitelist_ptr = self.process_whitelist.load(Ordering::Acquire, &guard);
-        let empty_vec = vec![];
-        let whitelist = whitelist_ptr.as_ref().unwrap_or(&empty_vec);
-
-        if whitelist.is_empty() {
+        let Some(filter) = self.process_filter.load(Ordering::Acquire, &guard).as_ref() else {
             return true;
+        };
+        if filter.is_empty() {
+            return true;
+        }
+
+        match self.foreground_process(&guard) {
+            Some(ProcessInfo {
+                name: Some(name),
+                path: Some(path),
+                ..
+            }) => filter.allows(name, path),
+            _ => true,
         }
+    }
 
+    /// Foreground process, re-queried at most every 50 ms and served from
+    /// `cached_process_info` in between.
+    #[inline(always)]
+    fn foreground_process<'g>(&self, guard: &'g Guard) -> Option<&'g ProcessInfo> {
         const CACHE_DURATION_MS: u64 = 50;
-        let now = Instant::now();
+        let now = self.clock.now();
 
-        let process_name = {
-            let cache_ptr = self.cached_process_info.load(Ordering::Acquire, &guard);
-            let cache = cache_ptr.as_ref();
+        let cache_ptr = self.cached_process_info.load(Ordering::Acquire, guard);
+        if let Some(info) = cache_ptr.as_ref()
+            && likely(now.duration_since(info.timestamp) < Duration::from_millis(CACHE_DURATION_MS))
+        {
+            return Some(info);
+        }
 
-            if let Some(info) = cache {
-                if likely(
-                    now.duration_since(info.timestamp) < Duration::from_millis(CACHE_DURATION_MS),
-                ) {
-                    info.name.clone()
-                } else {
-                    let new_name = Self::get_foreground_process_name();
-                    let new_cache = Shared::new(ProcessInfo {
-                        name: new_name.clone(),
-                        timestamp: now,
-                    });
-                    let _ = self
-                        .cached_process_info
-                        .swap((Some(new_cache), Tag::None), Ordering::Release);
-                    new_name
-                }
-            } else {
-                let new_name = Self::get_foreground_process_name();
-                let new_cache = Shared::new(ProcessInfo {
-                    name: new_name.clone(),
+        let path = Self::get_foreground_process_path();
+        let name = path
+            .as_deref()
+            .and_then(|p| p.rsplit('\\').next())
+            .map(str::to_string);
+        let _ = self.cached_process_info.swap(
+            (
+                Some(Shared::new(ProcessInfo {
+                    name,
+                    path,
                     timestamp: now,
-                });
-                let _ = self
-                    .cached_process_info
-                    .swap((Some(new_cache), Tag::None), Ordering::Release);
-                new_name
-            }
-        };
-
-        if let Some(name) = process_name {
-            whitelist.iter().any(|p| p.to_lowercase() == name)
-        } else {
-            true
-        }
+                })),
+                Tag::None,
+            ),
+            Ordering::Release,
+        );
+        self.cached_process_info
+            .load(Ordering::Acquire, guard)
+            .as_ref()
     }
 
+    /// Builds the trigger -> action table. A trigger used by several
+    /// mappings maps to the one tried first, with the rest chained behind
+    /// it through `InputMappingInfo::fallback`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if any mapping has an error-level diagnostic from
+    /// `AppConfig::validate`; the message lists all of them, not just the
+    /// first. The mappings of profiles other than the resolved one do not
+    /// count.
     pub fn create_input_mappings(
         config: &AppConfig,
     ) -> anyhow::Result<HashMap<InputDevice, InputMappingInfo>> {
-        let mut input_mappings = HashMap::new();
+        let errors: Vec<String> = config
+            .validate()
+            .into_iter()
+            .filter(|d| {
+                d.is_error()
+                    && d.mapping_index.is_some()
+                    && d.location.as_ref().is_some_and(|l| l.profile.is_none())
+            })
+            .map(|d| d.to_string())
+            .collect();
+        if !errors.is_empty() {
+            anyhow::bail!("Invalid mappings:\n{}", errors.join("\n"));
+        }
 
-        for mapping in &config.mappings {
-            // For sequence triggers, use the LAST key in the sequence as the trigger device
-            // This aligns with the sequence matcher which returns the last key for triggering
-            let trigger_device = if mapping.is_sequence_trigger() {
-                // Extract last key from trigger_sequence
-                if let Some(seq_str) = &mapping.trigger_sequence {
-                    let parts: Vec<&str> = seq_str.split(',').collect();
-                    if let Some(last_key) = parts.last() {
-                        let last_key_trimmed = last_key.trim();
-
-                        parsing::input_name_to_device(last_key_trimmed).ok_or_else(|| {
-                            anyhow::anyhow!("Invalid sequence last key: {}", last_key_trimmed)
-                        })?
-                    } else {
-                        parsing::input_name_to_device(&mapping.trigger_key).ok_or_else(|| {
-                            anyhow::anyhow!("Invalid trigger input: {}", mapping.trigger_key)
-                        })?
+        // Trigger device -> (priority, index, info) of every mapping on
+        // it, and the sequence-only entries for the inputs of sequences.
+        let mut candidates: HashMap<InputDevice, Vec<(i32, usize, InputMappingInfo)>> =
+            HashMap::new();
+        let mut sequence_keys: HashMap<InputDevice, InputMappingInfo> = HashMap::new();
+        let mut gesture_inputs: Vec<(InputDevice, &str)> = Vec::new();
+        let layer_names = config.layer_names();
+
+        for (idx, mapping) in config.mappings.iter().enumerate() {
+            // A sequence mapping is keyed by the identity the sequence
+            // matcher reports for it. A sequence that does not parse is
+            // never registered with the matcher, so it is left out.
+            let sequence = match &mapping.trigger_sequence {
+                Some(seq_str) => match crate::sequence_matcher::parse_sequence_string(
+                    seq_str,
+                    Some(mapping.sequence_window_ms),
+                ) {
+                    Ok(sequence) => Some(sequence),
+                    Err(_) => continue,
+                },
+                None => None,
+            };
+            let trigger_device = match &sequence {
+                Some(sequence) => sequence.identity(),
+                None => parsing::input_name_to_device(&mapping.trigger_key).ok_or_else(|| {
+                    anyhow::anyhow!("Invalid trigger input: {}", mapping.trigger_key)
+                })?,
+            };
+            // A wheel notch is pressed and released at once, so it can
+            // neither be held nor make a gesture.
+            let wheel_trigger = trigger_device.is_wheel_trigger();
+            // A gesture mapping is keyed by its gesture; the plain
+            // presses of its input keep their own mappings.
+            let gesture = mapping.gesture().filter(|_| !wheel_trigger);
+            let trigger_device = match gesture {
+                Some(gesture) => {
+                    if !gesture_inputs
+                        .iter()
+                        .any(|(input, _)| *input == trigger_device)
+                    {
+                        gesture_inputs.push((trigger_device.clone(), mapping.trigger_key.as_str()));
                     }
-                } else {
-                    parsing::input_name_to_device(&mapping.trigger_key).ok_or_else(|| {
-                        anyhow::anyhow!("Invalid trigger input: {}", mapping.trigger_key)
-                    })?
+                    InputDevice::Gesture(Arc::new(trigger_device), gesture.kind)
                 }
-            } else {
-                // For non-sequence triggers, use trigger_key as usual
-                parsing::input_name_to_device(&mapping.trigger_key).ok_or_else(|| {
-                    anyhow::anyhow!("Invalid trigger input: {}", mapping.trigger_key)
-                })?
+                None => trigger_device,
+            };
+
+            // Layers past the cap never activate, so neither do their
+            // mappings.
+            let layer_bit = |name: &str| {
+                layer_names
+                    .iter()
+                    .position(|n| *n == name)
+                    .map(|bit| 1u64 << bit)
+            };
+            let layer = match mapping.layer_condition() {
+                Some(name) => match layer_bit(name) {
+                    Some(bit) => bit,
+                    None => continue,
+                },
+                None => 0,
+            };
+            let layer_switch = match mapping.layer_switch() {
+                Some((name, mode)) => match layer_bit(name) {
+                    Some(layer) => Some(LayerSwitch { layer, mode }),
+                    None => continue,
+                },
+                None => None,
             };
 
-            let target_keys = mapping.get_target_keys();
-            if target_keys.is_empty() {
+            // A script or a text replaces the target keys.
+            let script = match mapping.macro_script().filter(|_| layer_switch.is_none()) {
+                Some(source) => Some(Arc::new(
+                    Script::parse(source).map_err(|e| anyhow::anyhow!("Invalid macro: {e}"))?,
+                )),
+                None => None,
+            };
+            let text = mapping
+                .type_text()
+                .filter(|_| layer_switch.is_none() && script.is_none());
+            let is_script = script.is_some();
+            let replaces_keys = is_script || text.is_some();
+            let target_keys: &[String] = if replaces_keys {
+                &[]
+            } else {
+                mapping.get_target_keys()
+            };
+            if target_keys.is_empty() && layer_switch.is_none() && !replaces_keys {
                 continue; // Skip mappings without target keys
             }
 
-            let interval = mapping.interval.unwrap_or(config.interval).max(5);
+            let interval = mapping
+                .interval
+                .unwrap_or(config.interval)
+                .max(MIN_INTERVAL_MS);
             let event_duration = mapping
                 .event_duration
                 .unwrap_or(config.event_duration)
-                .max(2);
+                .max(MIN_EVENT_DURATION_MS);
             let move_speed = mapping.move_speed.max(1);
 
             // Parse target keys into output actions
             let mut actions: SmallVec<[OutputAction; 4]> = SmallVec::new();
             for target_key in target_keys {
-                if let Some(action) = parsing::input_name_to_output(target_key) {
+                let (name, _) = split_step_timing(target_key);
+                if let Some(action) = parsing::input_name_to_output(name) {
                     // Update MouseMove and MouseScroll actions with configured speed
                     let action = match action {
                         OutputAction::MouseMove(direction, _) => {
@@ -1197,7 +1760,7 @@ impl AppState {
                 }
             }
 
-            if actions.is_empty() {
+            if actions.is_empty() && layer_switch.is_none() && !replaces_keys {
                 continue; // Skip if no valid actions
             }
 
@@ -1247,7 +1810,13 @@ impl AppState {
             let rule_props_effective =
                 has_rule_props && (hold_mask != 0 || !append_actions.is_empty());
 
-            let target_action = if rule_props_effective {
+            let target_action = if let Some(switch) = layer_switch {
+                OutputAction::SwitchLayer(switch)
+            } else if let Some(script) = script {
+                OutputAction::Script(script)
+            } else if let Some(text) = text {
+                OutputAction::TypeText(Arc::new(TextPlan::new(text.text, text.char_delay_ms)))
+            } else if rule_props_effective {
                 OutputAction::MappingHold {
                     actions: Arc::new(actions),
                     interval_ms: interval,
@@ -1255,6 +1824,20 @@ impl AppState {
                     append: Arc::new(append_actions),
                     sequential: mapping.target_mode == 2,
                 }
+            } else if let Some(timings) = mapping.step_timings() {
+                // Steps without their own timing keep the mapping's.
+                let steps: Vec<SequenceStep> = actions
+                    .into_iter()
+                    .zip(timings)
+                    .map(|(action, timing)| SequenceStep {
+                        action,
+                        delay_ms: timing.delay_ms.map_or(interval, |d| d.max(MIN_INTERVAL_MS)),
+                        duration_ms: timing
+                            .duration_ms
+                            .map_or(event_duration, |d| d.max(MIN_EVENT_DURATION_MS)),
+                    })
+                    .collect();
+                OutputAction::TimedSequence(steps.into())
             } else if actions.len() == 1 {
                 actions.into_iter().next().unwrap()
             } else if mapping.target_mode == 2 {
@@ -1264,52 +1847,212 @@ impl AppState {
                 OutputAction::MultipleActions(Arc::new(actions))
             };
 
-            // Create input mapping
-            // For sequence triggers, this maps the LAST key to the action,
-            // allowing users to hold the last key for continuous repeat
-            input_mappings.insert(
-                trigger_device.clone(),
+            let tap_hold = match mapping.tap_hold() {
+                Some(hold) if layer_switch.is_none() && gesture.is_none() && !wheel_trigger => {
+                    let mut hold_actions: SmallVec<[OutputAction; 4]> = SmallVec::new();
+                    for name in hold.hold_keys {
+                        let action = parsing::input_name_to_output(name).ok_or_else(|| {
+                            anyhow::anyhow!("Invalid hold target input: {}", name)
+                        })?;
+                        hold_actions.push(action);
+                    }
+                    let hold_action = if hold_actions.len() == 1 {
+                        hold_actions.into_iter().next().unwrap()
+                    } else {
+                        OutputAction::MultipleActions(Arc::new(hold_actions))
+                    };
+                    Some(Arc::new(TapHold {
+                        hold_action,
+                        tapping_term: hold.tapping_term_ms,
+                        permissive: hold.permissive_hold,
+                    }))
+                }
+                _ => None,
+            };
+            let burst = mapping
+                .burst()
+                .filter(|_| layer_switch.is_none() && tap_hold.is_none())
+                .map(|burst| Burst {
+                    count: burst.count,
+                    cancel_on_release: burst.cancel_on_release && !wheel_trigger,
+                })
+                // Each wheel notch sends one full output cycle; its
+                // release comes with the press and must not cut it short.
+                .or_else(|| {
+                    (wheel_trigger && layer_switch.is_none()).then_some(Burst {
+                        count: 1,
+                        cancel_on_release: false,
+                    })
+                })
+                // A text types once per press unless a burst repeats it.
+                // A script without turbo runs once per press too, cut
+                // short by the release unless it is the tap of a
+                // tap-hold, which is released right away.
+                .or_else(|| {
+                    if is_script {
+                        let once = tap_hold.is_some()
+                            || (!mapping.turbo_enabled && mapping.toggle().is_none());
+                        once.then_some(Burst {
+                            count: 1,
+                            cancel_on_release: tap_hold.is_none(),
+                        })
+                    } else {
+                        text.filter(|_| tap_hold.is_none()).map(|_| Burst {
+                            count: 1,
+                            cancel_on_release: false,
+                        })
+                    }
+                });
+            // A tap fires exactly once, so it runs as a single turbo shot
+            // between the dispatched press and release. A burst counts
+            // turbo shots.
+            // The turbo workers step scripts between their waits.
+            let turbo_enabled =
+                mapping.turbo_enabled || tap_hold.is_some() || burst.is_some() || is_script;
+
+            // Layer switches keep their own toggle mode, and a burst stops
+            // by itself.
+            let toggle = mapping
+                .toggle()
+                .filter(|_| layer_switch.is_none() && burst.is_none())
+                .map(|toggle| Toggle {
+                    auto_stop: toggle.auto_stop_ms,
+                });
+            let jitter = mapping.jitter().map(|jitter| Jitter {
+                interval: jitter.interval_ms,
+                duration: jitter.duration_ms,
+                distribution: jitter.distribution,
+            });
+
+            let process_scope = mapping.process_scope().map(Arc::new);
+            let lock_keys = mapping.lock_key_condition();
+
+            candidates.entry(trigger_device.clone()).or_default().push((
+                mapping.priority,
+                idx,
                 InputMappingInfo {
                     target_action: target_action.clone(),
                     interval,
                     event_duration,
-                    turbo_enabled: mapping.turbo_enabled,
+                    turbo_enabled,
                     is_sequence: mapping.is_sequence_trigger(),
+                    process_scope: process_scope.clone(),
+                    lock_keys,
+                    layer,
+                    tap_hold: tap_hold.clone(),
+                    toggle,
+                    burst,
+                    jitter,
+                    is_conditional: false,
+                    fallback: None,
+                    pass_through: false,
                 },
-            );
+            ));
+
+            // Register every input of a sequence so rawinput/xinput
+            // record it for the matcher.
+            if let Some(sequence) = &sequence {
+                for device in sequence.inputs() {
+                    sequence_keys
+                        .entry(device.clone())
+                        .or_insert_with(|| InputMappingInfo {
+                            target_action: target_action.clone(),
+                            interval,
+                            event_duration,
+                            turbo_enabled: mapping.turbo_enabled,
+                            is_sequence: true, // Mark as sequence-only
+                            process_scope: process_scope.clone(),
+                            lock_keys,
+                            layer,
+                            tap_hold: None,
+                            toggle: None,
+                            burst: None,
+                            jitter: None,
+                            is_conditional: false,
+                            fallback: None,
+                            pass_through: false,
+                        });
+                }
+            }
+        }
 
-            // For sequence triggers, register intermediate keys to enable detection
-            // These mappings allow input subsystems to recognize and record sequence inputs
-            if mapping.is_sequence_trigger()
-                && let Some(seq_str) = &mapping.trigger_sequence
-            {
-                let parts: Vec<&str> = seq_str.split(',').collect();
-                for (idx, part) in parts.iter().enumerate() {
-                    if idx == parts.len() - 1 {
-                        continue; // Skip last key, already registered above
-                    }
+        // The plain presses of a gesture input must reach the gesture
+        // state whatever else applies, so the input falls back to a
+        // stand-in after all its own mappings. Presses that go through
+        // pass to the OS as they are; only deferred ones are replayed,
+        // which inputs without an output of their own cannot be.
+        for (device, name) in gesture_inputs {
+            let target_action = parsing::input_name_to_output(name)
+                .unwrap_or_else(|| OutputAction::MultipleActions(Arc::default()));
+            candidates.entry(device).or_default().push((
+                i32::MIN,
+                0,
+                InputMappingInfo {
+                    target_action,
+                    interval: config.interval.max(MIN_INTERVAL_MS),
+                    event_duration: config.event_duration.max(MIN_EVENT_DURATION_MS),
+                    turbo_enabled: false,
+                    is_sequence: false,
+                    process_scope: None,
+                    lock_keys: None,
+                    layer: 0,
+                    tap_hold: None,
+                    toggle: None,
+                    burst: None,
+                    jitter: None,
+                    is_conditional: false,
+                    fallback: None,
+                    pass_through: true,
+                },
+            ));
+        }
 
-                    let part_trimmed = part.trim();
-                    if let Some(device) = parsing::input_name_to_device(part_trimmed) {
-                        // Register intermediate key with same mapping info
-                        // This ensures rawinput/xinput will process it
-                        input_mappings
-                            .entry(device)
-                            .or_insert_with(|| InputMappingInfo {
-                                target_action: target_action.clone(),
-                                interval,
-                                event_duration,
-                                turbo_enabled: mapping.turbo_enabled,
-                                is_sequence: true, // Mark as sequence-only
-                            });
-                    }
-                }
+        // Chain each trigger's mappings in the order they are tried:
+        // highest priority first, then mappings on a layer, then later
+        // mappings first among equals, so a plain duplicate still
+        // replaces the mapping before it.
+        let mut input_mappings = HashMap::new();
+        for (device, mut list) in candidates {
+            list.sort_by_key(|(priority, idx, info)| {
+                (Reverse(*priority), Reverse(info.layer != 0), Reverse(*idx))
+            });
+            let is_conditional = list.iter().any(|(_, _, info)| info.has_conditions());
+            let mut head: Option<InputMappingInfo> = None;
+            for (_, _, mut info) in list.into_iter().rev() {
+                info.is_conditional = is_conditional;
+                info.fallback = head.take().map(Arc::new);
+                head = Some(info);
+            }
+            if let Some(head) = head {
+                input_mappings.insert(device, head);
             }
         }
+        // Sequence inputs only fill triggers no mapping uses.
+        for (device, mut info) in sequence_keys {
+            info.is_conditional = info.has_conditions();
+            input_mappings.entry(device).or_insert(info);
+        }
 
         Ok(input_mappings)
     }
 
+    /// Adds `device` to the wheel combo index if it is a wheel combo.
+    fn index_wheel_combo(
+        cache: &scc::HashMap<MouseScrollDirection, Vec<InputDevice>>,
+        device: &InputDevice,
+    ) {
+        if let InputDevice::WheelCombo(_, direction) = device {
+            let mut combos = cache
+                .get_sync(direction)
+                .map(|v| v.clone())
+                .unwrap_or_default();
+            if !combos.contains(device) {
+                combos.push(device.clone());
+                let _ = cache.upsert_sync(*direction, combos);
+            }
+        }
+    }
+
     /// Resolves a configured key name to a keyboard virtual-key code for the
     /// sequence-finalize hotkey. Falls back to VK_RETURN when the name does
     /// not parse to a keyboard key. The capture flow always needs a valid
@@ -1348,7 +2091,13 @@ impl AppState {
             InputDevice::GenericDevice { button_id, .. } => {
                 cache.generic_button_id.store(*button_id, Ordering::Relaxed);
             }
-            InputDevice::KeyCombo(_) | InputDevice::Mouse(_) | InputDevice::MouseMove(_) => {}
+            InputDevice::KeyCombo(_)
+            | InputDevice::Mouse(_)
+            | InputDevice::MouseMove(_)
+            | InputDevice::Wheel(_)
+            | InputDevice::WheelCombo(..)
+            | InputDevice::Sequence(_)
+            | InputDevice::Gesture(..) => {}
         }
 
         let shared_device = Shared::new(device);
@@ -1358,6 +2107,19 @@ impl AppState {
         Ok(())
     }
 
+    /// Like `update_switch_key_cache`, but an empty name disables the
+    /// hotkey instead of failing.
+    fn update_profile_key_cache(cache: &SwitchKeyCache, key_name: &str) -> anyhow::Result<()> {
+        if key_name.is_empty() {
+            cache.clear();
+            return Ok(());
+        }
+        if parsing::input_name_to_device(key_name).is_none() {
+            anyhow::bail!("Invalid profile key: {}", key_name);
+        }
+        Self::update_switch_key_cache(cache, key_name)
+    }
+
     /// Convert button IDs to bitmask
     #[inline(always)]
     fn inputs_to_bitset(inputs: &[u32]) -> u32 {
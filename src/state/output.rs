@@ -0,0 +1,94 @@
+//! Output backends for synthesized input.
+//!
+//! The simulation layer never talks to the OS directly. It describes what it
+//! wants injected as a batch of [`OutputEvent`]s and hands the batch to the
+//! [`OutputSink`] owned by `AppState`. The Win32 backend in `win32` turns
+//! each batch into a single `SendInput` call, preserving the atomicity of
+//! combo presses; the recording backend keeps the stream in memory so tests
+//! can assert on the exact sequence the ref-counted hold logic produced.
+
+use std::sync::Mutex;
+
+use super::types::MouseButton;
+
+/// A single primitive input event produced by the simulation layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OutputEvent {
+    /// Keyboard key down, identified by hardware scancode.
+    KeyDown(u16),
+    /// Keyboard key up, identified by hardware scancode.
+    KeyUp(u16),
+    MouseDown(MouseButton),
+    MouseUp(MouseButton),
+    /// Relative pointer movement in pixels.
+    MouseMove {
+        dx: i32,
+        dy: i32,
+    },
+    /// Vertical wheel rotation. Positive scrolls away from the user, in
+    /// the same units as `WHEEL_DELTA` (120 per notch).
+    Wheel(i32),
+    /// Horizontal wheel rotation. Positive scrolls right, in the same
+    /// units as `Wheel`.
+    HWheel(i32),
+    /// UTF-16 code unit pressed as Unicode input, for characters the
+    /// keyboard has no key for.
+    UnicodeDown(u16),
+    /// UTF-16 code unit released as Unicode input.
+    UnicodeUp(u16),
+}
+
+/// Destination for synthesized input.
+///
+/// `send` receives events in the order they must reach the OS. A batch is
+/// always delivered as one unit so a backend may inject it atomically;
+/// callers rely on that to keep combo modifiers and their main key
+/// together. Empty batches are never passed in.
+pub trait OutputSink: Send + Sync {
+    fn send(&self, events: &[OutputEvent]);
+}
+
+/// In-memory backend that records every event instead of injecting it.
+/// Batches are flattened in arrival order; batch boundaries are kept
+/// separately for tests that care about what was sent together.
+#[allow(dead_code)]
+#[derive(Debug, Default)]
+pub struct RecordingOutputSink {
+    events: Mutex<Vec<OutputEvent>>,
+    batch_sizes: Mutex<Vec<usize>>,
+}
+
+#[allow(dead_code)]
+impl RecordingOutputSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Snapshot of every event recorded so far.
+    pub fn events(&self) -> Vec<OutputEvent> {
+        self.events.lock().unwrap().clone()
+    }
+
+    /// Size of each `send` call recorded so far, in order.
+    pub fn batch_sizes(&self) -> Vec<usize> {
+        self.batch_sizes.lock().unwrap().clone()
+    }
+
+    /// Returns the recorded events and clears the log.
+    pub fn take(&self) -> Vec<OutputEvent> {
+        self.batch_sizes.lock().unwrap().clear();
+        std::mem::take(&mut *self.events.lock().unwrap())
+    }
+
+    pub fn clear(&self) {
+        self.events.lock().unwrap().clear();
+        self.batch_sizes.lock().unwrap().clear();
+    }
+}
+
+impl OutputSink for RecordingOutputSink {
+    fn send(&self, events: &[OutputEvent]) {
+        self.events.lock().unwrap().extend_from_slice(events);
+        self.batch_sizes.lock().unwrap().push(events.len());
+    }
+}
@@ -216,6 +216,11 @@ pub fn input_name_to_device(name: &str) -> Option<InputDevice> {
         return Some(InputDevice::MouseMove(direction));
     }
 
+    // Try wheel notch or tilt
+    if let Some(direction) = mouse_scroll_name_to_direction(&name_upper) {
+        return Some(InputDevice::Wheel(direction));
+    }
+
     // Check if it's a key combination (contains '+')
     if name.contains('+') {
         let parts: Vec<&str> = name.split('+').map(|s| s.trim()).collect();
@@ -238,6 +243,18 @@ pub fn input_name_to_device(name: &str) -> Option<InputDevice> {
             }
         }
 
+        // Keys held while the wheel turns, e.g. "LCTRL+WHEEL_UP"
+        if let Some(&InputDevice::Wheel(direction)) = devices.last() {
+            let mut keys = Vec::with_capacity(devices.len() - 1);
+            for device in &devices[..devices.len() - 1] {
+                match device {
+                    InputDevice::Keyboard(vk) => keys.push(*vk),
+                    _ => return None,
+                }
+            }
+            return Some(InputDevice::WheelCombo(keys, direction));
+        }
+
         // If all parts are keyboard keys, use KeyCombo for efficiency
         let all_keyboard = devices
             .iter()
@@ -264,6 +281,93 @@ pub fn input_name_to_device(name: &str) -> Option<InputDevice> {
     None
 }
 
+/// Converts an XInput input ID to its button name.
+#[inline(always)]
+pub fn xinput_id_to_name(input_id: u32) -> &'static str {
+    match input_id {
+        // D-Pad
+        0x01 => "DPad_Up",
+        0x02 => "DPad_Down",
+        0x03 => "DPad_Left",
+        0x04 => "DPad_Right",
+        // Buttons
+        0x05 => "Start",
+        0x06 => "Back",
+        0x07 => "LS_Click",
+        0x08 => "RS_Click",
+        0x09 => "LB",
+        0x0A => "RB",
+        0x0B => "A",
+        0x0C => "B",
+        0x0D => "X",
+        0x0E => "Y",
+        // Left Stick
+        0x10 => "LS_Right",
+        0x11 => "LS_Left",
+        0x12 => "LS_Up",
+        0x13 => "LS_Down",
+        // Right Stick
+        0x14 => "RS_Right",
+        0x15 => "RS_Left",
+        0x16 => "RS_Up",
+        0x17 => "RS_Down",
+        // Triggers
+        0x18 => "LT",
+        0x19 => "RT",
+        _ => "Unknown",
+    }
+}
+
+/// Converts an XInput button name to its input ID.
+#[inline(always)]
+pub fn xinput_name_to_id(name: &str) -> Option<u32> {
+    match name {
+        // D-Pad
+        "DPad_Up" | "DPAD_UP" => Some(0x01),
+        "DPad_Down" | "DPAD_DOWN" => Some(0x02),
+        "DPad_Left" | "DPAD_LEFT" => Some(0x03),
+        "DPad_Right" | "DPAD_RIGHT" => Some(0x04),
+        // Buttons
+        "Start" | "START" => Some(0x05),
+        "Back" | "BACK" => Some(0x06),
+        "LS_Click" | "LS_CLICK" => Some(0x07),
+        "RS_Click" | "RS_CLICK" => Some(0x08),
+        "LB" => Some(0x09),
+        "RB" => Some(0x0A),
+        "A" => Some(0x0B),
+        "B" => Some(0x0C),
+        "X" => Some(0x0D),
+        "Y" => Some(0x0E),
+        // Left Stick
+        "LS_Right" | "LS_RIGHT" => Some(0x10),
+        "LS_Left" | "LS_LEFT" => Some(0x11),
+        "LS_Up" | "LS_UP" => Some(0x12),
+        "LS_Down" | "LS_DOWN" => Some(0x13),
+        // Right Stick
+        "RS_Right" | "RS_RIGHT" => Some(0x14),
+        "RS_Left" | "RS_LEFT" => Some(0x15),
+        "RS_Up" | "RS_UP" => Some(0x16),
+        "RS_Down" | "RS_DOWN" => Some(0x17),
+        // Triggers
+        "LT" => Some(0x18),
+        "RT" => Some(0x19),
+        // Diagonal combinations
+        "LS_RightUp" | "LS_RIGHTUP" => None, // Special: needs to return [0x10, 0x12]
+        "LS_RightDown" | "LS_RIGHTDOWN" => None,
+        "LS_LeftUp" | "LS_LEFTUP" => None,
+        "LS_LeftDown" | "LS_LEFTDOWN" => None,
+        "RS_RightUp" | "RS_RIGHTUP" => None,
+        "RS_RightDown" | "RS_RIGHTDOWN" => None,
+        "RS_LeftUp" | "RS_LEFTUP" => None,
+        "RS_LeftDown" | "RS_LEFTDOWN" => None,
+        "DPad_UpRight" | "DPAD_UPRIGHT" => None,
+        "DPad_UpLeft" | "DPAD_UPLEFT" => None,
+        "DPad_DownRight" | "DPAD_DOWNRIGHT" => None,
+        "DPad_DownLeft" | "DPAD_DOWNLEFT" => None,
+        _ => None,
+    }
+}
+
 /// Supported formats:
 /// - "GAMEPAD_045E_0B05_ABC123_B2.0" (with serial number)
 /// - "GAMEPAD_045E_0B05_DEV12345678_B2.0" (without serial number, uses device handle)
@@ -301,7 +405,7 @@ fn parse_xinput_combo(input: &str) -> Option<InputDevice> {
     // Split by '+' to support combinations like "DPad_Right+X+Y"
     for button_name in button_str.split('+') {
         let button_name = button_name.trim();
-        if let Some(input_id) = crate::xinput::XInputHandler::name_to_input_id(button_name) {
+        if let Some(input_id) = xinput_name_to_id(button_name) {
             button_ids.push(input_id);
         } else {
             // Check for diagonal stick directions (these need to be expanded to two IDs)
@@ -501,6 +605,12 @@ pub fn mouse_scroll_name_to_direction(name: &str) -> Option<MouseScrollDirection
         "SCROLL_DOWN" | "SCROLLDOWN" | "WHEEL_DOWN" | "WHEELDOWN" => {
             Some(MouseScrollDirection::Down)
         }
+        "SCROLL_LEFT" | "SCROLLLEFT" | "WHEEL_LEFT" | "WHEELLEFT" => {
+            Some(MouseScrollDirection::Left)
+        }
+        "SCROLL_RIGHT" | "SCROLLRIGHT" | "WHEEL_RIGHT" | "WHEELRIGHT" => {
+            Some(MouseScrollDirection::Right)
+        }
         _ => None,
     }
 }
@@ -0,0 +1,391 @@
+//! Macro scripts for `macro` targets.
+//!
+//! A script is parsed once, when the mappings are built, into a flat
+//! [`Script`] program. The turbo worker runs it a stretch at a time with
+//! [`AppState::step_script`]: every call plays the commands up to the next
+//! `wait` and returns how long to wait, so the worker keeps handling the
+//! trigger's events in between and can cancel the run on release. Parsing
+//! touches no OS API, and the interpreter only talks to the output sink.
+//!
+//! ```text
+//! down LSHIFT      # press and keep held
+//! tap A            # press, hold event_duration, release
+//! wait 35          # milliseconds
+//! up LSHIFT
+//! repeat 3 { tap B; wait 20 }
+//! move 10 -4       # relative pointer move in pixels
+//! scroll 120       # wheel delta, 120 per notch, positive away from the user
+//! ```
+
+use std::fmt;
+
+use smallvec::SmallVec;
+
+use super::AppState;
+use super::output::OutputEvent;
+use super::parsing::input_name_to_output;
+use super::types::{Jitter, OutputAction};
+
+/// One instruction of a compiled script.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptOp {
+    /// Press `keys[i]` and keep it held.
+    Down(usize),
+    /// Release `keys[i]` if the script holds it.
+    Up(usize),
+    /// Press, hold for the event duration and release `keys[i]`.
+    Tap(usize),
+    /// Pause for this many milliseconds.
+    Wait(u64),
+    /// Relative pointer move in pixels.
+    Move { dx: i32, dy: i32 },
+    /// Wheel rotation in `WHEEL_DELTA` units.
+    Scroll(i32),
+    /// Start of a `repeat` block, run `count` times. `end` is the index of
+    /// its [`ScriptOp::EndRepeat`].
+    Repeat { count: u32, end: usize },
+    /// End of a `repeat` block started at `start`.
+    EndRepeat { start: usize },
+}
+
+/// A parsed macro script.
+#[derive(Debug)]
+pub struct Script {
+    pub ops: Box<[ScriptOp]>,
+    /// Keys the script presses, one entry per distinct name.
+    pub keys: Box<[OutputAction]>,
+}
+
+/// Where a script failed to parse, 1-based.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+/// Progress of one run of a script.
+#[derive(Debug, Default)]
+pub struct ScriptRun {
+    /// Next instruction.
+    pc: usize,
+    /// Passes left in each open `repeat` block, innermost last.
+    loops: SmallVec<[u32; 4]>,
+    /// Keys pressed with `down` and not yet released, as `keys` indices.
+    held: SmallVec<[usize; 4]>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TokenKind<'a> {
+    Word(&'a str),
+    Open,
+    Close,
+    /// End of a command: a newline or `;`.
+    End,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Token<'a> {
+    kind: TokenKind<'a>,
+    line: usize,
+    column: usize,
+}
+
+fn tokenize(source: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    for (line_idx, line) in source.lines().enumerate() {
+        let line_no = line_idx + 1;
+        let mut chars = line.char_indices().enumerate().peekable();
+        while let Some((col, (start, ch))) = chars.next() {
+            let kind = match ch {
+                '#' => break,
+                '{' => TokenKind::Open,
+                '}' => TokenKind::Close,
+                ';' => TokenKind::End,
+                ch if ch.is_whitespace() => continue,
+                _ => {
+                    let mut end = start + ch.len_utf8();
+                    while let Some(&(_, (i, c))) = chars.peek() {
+                        if c.is_whitespace() || matches!(c, '{' | '}' | ';' | '#') {
+                            break;
+                        }
+                        end = i + c.len_utf8();
+                        chars.next();
+                    }
+                    TokenKind::Word(&line[start..end])
+                }
+            };
+            tokens.push(Token {
+                kind,
+                line: line_no,
+                column: col + 1,
+            });
+        }
+        tokens.push(Token {
+            kind: TokenKind::End,
+            line: line_no,
+            column: line.chars().count() + 1,
+        });
+    }
+    tokens
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+    ops: Vec<ScriptOp>,
+    key_names: Vec<String>,
+    keys: Vec<OutputAction>,
+}
+
+impl<'a> Parser<'a> {
+    fn error<T>(token: Token<'_>, message: impl Into<String>) -> Result<T, ScriptError> {
+        Err(ScriptError {
+            line: token.line,
+            column: token.column,
+            message: message.into(),
+        })
+    }
+
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    /// Parses commands until the end of the input, or until the `}` that
+    /// closes the block opened at `open`.
+    fn block(&mut self, open: Option<Token<'a>>) -> Result<(), ScriptError> {
+        loop {
+            let Some(token) = self.peek() else {
+                return match open {
+                    Some(open) => Self::error(open, "\"{\" is never closed"),
+                    None => Ok(()),
+                };
+            };
+            self.pos += 1;
+            match token.kind {
+                TokenKind::End => {}
+                TokenKind::Close => {
+                    return match open {
+                        Some(_) => Ok(()),
+                        None => Self::error(token, "\"}\" without a matching \"{\""),
+                    };
+                }
+                TokenKind::Open => return Self::error(token, "unexpected \"{\""),
+                TokenKind::Word(command) => self.command(token, command)?,
+            }
+        }
+    }
+
+    /// The arguments of a command, up to its end.
+    fn args(&mut self) -> SmallVec<[Token<'a>; 2]> {
+        let mut args = SmallVec::new();
+        while let Some(token) = self.peek() {
+            if !matches!(token.kind, TokenKind::Word(_)) {
+                break;
+            }
+            args.push(token);
+            self.pos += 1;
+        }
+        args
+    }
+
+    fn command(&mut self, at: Token<'a>, command: &str) -> Result<(), ScriptError> {
+        let args = self.args();
+        let word = |token: &Token<'a>| match token.kind {
+            TokenKind::Word(word) => word,
+            _ => "",
+        };
+        let expect = |count: usize, usage: &str| {
+            if args.len() == count {
+                Ok(())
+            } else {
+                let token = args.get(count).copied().unwrap_or(at);
+                Self::error(token, format!("expected \"{usage}\""))
+            }
+        };
+        match command.to_ascii_lowercase().as_str() {
+            name @ ("down" | "up" | "tap") => {
+                expect(1, &format!("{name} KEY"))?;
+                let key = self.key(args[0], word(&args[0]), name != "tap")?;
+                self.ops.push(match name {
+                    "down" => ScriptOp::Down(key),
+                    "up" => ScriptOp::Up(key),
+                    _ => ScriptOp::Tap(key),
+                });
+            }
+            "wait" => {
+                expect(1, "wait MILLISECONDS")?;
+                let ms = Self::number::<u64>(args[0], word(&args[0]))?;
+                self.ops.push(ScriptOp::Wait(ms));
+            }
+            "move" => {
+                expect(2, "move DX DY")?;
+                let dx = Self::number::<i32>(args[0], word(&args[0]))?;
+                let dy = Self::number::<i32>(args[1], word(&args[1]))?;
+                self.ops.push(ScriptOp::Move { dx, dy });
+            }
+            "scroll" => {
+                expect(1, "scroll DELTA")?;
+                let delta = Self::number::<i32>(args[0], word(&args[0]))?;
+                self.ops.push(ScriptOp::Scroll(delta));
+            }
+            "repeat" => {
+                expect(1, "repeat COUNT {")?;
+                let count = Self::number::<u32>(args[0], word(&args[0]))?;
+                if count == 0 {
+                    return Self::error(args[0], "repeat count must be at least 1");
+                }
+                let open = match self.peek() {
+                    Some(token) if token.kind == TokenKind::Open => token,
+                    _ => return Self::error(at, "expected \"{\" after repeat COUNT"),
+                };
+                self.pos += 1;
+                let start = self.ops.len();
+                self.ops.push(ScriptOp::Repeat { count, end: 0 });
+                self.block(Some(open))?;
+                let end = self.ops.len();
+                self.ops.push(ScriptOp::EndRepeat { start });
+                self.ops[start] = ScriptOp::Repeat { count, end };
+            }
+            _ => return Self::error(at, format!("unknown command \"{command}\"")),
+        }
+        Ok(())
+    }
+
+    fn number<T: std::str::FromStr>(token: Token<'_>, text: &str) -> Result<T, ScriptError> {
+        text.parse()
+            .or_else(|_| Self::error(token, format!("\"{text}\" is not a valid number")))
+    }
+
+    /// Index of `name` in the key table, added on first use.
+    fn key(&mut self, token: Token<'_>, name: &str, held: bool) -> Result<usize, ScriptError> {
+        let upper = name.to_uppercase();
+        let idx = match self.key_names.iter().position(|n| *n == upper) {
+            Some(idx) => idx,
+            None => {
+                let Some(action) = input_name_to_output(name) else {
+                    return Self::error(token, format!("unknown key name \"{name}\""));
+                };
+                self.key_names.push(upper);
+                self.keys.push(action);
+                self.keys.len() - 1
+            }
+        };
+        // Checked on every use: a name first tapped may not be held later.
+        if held
+            && !matches!(
+                self.keys[idx],
+                OutputAction::KeyboardKey(_)
+                    | OutputAction::KeyCombo(_)
+                    | OutputAction::MouseButton(_)
+            )
+        {
+            return Self::error(
+                token,
+                format!("\"{name}\" cannot be held; use move or scroll"),
+            );
+        }
+        Ok(idx)
+    }
+}
+
+impl Script {
+    /// Parses `source`, stopping at the first error.
+    pub fn parse(source: &str) -> Result<Self, ScriptError> {
+        let mut parser = Parser {
+            tokens: tokenize(source),
+            pos: 0,
+            ops: Vec::new(),
+            key_names: Vec::new(),
+            keys: Vec::new(),
+        };
+        parser.block(None)?;
+        Ok(Self {
+            ops: parser.ops.into_boxed_slice(),
+            keys: parser.keys.into_boxed_slice(),
+        })
+    }
+}
+
+impl AppState {
+    /// Plays `script` from where `run` left off up to the next `wait`, and
+    /// returns the milliseconds to wait before the next call. Returns
+    /// `None` once the script is over, after releasing whatever it still
+    /// holds; `run` then starts from the top again. `tap` holds keys for
+    /// `duration`, and `jitter` moves the taps and waits.
+    pub fn step_script(
+        &self,
+        script: &Script,
+        run: &mut ScriptRun,
+        duration: u64,
+        jitter: Option<Jitter>,
+    ) -> Option<u64> {
+        while let Some(&op) = script.ops.get(run.pc) {
+            run.pc += 1;
+            match op {
+                ScriptOp::Down(key) => {
+                    self.simulate_initial_press(&script.keys[key]);
+                    run.held.push(key);
+                }
+                ScriptOp::Up(key) => {
+                    if let Some(pos) = run.held.iter().rposition(|&k| k == key) {
+                        run.held.remove(pos);
+                        self.simulate_release(&script.keys[key]);
+                    }
+                }
+                ScriptOp::Tap(key) => {
+                    self.simulate_jittered_action(script.keys[key].clone(), duration, jitter);
+                }
+                ScriptOp::Wait(ms) => {
+                    if ms > 0 {
+                        return Some(self.jitter_interval(ms, jitter));
+                    }
+                }
+                ScriptOp::Move { dx, dy } => self.emit(&[OutputEvent::MouseMove { dx, dy }]),
+                ScriptOp::Scroll(delta) => self.emit(&[OutputEvent::Wheel(delta)]),
+                ScriptOp::Repeat { count, .. } => run.loops.push(count),
+                ScriptOp::EndRepeat { start } => {
+                    if let Some(left) = run.loops.last_mut() {
+                        *left -= 1;
+                        if *left > 0 {
+                            run.pc = start + 1;
+                        } else {
+                            run.loops.pop();
+                        }
+                    }
+                }
+            }
+        }
+        self.cancel_script(script, run);
+        None
+    }
+
+    /// Stops `run`, releasing the keys it holds, so the next step starts
+    /// the script over.
+    pub fn cancel_script(&self, script: &Script, run: &mut ScriptRun) {
+        for &key in run.held.iter().rev() {
+            self.simulate_release(&script.keys[key]);
+        }
+        *run = ScriptRun::default();
+    }
+
+    /// Plays the whole of `script` at once, sleeping through its waits.
+    pub fn simulate_script(&self, script: &Script, duration: u64, jitter: Option<Jitter>) {
+        let mut run = ScriptRun::default();
+        while let Some(wait) = self.step_script(script, &mut run, duration, jitter) {
+            self.clock().sleep(std::time::Duration::from_millis(wait));
+        }
+    }
+}
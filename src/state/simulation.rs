@@ -1,9 +1,10 @@
 use std::sync::atomic::Ordering;
+use std::time::Duration;
 
 use smallvec::SmallVec;
-use windows::Win32::UI::Input::KeyboardAndMouse::*;
 
 use super::AppState;
+use super::output::OutputEvent;
 use super::types::*;
 
 impl AppState {
@@ -11,19 +12,48 @@ impl AppState {
     /// based on action variant.
     #[inline]
     pub fn simulate_action(&self, action: OutputAction, duration: u64) {
+        self.simulate_jittered_action(action, duration, None);
+    }
+
+    /// `simulate_action` with every press hold and sequence step gap
+    /// moved by the mapping's `jitter`.
+    #[inline]
+    pub fn simulate_jittered_action(
+        &self,
+        action: OutputAction,
+        duration: u64,
+        jitter: Option<Jitter>,
+    ) {
         match action {
             OutputAction::MouseMove(direction, speed) => {
-                Self::send_mouse_move(direction, speed);
+                self.send_mouse_move(direction, speed);
             }
             OutputAction::MouseScroll(direction, speed) => {
-                Self::send_mouse_scroll(direction, speed);
+                self.send_mouse_scroll(direction, speed);
+            }
+            OutputAction::TypeText(plan) => {
+                self.simulate_text(&plan);
+            }
+            OutputAction::Script(script) => {
+                self.simulate_script(&script, duration, jitter);
             }
             OutputAction::SequentialActions(actions, interval_ms) => {
                 let last = actions.len().saturating_sub(1);
                 for (idx, a) in actions.iter().enumerate() {
-                    self.simulate_action(a.clone(), duration);
+                    self.simulate_jittered_action(a.clone(), duration, jitter);
                     if idx < last {
-                        std::thread::sleep(std::time::Duration::from_millis(interval_ms));
+                        let gap = self.jitter_interval(interval_ms, jitter);
+                        self.clock().sleep(Duration::from_millis(gap));
+                    }
+                }
+            }
+            OutputAction::TimedSequence(steps) => {
+                let last = steps.len().saturating_sub(1);
+                for (idx, step) in steps.iter().enumerate() {
+                    self.simulate_jittered_action(step.action.clone(), step.duration_ms, jitter);
+                    if idx < last {
+                        let gap = self.jitter_interval(step.delay_ms, jitter);
+                        self.clock().sleep(Duration::from_millis(gap));
                     }
                 }
             }
@@ -43,10 +73,11 @@ impl AppState {
                         if idx < 16 && (hold_mask & (1u16 << idx)) != 0 {
                             self.simulate_initial_press(a);
                         } else {
-                            self.simulate_action(a.clone(), duration);
+                            self.simulate_jittered_action(a.clone(), duration, jitter);
                         }
                         if idx < last {
-                            std::thread::sleep(std::time::Duration::from_millis(interval_ms));
+                            let gap = self.jitter_interval(interval_ms, jitter);
+                            self.clock().sleep(Duration::from_millis(gap));
                         }
                     }
                 } else {
@@ -58,7 +89,8 @@ impl AppState {
                         self.simulate_initial_press(a);
                     }
                     if duration > 0 {
-                        std::thread::sleep(std::time::Duration::from_millis(duration));
+                        let hold = self.jitter_duration(duration, jitter);
+                        self.clock().sleep(Duration::from_millis(hold));
                     }
                     for (idx, a) in actions.iter().enumerate().rev() {
                         let held = idx < 16 && (hold_mask & (1u16 << idx)) != 0;
@@ -84,7 +116,8 @@ impl AppState {
                 // ref-counted helpers so overlapping triggers can share keys
                 // without releasing each other's holds prematurely.
                 self.simulate_initial_press(&action);
-                std::thread::sleep(std::time::Duration::from_millis(duration));
+                let hold = self.jitter_duration(duration, jitter);
+                self.clock().sleep(Duration::from_millis(hold));
                 self.simulate_release(&action);
             }
         }
@@ -99,27 +132,23 @@ impl AppState {
         let mut btns: SmallVec<[MouseButton; 4]> = SmallVec::new();
         Self::collect_primitives(action, &mut scs, &mut btns);
 
-        let mut inputs: SmallVec<[INPUT; 12]> = SmallVec::new();
+        let mut events: SmallVec<[OutputEvent; 12]> = SmallVec::new();
         for &scancode in &scs {
             let idx = scancode as usize;
             if idx >= 256 {
                 continue;
             }
             if self.held_scancodes[idx].fetch_add(1, Ordering::AcqRel) == 0 {
-                inputs.push(Self::build_key_input(scancode, false));
+                events.push(OutputEvent::KeyDown(scancode));
             }
         }
         for &btn in &btns {
             if self.held_mouse_buttons[btn as usize].fetch_add(1, Ordering::AcqRel) == 0 {
-                inputs.push(Self::build_mouse_input(btn, false));
+                events.push(OutputEvent::MouseDown(btn));
             }
         }
 
-        if !inputs.is_empty() {
-            unsafe {
-                SendInput(&inputs, std::mem::size_of::<INPUT>() as i32);
-            }
-        }
+        self.emit(&events);
     }
 
     /// Sends `KEYDOWN` for every held primitive in `action` without touching
@@ -132,19 +161,15 @@ impl AppState {
         let mut btns: SmallVec<[MouseButton; 4]> = SmallVec::new();
         Self::collect_primitives(action, &mut scs, &mut btns);
 
-        let mut inputs: SmallVec<[INPUT; 12]> = SmallVec::new();
+        let mut events: SmallVec<[OutputEvent; 12]> = SmallVec::new();
         for &scancode in &scs {
-            inputs.push(Self::build_key_input(scancode, false));
+            events.push(OutputEvent::KeyDown(scancode));
         }
         for &btn in &btns {
-            inputs.push(Self::build_mouse_input(btn, false));
+            events.push(OutputEvent::MouseDown(btn));
         }
 
-        if !inputs.is_empty() {
-            unsafe {
-                SendInput(&inputs, std::mem::size_of::<INPUT>() as i32);
-            }
-        }
+        self.emit(&events);
     }
 
     /// Releases every primitive in `action`, decrementing per-key ref counts.
@@ -156,7 +181,7 @@ impl AppState {
         let mut btns: SmallVec<[MouseButton; 4]> = SmallVec::new();
         Self::collect_primitives(action, &mut scs, &mut btns);
 
-        let mut inputs: SmallVec<[INPUT; 12]> = SmallVec::new();
+        let mut events: SmallVec<[OutputEvent; 12]> = SmallVec::new();
         // Reverse order matches the historical KeyCombo release-in-reverse
         // behavior so modifiers outlive their main key.
         for &scancode in scs.iter().rev() {
@@ -169,20 +194,16 @@ impl AppState {
                 continue;
             }
             if Self::ref_decrement_saturating(&self.held_scancodes[idx]) {
-                inputs.push(Self::build_key_input(scancode, true));
+                events.push(OutputEvent::KeyUp(scancode));
             }
         }
         for &btn in btns.iter().rev() {
             if Self::ref_decrement_saturating(&self.held_mouse_buttons[btn as usize]) {
-                inputs.push(Self::build_mouse_input(btn, true));
+                events.push(OutputEvent::MouseUp(btn));
             }
         }
 
-        if !inputs.is_empty() {
-            unsafe {
-                SendInput(&inputs, std::mem::size_of::<INPUT>() as i32);
-            }
-        }
+        self.emit(&events);
     }
 
     /// Atomically decrements a non-negative ref count, returning `true` when
@@ -232,6 +253,11 @@ impl AppState {
                     Self::collect_primitives(a, scs, btns);
                 }
             }
+            OutputAction::TimedSequence(steps) => {
+                for step in steps.iter() {
+                    Self::collect_primitives(&step.action, scs, btns);
+                }
+            }
             OutputAction::MappingHold {
                 actions,
                 hold_mask,
@@ -251,8 +277,13 @@ impl AppState {
                     Self::collect_primitives(a, scs, btns);
                 }
             }
-            OutputAction::MouseMove(..) | OutputAction::MouseScroll(..) => {
-                // Edge-event primitives: no held state.
+            OutputAction::MouseMove(..)
+            | OutputAction::MouseScroll(..)
+            | OutputAction::SwitchLayer(_)
+            | OutputAction::TypeText(_)
+            | OutputAction::Script(_) => {
+                // Edge-event primitives, layer switches, texts and scripts:
+                // no held state. A script tracks the keys it holds itself.
             }
         }
     }
@@ -300,16 +331,14 @@ impl AppState {
                 return;
             }
 
-            let mut inputs: SmallVec<[INPUT; 12]> = SmallVec::new();
+            let mut events: SmallVec<[OutputEvent; 12]> = SmallVec::new();
             for &sc in &scs {
-                inputs.push(Self::build_key_input(sc, false));
+                events.push(OutputEvent::KeyDown(sc));
             }
             for &btn in &btns {
-                inputs.push(Self::build_mouse_input(btn, false));
-            }
-            unsafe {
-                SendInput(&inputs, std::mem::size_of::<INPUT>() as i32);
+                events.push(OutputEvent::MouseDown(btn));
             }
+            self.emit(&events);
         }
     }
 
@@ -349,7 +378,7 @@ impl AppState {
             }
 
             if duration > 0 {
-                std::thread::sleep(std::time::Duration::from_millis(duration));
+                self.clock().sleep(Duration::from_millis(duration));
             }
 
             // Release phase: body held set first (reversed), then append
@@ -397,59 +426,18 @@ impl AppState {
         }
     }
 
+    /// Hands a batch of primitive events to the configured output sink.
+    /// Empty batches are dropped here so backends never see them.
     #[inline(always)]
-    pub(super) fn build_key_input(scancode: u16, up: bool) -> INPUT {
-        let mut flags = KEYEVENTF_SCANCODE;
-        if Self::is_extended_scancode(scancode) {
-            flags |= KEYEVENTF_EXTENDEDKEY;
-        }
-        if up {
-            flags |= KEYEVENTF_KEYUP;
-        }
-        INPUT {
-            r#type: INPUT_KEYBOARD,
-            Anonymous: INPUT_0 {
-                ki: KEYBDINPUT {
-                    wVk: VIRTUAL_KEY(0),
-                    wScan: scancode,
-                    dwFlags: flags,
-                    time: 0,
-                    dwExtraInfo: SIMULATED_EVENT_MARKER,
-                },
-            },
+    pub(super) fn emit(&self, events: &[OutputEvent]) {
+        if !events.is_empty() {
+            self.output_sink.send(events);
         }
     }
 
+    /// Emits one relative pointer step of `speed` pixels in `direction`.
     #[inline(always)]
-    pub(super) fn build_mouse_input(button: MouseButton, up: bool) -> INPUT {
-        let (down_flag, up_flag) = match button {
-            MouseButton::Left => (MOUSEEVENTF_LEFTDOWN, MOUSEEVENTF_LEFTUP),
-            MouseButton::Right => (MOUSEEVENTF_RIGHTDOWN, MOUSEEVENTF_RIGHTUP),
-            MouseButton::Middle => (MOUSEEVENTF_MIDDLEDOWN, MOUSEEVENTF_MIDDLEUP),
-            MouseButton::X1 | MouseButton::X2 => (MOUSEEVENTF_XDOWN, MOUSEEVENTF_XUP),
-        };
-        let mouse_data = match button {
-            MouseButton::X1 => 1u32,
-            MouseButton::X2 => 2u32,
-            _ => 0,
-        };
-        INPUT {
-            r#type: INPUT_MOUSE,
-            Anonymous: INPUT_0 {
-                mi: MOUSEINPUT {
-                    dx: 0,
-                    dy: 0,
-                    mouseData: mouse_data,
-                    dwFlags: if up { up_flag } else { down_flag },
-                    time: 0,
-                    dwExtraInfo: SIMULATED_EVENT_MARKER,
-                },
-            },
-        }
-    }
-
-    #[inline(always)]
-    fn send_mouse_move(direction: MouseMoveDirection, speed: i32) {
+    fn send_mouse_move(&self, direction: MouseMoveDirection, speed: i32) {
         let (dx, dy) = match direction {
             MouseMoveDirection::Up => (0, -speed),
             MouseMoveDirection::Down => (0, speed),
@@ -460,65 +448,25 @@ impl AppState {
             MouseMoveDirection::DownLeft => (-speed, speed),
             MouseMoveDirection::DownRight => (speed, speed),
         };
-        let input = INPUT {
-            r#type: INPUT_MOUSE,
-            Anonymous: INPUT_0 {
-                mi: MOUSEINPUT {
-                    dx,
-                    dy,
-                    mouseData: 0,
-                    dwFlags: MOUSEEVENTF_MOVE,
-                    time: 0,
-                    dwExtraInfo: SIMULATED_EVENT_MARKER,
-                },
-            },
-        };
-        unsafe {
-            SendInput(&[input], std::mem::size_of::<INPUT>() as i32);
-        }
+        self.emit(&[OutputEvent::MouseMove { dx, dy }]);
     }
 
+    /// Emits a relative pointer step that has already been resolved to a
+    /// pixel delta, e.g. the normalized diagonal sum computed by the mouse
+    /// movement worker.
     #[inline(always)]
-    fn send_mouse_scroll(direction: MouseScrollDirection, speed: i32) {
-        let wheel_delta = match direction {
-            MouseScrollDirection::Up => speed,
-            MouseScrollDirection::Down => -speed,
-        };
-        let input = INPUT {
-            r#type: INPUT_MOUSE,
-            Anonymous: INPUT_0 {
-                mi: MOUSEINPUT {
-                    dx: 0,
-                    dy: 0,
-                    mouseData: wheel_delta as u32,
-                    dwFlags: MOUSEEVENTF_WHEEL,
-                    time: 0,
-                    dwExtraInfo: SIMULATED_EVENT_MARKER,
-                },
-            },
-        };
-        unsafe {
-            SendInput(&[input], std::mem::size_of::<INPUT>() as i32);
-        }
+    pub fn send_mouse_move_relative(&self, dx: i32, dy: i32) {
+        self.emit(&[OutputEvent::MouseMove { dx, dy }]);
     }
 
     #[inline(always)]
-    pub(super) fn is_extended_scancode(scancode: u16) -> bool {
-        const EXTENDED_KEYS_BITMAP: u128 = (1u128 << 0x1D)
-            | (1u128 << 0x38)
-            | (1u128 << 0x47)
-            | (1u128 << 0x48)
-            | (1u128 << 0x49)
-            | (1u128 << 0x4B)
-            | (1u128 << 0x4D)
-            | (1u128 << 0x4F)
-            | (1u128 << 0x50)
-            | (1u128 << 0x51)
-            | (1u128 << 0x52)
-            | (1u128 << 0x53)
-            | (1u128 << 0x5B)
-            | (1u128 << 0x5C);
-
-        scancode < 128 && (EXTENDED_KEYS_BITMAP & (1u128 << scancode)) != 0
+    fn send_mouse_scroll(&self, direction: MouseScrollDirection, speed: i32) {
+        let event = match direction {
+            MouseScrollDirection::Up => OutputEvent::Wheel(speed),
+            MouseScrollDirection::Down => OutputEvent::Wheel(-speed),
+            MouseScrollDirection::Left => OutputEvent::HWheel(-speed),
+            MouseScrollDirection::Right => OutputEvent::HWheel(speed),
+        };
+        self.emit(&[event]);
     }
 }
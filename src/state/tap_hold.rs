@@ -0,0 +1,152 @@
+//! Tap-hold decisions.
+//!
+//! A tap-hold trigger produces no output when it goes down. It is first
+//! pending; the hold output is pressed once it stays down for the tapping
+//! term, or as soon as another input is pressed with `permissive_hold`.
+//! Released while still pending, it taps instead: the dispatcher forwards
+//! the press and release to a worker, which plays `target_action` once.
+//!
+//! Decisions are made on the thread that dispatches the trigger's events,
+//! and the hold output is pressed there too, so it is down before the
+//! input that interrupted the tap reaches the system.
+
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+use super::AppState;
+use super::output::OutputEvent;
+use super::types::*;
+
+/// Tap-hold trigger that is currently down.
+#[derive(Debug, Clone)]
+pub(crate) struct TapHoldPress {
+    since: Instant,
+    tap_hold: Arc<TapHold>,
+    /// Whether the hold output is pressed.
+    held: bool,
+}
+
+/// What the dispatcher does with an event after the tap-hold check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TapHoldOutcome {
+    /// Not a tap-hold event; dispatch it as usual.
+    Forward,
+    /// Handled here; dispatch nothing.
+    Consumed,
+    /// The trigger was tapped; dispatch its press and release.
+    Tap,
+}
+
+impl AppState {
+    /// Whether any installed mapping is a tap-hold mapping. Lets the
+    /// dispatcher skip the tap-hold lookup for configs without one.
+    #[inline(always)]
+    pub(crate) fn has_tap_holds(&self) -> bool {
+        self.has_tap_holds.load(Ordering::Relaxed)
+    }
+
+    #[inline(always)]
+    fn no_tap_hold_down(&self) -> bool {
+        !self.has_tap_holds() || self.tap_holds.is_empty()
+    }
+
+    /// Runs `event` through the tap-hold state.
+    #[cfg_attr(not(windows), allow(dead_code))]
+    pub(crate) fn handle_tap_hold_event(&self, event: &InputEvent) -> TapHoldOutcome {
+        match event {
+            InputEvent::Pressed(device) => {
+                if self.tap_holds.contains_sync(device) {
+                    // Auto-repeat of a trigger already deciding or held.
+                    return TapHoldOutcome::Consumed;
+                }
+                if !self.no_tap_hold_down() {
+                    self.interrupt_tap_holds();
+                }
+                match self.get_input_mapping(device) {
+                    Some(InputMappingInfo {
+                        tap_hold: Some(tap_hold),
+                        ..
+                    }) => {
+                        let press = TapHoldPress {
+                            since: self.clock().now(),
+                            tap_hold,
+                            held: false,
+                        };
+                        let _ = self.tap_holds.insert_sync(device.clone(), press);
+                        TapHoldOutcome::Consumed
+                    }
+                    _ => TapHoldOutcome::Forward,
+                }
+            }
+            InputEvent::Released(device) => match self.tap_holds.remove_sync(device) {
+                Some((_, press)) if press.held => {
+                    self.simulate_release(&press.tap_hold.hold_action);
+                    TapHoldOutcome::Consumed
+                }
+                Some(_) => TapHoldOutcome::Tap,
+                None => TapHoldOutcome::Forward,
+            },
+            InputEvent::RetapHold(device) => {
+                if self.tap_holds.contains_sync(device) {
+                    TapHoldOutcome::Consumed
+                } else {
+                    TapHoldOutcome::Forward
+                }
+            }
+        }
+    }
+
+    /// Picks the hold for every pending permissive trigger, as another
+    /// input was pressed. Returns true when any hold was pressed.
+    fn interrupt_tap_holds(&self) -> bool {
+        let mut pressed = false;
+        self.tap_holds.retain_sync(|_, press| {
+            if !press.held && press.tap_hold.permissive {
+                self.simulate_initial_press(&press.tap_hold.hold_action);
+                press.held = true;
+                pressed = true;
+            }
+            true
+        });
+        pressed
+    }
+
+    /// Lets an input the hooks pass through untouched pick the hold of
+    /// pending permissive triggers. Returns true when a hold was pressed;
+    /// `replay` has then been sent after it and the caller must block the
+    /// original input, which the system would otherwise see first.
+    pub(crate) fn hold_before_passthrough(&self, replay: OutputEvent) -> bool {
+        if self.no_tap_hold_down() || !self.interrupt_tap_holds() {
+            return false;
+        }
+        self.emit(&[replay]);
+        true
+    }
+
+    /// Presses the hold output of every trigger held past its tapping
+    /// term. Called from the worker loop.
+    #[cfg_attr(not(windows), allow(dead_code))]
+    pub(crate) fn expire_tap_holds(&self) {
+        if self.no_tap_hold_down() {
+            return;
+        }
+        let now = self.clock().now();
+        self.tap_holds.retain_sync(|_, press| {
+            if !press.held
+                && now.duration_since(press.since)
+                    >= Duration::from_millis(press.tap_hold.tapping_term)
+            {
+                self.simulate_initial_press(&press.tap_hold.hold_action);
+                press.held = true;
+            }
+            true
+        });
+    }
+
+    /// Forgets every tap-hold trigger that is down. Held outputs are
+    /// released by the caller.
+    pub(super) fn reset_tap_holds(&self) {
+        self.tap_holds.clear_sync();
+    }
+}
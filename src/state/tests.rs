@@ -1,17 +1,28 @@
 //! Unit tests for state module.
 
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
     use std::sync::Arc;
     use std::sync::atomic::Ordering;
+    use std::time::{Duration, Instant};
 
     use scc::Guard;
     use smallvec::SmallVec;
 
-    use crate::config::{AppConfig, KeyMapping};
+    use crate::clock::{Clock, VirtualClock};
+    use crate::config::{
+        Activation, AppConfig, CAPS_LOCK, DEFAULT_TAPPING_TERM_MS, JitterDistribution, KeyMapping,
+        LayerMode, LockKeys, MIN_EVENT_DURATION_MS, MIN_INTERVAL_MS, NUM_LOCK, ProcessListMode,
+        Profile, ProfileRule, SingleTap, TriggerKind,
+    };
     use crate::state::AppState;
+    use crate::state::output::{OutputEvent, OutputSink, RecordingOutputSink};
     use crate::state::parsing::{key_name_to_vk, mouse_button_name_to_type, vk_to_scancode};
+    use crate::state::script::{Script, ScriptOp};
+    use crate::state::text::{LayoutKeys, TextPlan, TextStroke};
     use crate::state::types::*;
+    use crate::state::worker::{DeviceSimState, TurboWorker, is_sequential_hold_action};
 
     #[test]
     fn test_key_name_to_vk_letters() {
@@ -117,35 +128,25 @@ mod tests {
 
     #[test]
     fn test_create_input_mappings_valid() {
-        let mut config = AppConfig::default();
-        config.mappings = vec![
-            KeyMapping {
-                trigger_key: "A".to_string(),
-                target_keys: SmallVec::from_vec(vec!["B".to_string()]),
-                interval: Some(10),
-                event_duration: Some(5),
-                turbo_enabled: true,
-                move_speed: 10,
-                target_mode: 0,
-                trigger_sequence: None,
-                sequence_window_ms: 500,
-                hold_indices: None,
-                append_keys: None,
-            },
-            KeyMapping {
-                trigger_key: "F1".to_string(),
-                target_keys: SmallVec::from_vec(vec!["SPACE".to_string()]),
-                interval: None,
-                event_duration: None,
-                turbo_enabled: true,
-                move_speed: 10,
-                target_mode: 0,
-                trigger_sequence: None,
-                sequence_window_ms: 500,
-                hold_indices: None,
-                append_keys: None,
-            },
-        ];
+        let config = AppConfig {
+            mappings: vec![
+                KeyMapping {
+                    trigger_key: "A".to_string(),
+                    target_keys: SmallVec::from_vec(vec!["B".to_string()]),
+                    interval: Some(10),
+                    event_duration: Some(5),
+                    move_speed: 10,
+                    ..Default::default()
+                },
+                KeyMapping {
+                    trigger_key: "F1".to_string(),
+                    target_keys: SmallVec::from_vec(vec!["SPACE".to_string()]),
+                    move_speed: 10,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
 
         let result = AppState::create_input_mappings(&config);
         assert!(result.is_ok());
@@ -166,20 +167,15 @@ mod tests {
 
     #[test]
     fn test_create_input_mappings_invalid_trigger() {
-        let mut config = AppConfig::default();
-        config.mappings = vec![KeyMapping {
-            trigger_key: "INVALID_KEY".to_string(),
-            target_keys: SmallVec::from_vec(vec!["A".to_string()]),
-            interval: None,
-            event_duration: None,
-            turbo_enabled: true,
-            move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        }];
+        let config = AppConfig {
+            mappings: vec![KeyMapping {
+                trigger_key: "INVALID_KEY".to_string(),
+                target_keys: SmallVec::from_vec(vec!["A".to_string()]),
+                move_speed: 10,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
 
         let result = AppState::create_input_mappings(&config);
         assert!(result.is_err());
@@ -187,20 +183,15 @@ mod tests {
 
     #[test]
     fn test_create_input_mappings_invalid_target() {
-        let mut config = AppConfig::default();
-        config.mappings = vec![KeyMapping {
-            trigger_key: "A".to_string(),
-            target_keys: SmallVec::from_vec(vec!["INVALID_KEY".to_string()]),
-            interval: None,
-            event_duration: None,
-            turbo_enabled: true,
-            move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        }];
+        let config = AppConfig {
+            mappings: vec![KeyMapping {
+                trigger_key: "A".to_string(),
+                target_keys: SmallVec::from_vec(vec!["INVALID_KEY".to_string()]),
+                move_speed: 10,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
 
         let result = AppState::create_input_mappings(&config);
         assert!(result.is_err());
@@ -208,20 +199,17 @@ mod tests {
 
     #[test]
     fn test_create_input_mappings_interval_validation() {
-        let mut config = AppConfig::default();
-        config.interval = 3; // Below minimum
+        let mut config = AppConfig {
+            interval: 3,
+            ..Default::default()
+        };
+        // Below minimum
         config.mappings = vec![KeyMapping {
             trigger_key: "A".to_string(),
             target_keys: SmallVec::from_vec(vec!["B".to_string()]),
             interval: Some(3), // Below minimum
-            event_duration: None,
-            turbo_enabled: true,
             move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
+            ..Default::default()
         }];
 
         let result = AppState::create_input_mappings(&config);
@@ -238,20 +226,17 @@ mod tests {
 
     #[test]
     fn test_create_input_mappings_duration_validation() {
-        let mut config = AppConfig::default();
-        config.event_duration = 2; // Below minimum
+        let mut config = AppConfig {
+            event_duration: 2,
+            ..Default::default()
+        };
+        // Below minimum
         config.mappings = vec![KeyMapping {
             trigger_key: "A".to_string(),
             target_keys: SmallVec::from_vec(vec!["B".to_string()]),
-            interval: None,
             event_duration: Some(3), // Below minimum
-            turbo_enabled: true,
             move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
+            ..Default::default()
         }];
 
         let result = AppState::create_input_mappings(&config);
@@ -276,48 +261,35 @@ mod tests {
 
     #[test]
     fn test_multiple_input_mappings() {
-        let mut config = AppConfig::default();
-        config.mappings = vec![
-            KeyMapping {
-                trigger_key: "A".to_string(),
-                target_keys: SmallVec::from_vec(vec!["1".to_string()]),
-                interval: Some(10),
-                event_duration: Some(5),
-                turbo_enabled: true,
-                move_speed: 10,
-                target_mode: 0,
-                trigger_sequence: None,
-                sequence_window_ms: 500,
-                hold_indices: None,
-                append_keys: None,
-            },
-            KeyMapping {
-                trigger_key: "B".to_string(),
-                target_keys: SmallVec::from_vec(vec!["2".to_string()]),
-                interval: Some(15),
-                event_duration: Some(8),
-                turbo_enabled: true,
-                move_speed: 10,
-                target_mode: 0,
-                trigger_sequence: None,
-                sequence_window_ms: 500,
-                hold_indices: None,
-                append_keys: None,
-            },
-            KeyMapping {
-                trigger_key: "C".to_string(),
-                target_keys: SmallVec::from_vec(vec!["3".to_string()]),
-                interval: Some(20),
-                event_duration: Some(10),
-                turbo_enabled: true,
-                move_speed: 10,
-                target_mode: 0,
-                trigger_sequence: None,
-                sequence_window_ms: 500,
-                hold_indices: None,
-                append_keys: None,
-            },
-        ];
+        let config = AppConfig {
+            mappings: vec![
+                KeyMapping {
+                    trigger_key: "A".to_string(),
+                    target_keys: SmallVec::from_vec(vec!["1".to_string()]),
+                    interval: Some(10),
+                    event_duration: Some(5),
+                    move_speed: 10,
+                    ..Default::default()
+                },
+                KeyMapping {
+                    trigger_key: "B".to_string(),
+                    target_keys: SmallVec::from_vec(vec!["2".to_string()]),
+                    interval: Some(15),
+                    event_duration: Some(8),
+                    move_speed: 10,
+                    ..Default::default()
+                },
+                KeyMapping {
+                    trigger_key: "C".to_string(),
+                    target_keys: SmallVec::from_vec(vec!["3".to_string()]),
+                    interval: Some(20),
+                    event_duration: Some(10),
+                    move_speed: 10,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
 
         let input_mappings = AppState::create_input_mappings(&config).unwrap();
         assert_eq!(input_mappings.len(), 3);
@@ -344,10 +316,12 @@ mod tests {
         ); // DELETE
 
         // Create new config
-        let mut new_config = AppConfig::default();
-        new_config.switch_key = "F11".to_string();
-        new_config.show_tray_icon = false;
-        new_config.input_timeout = 50;
+        let new_config = AppConfig {
+            switch_key: "F11".to_string(),
+            show_tray_icon: false,
+            input_timeout: 50,
+            ..Default::default()
+        };
 
         // Reload config
         state.reload_config(new_config).unwrap();
@@ -363,22 +337,18 @@ mod tests {
 
     #[test]
     fn test_key_mapping_with_boundary_values() {
-        let mut config = AppConfig::default();
-
         // Test with minimum interval
-        config.mappings = vec![KeyMapping {
-            trigger_key: "A".to_string(),
-            target_keys: SmallVec::from_vec(vec!["B".to_string()]),
-            interval: Some(5), // Minimum valid value
-            event_duration: Some(2),
-            turbo_enabled: true,
-            move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        }];
+        let config = AppConfig {
+            mappings: vec![KeyMapping {
+                trigger_key: "A".to_string(),
+                target_keys: SmallVec::from_vec(vec!["B".to_string()]),
+                interval: Some(5), // Minimum valid value
+                event_duration: Some(2),
+                move_speed: 10,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
 
         let state = AppState::new(config);
         assert!(state.is_ok());
@@ -386,28 +356,24 @@ mod tests {
 
     #[test]
     fn test_key_mapping_with_zero_interval() {
-        let mut config = AppConfig::default();
-
         // Test with zero interval (should be auto-adjusted to minimum)
-        config.mappings = vec![KeyMapping {
-            trigger_key: "A".to_string(),
-            target_keys: SmallVec::from_vec(vec!["B".to_string()]),
-            interval: Some(0),
-            event_duration: Some(0),
-            turbo_enabled: true,
-            move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        }];
+        let config = AppConfig {
+            mappings: vec![KeyMapping {
+                trigger_key: "A".to_string(),
+                target_keys: SmallVec::from_vec(vec!["B".to_string()]),
+                interval: Some(0),
+                event_duration: Some(0),
+                move_speed: 10,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
 
         let state = AppState::new(config).unwrap();
 
         // Values should be adjusted to minimum of 2
         // This test verifies auto-adjustment behavior
-        assert!(state.input_mappings.len() > 0);
+        assert!(!state.input_mappings.is_empty());
     }
 
     #[test]
@@ -695,12 +661,12 @@ mod tests {
         // Test that different handles produce different button IDs
         let device1 = InputDevice::GenericDevice {
             device_type: DeviceType::Gamepad(0x045e),
-            button_id: (0x11111111u64 << 32) | (2u64 << 16) | 0u64,
+            button_id: (0x11111111u64 << 32) | (2u64 << 16),
         };
 
         let device2 = InputDevice::GenericDevice {
             device_type: DeviceType::Gamepad(0x045e),
-            button_id: (0x22222222u64 << 32) | (2u64 << 16) | 0u64,
+            button_id: (0x22222222u64 << 32) | (2u64 << 16),
         };
 
         assert_ne!(device1, device2);
@@ -720,35 +686,25 @@ mod tests {
 
     #[test]
     fn test_key_combo_mapping_creation() {
-        let mut config = AppConfig::default();
-        config.mappings = vec![
-            KeyMapping {
-                trigger_key: "ALT+A".to_string(),
-                target_keys: SmallVec::from_vec(vec!["B".to_string()]),
-                interval: Some(10),
-                event_duration: Some(5),
-                turbo_enabled: true,
-                move_speed: 10,
-                target_mode: 0,
-                trigger_sequence: None,
-                sequence_window_ms: 500,
-                hold_indices: None,
-                append_keys: None,
-            },
-            KeyMapping {
-                trigger_key: "CTRL+SHIFT+F".to_string(),
-                target_keys: SmallVec::from_vec(vec!["ALT+F4".to_string()]),
-                interval: None,
-                event_duration: None,
-                turbo_enabled: true,
-                move_speed: 10,
-                target_mode: 0,
-                trigger_sequence: None,
-                sequence_window_ms: 500,
-                hold_indices: None,
-                append_keys: None,
-            },
-        ];
+        let config = AppConfig {
+            mappings: vec![
+                KeyMapping {
+                    trigger_key: "ALT+A".to_string(),
+                    target_keys: SmallVec::from_vec(vec!["B".to_string()]),
+                    interval: Some(10),
+                    event_duration: Some(5),
+                    move_speed: 10,
+                    ..Default::default()
+                },
+                KeyMapping {
+                    trigger_key: "CTRL+SHIFT+F".to_string(),
+                    target_keys: SmallVec::from_vec(vec!["ALT+F4".to_string()]),
+                    move_speed: 10,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
 
         let input_mappings = AppState::create_input_mappings(&config).unwrap();
         assert_eq!(input_mappings.len(), 2);
@@ -807,8 +763,10 @@ mod tests {
 
     #[test]
     fn test_empty_process_whitelist() {
-        let mut config = AppConfig::default();
-        config.process_whitelist = vec![];
+        let config = AppConfig {
+            process_whitelist: vec![],
+            ..Default::default()
+        };
 
         let state = AppState::new(config).unwrap();
 
@@ -819,10 +777,11 @@ mod tests {
     #[test]
     fn test_process_whitelist_cache() {
         use std::thread;
-        use std::time::Duration;
 
-        let mut config = AppConfig::default();
-        config.process_whitelist = vec!["explorer.exe".to_string()];
+        let config = AppConfig {
+            process_whitelist: vec!["explorer.exe".to_string()],
+            ..Default::default()
+        };
 
         let state = AppState::new(config).unwrap();
 
@@ -857,9 +816,46 @@ mod tests {
         }
     }
 
+    /// Same cache contract as above, driven by a virtual clock so the
+    /// 50 ms boundary is checked exactly instead of with a wall-clock
+    /// sleep.
+    #[test]
+    fn test_process_whitelist_cache_expiry_with_virtual_clock() {
+        let config = AppConfig {
+            process_whitelist: vec!["explorer.exe".to_string()],
+            ..Default::default()
+        };
+
+        let clock = Arc::new(VirtualClock::new());
+        let state =
+            AppState::with_backends(config, Arc::new(RecordingOutputSink::new()), clock.clone())
+                .unwrap();
+        let start = clock.now();
+
+        let cached_timestamp = |state: &AppState| {
+            let guard = Guard::new();
+            state
+                .cached_process_info
+                .load(Ordering::Acquire, &guard)
+                .as_ref()
+                .map(|c| c.timestamp)
+        };
+
+        // Still inside the window seeded at construction: no refresh.
+        clock.advance(Duration::from_millis(49));
+        let _ = state.is_process_whitelisted();
+        assert_eq!(cached_timestamp(&state), Some(start));
+
+        // Exactly at the 50 ms boundary the cache is refreshed and
+        // stamped with the clock's current time.
+        clock.advance(Duration::from_millis(1));
+        let _ = state.is_process_whitelisted();
+        assert_eq!(cached_timestamp(&state), Some(clock.now()));
+    }
+
     #[test]
     fn test_x_button_parsing() {
-        use windows::Win32::UI::WindowsAndMessaging::*;
+        use crate::state::win32::*;
 
         let config = AppConfig::default();
         let state = AppState::new(config).unwrap();
@@ -875,6 +871,42 @@ mod tests {
         // Should parse as X2 button
     }
 
+    #[test]
+    fn test_wheel_name_parsing() {
+        use crate::state::parsing::{input_name_to_device, input_name_to_output};
+
+        assert!(matches!(
+            input_name_to_output("SCROLL_LEFT"),
+            Some(OutputAction::MouseScroll(MouseScrollDirection::Left, _))
+        ));
+        assert!(matches!(
+            input_name_to_output("wheel_right"),
+            Some(OutputAction::MouseScroll(MouseScrollDirection::Right, _))
+        ));
+        assert_eq!(
+            input_name_to_device("WHEEL_LEFT"),
+            Some(InputDevice::Wheel(MouseScrollDirection::Left))
+        );
+        assert_eq!(
+            input_name_to_device("SCROLL_RIGHT"),
+            Some(InputDevice::Wheel(MouseScrollDirection::Right))
+        );
+        assert_eq!(
+            input_name_to_device("WHEEL_UP"),
+            Some(InputDevice::Wheel(MouseScrollDirection::Up))
+        );
+
+        let combo = InputDevice::WheelCombo(vec![0xA2, 0xA0], MouseScrollDirection::Down);
+        assert_eq!(
+            input_name_to_device("LCTRL+LSHIFT+WHEEL_DOWN"),
+            Some(combo.clone())
+        );
+        assert_eq!(combo.to_string(), "LCTRL+LSHIFT+WHEEL_DOWN");
+        // Only keys can be held with a notch.
+        assert_eq!(input_name_to_device("LBUTTON+WHEEL_UP"), None);
+        assert_eq!(input_name_to_device("WHEEL_UP+A"), None);
+    }
+
     #[test]
     fn test_mouse_button_name_parsing() {
         // Test X button name parsing
@@ -917,20 +949,20 @@ mod tests {
 
     #[test]
     fn test_create_multiple_target_keys_mapping() {
-        let mut config = AppConfig::default();
-        config.mappings = vec![KeyMapping {
-            trigger_key: "Q".to_string(),
-            target_keys: SmallVec::from_vec(vec!["MOUSE_UP".to_string(), "MOUSE_LEFT".to_string()]),
-            interval: Some(5),
-            event_duration: Some(5),
-            turbo_enabled: true,
-            move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        }];
+        let config = AppConfig {
+            mappings: vec![KeyMapping {
+                trigger_key: "Q".to_string(),
+                target_keys: SmallVec::from_vec(vec![
+                    "MOUSE_UP".to_string(),
+                    "MOUSE_LEFT".to_string(),
+                ]),
+                interval: Some(5),
+                event_duration: Some(5),
+                move_speed: 10,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
 
         let result = AppState::create_input_mappings(&config);
         assert!(result.is_ok());
@@ -950,24 +982,21 @@ mod tests {
 
     #[test]
     fn test_multiple_target_keys_creates_multiple_actions() {
-        let mut config = AppConfig::default();
-        config.mappings = vec![KeyMapping {
-            trigger_key: "A".to_string(),
-            target_keys: SmallVec::from_vec(vec![
-                "1".to_string(),
-                "2".to_string(),
-                "3".to_string(),
-            ]),
-            interval: Some(10),
-            event_duration: Some(5),
-            turbo_enabled: true,
-            move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        }];
+        let config = AppConfig {
+            mappings: vec![KeyMapping {
+                trigger_key: "A".to_string(),
+                target_keys: SmallVec::from_vec(vec![
+                    "1".to_string(),
+                    "2".to_string(),
+                    "3".to_string(),
+                ]),
+                interval: Some(10),
+                event_duration: Some(5),
+                move_speed: 10,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
 
         let result = AppState::create_input_mappings(&config);
         assert!(result.is_ok());
@@ -985,20 +1014,17 @@ mod tests {
 
     #[test]
     fn test_single_target_key_not_wrapped_in_multiple_actions() {
-        let mut config = AppConfig::default();
-        config.mappings = vec![KeyMapping {
-            trigger_key: "A".to_string(),
-            target_keys: SmallVec::from_vec(vec!["B".to_string()]),
-            interval: Some(10),
-            event_duration: Some(5),
-            turbo_enabled: true,
-            move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        }];
+        let config = AppConfig {
+            mappings: vec![KeyMapping {
+                trigger_key: "A".to_string(),
+                target_keys: SmallVec::from_vec(vec!["B".to_string()]),
+                interval: Some(10),
+                event_duration: Some(5),
+                move_speed: 10,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
 
         let result = AppState::create_input_mappings(&config);
         assert!(result.is_ok());
@@ -1016,20 +1042,16 @@ mod tests {
 
     #[test]
     fn test_empty_target_keys_skipped() {
-        let mut config = AppConfig::default();
-        config.mappings = vec![KeyMapping {
-            trigger_key: "A".to_string(),
-            target_keys: SmallVec::new(),
-            interval: Some(10),
-            event_duration: Some(5),
-            turbo_enabled: true,
-            move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        }];
+        let config = AppConfig {
+            mappings: vec![KeyMapping {
+                trigger_key: "A".to_string(),
+                interval: Some(10),
+                event_duration: Some(5),
+                move_speed: 10,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
 
         let result = AppState::create_input_mappings(&config);
         assert!(result.is_ok());
@@ -1041,24 +1063,21 @@ mod tests {
 
     #[test]
     fn test_multiple_target_keys_with_mixed_types() {
-        let mut config = AppConfig::default();
-        config.mappings = vec![KeyMapping {
-            trigger_key: "Q".to_string(),
-            target_keys: SmallVec::from_vec(vec![
-                "A".to_string(),
-                "B".to_string(),
-                "C".to_string(),
-            ]),
-            interval: Some(10),
-            event_duration: Some(5),
-            turbo_enabled: true,
-            move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        }];
+        let config = AppConfig {
+            mappings: vec![KeyMapping {
+                trigger_key: "Q".to_string(),
+                target_keys: SmallVec::from_vec(vec![
+                    "A".to_string(),
+                    "B".to_string(),
+                    "C".to_string(),
+                ]),
+                interval: Some(10),
+                event_duration: Some(5),
+                move_speed: 10,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
 
         let result = AppState::create_input_mappings(&config);
         assert!(result.is_ok());
@@ -1080,20 +1099,15 @@ mod tests {
 
     #[test]
     fn test_multiple_target_keys_validation() {
-        let mut config = AppConfig::default();
-        config.mappings = vec![KeyMapping {
-            trigger_key: "A".to_string(),
-            target_keys: SmallVec::from_vec(vec!["B".to_string(), "INVALID_KEY".to_string()]),
-            interval: None,
-            event_duration: None,
-            turbo_enabled: true,
-            move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        }];
+        let config = AppConfig {
+            mappings: vec![KeyMapping {
+                trigger_key: "A".to_string(),
+                target_keys: SmallVec::from_vec(vec!["B".to_string(), "INVALID_KEY".to_string()]),
+                move_speed: 10,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
 
         let result = AppState::create_input_mappings(&config);
         // Should fail due to invalid target key
@@ -1102,20 +1116,17 @@ mod tests {
 
     #[test]
     fn test_smallvec_optimization_in_multiple_actions() {
-        let mut config = AppConfig::default();
-        config.mappings = vec![KeyMapping {
-            trigger_key: "A".to_string(),
-            target_keys: SmallVec::from_vec(vec!["1".to_string(), "2".to_string()]),
-            interval: Some(10),
-            event_duration: Some(5),
-            turbo_enabled: true,
-            move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        }];
+        let config = AppConfig {
+            mappings: vec![KeyMapping {
+                trigger_key: "A".to_string(),
+                target_keys: SmallVec::from_vec(vec!["1".to_string(), "2".to_string()]),
+                interval: Some(10),
+                event_duration: Some(5),
+                move_speed: 10,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
 
         let result = AppState::create_input_mappings(&config);
         assert!(result.is_ok());
@@ -1142,20 +1153,19 @@ mod tests {
     /// with the right `hold_mask` bits set and keep unset indices clear.
     #[test]
     fn test_sequential_hold_mask_built_from_indices() {
-        let mut config = AppConfig::default();
-        config.mappings = vec![KeyMapping {
-            trigger_key: "F1".to_string(),
-            target_keys: SmallVec::from_vec(vec!["RIGHT".to_string(), "RIGHT".to_string()]),
-            interval: Some(5),
-            event_duration: Some(5),
-            turbo_enabled: false,
-            move_speed: 5,
-            target_mode: 2,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: Some(SmallVec::from_vec(vec![1])),
-            append_keys: None,
-        }];
+        let config = AppConfig {
+            mappings: vec![KeyMapping {
+                trigger_key: "F1".to_string(),
+                target_keys: SmallVec::from_vec(vec!["RIGHT".to_string(), "RIGHT".to_string()]),
+                interval: Some(5),
+                event_duration: Some(5),
+                turbo_enabled: false,
+                target_mode: 2,
+                hold_indices: Some(SmallVec::from_vec(vec![1])),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
 
         let mappings = AppState::create_input_mappings(&config).unwrap();
         let info = mappings
@@ -1181,21 +1191,18 @@ mod tests {
     /// panicking. Keeps stale configs from crashing on load.
     #[test]
     fn test_sequential_hold_ignores_out_of_range_indices() {
-        let mut config = AppConfig::default();
-        config.mappings = vec![KeyMapping {
-            trigger_key: "F2".to_string(),
-            target_keys: SmallVec::from_vec(vec!["A".to_string(), "B".to_string()]),
-            interval: None,
-            event_duration: None,
-            turbo_enabled: false,
-            move_speed: 5,
-            target_mode: 2,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            // idx 5 is past the sequence length; idx 20 past the u16 mask width.
-            hold_indices: Some(SmallVec::from_vec(vec![0, 5, 20])),
-            append_keys: None,
-        }];
+        let config = AppConfig {
+            mappings: vec![KeyMapping {
+                trigger_key: "F2".to_string(),
+                target_keys: SmallVec::from_vec(vec!["A".to_string(), "B".to_string()]),
+                turbo_enabled: false,
+                target_mode: 2,
+                // idx 5 is past the sequence length; idx 20 past the u16 mask width.
+                hold_indices: Some(SmallVec::from_vec(vec![0, 5, 20])),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
 
         let mappings = AppState::create_input_mappings(&config).unwrap();
         let info = mappings
@@ -1213,23 +1220,22 @@ mod tests {
     /// mapping's `move_speed` for mouse directions / scrolls.
     #[test]
     fn test_sequential_hold_with_append_keys() {
-        let mut config = AppConfig::default();
-        config.mappings = vec![KeyMapping {
-            trigger_key: "F3".to_string(),
-            target_keys: SmallVec::from_vec(vec!["RIGHT".to_string(), "RIGHT".to_string()]),
-            interval: None,
-            event_duration: None,
-            turbo_enabled: false,
-            move_speed: 7,
-            target_mode: 2,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: Some(SmallVec::from_vec(vec![1])),
-            append_keys: Some(SmallVec::from_vec(vec![
-                "UP".to_string(),
-                "MOUSE_UP".to_string(),
-            ])),
-        }];
+        let config = AppConfig {
+            mappings: vec![KeyMapping {
+                trigger_key: "F3".to_string(),
+                target_keys: SmallVec::from_vec(vec!["RIGHT".to_string(), "RIGHT".to_string()]),
+                turbo_enabled: false,
+                move_speed: 7,
+                target_mode: 2,
+                hold_indices: Some(SmallVec::from_vec(vec![1])),
+                append_keys: Some(SmallVec::from_vec(vec![
+                    "UP".to_string(),
+                    "MOUSE_UP".to_string(),
+                ])),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
 
         let mappings = AppState::create_input_mappings(&config).unwrap();
         let info = mappings
@@ -1255,20 +1261,16 @@ mod tests {
     /// `simulate_hold_cycle` to pulse just the configured subset.
     #[test]
     fn test_sequential_hold_kept_when_turbo_on() {
-        let mut config = AppConfig::default();
-        config.mappings = vec![KeyMapping {
-            trigger_key: "F4".to_string(),
-            target_keys: SmallVec::from_vec(vec!["RIGHT".to_string(), "RIGHT".to_string()]),
-            interval: None,
-            event_duration: None,
-            turbo_enabled: true,
-            move_speed: 5,
-            target_mode: 2,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: Some(SmallVec::from_vec(vec![1])),
-            append_keys: None,
-        }];
+        let config = AppConfig {
+            mappings: vec![KeyMapping {
+                trigger_key: "F4".to_string(),
+                target_keys: SmallVec::from_vec(vec!["RIGHT".to_string(), "RIGHT".to_string()]),
+                target_mode: 2,
+                hold_indices: Some(SmallVec::from_vec(vec![1])),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
 
         let mappings = AppState::create_input_mappings(&config).unwrap();
         let info = mappings
@@ -1284,24 +1286,21 @@ mod tests {
     /// simultaneous-playback `MappingHold` (`sequential == false`).
     #[test]
     fn test_multi_target_hold_uses_simultaneous_mode() {
-        let mut config = AppConfig::default();
-        config.mappings = vec![KeyMapping {
-            trigger_key: "F6".to_string(),
-            target_keys: SmallVec::from_vec(vec![
-                "A".to_string(),
-                "B".to_string(),
-                "C".to_string(),
-            ]),
-            interval: None,
-            event_duration: None,
-            turbo_enabled: false,
-            move_speed: 5,
-            target_mode: 1,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: Some(SmallVec::from_vec(vec![0, 2])),
-            append_keys: None,
-        }];
+        let config = AppConfig {
+            mappings: vec![KeyMapping {
+                trigger_key: "F6".to_string(),
+                target_keys: SmallVec::from_vec(vec![
+                    "A".to_string(),
+                    "B".to_string(),
+                    "C".to_string(),
+                ]),
+                turbo_enabled: false,
+                target_mode: 1,
+                hold_indices: Some(SmallVec::from_vec(vec![0, 2])),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
 
         let mappings = AppState::create_input_mappings(&config).unwrap();
         let info = mappings
@@ -1324,20 +1323,16 @@ mod tests {
     /// simultaneous mode, even with just one body action.
     #[test]
     fn test_single_target_hold_uses_simultaneous_mode() {
-        let mut config = AppConfig::default();
-        config.mappings = vec![KeyMapping {
-            trigger_key: "F7".to_string(),
-            target_keys: SmallVec::from_vec(vec!["A".to_string()]),
-            interval: None,
-            event_duration: None,
-            turbo_enabled: false,
-            move_speed: 5,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: Some(SmallVec::from_vec(vec![0])),
-            append_keys: None,
-        }];
+        let config = AppConfig {
+            mappings: vec![KeyMapping {
+                trigger_key: "F7".to_string(),
+                target_keys: SmallVec::from_vec(vec!["A".to_string()]),
+                turbo_enabled: false,
+                hold_indices: Some(SmallVec::from_vec(vec![0])),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
 
         let mappings = AppState::create_input_mappings(&config).unwrap();
         let info = mappings
@@ -1362,20 +1357,16 @@ mod tests {
     /// sequence intact so old configs remain observationally identical.
     #[test]
     fn test_sequential_hold_absent_when_no_fields() {
-        let mut config = AppConfig::default();
-        config.mappings = vec![KeyMapping {
-            trigger_key: "F5".to_string(),
-            target_keys: SmallVec::from_vec(vec!["A".to_string(), "B".to_string()]),
-            interval: None,
-            event_duration: None,
-            turbo_enabled: false,
-            move_speed: 5,
-            target_mode: 2,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        }];
+        let config = AppConfig {
+            mappings: vec![KeyMapping {
+                trigger_key: "F5".to_string(),
+                target_keys: SmallVec::from_vec(vec!["A".to_string(), "B".to_string()]),
+                turbo_enabled: false,
+                target_mode: 2,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
 
         let mappings = AppState::create_input_mappings(&config).unwrap();
         let info = mappings
@@ -1393,21 +1384,18 @@ mod tests {
     /// no-op `MappingHold` that would swallow the trigger silently.
     #[test]
     fn test_out_of_range_only_indices_fall_back_to_classic() {
-        let mut config = AppConfig::default();
-        config.mappings = vec![KeyMapping {
-            trigger_key: "F8".to_string(),
-            target_keys: SmallVec::from_vec(vec!["A".to_string(), "B".to_string()]),
-            interval: None,
-            event_duration: None,
-            turbo_enabled: false,
-            move_speed: 5,
-            target_mode: 2,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            // Both indices are past the action count.
-            hold_indices: Some(SmallVec::from_vec(vec![7, 8])),
-            append_keys: None,
-        }];
+        let config = AppConfig {
+            mappings: vec![KeyMapping {
+                trigger_key: "F8".to_string(),
+                target_keys: SmallVec::from_vec(vec!["A".to_string(), "B".to_string()]),
+                turbo_enabled: false,
+                target_mode: 2,
+                // Both indices are past the action count.
+                hold_indices: Some(SmallVec::from_vec(vec![7, 8])),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
 
         let mappings = AppState::create_input_mappings(&config).unwrap();
         let info = mappings
@@ -1423,20 +1411,17 @@ mod tests {
     /// still produce a `MappingHold` so the append phase actually runs.
     #[test]
     fn test_hold_mask_zero_but_append_builds_mapping_hold() {
-        let mut config = AppConfig::default();
-        config.mappings = vec![KeyMapping {
-            trigger_key: "F9".to_string(),
-            target_keys: SmallVec::from_vec(vec!["A".to_string(), "B".to_string()]),
-            interval: None,
-            event_duration: None,
-            turbo_enabled: false,
-            move_speed: 5,
-            target_mode: 2,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: Some(SmallVec::from_vec(vec!["LSHIFT".to_string()])),
-        }];
+        let config = AppConfig {
+            mappings: vec![KeyMapping {
+                trigger_key: "F9".to_string(),
+                target_keys: SmallVec::from_vec(vec!["A".to_string(), "B".to_string()]),
+                turbo_enabled: false,
+                target_mode: 2,
+                append_keys: Some(SmallVec::from_vec(vec!["LSHIFT".to_string()])),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
 
         let mappings = AppState::create_input_mappings(&config).unwrap();
         let info = mappings
@@ -1457,20 +1442,19 @@ mod tests {
     /// `interval` (either the explicit override or the global fallback).
     #[test]
     fn test_mapping_hold_interval_inherits_mapping_interval() {
-        let mut config = AppConfig::default();
-        config.mappings = vec![KeyMapping {
-            trigger_key: "F10".to_string(),
-            target_keys: SmallVec::from_vec(vec!["A".to_string(), "B".to_string()]),
-            interval: Some(37),
-            event_duration: Some(5),
-            turbo_enabled: false,
-            move_speed: 5,
-            target_mode: 2,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: Some(SmallVec::from_vec(vec![1])),
-            append_keys: None,
-        }];
+        let config = AppConfig {
+            mappings: vec![KeyMapping {
+                trigger_key: "F10".to_string(),
+                target_keys: SmallVec::from_vec(vec!["A".to_string(), "B".to_string()]),
+                interval: Some(37),
+                event_duration: Some(5),
+                turbo_enabled: false,
+                target_mode: 2,
+                hold_indices: Some(SmallVec::from_vec(vec![1])),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
 
         let mappings = AppState::create_input_mappings(&config).unwrap();
         let info = mappings
@@ -1499,15 +1483,10 @@ mod tests {
         config.mappings = vec![KeyMapping {
             trigger_key: "F1".to_string(),
             target_keys: SmallVec::from_vec(keys),
-            interval: None,
-            event_duration: None,
             turbo_enabled: false,
-            move_speed: 5,
             target_mode: 2,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
             hold_indices: Some(SmallVec::from_vec(vec![0u8, 15u8])),
-            append_keys: None,
+            ..Default::default()
         }];
 
         let mappings = AppState::create_input_mappings(&config).unwrap();
@@ -1533,16 +1512,11 @@ mod tests {
         config.mappings = vec![KeyMapping {
             trigger_key: "F2".to_string(),
             target_keys: SmallVec::from_vec(keys),
-            interval: None,
-            event_duration: None,
             turbo_enabled: false,
-            move_speed: 5,
             target_mode: 2,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
             // idx 3 is legal. idx 16 / 42 / 255 are all illegal.
             hold_indices: Some(SmallVec::from_vec(vec![3u8, 16u8, 42u8, 255u8])),
-            append_keys: None,
+            ..Default::default()
         }];
 
         let mappings = AppState::create_input_mappings(&config).unwrap();
@@ -1561,24 +1535,21 @@ mod tests {
     /// construction; the remaining parsed entries keep their order.
     #[test]
     fn test_unparseable_append_names_are_skipped() {
-        let mut config = AppConfig::default();
-        config.mappings = vec![KeyMapping {
-            trigger_key: "C".to_string(),
-            target_keys: SmallVec::from_vec(vec!["A".to_string()]),
-            interval: None,
-            event_duration: None,
-            turbo_enabled: false,
-            move_speed: 5,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: Some(SmallVec::from_vec(vec![0])),
-            append_keys: Some(SmallVec::from_vec(vec![
-                "UP".to_string(),
-                "NOT_A_REAL_KEY".to_string(),
-                "LSHIFT".to_string(),
-            ])),
-        }];
+        let config = AppConfig {
+            mappings: vec![KeyMapping {
+                trigger_key: "C".to_string(),
+                target_keys: SmallVec::from_vec(vec!["A".to_string()]),
+                turbo_enabled: false,
+                hold_indices: Some(SmallVec::from_vec(vec![0])),
+                append_keys: Some(SmallVec::from_vec(vec![
+                    "UP".to_string(),
+                    "NOT_A_REAL_KEY".to_string(),
+                    "LSHIFT".to_string(),
+                ])),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
 
         let mappings = AppState::create_input_mappings(&config).unwrap();
         let info = mappings
@@ -1600,24 +1571,21 @@ mod tests {
     /// modifier-outlives-main-key semantics.
     #[test]
     fn test_append_order_preserved() {
-        let mut config = AppConfig::default();
-        config.mappings = vec![KeyMapping {
-            trigger_key: "D".to_string(),
-            target_keys: SmallVec::from_vec(vec!["A".to_string()]),
-            interval: None,
-            event_duration: None,
-            turbo_enabled: false,
-            move_speed: 5,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: Some(SmallVec::from_vec(vec![0])),
-            append_keys: Some(SmallVec::from_vec(vec![
-                "LSHIFT".to_string(),
-                "LCTRL".to_string(),
-                "LALT".to_string(),
-            ])),
-        }];
+        let config = AppConfig {
+            mappings: vec![KeyMapping {
+                trigger_key: "D".to_string(),
+                target_keys: SmallVec::from_vec(vec!["A".to_string()]),
+                turbo_enabled: false,
+                hold_indices: Some(SmallVec::from_vec(vec![0])),
+                append_keys: Some(SmallVec::from_vec(vec![
+                    "LSHIFT".to_string(),
+                    "LCTRL".to_string(),
+                    "LALT".to_string(),
+                ])),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
 
         let mappings = AppState::create_input_mappings(&config).unwrap();
         let info = mappings
@@ -1643,23 +1611,21 @@ mod tests {
     /// `input_name_to_output` without the speed rewrite.
     #[test]
     fn test_append_mouse_scroll_inherits_move_speed() {
-        let mut config = AppConfig::default();
-        config.mappings = vec![KeyMapping {
-            trigger_key: "E".to_string(),
-            target_keys: SmallVec::from_vec(vec!["A".to_string()]),
-            interval: None,
-            event_duration: None,
-            turbo_enabled: false,
-            move_speed: 13,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: Some(SmallVec::from_vec(vec![0])),
-            append_keys: Some(SmallVec::from_vec(vec![
-                "SCROLL_UP".to_string(),
-                "MOUSE_LEFT".to_string(),
-            ])),
-        }];
+        let config = AppConfig {
+            mappings: vec![KeyMapping {
+                trigger_key: "E".to_string(),
+                target_keys: SmallVec::from_vec(vec!["A".to_string()]),
+                turbo_enabled: false,
+                move_speed: 13,
+                hold_indices: Some(SmallVec::from_vec(vec![0])),
+                append_keys: Some(SmallVec::from_vec(vec![
+                    "SCROLL_UP".to_string(),
+                    "MOUSE_LEFT".to_string(),
+                ])),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
 
         let mappings = AppState::create_input_mappings(&config).unwrap();
         let info = mappings
@@ -1686,20 +1652,15 @@ mod tests {
     /// `SequentialActions`). Guards the "classic single-key" fast path.
     #[test]
     fn test_single_target_no_hold_keeps_single_variant() {
-        let mut config = AppConfig::default();
-        config.mappings = vec![KeyMapping {
-            trigger_key: "G".to_string(),
-            target_keys: SmallVec::from_vec(vec!["A".to_string()]),
-            interval: None,
-            event_duration: None,
-            turbo_enabled: false,
-            move_speed: 5,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        }];
+        let config = AppConfig {
+            mappings: vec![KeyMapping {
+                trigger_key: "G".to_string(),
+                target_keys: SmallVec::from_vec(vec!["A".to_string()]),
+                turbo_enabled: false,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
 
         let mappings = AppState::create_input_mappings(&config).unwrap();
         let info = mappings
@@ -1713,20 +1674,16 @@ mod tests {
     /// once and releases on trigger release.
     #[test]
     fn test_multi_target_no_hold_keeps_multiple_actions() {
-        let mut config = AppConfig::default();
-        config.mappings = vec![KeyMapping {
-            trigger_key: "H".to_string(),
-            target_keys: SmallVec::from_vec(vec!["A".to_string(), "B".to_string()]),
-            interval: None,
-            event_duration: None,
-            turbo_enabled: false,
-            move_speed: 5,
-            target_mode: 1,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        }];
+        let config = AppConfig {
+            mappings: vec![KeyMapping {
+                trigger_key: "H".to_string(),
+                target_keys: SmallVec::from_vec(vec!["A".to_string(), "B".to_string()]),
+                turbo_enabled: false,
+                target_mode: 1,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
 
         let mappings = AppState::create_input_mappings(&config).unwrap();
         let info = mappings
@@ -1850,21 +1807,18 @@ mod tests {
     /// minimum-clamp branch (a value below 5 gets raised to 5).
     #[test]
     fn test_mapping_hold_interval_uses_global_fallback() {
-        let mut config = AppConfig::default();
-        config.interval = 42;
-        config.mappings = vec![KeyMapping {
-            trigger_key: "F11".to_string(),
-            target_keys: SmallVec::from_vec(vec!["A".to_string(), "B".to_string()]),
-            interval: None,
-            event_duration: None,
-            turbo_enabled: false,
-            move_speed: 5,
-            target_mode: 2,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: Some(SmallVec::from_vec(vec![1])),
-            append_keys: None,
-        }];
+        let config = AppConfig {
+            interval: 42,
+            mappings: vec![KeyMapping {
+                trigger_key: "F11".to_string(),
+                target_keys: SmallVec::from_vec(vec!["A".to_string(), "B".to_string()]),
+                turbo_enabled: false,
+                target_mode: 2,
+                hold_indices: Some(SmallVec::from_vec(vec![1])),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
 
         let mappings = AppState::create_input_mappings(&config).unwrap();
         let info = mappings
@@ -1883,20 +1837,18 @@ mod tests {
     /// saturating the CPU on a held sequence mapping.
     #[test]
     fn test_mapping_hold_interval_clamps_to_minimum() {
-        let mut config = AppConfig::default();
-        config.mappings = vec![KeyMapping {
-            trigger_key: "F12".to_string(),
-            target_keys: SmallVec::from_vec(vec!["A".to_string(), "B".to_string()]),
-            interval: Some(1),
-            event_duration: None,
-            turbo_enabled: false,
-            move_speed: 5,
-            target_mode: 2,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: Some(SmallVec::from_vec(vec![1])),
-            append_keys: None,
-        }];
+        let config = AppConfig {
+            mappings: vec![KeyMapping {
+                trigger_key: "F12".to_string(),
+                target_keys: SmallVec::from_vec(vec!["A".to_string(), "B".to_string()]),
+                interval: Some(1),
+                turbo_enabled: false,
+                target_mode: 2,
+                hold_indices: Some(SmallVec::from_vec(vec![1])),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
 
         let mappings = AppState::create_input_mappings(&config).unwrap();
         let info = mappings
@@ -1917,21 +1869,19 @@ mod tests {
     /// traverses the `has_hold = true` branch of `has_rule_props`.
     #[test]
     fn test_out_of_range_hold_plus_append_builds_mapping_hold() {
-        let mut config = AppConfig::default();
-        config.mappings = vec![KeyMapping {
-            trigger_key: "Z".to_string(),
-            target_keys: SmallVec::from_vec(vec!["A".to_string(), "B".to_string()]),
-            interval: None,
-            event_duration: None,
-            turbo_enabled: false,
-            move_speed: 5,
-            target_mode: 1,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            // All indices out of range → effective hold_mask = 0.
-            hold_indices: Some(SmallVec::from_vec(vec![99, 100])),
-            append_keys: Some(SmallVec::from_vec(vec!["LSHIFT".to_string()])),
-        }];
+        let config = AppConfig {
+            mappings: vec![KeyMapping {
+                trigger_key: "Z".to_string(),
+                target_keys: SmallVec::from_vec(vec!["A".to_string(), "B".to_string()]),
+                turbo_enabled: false,
+                target_mode: 1,
+                // All indices out of range → effective hold_mask = 0.
+                hold_indices: Some(SmallVec::from_vec(vec![99, 100])),
+                append_keys: Some(SmallVec::from_vec(vec!["LSHIFT".to_string()])),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
 
         let mappings = AppState::create_input_mappings(&config).unwrap();
         let info = mappings
@@ -1947,4 +1897,2099 @@ mod tests {
             other => panic!("expected MappingHold, got {:?}", other),
         }
     }
+
+    // -----------------------------------------------------------------
+    // Output sink event-stream tests
+    // -----------------------------------------------------------------
+
+    /// Builds a default state wired to a recording sink so the test can
+    /// read back exactly what the simulation layer would have injected.
+    fn recording_state() -> (AppState, Arc<RecordingOutputSink>) {
+        let sink = Arc::new(RecordingOutputSink::new());
+        let state = AppState::with_output_sink(AppConfig::default(), sink.clone())
+            .expect("default AppState should build");
+        (state, sink)
+    }
+
+    /// A plain key cycle emits exactly one down and one up, and the
+    /// press half is delivered as its own batch.
+    #[test]
+    fn test_output_sink_records_single_key_cycle() {
+        let (state, sink) = recording_state();
+        let a_sc = vk_to_scancode(0x41);
+
+        state.simulate_action(OutputAction::KeyboardKey(a_sc), 0);
+
+        assert_eq!(
+            sink.events(),
+            vec![OutputEvent::KeyDown(a_sc), OutputEvent::KeyUp(a_sc)]
+        );
+        assert_eq!(sink.batch_sizes(), vec![1, 1]);
+    }
+
+    /// KeyCombo presses go out as one batch in declaration order and
+    /// release in reverse so the modifier outlives the main key.
+    #[test]
+    fn test_output_sink_combo_release_is_reversed() {
+        let (state, sink) = recording_state();
+        let ctrl_sc = vk_to_scancode(0xA2);
+        let c_sc = vk_to_scancode(0x43);
+        let combo = OutputAction::KeyCombo(Arc::from(vec![ctrl_sc, c_sc]));
+
+        state.simulate_action(combo, 0);
+
+        assert_eq!(
+            sink.events(),
+            vec![
+                OutputEvent::KeyDown(ctrl_sc),
+                OutputEvent::KeyDown(c_sc),
+                OutputEvent::KeyUp(c_sc),
+                OutputEvent::KeyUp(ctrl_sc),
+            ]
+        );
+        assert_eq!(sink.batch_sizes(), vec![2, 2]);
+    }
+
+    /// `SequentialActions` plays each step as its own full cycle, in
+    /// order, with no step left held afterwards.
+    #[test]
+    fn test_output_sink_sequential_actions_stream() {
+        let (state, sink) = recording_state();
+        let a_sc = vk_to_scancode(0x41);
+        let b_sc = vk_to_scancode(0x42);
+        let body: SmallVec<[OutputAction; 4]> = SmallVec::from_vec(vec![
+            OutputAction::KeyboardKey(a_sc),
+            OutputAction::MouseButton(MouseButton::Left),
+            OutputAction::KeyboardKey(b_sc),
+        ]);
+
+        state.simulate_action(OutputAction::SequentialActions(Arc::new(body), 0), 0);
+
+        assert_eq!(
+            sink.events(),
+            vec![
+                OutputEvent::KeyDown(a_sc),
+                OutputEvent::KeyUp(a_sc),
+                OutputEvent::MouseDown(MouseButton::Left),
+                OutputEvent::MouseUp(MouseButton::Left),
+                OutputEvent::KeyDown(b_sc),
+                OutputEvent::KeyUp(b_sc),
+            ]
+        );
+    }
+
+    /// Sequential `MappingHold`: non-held steps cycle, held steps and
+    /// the append list only press. The trigger release then lets go of
+    /// the held body first and the append modifier last.
+    #[test]
+    fn test_output_sink_mapping_hold_stream() {
+        let (state, sink) = recording_state();
+        let a_sc = vk_to_scancode(0x41);
+        let b_sc = vk_to_scancode(0x42);
+        let lshift_sc = vk_to_scancode(0xA0);
+        let body: SmallVec<[OutputAction; 4]> = SmallVec::from_vec(vec![
+            OutputAction::KeyboardKey(a_sc),
+            OutputAction::KeyboardKey(b_sc),
+        ]);
+        let append: SmallVec<[OutputAction; 4]> =
+            SmallVec::from_vec(vec![OutputAction::KeyboardKey(lshift_sc)]);
+        let action = OutputAction::MappingHold {
+            actions: Arc::new(body),
+            interval_ms: 0,
+            hold_mask: 0b10,
+            append: Arc::new(append),
+            sequential: true,
+        };
+
+        state.simulate_action(action.clone(), 0);
+        assert_eq!(
+            sink.take(),
+            vec![
+                OutputEvent::KeyDown(a_sc),
+                OutputEvent::KeyUp(a_sc),
+                OutputEvent::KeyDown(b_sc),
+                OutputEvent::KeyDown(lshift_sc),
+            ]
+        );
+
+        state.simulate_hold_repeat(&action);
+        assert_eq!(
+            sink.take(),
+            vec![OutputEvent::KeyDown(b_sc), OutputEvent::KeyDown(lshift_sc)]
+        );
+
+        state.simulate_hold_release(&action);
+        assert_eq!(
+            sink.take(),
+            vec![OutputEvent::KeyUp(b_sc), OutputEvent::KeyUp(lshift_sc)]
+        );
+    }
+
+    /// Two triggers sharing a key: the shared key goes down once, stays
+    /// down when the first trigger releases and only goes up when the
+    /// last holder lets go.
+    #[test]
+    fn test_output_sink_overlapping_triggers_share_key() {
+        let (state, sink) = recording_state();
+        let w_sc = vk_to_scancode(0x57);
+        let d_sc = vk_to_scancode(0x44);
+        let cardinal = OutputAction::KeyboardKey(w_sc);
+        let diagonal = OutputAction::KeyCombo(Arc::from(vec![w_sc, d_sc]));
+
+        state.simulate_initial_press(&cardinal);
+        state.simulate_initial_press(&diagonal);
+        state.simulate_release(&cardinal);
+        assert_eq!(
+            sink.take(),
+            vec![OutputEvent::KeyDown(w_sc), OutputEvent::KeyDown(d_sc)]
+        );
+
+        state.simulate_release(&diagonal);
+        assert_eq!(
+            sink.take(),
+            vec![OutputEvent::KeyUp(d_sc), OutputEvent::KeyUp(w_sc)]
+        );
+    }
+
+    /// `release_all_held_keys` emits one up per held primitive, however
+    /// many triggers were holding it, and leaves nothing to release on a
+    /// second call.
+    #[test]
+    fn test_output_sink_release_all_held_keys() {
+        let (state, sink) = recording_state();
+        let a_sc = vk_to_scancode(0x41);
+        let action = OutputAction::MultipleActions(Arc::new(SmallVec::from_vec(vec![
+            OutputAction::KeyboardKey(a_sc),
+            OutputAction::MouseButton(MouseButton::Right),
+        ])));
+
+        state.simulate_initial_press(&action);
+        state.simulate_initial_press(&action);
+        sink.clear();
+
+        state.release_all_held_keys();
+        assert_eq!(
+            sink.take(),
+            vec![
+                OutputEvent::KeyUp(a_sc),
+                OutputEvent::MouseUp(MouseButton::Right),
+            ]
+        );
+
+        state.release_all_held_keys();
+        assert!(sink.events().is_empty());
+    }
+
+    /// Movement and wheel outputs map to relative-move and signed wheel
+    /// events and never touch the hold ref counts.
+    #[test]
+    fn test_output_sink_move_and_scroll_events() {
+        let (state, sink) = recording_state();
+
+        state.simulate_action(OutputAction::MouseMove(MouseMoveDirection::UpLeft, 7), 0);
+        state.simulate_action(
+            OutputAction::MouseScroll(MouseScrollDirection::Down, 120),
+            0,
+        );
+
+        assert_eq!(
+            sink.events(),
+            vec![
+                OutputEvent::MouseMove { dx: -7, dy: -7 },
+                OutputEvent::Wheel(-120),
+            ]
+        );
+    }
+
+    /// Horizontal scrolls go to the tilt wheel, positive to the right,
+    /// and deltas below a notch pass through unchanged.
+    #[test]
+    fn test_output_sink_horizontal_and_fine_scroll() {
+        let (state, sink) = recording_state();
+
+        state.simulate_action(OutputAction::MouseScroll(MouseScrollDirection::Left, 30), 0);
+        state.simulate_action(
+            OutputAction::MouseScroll(MouseScrollDirection::Right, 120),
+            0,
+        );
+        state.simulate_action(OutputAction::MouseScroll(MouseScrollDirection::Up, 15), 0);
+
+        assert_eq!(
+            sink.events(),
+            vec![
+                OutputEvent::HWheel(-30),
+                OutputEvent::HWheel(120),
+                OutputEvent::Wheel(15),
+            ]
+        );
+    }
+
+    // -----------------------------------------------------------------
+    // Profile tests
+    // -----------------------------------------------------------------
+
+    fn simple_mapping(trigger: &str, target: &str) -> KeyMapping {
+        KeyMapping {
+            trigger_key: trigger.to_string(),
+            target_keys: SmallVec::from_vec(vec![target.to_string()]),
+            move_speed: 10,
+            ..Default::default()
+        }
+    }
+
+    /// Default profile maps Q; "game" maps A with its own interval and
+    /// whitelist; F9 / F10 cycle forward / back.
+    fn profile_config() -> AppConfig {
+        AppConfig {
+            next_profile_key: "F9".to_string(),
+            prev_profile_key: "F10".to_string(),
+            profiles: vec![Profile {
+                name: "game".to_string(),
+                mappings: vec![simple_mapping("A", "1")],
+                interval: Some(30),
+                event_duration: None,
+                process_list_mode: None,
+                process_whitelist: Some(vec!["game.exe".to_string()]),
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_switch_profile_replaces_mappings() {
+        let state = AppState::new(profile_config()).unwrap();
+        let q = InputDevice::Keyboard(0x51);
+        let a = InputDevice::Keyboard(0x41);
+
+        assert_eq!(state.active_profile_name(), "Default");
+        assert!(state.get_input_mapping(&q).is_some());
+        assert!(state.get_input_mapping(&a).is_none());
+
+        state.switch_profile("game").unwrap();
+        assert_eq!(state.active_profile_name(), "game");
+        assert!(state.get_input_mapping(&q).is_none());
+        // Profile interval is the default for its mappings.
+        assert_eq!(state.get_input_mapping(&a).unwrap().interval, 30);
+    }
+
+    #[test]
+    fn test_switch_to_unknown_profile_keeps_current() {
+        let state = AppState::new(profile_config()).unwrap();
+        assert!(state.switch_profile("missing").is_err());
+        assert_eq!(state.active_profile_name(), "Default");
+        assert!(
+            state
+                .get_input_mapping(&InputDevice::Keyboard(0x51))
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_active_profile_applies_at_startup() {
+        let mut config = profile_config();
+        config.active_profile = "game".to_string();
+        let state = AppState::new(config).unwrap();
+        assert_eq!(state.active_profile_name(), "game");
+        assert!(
+            state
+                .get_input_mapping(&InputDevice::Keyboard(0x41))
+                .is_some()
+        );
+
+        let mut config = profile_config();
+        config.active_profile = "gone".to_string();
+        let state = AppState::new(config).unwrap();
+        assert_eq!(state.active_profile_name(), "Default");
+    }
+
+    #[test]
+    fn test_profile_hotkeys_cycle_and_wrap() {
+        use crate::state::win32::{WM_KEYDOWN, WM_KEYUP};
+
+        let state = AppState::new(profile_config()).unwrap();
+        let press = |vk: u32| {
+            let blocked = state.handle_key_event(WM_KEYDOWN, vk);
+            state.handle_key_event(WM_KEYUP, vk);
+            blocked
+        };
+
+        assert!(press(0x78)); // F9
+        assert_eq!(state.active_profile_name(), "game");
+        assert!(press(0x78));
+        assert_eq!(state.active_profile_name(), "Default");
+        assert!(press(0x79)); // F10 wraps backwards
+        assert_eq!(state.active_profile_name(), "game");
+    }
+
+    #[test]
+    fn test_profile_switch_releases_held_keys() {
+        let sink = Arc::new(RecordingOutputSink::new());
+        let state = AppState::with_output_sink(profile_config(), sink.clone()).unwrap();
+        let sc = vk_to_scancode(0x51);
+        state.held_scancodes[sc as usize].store(1, Ordering::Release);
+
+        state.switch_profile("game").unwrap();
+
+        assert_eq!(sink.events(), vec![OutputEvent::KeyUp(sc)]);
+        assert_eq!(state.held_scancodes[sc as usize].load(Ordering::Acquire), 0);
+    }
+
+    #[test]
+    fn test_reload_config_keeps_profile_list() {
+        let state = AppState::new(AppConfig::default()).unwrap();
+        assert_eq!(state.profile_names(), vec!["Default".to_string()]);
+
+        let mut config = profile_config();
+        config.active_profile = "game".to_string();
+        state.reload_config(config).unwrap();
+        assert_eq!(
+            state.profile_names(),
+            vec!["Default".to_string(), "game".to_string()]
+        );
+        assert_eq!(state.active_profile_name(), "game");
+        assert!(
+            state
+                .get_input_mapping(&InputDevice::Keyboard(0x41))
+                .is_some()
+        );
+    }
+
+    /// Pins the cached foreground process so the rule engine sees `name`
+    /// for as long as the virtual clock does not move.
+    fn set_foreground(state: &AppState, clock: &VirtualClock, name: Option<&str>) {
+        let info = crate::state::ProcessInfo {
+            name: name.map(str::to_string),
+            path: name.map(|n| format!("c:\\apps\\{n}")),
+            timestamp: clock.now(),
+        };
+        let _ = state.cached_process_info.swap(
+            (Some(scc::Shared::new(info)), scc::Tag::None),
+            Ordering::Release,
+        );
+    }
+
+    fn rule_state() -> (AppState, Arc<RecordingOutputSink>, Arc<VirtualClock>) {
+        let mut config = profile_config();
+        config.profile_rules = vec![ProfileRule {
+            process: "Game.exe".to_string(),
+            profile: "game".to_string(),
+        }];
+        config.fallback_profile = "Default".to_string();
+        let sink = Arc::new(RecordingOutputSink::new());
+        let clock = Arc::new(VirtualClock::new());
+        let state = AppState::with_backends(config, sink.clone(), clock.clone()).unwrap();
+        (state, sink, clock)
+    }
+
+    #[test]
+    fn test_profile_rules_follow_foreground_process() {
+        let (state, sink, clock) = rule_state();
+        let sc = vk_to_scancode(0x51);
+        state.held_scancodes[sc as usize].store(1, Ordering::Release);
+
+        // The hook only notices the change; a worker switches.
+        set_foreground(&state, &clock, Some("game.exe"));
+        assert!(state.is_process_whitelisted());
+        assert_eq!(state.active_profile_name(), "Default");
+        assert!(sink.events().is_empty());
+        state.apply_pending_profile_switch();
+        assert_eq!(state.active_profile_name(), "game");
+        assert_eq!(sink.events(), vec![OutputEvent::KeyUp(sc)]);
+
+        set_foreground(&state, &clock, Some("notepad.exe"));
+        let _ = state.is_process_whitelisted();
+        state.apply_pending_profile_switch();
+        assert_eq!(state.active_profile_name(), "Default");
+    }
+
+    #[test]
+    fn test_profile_switch_goes_to_last_foreground_process() {
+        let (state, _sink, clock) = rule_state();
+
+        set_foreground(&state, &clock, Some("game.exe"));
+        let _ = state.is_process_whitelisted();
+        set_foreground(&state, &clock, Some("notepad.exe"));
+        let _ = state.is_process_whitelisted();
+        state.apply_pending_profile_switch();
+        assert_eq!(state.active_profile_name(), "Default");
+    }
+
+    #[test]
+    fn test_manual_switch_sticks_until_focus_moves() {
+        let (state, _sink, clock) = rule_state();
+
+        let tick = |state: &AppState| {
+            let _ = state.is_process_whitelisted();
+            state.apply_pending_profile_switch();
+        };
+
+        set_foreground(&state, &clock, Some("game.exe"));
+        tick(&state);
+        state.switch_profile("Default").unwrap();
+        tick(&state);
+        assert_eq!(state.active_profile_name(), "Default");
+
+        // An unreadable foreground process changes nothing either.
+        set_foreground(&state, &clock, None);
+        tick(&state);
+        assert_eq!(state.active_profile_name(), "Default");
+
+        set_foreground(&state, &clock, Some("notepad.exe"));
+        tick(&state);
+        set_foreground(&state, &clock, Some("game.exe"));
+        tick(&state);
+        assert_eq!(state.active_profile_name(), "game");
+    }
+
+    #[test]
+    fn test_process_blacklist_mode() {
+        let config = AppConfig {
+            process_list_mode: ProcessListMode::Blacklist,
+            process_whitelist: vec!["discord.exe".to_string(), "*launcher*.exe".to_string()],
+            ..Default::default()
+        };
+        let clock = Arc::new(VirtualClock::new());
+        let state =
+            AppState::with_backends(config, Arc::new(RecordingOutputSink::new()), clock.clone())
+                .unwrap();
+
+        set_foreground(&state, &clock, Some("discord.exe"));
+        assert!(!state.is_process_whitelisted());
+        set_foreground(&state, &clock, Some("steamlauncher.exe"));
+        assert!(!state.is_process_whitelisted());
+        set_foreground(&state, &clock, Some("game.exe"));
+        assert!(state.is_process_whitelisted());
+    }
+
+    #[test]
+    fn test_process_whitelist_full_path() {
+        let config = AppConfig {
+            process_whitelist: vec!["C:\\Apps\\game.exe".to_string()],
+            ..Default::default()
+        };
+        let clock = Arc::new(VirtualClock::new());
+        let state =
+            AppState::with_backends(config, Arc::new(RecordingOutputSink::new()), clock.clone())
+                .unwrap();
+
+        // `set_foreground` places every process under c:\apps.
+        set_foreground(&state, &clock, Some("game.exe"));
+        assert!(state.is_process_whitelisted());
+        set_foreground(&state, &clock, Some("other.exe"));
+        assert!(!state.is_process_whitelisted());
+    }
+
+    fn scoped_state() -> (AppState, Arc<RecordingOutputSink>, Arc<VirtualClock>) {
+        let mut game_only = simple_mapping("A", "1");
+        game_only.processes = Some(vec!["game.exe".to_string()]);
+        let mut not_in_editor = simple_mapping("B", "2");
+        not_in_editor.exclude_processes = Some(vec!["editor.exe".to_string()]);
+
+        let config = AppConfig {
+            mappings: vec![game_only, not_in_editor, simple_mapping("C", "3")],
+            ..Default::default()
+        };
+        let sink = Arc::new(RecordingOutputSink::new());
+        let clock = Arc::new(VirtualClock::new());
+        let state = AppState::with_backends(config, sink.clone(), clock.clone()).unwrap();
+        (state, sink, clock)
+    }
+
+    #[test]
+    fn test_mapping_process_scope_follows_foreground() {
+        let (state, _sink, clock) = scoped_state();
+        let a = InputDevice::Keyboard(0x41);
+        let b = InputDevice::Keyboard(0x42);
+        let c = InputDevice::Keyboard(0x43);
+
+        set_foreground(&state, &clock, Some("game.exe"));
+        assert!(state.get_input_mapping(&a).is_some());
+        assert!(state.get_input_mapping(&b).is_some());
+        assert!(state.get_input_mapping(&c).is_some());
+
+        set_foreground(&state, &clock, Some("editor.exe"));
+        assert!(state.get_input_mapping(&a).is_none());
+        assert!(state.get_input_mapping(&b).is_none());
+        assert!(state.get_input_mapping(&c).is_some());
+
+        // Unknown foreground process: scopes do not apply.
+        set_foreground(&state, &clock, None);
+        assert!(state.get_input_mapping(&a).is_some());
+    }
+
+    #[test]
+    fn test_focus_change_releases_keys_with_scoped_mappings() {
+        let (state, sink, clock) = scoped_state();
+        set_foreground(&state, &clock, Some("game.exe"));
+        assert!(state.is_process_whitelisted());
+
+        let sc = vk_to_scancode(0x31);
+        state.held_scancodes[sc as usize].store(1, Ordering::Release);
+        // Same process again: nothing is released.
+        assert!(state.is_process_whitelisted());
+        assert!(sink.events().is_empty());
+
+        set_foreground(&state, &clock, Some("editor.exe"));
+        assert!(state.is_process_whitelisted());
+        assert_eq!(sink.events(), vec![OutputEvent::KeyUp(sc)]);
+    }
+
+    /// Three mappings on A, tried caps-lock first (priority 1), then the
+    /// game.exe one, then the plain one. Each has its own interval so the
+    /// tests can tell them apart.
+    fn shared_trigger_state() -> (AppState, Arc<VirtualClock>) {
+        let mut plain = simple_mapping("A", "1");
+        plain.interval = Some(10);
+        let mut in_game = simple_mapping("A", "2");
+        in_game.interval = Some(20);
+        in_game.processes = Some(vec!["game.exe".to_string()]);
+        let mut caps = simple_mapping("A", "3");
+        caps.interval = Some(30);
+        caps.priority = 1;
+        caps.lock_keys = Some(LockKeys {
+            capslock: Some(true),
+            ..LockKeys::default()
+        });
+
+        let config = AppConfig {
+            mappings: vec![caps, plain, in_game],
+            ..Default::default()
+        };
+        let clock = Arc::new(VirtualClock::new());
+        let state =
+            AppState::with_backends(config, Arc::new(RecordingOutputSink::new()), clock.clone())
+                .unwrap();
+        (state, clock)
+    }
+
+    #[test]
+    fn test_shared_trigger_picks_first_matching_mapping() {
+        use crate::state::win32::{WM_KEYDOWN, WM_KEYUP};
+
+        let (state, clock) = shared_trigger_state();
+        let a = InputDevice::Keyboard(0x41);
+        let interval = |state: &AppState| state.get_input_mapping(&a).unwrap().interval;
+
+        set_foreground(&state, &clock, Some("notepad.exe"));
+        assert_eq!(interval(&state), 10);
+        set_foreground(&state, &clock, Some("game.exe"));
+        assert_eq!(interval(&state), 20);
+
+        state.track_lock_key(WM_KEYDOWN, 0x14);
+        state.track_lock_key(WM_KEYUP, 0x14);
+        assert_eq!(interval(&state), 30);
+        set_foreground(&state, &clock, Some("notepad.exe"));
+        assert_eq!(interval(&state), 30);
+    }
+
+    #[test]
+    fn test_lock_key_toggles_once_per_press() {
+        use crate::state::win32::{WM_KEYDOWN, WM_KEYUP};
+
+        let state =
+            AppState::with_output_sink(AppConfig::default(), Arc::new(RecordingOutputSink::new()))
+                .unwrap();
+        assert_eq!(state.lock_key_state(), 0);
+
+        // Auto-repeat keydowns while Caps Lock is held do not toggle.
+        state.track_lock_key(WM_KEYDOWN, 0x14);
+        state.track_lock_key(WM_KEYDOWN, 0x14);
+        state.track_lock_key(WM_KEYUP, 0x14);
+        state.track_lock_key(WM_KEYDOWN, 0x90);
+        state.track_lock_key(WM_KEYUP, 0x90);
+        assert_eq!(state.lock_key_state(), CAPS_LOCK | NUM_LOCK);
+
+        state.track_lock_key(WM_KEYDOWN, 0x14);
+        state.track_lock_key(WM_KEYUP, 0x14);
+        state.track_lock_key(WM_KEYDOWN, 0x41);
+        assert_eq!(state.lock_key_state(), NUM_LOCK);
+    }
+
+    #[test]
+    fn test_create_input_mappings_chains_shared_triggers() {
+        let mut later = simple_mapping("A", "2");
+        later.interval = Some(20);
+        let config = AppConfig {
+            mappings: vec![simple_mapping("A", "1"), later],
+            ..Default::default()
+        };
+
+        let mappings = AppState::create_input_mappings(&config).unwrap();
+        let head = mappings.get(&InputDevice::Keyboard(0x41)).unwrap();
+        // A plain duplicate still takes over, as it did before triggers
+        // could hold several mappings.
+        assert_eq!(head.interval, 20);
+        assert!(!head.is_conditional);
+        assert_eq!(head.candidates().count(), 2);
+
+        let (state, _clock) = shared_trigger_state();
+        let head = state
+            .input_mappings
+            .read_sync(&InputDevice::Keyboard(0x41), |_, v| v.clone())
+            .unwrap();
+        assert!(head.is_conditional);
+        let intervals: Vec<u64> = head.candidates().map(|c| c.interval).collect();
+        assert_eq!(intervals, [30, 20, 10]);
+    }
+
+    /// Collects dispatched events instead of running workers.
+    #[derive(Default)]
+    struct RecordingDispatcher(std::sync::Mutex<Vec<InputEvent>>);
+
+    impl EventDispatcher for RecordingDispatcher {
+        fn dispatch(&self, event: InputEvent) -> bool {
+            self.0.lock().unwrap().push(event);
+            true
+        }
+
+        fn dispatch_tap(&self, device: InputDevice) {
+            let mut events = self.0.lock().unwrap();
+            events.push(InputEvent::Pressed(device.clone()));
+            events.push(InputEvent::Released(device));
+        }
+
+        fn clear_cache(&self) {}
+    }
+
+    #[test]
+    fn test_wheel_tilt_taps_its_mapping() {
+        use crate::state::win32::{WM_MOUSEHWHEEL, WM_MOUSEWHEEL};
+
+        let config = AppConfig {
+            mappings: vec![simple_mapping("WHEEL_RIGHT", "TAB")],
+            ..Default::default()
+        };
+        let mappings = AppState::create_input_mappings(&config).unwrap();
+        let tilt = InputDevice::Wheel(MouseScrollDirection::Right);
+        // Each notch runs one whole cycle, even with turbo off.
+        let info = &mappings[&tilt];
+        assert!(info.turbo_enabled);
+        assert_eq!(
+            info.burst,
+            Some(Burst {
+                count: 1,
+                cancel_on_release: false,
+            })
+        );
+
+        let state = AppState::with_backends(
+            config,
+            Arc::new(RecordingOutputSink::new()),
+            Arc::new(VirtualClock::new()),
+        )
+        .unwrap();
+        let dispatcher = Arc::new(RecordingDispatcher::default());
+        state.set_worker_pool(dispatcher.clone());
+
+        // The delta sits in the high word of mouseData.
+        let notch = |delta: i16| (delta as u16 as u32) << 16;
+        assert!(state.handle_mouse_event(WM_MOUSEHWHEEL, notch(120), 0, 0));
+        assert_eq!(
+            std::mem::take(&mut *dispatcher.0.lock().unwrap()),
+            vec![
+                InputEvent::Pressed(tilt.clone()),
+                InputEvent::Released(tilt)
+            ]
+        );
+
+        // An unmapped tilt and the vertical wheel pass through.
+        assert!(!state.handle_mouse_event(WM_MOUSEHWHEEL, notch(-120), 0, 0));
+        assert!(!state.handle_mouse_event(WM_MOUSEWHEEL, notch(120), 0, 0));
+        assert!(dispatcher.0.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_wheel_notches_tap_their_mappings() {
+        use crate::state::win32::{WM_KEYDOWN, WM_KEYUP, WM_MOUSEWHEEL};
+
+        let config = AppConfig {
+            mappings: vec![
+                simple_mapping("WHEEL_DOWN", "1"),
+                simple_mapping("LCTRL+WHEEL_DOWN", "2"),
+                simple_mapping("LCTRL+LSHIFT+WHEEL_DOWN", "3"),
+            ],
+            ..Default::default()
+        };
+        let state = AppState::with_backends(
+            config,
+            Arc::new(RecordingOutputSink::new()),
+            Arc::new(VirtualClock::new()),
+        )
+        .unwrap();
+        let dispatcher = Arc::new(RecordingDispatcher::default());
+        state.set_worker_pool(dispatcher.clone());
+        let wheel =
+            |delta: i16| state.handle_mouse_event(WM_MOUSEWHEEL, (delta as u16 as u32) << 16, 0, 0);
+        let taps = || {
+            std::mem::take(&mut *dispatcher.0.lock().unwrap())
+                .into_iter()
+                .filter_map(|event| match event {
+                    InputEvent::Pressed(device) => Some(device),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        let down = InputDevice::Wheel(MouseScrollDirection::Down);
+
+        // One tap per notch, however many one message carries.
+        assert!(wheel(-240));
+        assert_eq!(taps(), [down.clone(), down.clone()]);
+
+        // Fractions add up to a notch and are blocked on the way.
+        assert!(wheel(-50));
+        assert!(wheel(-50));
+        assert!(taps().is_empty());
+        assert!(wheel(-50));
+        assert_eq!(taps(), std::slice::from_ref(&down));
+
+        // Turning back drops what was left; up has no mapping.
+        assert!(!wheel(60));
+        assert!(wheel(-100));
+        assert!(taps().is_empty());
+
+        // The combo with the most held keys wins.
+        state.handle_key_event(WM_KEYDOWN, 0xA2);
+        assert!(wheel(-120));
+        state.handle_key_event(WM_KEYDOWN, 0xA0);
+        assert!(wheel(-120));
+        assert_eq!(
+            taps(),
+            [
+                InputDevice::WheelCombo(vec![0xA2], MouseScrollDirection::Down),
+                InputDevice::WheelCombo(vec![0xA2, 0xA0], MouseScrollDirection::Down),
+            ]
+        );
+        state.handle_key_event(WM_KEYUP, 0xA0);
+        state.handle_key_event(WM_KEYUP, 0xA2);
+        let _ = taps();
+        assert!(wheel(-120));
+        assert_eq!(taps(), [down]);
+    }
+
+    #[test]
+    fn test_sequences_ending_with_same_key_keep_their_mappings() {
+        use crate::sequence_matcher::parse_sequence_string;
+        use crate::state::win32::{WM_KEYDOWN, WM_KEYUP};
+
+        let mut quarter_circle = simple_mapping("A", "1");
+        quarter_circle.interval = Some(10);
+        quarter_circle.trigger_sequence = Some("DOWN,RIGHT,A".to_string());
+        let mut reversed = simple_mapping("A", "2");
+        reversed.interval = Some(20);
+        reversed.trigger_sequence = Some("RIGHT,DOWN,A".to_string());
+        let mut plain = simple_mapping("A", "3");
+        plain.interval = Some(30);
+        let config = AppConfig {
+            mappings: vec![plain, quarter_circle, reversed],
+            ..Default::default()
+        };
+        let identity = |seq: &str| parse_sequence_string(seq, None).unwrap().identity();
+
+        let mappings = AppState::create_input_mappings(&config).unwrap();
+        let interval = |device: &InputDevice| mappings.get(device).map(|m| m.interval);
+        assert_eq!(interval(&identity("DOWN,RIGHT,A")), Some(10));
+        assert_eq!(interval(&identity("RIGHT,DOWN,A")), Some(20));
+        assert_eq!(interval(&InputDevice::Keyboard(0x41)), Some(30));
+        assert!(!mappings[&InputDevice::Keyboard(0x41)].is_sequence);
+        assert!(mappings[&InputDevice::Keyboard(0x28)].is_sequence);
+
+        let clock = Arc::new(VirtualClock::new());
+        let state =
+            AppState::with_backends(config, Arc::new(RecordingOutputSink::new()), clock.clone())
+                .unwrap();
+        let dispatcher = Arc::new(RecordingDispatcher::default());
+        state.set_worker_pool(dispatcher.clone());
+        let tap = |vk: u32| {
+            state.handle_key_event(WM_KEYDOWN, vk);
+            clock.advance(std::time::Duration::from_millis(50));
+            state.handle_key_event(WM_KEYUP, vk);
+            clock.advance(std::time::Duration::from_millis(50));
+        };
+
+        tap(0x27); // RIGHT
+        tap(0x28); // DOWN
+        tap(0x41); // A
+        let events = std::mem::take(&mut *dispatcher.0.lock().unwrap());
+        assert!(events.contains(&InputEvent::Pressed(identity("RIGHT,DOWN,A"))));
+        assert!(events.contains(&InputEvent::Released(identity("RIGHT,DOWN,A"))));
+        assert!(!events.contains(&InputEvent::Pressed(InputDevice::Keyboard(0x41))));
+
+        // A on its own still fires the plain mapping.
+        clock.advance(std::time::Duration::from_secs(1));
+        tap(0x41);
+        let events = std::mem::take(&mut *dispatcher.0.lock().unwrap());
+        assert_eq!(
+            events.first(),
+            Some(&InputEvent::Pressed(InputDevice::Keyboard(0x41)))
+        );
+    }
+
+    fn layer_switch(trigger: &str, layer: &str, mode: LayerMode) -> KeyMapping {
+        let mut mapping = simple_mapping(trigger, "1");
+        mapping.target_keys.clear();
+        mapping.activate_layer = Some(layer.to_string());
+        mapping.layer_mode = mode;
+        mapping
+    }
+
+    #[test]
+    fn test_held_layer_overrides_base_mapping() {
+        let caps = InputDevice::Keyboard(0x14);
+        let tab = InputDevice::Keyboard(0x09);
+        let a = InputDevice::Keyboard(0x41);
+        let mut combat = simple_mapping("A", "2");
+        combat.interval = Some(20);
+        combat.layer = Some("combat".to_string());
+        let mut base = simple_mapping("A", "1");
+        base.interval = Some(10);
+        let config = AppConfig {
+            mappings: vec![
+                layer_switch("CAPSLOCK", "combat", LayerMode::Hold),
+                layer_switch("TAB", "combat", LayerMode::Hold),
+                combat,
+                base,
+            ],
+            ..Default::default()
+        };
+        let state = AppState::new(config).unwrap();
+        let interval = || state.get_input_mapping(&a).map(|m| m.interval);
+
+        assert_eq!(interval(), Some(10));
+        assert!(state.active_layer_names().is_empty());
+        assert!(state.handle_layer_event(&InputEvent::Pressed(caps.clone())));
+        assert_eq!(interval(), Some(20));
+        assert_eq!(state.active_layer_names(), ["combat"]);
+
+        // Auto-repeat is swallowed and a second switch for the same layer
+        // keeps it on until both are released.
+        assert!(state.handle_layer_event(&InputEvent::Pressed(caps.clone())));
+        assert!(state.handle_layer_event(&InputEvent::Pressed(tab.clone())));
+        assert!(state.handle_layer_event(&InputEvent::Released(caps)));
+        assert_eq!(interval(), Some(20));
+        assert!(state.handle_layer_event(&InputEvent::Released(tab)));
+        assert_eq!(interval(), Some(10));
+
+        // Other triggers pass through to the workers.
+        assert!(!state.handle_layer_event(&InputEvent::Pressed(a.clone())));
+        assert!(!state.handle_layer_event(&InputEvent::Released(a)));
+    }
+
+    #[test]
+    fn test_toggled_layer_survives_release_until_reset() {
+        let f1 = InputDevice::Keyboard(0x70);
+        let a = InputDevice::Keyboard(0x41);
+        let mut menu = simple_mapping("A", "2");
+        menu.layer = Some("menu".to_string());
+        let config = AppConfig {
+            mappings: vec![layer_switch("F1", "menu", LayerMode::Toggle), menu],
+            ..Default::default()
+        };
+        let state = AppState::new(config).unwrap();
+        let tap = || {
+            assert!(state.handle_layer_event(&InputEvent::Pressed(f1.clone())));
+            assert!(state.handle_layer_event(&InputEvent::Pressed(f1.clone())));
+            assert!(state.handle_layer_event(&InputEvent::Released(f1.clone())));
+        };
+
+        assert!(state.get_input_mapping(&a).is_none());
+        tap();
+        assert!(state.get_input_mapping(&a).is_some());
+        assert_eq!(state.active_layers(), 1);
+        tap();
+        assert!(state.get_input_mapping(&a).is_none());
+        tap();
+        assert_eq!(state.active_layer_names(), ["menu"]);
+
+        state.reset_transient_state();
+        assert_eq!(state.active_layers(), 0);
+        assert!(state.get_input_mapping(&a).is_none());
+    }
+
+    /// Caps Lock taps Escape and holds Left Ctrl.
+    fn tap_hold_state(permissive: bool) -> (AppState, Arc<VirtualClock>, Arc<RecordingOutputSink>) {
+        let mut caps = simple_mapping("CAPSLOCK", "ESCAPE");
+        caps.hold_target_keys = Some(SmallVec::from_vec(vec!["LCTRL".to_string()]));
+        caps.permissive_hold = permissive;
+        let config = AppConfig {
+            mappings: vec![caps, simple_mapping("F1", "1")],
+            ..Default::default()
+        };
+        let clock = Arc::new(VirtualClock::new());
+        let sink = Arc::new(RecordingOutputSink::new());
+        let state = AppState::with_backends(config, sink.clone(), clock.clone()).unwrap();
+        (state, clock, sink)
+    }
+
+    #[test]
+    fn test_tap_hold_taps_on_quick_release() {
+        use crate::state::TapHoldOutcome;
+
+        let (state, clock, sink) = tap_hold_state(false);
+        let caps = InputDevice::Keyboard(0x14);
+        let mapping = state.get_input_mapping(&caps).unwrap();
+        assert!(mapping.turbo_enabled);
+        assert!(mapping.tap_hold.is_some());
+
+        assert_eq!(
+            state.handle_tap_hold_event(&InputEvent::Pressed(caps.clone())),
+            TapHoldOutcome::Consumed
+        );
+        clock.advance(Duration::from_millis(DEFAULT_TAPPING_TERM_MS - 1));
+        state.expire_tap_holds();
+        // Without permissive hold another key leaves the decision open.
+        assert_eq!(
+            state.handle_tap_hold_event(&InputEvent::Pressed(InputDevice::Keyboard(0x70))),
+            TapHoldOutcome::Forward
+        );
+        assert_eq!(
+            state.handle_tap_hold_event(&InputEvent::Released(caps)),
+            TapHoldOutcome::Tap
+        );
+        assert!(sink.events().is_empty());
+    }
+
+    #[test]
+    fn test_tap_hold_holds_past_tapping_term() {
+        use crate::state::TapHoldOutcome;
+
+        let (state, clock, sink) = tap_hold_state(false);
+        let caps = InputDevice::Keyboard(0x14);
+        let ctrl = vk_to_scancode(0xA2);
+
+        state.handle_tap_hold_event(&InputEvent::Pressed(caps.clone()));
+        clock.advance(Duration::from_millis(DEFAULT_TAPPING_TERM_MS));
+        state.expire_tap_holds();
+        state.expire_tap_holds();
+        assert_eq!(sink.take(), [OutputEvent::KeyDown(ctrl)]);
+
+        // Auto-repeat stays with the hold.
+        assert_eq!(
+            state.handle_tap_hold_event(&InputEvent::Pressed(caps.clone())),
+            TapHoldOutcome::Consumed
+        );
+        assert_eq!(
+            state.handle_tap_hold_event(&InputEvent::Released(caps)),
+            TapHoldOutcome::Consumed
+        );
+        assert_eq!(sink.take(), [OutputEvent::KeyUp(ctrl)]);
+    }
+
+    #[test]
+    fn test_permissive_hold_presses_hold_before_interrupting_input() {
+        use crate::state::TapHoldOutcome;
+
+        let (state, _clock, sink) = tap_hold_state(true);
+        let caps = InputDevice::Keyboard(0x14);
+        let ctrl = vk_to_scancode(0xA2);
+        let c = vk_to_scancode(0x43);
+
+        // An input the hooks let through is replayed after the hold.
+        state.handle_tap_hold_event(&InputEvent::Pressed(caps.clone()));
+        assert!(state.hold_before_passthrough(OutputEvent::KeyDown(c)));
+        assert!(!state.hold_before_passthrough(OutputEvent::KeyDown(c)));
+        assert_eq!(
+            sink.take(),
+            [OutputEvent::KeyDown(ctrl), OutputEvent::KeyDown(c)]
+        );
+        state.handle_tap_hold_event(&InputEvent::Released(caps.clone()));
+        assert_eq!(sink.take(), [OutputEvent::KeyUp(ctrl)]);
+
+        // A mapped input is dispatched as usual once the hold is down.
+        state.handle_tap_hold_event(&InputEvent::Pressed(caps.clone()));
+        assert_eq!(
+            state.handle_tap_hold_event(&InputEvent::Pressed(InputDevice::Keyboard(0x70))),
+            TapHoldOutcome::Forward
+        );
+        assert_eq!(sink.take(), [OutputEvent::KeyDown(ctrl)]);
+
+        state.reset_transient_state();
+        assert_eq!(
+            state.handle_tap_hold_event(&InputEvent::Released(caps)),
+            TapHoldOutcome::Forward
+        );
+    }
+
+    fn gesture_state(
+        gesture: TriggerKind,
+        single_tap: SingleTap,
+    ) -> (AppState, Arc<VirtualClock>, Arc<RecordingDispatcher>) {
+        let mut sprint = simple_mapping("W", "LSHIFT");
+        sprint.trigger_kind = gesture;
+        sprint.single_tap = single_tap;
+        let config = AppConfig {
+            mappings: vec![sprint],
+            ..Default::default()
+        };
+        let clock = Arc::new(VirtualClock::new());
+        let sink = Arc::new(RecordingOutputSink::new());
+        let state = AppState::with_backends(config, sink, clock.clone()).unwrap();
+        let dispatcher = Arc::new(RecordingDispatcher::default());
+        state.set_worker_pool(dispatcher.clone());
+        (state, clock, dispatcher)
+    }
+
+    #[test]
+    fn test_double_tap_fires_gesture_after_plain_press() {
+        use crate::state::GestureOutcome;
+
+        let (state, clock, _dispatcher) =
+            gesture_state(TriggerKind::DoubleTap, SingleTap::Immediate);
+        let w = InputDevice::Keyboard(0x57);
+        let sprint = InputDevice::Gesture(Arc::new(w.clone()), TriggerKind::DoubleTap);
+        // Without a mapping of its own W still reaches the gesture state,
+        // and its plain press goes to the OS untouched.
+        assert!(state.get_input_mapping(&w).unwrap().pass_through);
+
+        assert_eq!(
+            state.handle_gesture_event(&InputEvent::Pressed(w.clone())),
+            GestureOutcome::PassThrough
+        );
+        assert_eq!(
+            state.handle_gesture_event(&InputEvent::Released(w.clone())),
+            GestureOutcome::PassThrough
+        );
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(
+            state.handle_gesture_event(&InputEvent::Pressed(w.clone())),
+            GestureOutcome::Replace(InputEvent::Pressed(sprint.clone()))
+        );
+        // Auto-repeat and release follow the gesture.
+        assert_eq!(
+            state.handle_gesture_event(&InputEvent::Pressed(w.clone())),
+            GestureOutcome::Replace(InputEvent::Pressed(sprint.clone()))
+        );
+        assert_eq!(
+            state.handle_gesture_event(&InputEvent::Released(w.clone())),
+            GestureOutcome::Replace(InputEvent::Released(sprint))
+        );
+
+        // A press after the tap gap starts a new run.
+        clock.advance(Duration::from_secs(1));
+        state.expire_gestures();
+        assert_eq!(
+            state.handle_gesture_event(&InputEvent::Pressed(w)),
+            GestureOutcome::PassThrough
+        );
+    }
+
+    #[test]
+    fn test_gesture_input_with_own_mapping_forwards_plain_press() {
+        use crate::state::GestureOutcome;
+
+        let mut sprint = simple_mapping("W", "LSHIFT");
+        sprint.trigger_kind = TriggerKind::DoubleTap;
+        let config = AppConfig {
+            mappings: vec![sprint, simple_mapping("W", "UP")],
+            ..Default::default()
+        };
+        let state = AppState::with_backends(
+            config,
+            Arc::new(RecordingOutputSink::new()),
+            Arc::new(VirtualClock::new()),
+        )
+        .unwrap();
+        state.set_worker_pool(Arc::new(RecordingDispatcher::default()));
+        let w = InputDevice::Keyboard(0x57);
+
+        assert!(!state.get_input_mapping(&w).unwrap().pass_through);
+        assert_eq!(
+            state.handle_gesture_event(&InputEvent::Pressed(w.clone())),
+            GestureOutcome::Forward
+        );
+        assert_eq!(
+            state.handle_gesture_event(&InputEvent::Released(w)),
+            GestureOutcome::Forward
+        );
+    }
+
+    #[test]
+    fn test_deferred_press_held_past_tap_gap_is_replayed() {
+        use crate::config::DEFAULT_TAP_GAP_MS;
+        use crate::state::GestureOutcome;
+
+        let (state, clock, dispatcher) = gesture_state(TriggerKind::DoubleTap, SingleTap::Deferred);
+        let w = InputDevice::Keyboard(0x57);
+
+        assert_eq!(
+            state.handle_gesture_event(&InputEvent::Pressed(w.clone())),
+            GestureOutcome::Consumed
+        );
+        clock.advance(Duration::from_millis(DEFAULT_TAP_GAP_MS + 1));
+        state.expire_gestures();
+        // The held-back press is sent again, so it and its release go to
+        // a worker instead of the OS.
+        assert_eq!(
+            std::mem::take(&mut *dispatcher.0.lock().unwrap()),
+            [InputEvent::Pressed(w.clone())]
+        );
+        assert_eq!(
+            state.handle_gesture_event(&InputEvent::Pressed(w.clone())),
+            GestureOutcome::Forward
+        );
+        assert_eq!(
+            state.handle_gesture_event(&InputEvent::Released(w)),
+            GestureOutcome::Forward
+        );
+    }
+
+    #[test]
+    fn test_deferred_single_tap_waits_for_tap_gap() {
+        use crate::config::DEFAULT_TAP_GAP_MS;
+        use crate::state::GestureOutcome;
+
+        let (state, clock, dispatcher) = gesture_state(TriggerKind::DoubleTap, SingleTap::Deferred);
+        let w = InputDevice::Keyboard(0x57);
+
+        assert_eq!(
+            state.handle_gesture_event(&InputEvent::Pressed(w.clone())),
+            GestureOutcome::Consumed
+        );
+        assert_eq!(
+            state.handle_gesture_event(&InputEvent::Released(w.clone())),
+            GestureOutcome::Consumed
+        );
+        clock.advance(Duration::from_millis(DEFAULT_TAP_GAP_MS - 1));
+        state.expire_gestures();
+        assert!(dispatcher.0.lock().unwrap().is_empty());
+
+        clock.advance(Duration::from_millis(2));
+        state.expire_gestures();
+        assert_eq!(
+            std::mem::take(&mut *dispatcher.0.lock().unwrap()),
+            [InputEvent::Pressed(w.clone()), InputEvent::Released(w)]
+        );
+    }
+
+    #[test]
+    fn test_long_press_fires_after_hold_time() {
+        use crate::config::DEFAULT_LONG_PRESS_MS;
+        use crate::state::GestureOutcome;
+
+        let (state, clock, dispatcher) = gesture_state(TriggerKind::LongPress, SingleTap::Deferred);
+        let w = InputDevice::Keyboard(0x57);
+        let long_press = InputDevice::Gesture(Arc::new(w.clone()), TriggerKind::LongPress);
+
+        // Released early, the press is a plain tap.
+        state.handle_gesture_event(&InputEvent::Pressed(w.clone()));
+        clock.advance(Duration::from_millis(DEFAULT_LONG_PRESS_MS - 1));
+        state.expire_gestures();
+        assert_eq!(
+            state.handle_gesture_event(&InputEvent::Released(w.clone())),
+            GestureOutcome::Tap
+        );
+
+        clock.advance(Duration::from_secs(1));
+        state.expire_gestures();
+        assert_eq!(
+            state.handle_gesture_event(&InputEvent::Pressed(w.clone())),
+            GestureOutcome::Consumed
+        );
+        clock.advance(Duration::from_millis(DEFAULT_LONG_PRESS_MS));
+        state.expire_gestures();
+        state.expire_gestures();
+        assert_eq!(
+            std::mem::take(&mut *dispatcher.0.lock().unwrap()),
+            [InputEvent::Pressed(long_press.clone())]
+        );
+        assert_eq!(
+            state.handle_gesture_event(&InputEvent::Released(w)),
+            GestureOutcome::Replace(InputEvent::Released(long_press))
+        );
+    }
+
+    fn jitter(interval: u64, duration: u64, distribution: JitterDistribution) -> Option<Jitter> {
+        Some(Jitter {
+            interval,
+            duration,
+            distribution,
+        })
+    }
+
+    #[test]
+    fn test_jitter_draws_stay_in_range() {
+        let state = AppState::with_backends(
+            AppConfig::default(),
+            Arc::new(RecordingOutputSink::new()),
+            Arc::new(VirtualClock::new()),
+        )
+        .unwrap();
+        state.seed_jitter(42);
+
+        let mut spread = Vec::new();
+        for distribution in [JitterDistribution::Uniform, JitterDistribution::Normal] {
+            let draws: Vec<u64> = (0..2000)
+                .map(|_| state.jitter_interval(20, jitter(8, 0, distribution)))
+                .collect();
+            assert!(draws.iter().all(|d| (12..=28).contains(d)));
+            assert!(draws.contains(&12) && draws.contains(&28));
+            spread.push(draws.iter().map(|&d| d.abs_diff(20)).sum::<u64>());
+
+            // Draws below the minimums are clamped up to them.
+            let low = state.jitter_interval(6, jitter(8, 0, distribution));
+            assert!((MIN_INTERVAL_MS..=14).contains(&low));
+            let short = state.jitter_duration(2, jitter(0, 5, distribution));
+            assert!((MIN_EVENT_DURATION_MS..=7).contains(&short));
+        }
+        // Normal draws cluster closer to the configured time.
+        assert!(spread[1] < spread[0]);
+
+        assert_eq!(state.jitter_interval(20, None), 20);
+        assert_eq!(
+            state.jitter_duration(20, jitter(8, 0, JitterDistribution::Uniform)),
+            20
+        );
+    }
+
+    #[test]
+    fn test_jitter_seed_is_per_thread() {
+        let state = Arc::new(
+            AppState::with_backends(
+                AppConfig::default(),
+                Arc::new(RecordingOutputSink::new()),
+                Arc::new(VirtualClock::new()),
+            )
+            .unwrap(),
+        );
+        let draws = |state: &AppState| -> Vec<u64> {
+            (0..8)
+                .map(|_| state.jitter_interval(100, jitter(50, 0, JitterDistribution::Uniform)))
+                .collect()
+        };
+
+        state.seed_jitter(7);
+        let first = draws(&state);
+        state.seed_jitter(7);
+        // Another thread seeding and drawing leaves this one's run alone.
+        let other = Arc::clone(&state);
+        std::thread::spawn(move || {
+            other.seed_jitter(7);
+            draws(&other)
+        })
+        .join()
+        .unwrap();
+        assert_eq!(draws(&state), first);
+    }
+
+    #[test]
+    fn test_sequential_playback_jitter() {
+        let b = OutputAction::KeyboardKey(vk_to_scancode(0x42));
+        let c = OutputAction::KeyboardKey(vk_to_scancode(0x43));
+        let sequence =
+            OutputAction::SequentialActions(Arc::new(SmallVec::from_vec(vec![b, c])), 10);
+        let clock = Arc::new(VirtualClock::new());
+        let state = AppState::with_backends(
+            AppConfig::default(),
+            Arc::new(RecordingOutputSink::new()),
+            clock.clone(),
+        )
+        .unwrap();
+        state.seed_jitter(3);
+
+        let play = |jitter: Option<Jitter>| {
+            let start = clock.elapsed();
+            state.simulate_jittered_action(sequence.clone(), 4, jitter);
+            (clock.elapsed() - start).as_millis() as u64
+        };
+        assert_eq!(play(None), 18);
+        let lengths: Vec<u64> = (0..20)
+            .map(|_| play(jitter(3, 1, JitterDistribution::Uniform)))
+            .collect();
+        // Two holds of 3..=5 ms around one gap of 7..=13 ms.
+        assert!(
+            lengths.iter().all(|l| (13..=23).contains(l)),
+            "{:?}",
+            lengths
+        );
+        assert!(lengths.iter().any(|&l| l != lengths[0]), "{:?}", lengths);
+    }
+
+    #[test]
+    fn test_text_plan_strokes() {
+        let key = |vk: u32, shift: bool| TextStroke::Key {
+            scancode: vk_to_scancode(vk),
+            shift,
+        };
+        // A German layout: Z and Y swapped, `?` on Shift+ß, `@` on AltGr
+        // and so missing.
+        let keys = LayoutKeys::from([
+            ('z', (0x59, false)),
+            ('Z', (0x59, true)),
+            (',', (0xBC, false)),
+            (' ', (0x20, false)),
+            ('?', (0xDB, true)),
+        ]);
+        let plan = TextPlan::new("zZ, ?@\r\n\t你😀", 10);
+        assert_eq!(
+            plan.strokes(&keys),
+            [
+                key(0x59, false),
+                key(0x59, true),
+                key(0xBC, false),
+                key(0x20, false),
+                key(0xDB, true),
+                TextStroke::Unicode('@'),
+                key(0x0D, false),
+                key(0x09, false),
+                TextStroke::Unicode('你'),
+                TextStroke::Unicode('😀'),
+            ]
+        );
+        assert_eq!(plan.char_delay_ms, 10);
+        assert!(TextPlan::new("", 10).strokes(&keys).is_empty());
+        // Without a layout only Enter and Tab are keys.
+        assert_eq!(
+            TextPlan::new("a\n", 10).strokes(&LayoutKeys::new()),
+            [TextStroke::Unicode('a'), key(0x0D, false)]
+        );
+    }
+
+    #[test]
+    fn test_double_tap_types_text() {
+        use crate::state::GestureOutcome;
+
+        let mut greeting = simple_mapping("W", "A");
+        greeting.target_keys = SmallVec::new();
+        greeting.type_text = Some("gg".to_string());
+        greeting.trigger_kind = TriggerKind::DoubleTap;
+        let config = AppConfig {
+            mappings: vec![greeting],
+            ..Default::default()
+        };
+        let state = AppState::with_backends(
+            config,
+            Arc::new(RecordingOutputSink::new()),
+            Arc::new(VirtualClock::new()),
+        )
+        .unwrap();
+        state.set_worker_pool(Arc::new(RecordingDispatcher::default()));
+        let w = InputDevice::Keyboard(0x57);
+        let greeting = InputDevice::Gesture(Arc::new(w.clone()), TriggerKind::DoubleTap);
+
+        assert!(matches!(
+            state.get_input_mapping(&greeting).unwrap().target_action,
+            OutputAction::TypeText(_)
+        ));
+        let _ = state.handle_gesture_event(&InputEvent::Pressed(w.clone()));
+        let _ = state.handle_gesture_event(&InputEvent::Released(w.clone()));
+        assert_eq!(
+            state.handle_gesture_event(&InputEvent::Pressed(w)),
+            GestureOutcome::Replace(InputEvent::Pressed(greeting))
+        );
+    }
+
+    #[test]
+    fn test_type_text_mapping_types_once_per_press() {
+        let mut typed = simple_mapping("F1", "A");
+        typed.type_text = Some("hi".to_string());
+        let mut repeated = simple_mapping("F2", "A");
+        repeated.type_text = Some("hi".to_string());
+        repeated.burst_count = Some(3);
+        let config = AppConfig {
+            mappings: vec![typed, repeated],
+            ..Default::default()
+        };
+
+        let mappings = AppState::create_input_mappings(&config).unwrap();
+        let once = &mappings[&InputDevice::Keyboard(0x70)];
+        match &once.target_action {
+            OutputAction::TypeText(plan) => assert_eq!(&*plan.text, "hi"),
+            other => panic!("expected TypeText, got {:?}", other),
+        }
+        assert!(once.turbo_enabled);
+        assert_eq!(once.burst.map(|b| b.count), Some(1));
+        let thrice = &mappings[&InputDevice::Keyboard(0x71)];
+        assert_eq!(thrice.burst.map(|b| b.count), Some(3));
+    }
+
+    #[test]
+    fn test_simulate_text_events() {
+        let sink = Arc::new(RecordingOutputSink::new());
+        let clock = Arc::new(VirtualClock::new());
+        let state =
+            AppState::with_backends(AppConfig::default(), sink.clone(), clock.clone()).unwrap();
+        let h = vk_to_scancode(0x48);
+        let shift = vk_to_scancode(0xA0);
+
+        let keys = LayoutKeys::from([('H', (0x48, true))]);
+        state.simulate_text_with(&TextPlan::new("Hé😀", 15), &keys);
+
+        let (high, low) = (0xD83D, 0xDE00);
+        assert_eq!(
+            sink.events(),
+            [
+                OutputEvent::KeyDown(shift),
+                OutputEvent::KeyDown(h),
+                OutputEvent::KeyUp(h),
+                OutputEvent::KeyUp(shift),
+                OutputEvent::UnicodeDown(0xE9),
+                OutputEvent::UnicodeUp(0xE9),
+                OutputEvent::UnicodeDown(high),
+                OutputEvent::UnicodeUp(high),
+                OutputEvent::UnicodeDown(low),
+                OutputEvent::UnicodeUp(low),
+            ]
+        );
+        // One batch per character, with the delay only between them.
+        assert_eq!(sink.batch_sizes(), [4, 2, 4]);
+        assert_eq!(clock.elapsed(), Duration::from_millis(30));
+    }
+
+    #[test]
+    fn test_script_parse_ops() {
+        let script = Script::parse(
+            "# strafe\ndown LSHIFT\nrepeat 2 {\n  tap A; wait 35\n}\nUP lshift\nmove 10 -4\nscroll -120\n",
+        )
+        .unwrap();
+        assert_eq!(
+            &*script.ops,
+            [
+                ScriptOp::Down(0),
+                ScriptOp::Repeat { count: 2, end: 4 },
+                ScriptOp::Tap(1),
+                ScriptOp::Wait(35),
+                ScriptOp::EndRepeat { start: 1 },
+                ScriptOp::Up(0),
+                ScriptOp::Move { dx: 10, dy: -4 },
+                ScriptOp::Scroll(-120),
+            ]
+        );
+        // Names are looked up once, whatever their case.
+        assert_eq!(script.keys.len(), 2);
+        assert!(Script::parse("   \n# nothing\n").unwrap().ops.is_empty());
+    }
+
+    #[test]
+    fn test_script_parse_errors() {
+        let error = |source: &str| {
+            let err = Script::parse(source).unwrap_err();
+            (err.line, err.column)
+        };
+        assert_eq!(error("tap A\n  jump B"), (2, 3));
+        assert_eq!(error("tap NOT_A_KEY"), (1, 5));
+        assert_eq!(error("tap A B"), (1, 7));
+        assert_eq!(error("wait"), (1, 1));
+        assert_eq!(error("wait soon"), (1, 6));
+        assert_eq!(error("down MOUSE_UP"), (1, 6));
+        assert_eq!(error("repeat 0 { tap A }"), (1, 8));
+        assert_eq!(error("repeat 2 tap A"), (1, 10));
+        assert_eq!(error("repeat 2 {\ntap A"), (1, 10));
+        assert_eq!(error("tap A\n}"), (2, 1));
+
+        let err = Script::parse("tap A\nwait x").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2, column 6: \"x\" is not a valid number"
+        );
+    }
+
+    #[test]
+    fn test_long_press_runs_macro() {
+        use crate::state::GestureOutcome;
+
+        let mut combo = simple_mapping("E", "A");
+        combo.target_keys = SmallVec::new();
+        combo.macro_script = Some("tap Q; wait 20; tap R".to_string());
+        combo.trigger_kind = TriggerKind::LongPress;
+        combo.single_tap = SingleTap::Deferred;
+        let config = AppConfig {
+            mappings: vec![combo],
+            ..Default::default()
+        };
+        let clock = Arc::new(VirtualClock::new());
+        let state =
+            AppState::with_backends(config, Arc::new(RecordingOutputSink::new()), clock.clone())
+                .unwrap();
+        let dispatcher = Arc::new(RecordingDispatcher::default());
+        state.set_worker_pool(dispatcher.clone());
+        let e = InputDevice::Keyboard(0x45);
+        let combo = InputDevice::Gesture(Arc::new(e.clone()), TriggerKind::LongPress);
+
+        assert!(matches!(
+            state.get_input_mapping(&combo).unwrap().target_action,
+            OutputAction::Script(_)
+        ));
+        assert_eq!(
+            state.handle_gesture_event(&InputEvent::Pressed(e)),
+            GestureOutcome::Consumed
+        );
+        clock.advance(Duration::from_millis(crate::config::DEFAULT_LONG_PRESS_MS));
+        state.expire_gestures();
+        assert_eq!(
+            std::mem::take(&mut *dispatcher.0.lock().unwrap()),
+            [InputEvent::Pressed(combo)]
+        );
+    }
+
+    #[test]
+    fn test_script_rejects_holding_a_name_tapped_before() {
+        let err = Script::parse("tap SCROLL_UP\ndown scroll_up").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 6));
+        assert!(err.to_string().contains("cannot be held"), "{}", err);
+        // Keys may be tapped and then held as before.
+        assert!(Script::parse("tap A\ndown A\nup A").is_ok());
+    }
+
+    #[test]
+    fn test_simulate_script_events() {
+        let sink = Arc::new(RecordingOutputSink::new());
+        let clock = Arc::new(VirtualClock::new());
+        let state =
+            AppState::with_backends(AppConfig::default(), sink.clone(), clock.clone()).unwrap();
+        let shift = vk_to_scancode(0xA0);
+        let a = vk_to_scancode(0x41);
+        // LSHIFT is still down at the end, so the script releases it.
+        let script =
+            Script::parse("down LSHIFT\nrepeat 2 { tap A; wait 10 }\nmove 3 -2\nscroll 60")
+                .unwrap();
+
+        state.simulate_script(&script, 5, None);
+
+        assert_eq!(
+            sink.events(),
+            [
+                OutputEvent::KeyDown(shift),
+                OutputEvent::KeyDown(a),
+                OutputEvent::KeyUp(a),
+                OutputEvent::KeyDown(a),
+                OutputEvent::KeyUp(a),
+                OutputEvent::MouseMove { dx: 3, dy: -2 },
+                OutputEvent::Wheel(60),
+                OutputEvent::KeyUp(shift),
+            ]
+        );
+        assert_eq!(clock.elapsed(), Duration::from_millis(30));
+        assert_eq!(
+            state.held_scancodes[shift as usize].load(Ordering::Relaxed),
+            0
+        );
+    }
+
+    #[test]
+    fn test_macro_mapping_replaces_target_keys() {
+        let mut once = simple_mapping("F1", "A");
+        once.macro_script = Some("tap B".to_string());
+        once.turbo_enabled = false;
+        let mut looping = simple_mapping("F2", "A");
+        looping.macro_script = Some("tap B; wait 50".to_string());
+        looping.turbo_enabled = true;
+        let mut config = AppConfig {
+            mappings: vec![once, looping],
+            ..Default::default()
+        };
+
+        let mappings = AppState::create_input_mappings(&config).unwrap();
+        let once = &mappings[&InputDevice::Keyboard(0x70)];
+        assert!(matches!(once.target_action, OutputAction::Script(_)));
+        assert!(once.turbo_enabled);
+        assert_eq!(
+            once.burst,
+            Some(Burst {
+                count: 1,
+                cancel_on_release: true
+            })
+        );
+        let looping = &mappings[&InputDevice::Keyboard(0x71)];
+        assert!(looping.turbo_enabled);
+        assert!(looping.burst.is_none());
+
+        config.mappings[0].macro_script = Some("tap B\nwait".to_string());
+        assert!(AppState::create_input_mappings(&config).is_err());
+    }
+
+    /// Sequence steps with their own timing build a `TimedSequence`,
+    /// clamped like the mapping's own interval and duration, and play
+    /// each step with its hold and the wait after it.
+    #[test]
+    fn test_step_timings_build_timed_sequence() {
+        let mut mapping = simple_mapping("F1", "A");
+        mapping.target_keys = SmallVec::from_vec(vec![
+            "A@40/20".to_string(),
+            "B".to_string(),
+            "C@1/1".to_string(),
+        ]);
+        mapping.target_mode = 2;
+        mapping.interval = Some(30);
+        mapping.event_duration = Some(8);
+        let config = AppConfig {
+            mappings: vec![mapping, simple_mapping("F2", "A")],
+            ..Default::default()
+        };
+
+        let mappings = AppState::create_input_mappings(&config).unwrap();
+        let action = mappings[&InputDevice::Keyboard(0x70)].target_action.clone();
+        let OutputAction::TimedSequence(steps) = &action else {
+            panic!("expected a timed sequence, got {action:?}");
+        };
+        let timings: Vec<(u64, u64)> = steps
+            .iter()
+            .map(|step| (step.delay_ms, step.duration_ms))
+            .collect();
+        assert_eq!(timings, [(40, 20), (30, 8), (5, 2)]);
+        assert!(matches!(
+            mappings[&InputDevice::Keyboard(0x71)].target_action,
+            OutputAction::KeyboardKey(_)
+        ));
+
+        let sink = Arc::new(RecordingOutputSink::new());
+        let clock = Arc::new(VirtualClock::new());
+        let state =
+            AppState::with_backends(AppConfig::default(), sink.clone(), clock.clone()).unwrap();
+        let (a, b, c) = (
+            vk_to_scancode(0x41),
+            vk_to_scancode(0x42),
+            vk_to_scancode(0x43),
+        );
+        state.simulate_action(action, 8);
+
+        assert_eq!(
+            sink.events(),
+            [
+                OutputEvent::KeyDown(a),
+                OutputEvent::KeyUp(a),
+                OutputEvent::KeyDown(b),
+                OutputEvent::KeyUp(b),
+                OutputEvent::KeyDown(c),
+                OutputEvent::KeyUp(c),
+            ]
+        );
+        // 20 + 40, 8 + 30, then the last hold with no wait after it.
+        assert_eq!(clock.elapsed(), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_is_sequential_hold_action_classification() {
+        // Only MappingHold counts as sequential-hold. The turbo dispatch
+        // relies on this matrix to route MappingHold targets through
+        // simulate_hold_cycle instead of simulate_action.
+        let mapping_hold = OutputAction::MappingHold {
+            actions: Arc::new(SmallVec::new()),
+            interval_ms: 0,
+            hold_mask: 0,
+            append: Arc::new(SmallVec::new()),
+            sequential: false,
+        };
+        assert!(is_sequential_hold_action(&mapping_hold));
+
+        assert!(!is_sequential_hold_action(&OutputAction::KeyboardKey(0x41)));
+        assert!(!is_sequential_hold_action(&OutputAction::KeyCombo(
+            Arc::from([0x11u16, 0x41u16].as_slice())
+        )));
+        assert!(!is_sequential_hold_action(&OutputAction::MouseButton(
+            MouseButton::Left
+        )));
+        assert!(!is_sequential_hold_action(&OutputAction::MultipleActions(
+            Arc::new(SmallVec::new())
+        )));
+        assert!(!is_sequential_hold_action(
+            &OutputAction::SequentialActions(Arc::new(SmallVec::new()), 0)
+        ));
+    }
+
+    #[test]
+    fn test_new_turbo_propagates_sequential_hold() {
+        let now = Instant::now();
+        let mapping_hold = OutputAction::MappingHold {
+            actions: Arc::new(SmallVec::new()),
+            interval_ms: 0,
+            hold_mask: 0,
+            append: Arc::new(SmallVec::new()),
+            sequential: false,
+        };
+        let plain_key = OutputAction::KeyboardKey(0x41);
+
+        // Construct turbo state through the same field the worker reads.
+        // Compute the flag exactly as handle_input_event does.
+        let sim_held = DeviceSimState::new_turbo(
+            mapping_hold.clone(),
+            10,
+            5,
+            now,
+            is_sequential_hold_action(&mapping_hold),
+        );
+        assert!(
+            sim_held.is_sequential_hold,
+            "turbo entry for MappingHold must record the flag so recurring \
+             ticks route to simulate_hold_cycle"
+        );
+
+        let sim_plain = DeviceSimState::new_turbo(
+            plain_key.clone(),
+            10,
+            5,
+            now,
+            is_sequential_hold_action(&plain_key),
+        );
+        assert!(
+            !sim_plain.is_sequential_hold,
+            "non-MappingHold turbo targets must keep the flag false so \
+             recurring ticks stay on simulate_action"
+        );
+    }
+
+    #[test]
+    fn test_new_non_turbo_propagates_sequential_hold() {
+        let now = Instant::now();
+        let mapping_hold = OutputAction::MappingHold {
+            actions: Arc::new(SmallVec::new()),
+            interval_ms: 0,
+            hold_mask: 0,
+            append: Arc::new(SmallVec::new()),
+            sequential: false,
+        };
+        let sim =
+            DeviceSimState::new_non_turbo(mapping_hold, 10, 5, now, false, false, false, true);
+        assert!(sim.is_sequential_hold);
+    }
+
+    /// Output sink that stamps every event with the virtual clock's
+    /// elapsed time, so cadence tests can assert exact timings.
+    struct TimedSink {
+        clock: Arc<VirtualClock>,
+        events: std::sync::Mutex<Vec<(u64, OutputEvent)>>,
+    }
+
+    impl OutputSink for TimedSink {
+        fn send(&self, events: &[OutputEvent]) {
+            let at = self.clock.elapsed().as_millis() as u64;
+            let mut log = self.events.lock().unwrap();
+            for e in events {
+                log.push((at, *e));
+            }
+        }
+    }
+
+    fn timed_state(mapping: KeyMapping) -> (Arc<AppState>, Arc<VirtualClock>, Arc<TimedSink>) {
+        let clock = Arc::new(VirtualClock::new());
+        let sink = Arc::new(TimedSink {
+            clock: clock.clone(),
+            events: std::sync::Mutex::new(Vec::new()),
+        });
+        let config = AppConfig {
+            mappings: vec![mapping],
+            ..Default::default()
+        };
+        let state = Arc::new(AppState::with_backends(config, sink.clone(), clock.clone()).unwrap());
+        (state, clock, sink)
+    }
+
+    /// Turbo pacing: with interval=10 and event_duration=4 each cycle
+    /// presses at a multiple of 10 ms and releases 4 ms later, driven
+    /// only by `handle_timeout` ticks on a virtual clock.
+    #[test]
+    fn test_turbo_cadence_with_virtual_clock() {
+        let (state, clock, sink) = timed_state(KeyMapping {
+            trigger_key: "A".to_string(),
+            target_keys: SmallVec::from_vec(vec!["B".to_string()]),
+            interval: Some(10),
+            event_duration: Some(4),
+            move_speed: 10,
+            ..Default::default()
+        });
+        let b_sc = vk_to_scancode(0x42);
+
+        let mut worker = TurboWorker::new();
+        worker.handle_input_event(&state, InputEvent::Pressed(InputDevice::Keyboard(0x41)));
+        while clock.elapsed() < Duration::from_millis(35) {
+            clock.advance(Duration::from_millis(1));
+            worker.handle_timeout(&state);
+        }
+        worker.handle_input_event(&state, InputEvent::Released(InputDevice::Keyboard(0x41)));
+
+        let events = sink.events.lock().unwrap().clone();
+        let mut expected = Vec::new();
+        for start in [0u64, 10, 20, 30] {
+            expected.push((start, OutputEvent::KeyDown(b_sc)));
+            expected.push((start + 4, OutputEvent::KeyUp(b_sc)));
+        }
+        assert_eq!(events, expected);
+        assert!(worker.is_empty());
+    }
+
+    /// Non-turbo synthetic repeat for a non-keyboard trigger waits the
+    /// system repeat delay before the first repeat and then fires once
+    /// per repeat interval.
+    #[test]
+    fn test_synthetic_repeat_cadence_with_virtual_clock() {
+        let (state, clock, sink) = timed_state(KeyMapping {
+            trigger_key: "LBUTTON".to_string(),
+            target_keys: SmallVec::from_vec(vec!["B".to_string()]),
+            interval: Some(10),
+            event_duration: Some(4),
+            turbo_enabled: false,
+            move_speed: 10,
+            ..Default::default()
+        });
+        let b_sc = vk_to_scancode(0x42);
+        let delay = state.kb_repeat_delay_ms();
+        let interval = state.kb_repeat_interval_ms();
+
+        let device = InputDevice::Mouse(MouseButton::Left);
+        let mut worker = TurboWorker::new();
+        worker.handle_input_event(&state, InputEvent::Pressed(device.clone()));
+        let end = delay + interval * 2;
+        while clock.elapsed() < Duration::from_millis(end) {
+            clock.advance(Duration::from_millis(1));
+            worker.handle_timeout(&state);
+        }
+        worker.handle_input_event(&state, InputEvent::Released(device));
+
+        let events = sink.events.lock().unwrap().clone();
+        assert_eq!(
+            events,
+            vec![
+                (0, OutputEvent::KeyDown(b_sc)),
+                (delay, OutputEvent::KeyDown(b_sc)),
+                (delay + interval, OutputEvent::KeyDown(b_sc)),
+                (delay + interval * 2, OutputEvent::KeyDown(b_sc)),
+                (end, OutputEvent::KeyUp(b_sc)),
+            ]
+        );
+    }
+
+    /// A → B with interval 10 and event_duration 4, held to fire.
+    fn base_mapping(turbo_enabled: bool) -> KeyMapping {
+        KeyMapping {
+            trigger_key: "A".to_string(),
+            target_keys: SmallVec::from_vec(vec!["B".to_string()]),
+            interval: Some(10),
+            event_duration: Some(4),
+            turbo_enabled,
+            move_speed: 10,
+            ..Default::default()
+        }
+    }
+
+    fn toggle_mapping(turbo_enabled: bool, auto_stop_ms: Option<u64>) -> KeyMapping {
+        KeyMapping {
+            activation: Activation::Toggle,
+            auto_stop_ms,
+            ..base_mapping(turbo_enabled)
+        }
+    }
+
+    /// A toggled turbo mapping keeps firing after the trigger is released
+    /// and stops on the next press.
+    #[test]
+    fn test_toggle_activation_latches_until_next_press() {
+        let (state, clock, sink) = timed_state(toggle_mapping(true, None));
+        let b_sc = vk_to_scancode(0x42);
+        let trigger = InputDevice::Keyboard(0x41);
+        let mut worker = TurboWorker::new();
+        let tick_until = |worker: &mut TurboWorker, ms: u64| {
+            while clock.elapsed() < Duration::from_millis(ms) {
+                clock.advance(Duration::from_millis(1));
+                worker.handle_timeout(&state);
+            }
+        };
+
+        worker.handle_input_event(&state, InputEvent::Pressed(trigger.clone()));
+        worker.handle_input_event(&state, InputEvent::Released(trigger.clone()));
+        tick_until(&mut worker, 35);
+        worker.handle_input_event(&state, InputEvent::Pressed(trigger.clone()));
+        tick_until(&mut worker, 60);
+        worker.handle_input_event(&state, InputEvent::Released(trigger));
+
+        let events = sink.events.lock().unwrap().clone();
+        let mut expected = Vec::new();
+        for start in [0u64, 10, 20, 30] {
+            expected.push((start, OutputEvent::KeyDown(b_sc)));
+            expected.push((start + 4, OutputEvent::KeyUp(b_sc)));
+        }
+        assert_eq!(events, expected);
+        assert!(worker.is_empty());
+    }
+
+    /// A toggled hold is released by `auto_stop_ms` without another press.
+    #[test]
+    fn test_toggle_activation_auto_stops() {
+        let (state, clock, sink) = timed_state(toggle_mapping(false, Some(50)));
+        let b_sc = vk_to_scancode(0x42);
+        let trigger = InputDevice::Keyboard(0x41);
+        let mut worker = TurboWorker::new();
+
+        worker.handle_input_event(&state, InputEvent::Pressed(trigger.clone()));
+        worker.handle_input_event(&state, InputEvent::Released(trigger));
+        while clock.elapsed() < Duration::from_millis(80) {
+            clock.advance(Duration::from_millis(1));
+            worker.handle_timeout(&state);
+        }
+
+        let events = sink.events.lock().unwrap().clone();
+        assert_eq!(
+            events,
+            vec![
+                (0, OutputEvent::KeyDown(b_sc)),
+                (50, OutputEvent::KeyUp(b_sc))
+            ]
+        );
+        assert!(worker.is_empty());
+    }
+
+    fn burst_mapping(cancel_on_release: bool) -> KeyMapping {
+        KeyMapping {
+            burst_count: Some(3),
+            cancel_burst_on_release: cancel_on_release,
+            ..base_mapping(false)
+        }
+    }
+
+    /// A burst fires its shots at turbo cadence and stops while the
+    /// trigger is still held.
+    #[test]
+    fn test_burst_stops_while_held() {
+        let (state, clock, sink) = timed_state(burst_mapping(false));
+        let b_sc = vk_to_scancode(0x42);
+        let trigger = InputDevice::Keyboard(0x41);
+        let mut worker = TurboWorker::new();
+
+        worker.handle_input_event(&state, InputEvent::Pressed(trigger.clone()));
+        while clock.elapsed() < Duration::from_millis(80) {
+            clock.advance(Duration::from_millis(1));
+            // The trigger's own key-repeat keeps arriving.
+            worker.handle_input_event(&state, InputEvent::Pressed(trigger.clone()));
+            worker.handle_timeout(&state);
+        }
+        worker.handle_input_event(&state, InputEvent::Released(trigger));
+
+        let events = sink.events.lock().unwrap().clone();
+        let mut expected = Vec::new();
+        for start in [0u64, 10, 20] {
+            expected.push((start, OutputEvent::KeyDown(b_sc)));
+            expected.push((start + 4, OutputEvent::KeyUp(b_sc)));
+        }
+        assert_eq!(events, expected);
+        assert!(worker.is_empty());
+    }
+
+    /// Releasing the trigger early lets the burst finish unless
+    /// `cancel_burst_on_release` is set.
+    #[test]
+    fn test_burst_early_release() {
+        for (cancel_on_release, shots) in [(false, vec![0u64, 10, 20]), (true, vec![0])] {
+            let (state, clock, sink) = timed_state(burst_mapping(cancel_on_release));
+            let b_sc = vk_to_scancode(0x42);
+            let trigger = InputDevice::Keyboard(0x41);
+            let mut worker = TurboWorker::new();
+
+            worker.handle_input_event(&state, InputEvent::Pressed(trigger.clone()));
+            worker.handle_input_event(&state, InputEvent::Released(trigger));
+            while clock.elapsed() < Duration::from_millis(80) {
+                clock.advance(Duration::from_millis(1));
+                worker.handle_timeout(&state);
+            }
+
+            let events = sink.events.lock().unwrap().clone();
+            let mut expected = Vec::new();
+            for start in shots {
+                expected.push((start, OutputEvent::KeyDown(b_sc)));
+                expected.push((start + 4, OutputEvent::KeyUp(b_sc)));
+            }
+            assert_eq!(
+                events, expected,
+                "cancel_on_release = {}",
+                cancel_on_release
+            );
+            assert!(worker.is_empty());
+        }
+    }
+
+    fn script_mapping(source: &str) -> KeyMapping {
+        KeyMapping {
+            target_keys: SmallVec::new(),
+            macro_script: Some(source.to_string()),
+            ..base_mapping(false)
+        }
+    }
+
+    /// A script runs a stretch per wait on the worker's ticks, and
+    /// releasing the trigger stops it and releases what it holds.
+    #[test]
+    fn test_script_steps_and_cancels_on_release() {
+        let sc = vk_to_scancode;
+        let (shift, b, c) = (sc(0xA0), sc(0x42), sc(0x43));
+        for (release_at, expected) in [
+            (
+                10u64,
+                vec![
+                    (0u64, OutputEvent::KeyDown(shift)),
+                    (0, OutputEvent::KeyDown(b)),
+                    (4, OutputEvent::KeyUp(b)),
+                    (10, OutputEvent::KeyUp(shift)),
+                ],
+            ),
+            (
+                80,
+                vec![
+                    (0, OutputEvent::KeyDown(shift)),
+                    (0, OutputEvent::KeyDown(b)),
+                    (4, OutputEvent::KeyUp(b)),
+                    (20, OutputEvent::KeyDown(c)),
+                    (24, OutputEvent::KeyUp(c)),
+                    (24, OutputEvent::KeyUp(shift)),
+                ],
+            ),
+        ] {
+            let (state, clock, sink) = timed_state(script_mapping(
+                "down LSHIFT\ntap B\nwait 20\ntap C\nup LSHIFT",
+            ));
+            let trigger = InputDevice::Keyboard(0x41);
+            let mut worker = TurboWorker::new();
+
+            worker.handle_input_event(&state, InputEvent::Pressed(trigger.clone()));
+            while clock.elapsed() < Duration::from_millis(release_at) {
+                clock.advance(Duration::from_millis(1));
+                worker.handle_timeout(&state);
+            }
+            worker.handle_input_event(&state, InputEvent::Released(trigger));
+
+            let events = sink.events.lock().unwrap().clone();
+            assert_eq!(events, expected, "released at {}", release_at);
+            assert!(worker.is_empty());
+        }
+    }
+
+    /// Jittered turbo keeps every gap and hold inside the configured
+    /// spread, varies them, and repeats exactly under the same seed.
+    #[test]
+    fn test_turbo_jitter_with_seeded_rng() {
+        let run = |seed: u64| {
+            let (state, clock, sink) = timed_state(KeyMapping {
+                interval_jitter_ms: Some(3),
+                duration_jitter_ms: Some(1),
+                ..base_mapping(true)
+            });
+            state.seed_jitter(seed);
+            let trigger = InputDevice::Keyboard(0x41);
+            let mut worker = TurboWorker::new();
+
+            worker.handle_input_event(&state, InputEvent::Pressed(trigger.clone()));
+            while clock.elapsed() < Duration::from_millis(300) {
+                clock.advance(Duration::from_millis(1));
+                worker.handle_timeout(&state);
+            }
+            worker.handle_input_event(&state, InputEvent::Released(trigger));
+            sink.events.lock().unwrap().clone()
+        };
+
+        let b_sc = vk_to_scancode(0x42);
+        let events = run(7);
+        let downs: Vec<u64> = events
+            .iter()
+            .filter(|(_, e)| *e == OutputEvent::KeyDown(b_sc))
+            .map(|(at, _)| *at)
+            .collect();
+        let ups: Vec<u64> = events
+            .iter()
+            .filter(|(_, e)| *e == OutputEvent::KeyUp(b_sc))
+            .map(|(at, _)| *at)
+            .collect();
+        assert_eq!(downs.len(), ups.len());
+        for (down, up) in downs.iter().zip(&ups) {
+            assert!((3..=5).contains(&(up - down)), "hold {}", up - down);
+        }
+        let gaps: Vec<u64> = downs.windows(2).map(|w| w[1] - w[0]).collect();
+        assert!(gaps.iter().all(|gap| (7..=13).contains(gap)), "{:?}", gaps);
+        assert!(gaps.iter().any(|&gap| gap != gaps[0]), "{:?}", gaps);
+        assert_eq!(run(7), events);
+    }
 }
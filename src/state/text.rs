@@ -0,0 +1,119 @@
+//! Typing plans for `type_text` targets.
+//!
+//! A text is kept as written when the mappings are built and compiled into
+//! one [`TextStroke`] per character each time it is typed, against the
+//! keyboard layout of the window being typed into. Characters that layout
+//! has a key for become that key's scancode, with Shift around it where
+//! the layout needs it, so games that only read scancodes still see them.
+//! Everything else goes out as Unicode input. The compiling itself touches
+//! no OS API; the layout arrives as a [`LayoutKeys`] table.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use super::AppState;
+use super::output::OutputEvent;
+use super::parsing::vk_to_scancode;
+use super::win32;
+
+/// Scancode of the left Shift key.
+const LSHIFT_SCANCODE: u16 = 0x2A;
+
+/// Keys that type characters on one keyboard layout: the virtual key of
+/// each character and whether it needs Shift. Characters the table lacks
+/// are sent as Unicode.
+pub type LayoutKeys = HashMap<char, (u32, bool)>;
+
+/// How one character of a text is sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextStroke {
+    /// A key tap, with Shift held around it when `shift` is set.
+    Key { scancode: u16, shift: bool },
+    /// A character sent as Unicode input.
+    Unicode(char),
+}
+
+/// A text to type and the pace to type it at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextPlan {
+    pub text: Box<str>,
+    /// Wait between two characters, in milliseconds.
+    pub char_delay_ms: u64,
+}
+
+impl TextPlan {
+    pub fn new(text: &str, char_delay_ms: u64) -> Self {
+        // Windows line endings type a single Enter.
+        Self {
+            text: text.replace("\r\n", "\n").into(),
+            char_delay_ms,
+        }
+    }
+
+    /// Strokes that type the text on the layout `keys` describes.
+    pub fn strokes(&self, keys: &LayoutKeys) -> Vec<TextStroke> {
+        self.text.chars().map(|ch| text_stroke(ch, keys)).collect()
+    }
+}
+
+/// Stroke that types `ch` on the layout `keys` describes. Enter and Tab
+/// sit on the same keys on every layout.
+pub fn text_stroke(ch: char, keys: &LayoutKeys) -> TextStroke {
+    let key = match ch {
+        '\n' => Some((0x0D, false)),
+        '\t' => Some((0x09, false)),
+        _ => keys.get(&ch).copied(),
+    };
+    match key.map(|(vk, shift)| (vk_to_scancode(vk), shift)) {
+        Some((scancode, shift)) if scancode != 0 => TextStroke::Key { scancode, shift },
+        _ => TextStroke::Unicode(ch),
+    }
+}
+
+impl AppState {
+    /// Types `plan` into the foreground window, on that window's
+    /// keyboard layout.
+    pub fn simulate_text(&self, plan: &TextPlan) {
+        self.simulate_text_with(plan, &win32::layout_keys(&plan.text));
+    }
+
+    /// Types `plan` one character at a time on the layout `keys`
+    /// describes. Each character goes out as a single batch, so nothing
+    /// else lands between its press and its release.
+    pub fn simulate_text_with(&self, plan: &TextPlan, keys: &LayoutKeys) {
+        let strokes = plan.strokes(keys);
+        let last = strokes.len().saturating_sub(1);
+        for (idx, stroke) in strokes.iter().enumerate() {
+            match *stroke {
+                TextStroke::Key { scancode, shift } => {
+                    if shift {
+                        self.emit(&[
+                            OutputEvent::KeyDown(LSHIFT_SCANCODE),
+                            OutputEvent::KeyDown(scancode),
+                            OutputEvent::KeyUp(scancode),
+                            OutputEvent::KeyUp(LSHIFT_SCANCODE),
+                        ]);
+                    } else {
+                        self.emit(&[OutputEvent::KeyDown(scancode), OutputEvent::KeyUp(scancode)]);
+                    }
+                }
+                TextStroke::Unicode(ch) => {
+                    // Characters past the BMP take two UTF-16 units,
+                    // each pressed and released in turn.
+                    let mut units = [0u16; 2];
+                    let mut events = [OutputEvent::UnicodeDown(0); 4];
+                    let units = ch.encode_utf16(&mut units);
+                    for (i, &unit) in units.iter().enumerate() {
+                        events[i * 2] = OutputEvent::UnicodeDown(unit);
+                        events[i * 2 + 1] = OutputEvent::UnicodeUp(unit);
+                    }
+                    self.emit(&events[..units.len() * 2]);
+                }
+            }
+            if idx < last && plan.char_delay_ms > 0 {
+                self.clock()
+                    .sleep(Duration::from_millis(plan.char_delay_ms));
+            }
+        }
+    }
+}
@@ -8,6 +8,11 @@ use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use scc::{AtomicShared, Tag};
 use smallvec::SmallVec;
 
+use super::script::Script;
+use super::text::TextPlan;
+use crate::config::{JitterDistribution, LayerMode, LockKeys, ProcessScope, TriggerKind};
+use crate::sequence_matcher::MAX_SEQUENCE_LENGTH;
+
 /// HID device activation request information.
 #[derive(Debug, Clone)]
 pub struct HidActivationRequest {
@@ -21,7 +26,13 @@ pub struct HidActivationRequest {
 
 /// Trait for dispatching input events to worker threads.
 pub trait EventDispatcher: Send + Sync {
-    fn dispatch(&self, event: InputEvent);
+    /// Routes `event` to its worker. Returns false when the input should
+    /// reach the OS untouched instead, as the plain presses of a gesture
+    /// input with no mapping of its own do.
+    fn dispatch(&self, event: InputEvent) -> bool;
+    /// Sends a press and release of `device` for a plain press the
+    /// gesture state held back, without counting it again.
+    fn dispatch_tap(&self, device: InputDevice);
     /// Clear internal caches (called when configuration is reloaded)
     fn clear_cache(&self);
 }
@@ -47,6 +58,12 @@ pub enum InputDevice {
     Mouse(MouseButton),
     /// Mouse movement direction input (for sequence triggers)
     MouseMove(MouseMoveDirection),
+    /// One notch of the mouse wheel. Wheels have no release, so each
+    /// notch is pressed and released at once.
+    Wheel(MouseScrollDirection),
+    /// Wheel notch while keys are held, e.g. `LCTRL+WHEEL_UP`
+    /// Format: ([held_vk1, held_vk2, ...], direction)
+    WheelCombo(Vec<u32>, MouseScrollDirection),
     /// Key combination input (modifier keys + main key)
     /// Format: [modifier1, modifier2, ..., main_key]
     /// The last element is always the main key, others are modifiers
@@ -63,6 +80,31 @@ pub enum InputDevice {
         device_type: DeviceType,
         button_id: u64,
     },
+    /// A whole trigger sequence, in the order its inputs are pressed. The
+    /// sequence matcher reports matches with this identity and sequence
+    /// mappings are keyed by it, so sequences that end with the same
+    /// input stay distinct.
+    Sequence(SequenceInputs),
+    /// A multi-tap or long press of an input. The gesture state reports
+    /// recognized gestures with this identity and gesture mappings are
+    /// keyed by it, apart from the plain presses of the input.
+    Gesture(Arc<InputDevice>, TriggerKind),
+}
+
+/// Inputs of a trigger sequence, shared between the sequence matcher and
+/// the mapping table.
+pub type SequenceInputs = Arc<SmallVec<[InputDevice; MAX_SEQUENCE_LENGTH]>>;
+
+impl InputDevice {
+    /// Whether the trigger fires on a wheel notch, alone or as the last
+    /// input of a sequence.
+    pub fn is_wheel_trigger(&self) -> bool {
+        match self {
+            InputDevice::Wheel(_) | InputDevice::WheelCombo(..) => true,
+            InputDevice::Sequence(inputs) => inputs.last().is_some_and(Self::is_wheel_trigger),
+            _ => false,
+        }
+    }
 }
 
 impl std::fmt::Display for InputDevice {
@@ -94,6 +136,13 @@ impl std::fmt::Display for InputDevice {
                 };
                 write!(f, "{}", name)
             }
+            InputDevice::Wheel(direction) => write!(f, "{}", direction.wheel_name()),
+            InputDevice::WheelCombo(keys, direction) => {
+                for &vk in keys {
+                    write!(f, "{}+", super::parsing::vk_to_key_name(vk))?;
+                }
+                write!(f, "{}", direction.wheel_name())
+            }
             InputDevice::KeyCombo(keys) => {
                 for (i, &vk) in keys.iter().enumerate() {
                     if i > 0 {
@@ -125,11 +174,7 @@ impl std::fmt::Display for InputDevice {
                     if i > 0 {
                         write!(f, "+")?;
                     }
-                    write!(
-                        f,
-                        "{}",
-                        crate::xinput::XInputHandler::input_id_to_name(input_id)
-                    )?;
+                    write!(f, "{}", super::parsing::xinput_id_to_name(input_id))?;
                 }
 
                 Ok(())
@@ -142,8 +187,11 @@ impl std::fmt::Display for InputDevice {
                 let stable_device_id = (button_id >> 32) as u32;
                 let position = (button_id & 0xFFFFFFFF) as u32;
 
+                #[cfg(windows)]
                 let display_info_opt =
                     crate::rawinput::get_device_display_info(stable_device_id as u64);
+                #[cfg(not(windows))]
+                let display_info_opt: Option<DeviceDisplayInfo> = None;
 
                 let display_info = if let Some(info) = display_info_opt {
                     info
@@ -153,7 +201,7 @@ impl std::fmt::Display for InputDevice {
                         DeviceType::Joystick(vid) => *vid,
                         DeviceType::HidDevice { .. } => 0,
                     };
-                    crate::rawinput::DeviceDisplayInfo {
+                    DeviceDisplayInfo {
                         vendor_id: vid,
                         product_id: 0,
                         serial_number: None,
@@ -244,10 +292,28 @@ impl std::fmt::Display for InputDevice {
                     }
                 }
             }
+            InputDevice::Sequence(inputs) => {
+                for (i, input) in inputs.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", input)?;
+                }
+                Ok(())
+            }
+            InputDevice::Gesture(input, kind) => write!(f, "{}:{}", input, kind.as_str()),
         }
     }
 }
 
+/// Display information for HID devices.
+#[derive(Debug, Clone)]
+pub struct DeviceDisplayInfo {
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub serial_number: Option<String>,
+}
+
 /// Device type classification for generic input devices.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum DeviceType {
@@ -410,11 +476,26 @@ impl MouseMoveDirection {
     }
 }
 
-/// Mouse scroll direction
+/// Mouse scroll direction. `Left` and `Right` are the horizontal wheel,
+/// which tilting the wheel turns on most mice.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum MouseScrollDirection {
     Up,
     Down,
+    Left,
+    Right,
+}
+
+impl MouseScrollDirection {
+    /// Trigger name of a notch in this direction.
+    pub fn wheel_name(self) -> &'static str {
+        match self {
+            MouseScrollDirection::Up => "WHEEL_UP",
+            MouseScrollDirection::Down => "WHEEL_DOWN",
+            MouseScrollDirection::Left => "WHEEL_LEFT",
+            MouseScrollDirection::Right => "WHEEL_RIGHT",
+        }
+    }
 }
 
 /// Output action type for input mapping.
@@ -426,7 +507,8 @@ pub enum OutputAction {
     MouseButton(MouseButton),
     /// Mouse movement output (direction, speed in pixels per move)
     MouseMove(MouseMoveDirection, i32),
-    /// Mouse scroll output (direction, wheel delta)
+    /// Mouse scroll output (direction, wheel delta). 120 is one notch;
+    /// smaller deltas scroll smoothly on high-resolution aware windows.
     MouseScroll(MouseScrollDirection, i32),
     /// Key combination output (modifier scancodes + main key scancode)
     /// Format: [modifier1_scancode, modifier2_scancode, ..., main_key_scancode]
@@ -438,6 +520,10 @@ pub enum OutputAction {
     /// Sequential actions with interval between each action (for target sequence mode)
     /// (actions, interval_ms between each action)
     SequentialActions(Arc<SmallVec<[OutputAction; 4]>>, u64),
+    /// Sequential actions where each step waits and holds for its own
+    /// time (sequence targets with `step_delays_ms`, `step_durations_ms`
+    /// or inline `KEY@DELAY/DURATION` timings).
+    TimedSequence(Arc<[SequenceStep]>),
     /// Play a mapping body once, then keep a chosen subset of actions
     /// (plus any `append` actions) held until the trigger is released.
     /// Used for any mapping whose "rule properties" define a hold subset
@@ -471,6 +557,72 @@ pub enum OutputAction {
         /// between them. When false, press them simultaneously.
         sequential: bool,
     },
+    /// Switch a mapping layer instead of sending input. Consumed by the
+    /// dispatcher before the event reaches a worker.
+    SwitchLayer(LayerSwitch),
+    /// Type a text one character at a time (`type_text` mappings).
+    TypeText(Arc<TextPlan>),
+    /// Run a macro script (`macro` mappings). The turbo worker steps it
+    /// between waits; anywhere else it plays through at once.
+    Script(Arc<Script>),
+}
+
+/// One step of a [`OutputAction::TimedSequence`].
+#[derive(Debug, Clone)]
+pub struct SequenceStep {
+    pub action: OutputAction,
+    /// Wait after the step, in milliseconds. Unused on the last step.
+    pub delay_ms: u64,
+    /// How long the step is held, in milliseconds.
+    pub duration_ms: u64,
+}
+
+/// Layer switched by an `activate_layer` mapping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayerSwitch {
+    /// Bit of the layer in the active-layer masks.
+    pub layer: u64,
+    pub mode: LayerMode,
+}
+
+/// Hold side of a tap-hold mapping; the tap side is the mapping's
+/// `target_action`.
+#[derive(Debug)]
+pub struct TapHold {
+    /// Output held while the trigger is held.
+    pub hold_action: OutputAction,
+    /// How long the trigger must stay down to count as held, in
+    /// milliseconds.
+    pub tapping_term: u64,
+    /// Whether another input pressed before the tapping term ends picks
+    /// the hold.
+    pub permissive: bool,
+}
+
+/// Latched activation of a mapping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Toggle {
+    /// Milliseconds after which the output stops by itself.
+    pub auto_stop: Option<u64>,
+}
+
+/// Fixed number of output cycles a mapping sends per press.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Burst {
+    /// Output cycles per press.
+    pub count: u32,
+    /// Whether releasing the trigger stops an unfinished burst.
+    pub cancel_on_release: bool,
+}
+
+/// Random timing spread of a mapping's output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Jitter {
+    /// Largest change to an interval, in milliseconds.
+    pub interval: u64,
+    /// Largest change to a press duration, in milliseconds.
+    pub duration: u64,
+    pub distribution: JitterDistribution,
 }
 
 /// Configuration for a single input mapping.
@@ -486,6 +638,48 @@ pub struct InputMappingInfo {
     pub turbo_enabled: bool,
     /// Is this a sequence trigger (only triggered by sequence match)
     pub is_sequence: bool,
+    /// Processes the mapping is limited to; `None` for every process.
+    pub process_scope: Option<Arc<ProcessScope>>,
+    /// Lock-key states the mapping requires; `None` for any.
+    pub lock_keys: Option<LockKeys>,
+    /// Bit of the layer the mapping is limited to; 0 for none.
+    pub layer: u64,
+    /// Set for tap-hold mappings, which decide between tap and hold
+    /// before any output.
+    pub tap_hold: Option<Arc<TapHold>>,
+    /// Set for `activation = "toggle"` mappings, whose output runs from
+    /// one press of the trigger to the next.
+    pub toggle: Option<Toggle>,
+    /// Set for `burst_count` mappings, whose output stops after that many
+    /// cycles.
+    pub burst: Option<Burst>,
+    /// Set for mappings whose turbo intervals and press durations vary
+    /// at random.
+    pub jitter: Option<Jitter>,
+    /// Whether any mapping on the same trigger has conditions, so which
+    /// one fires can change from one press to the next.
+    pub is_conditional: bool,
+    /// Mapping tried next for the same trigger when this one's conditions
+    /// do not hold.
+    pub fallback: Option<Arc<InputMappingInfo>>,
+    /// Set for the stand-in mapping of a gesture input with no mapping of
+    /// its own: presses that go through reach the OS untouched, and
+    /// `target_action` only replays presses the gesture state held back.
+    pub pass_through: bool,
+}
+
+impl InputMappingInfo {
+    /// Whether the mapping only fires under some condition.
+    #[inline]
+    pub fn has_conditions(&self) -> bool {
+        self.layer != 0 || self.process_scope.is_some() || self.lock_keys.is_some()
+    }
+
+    /// This mapping followed by its fallbacks, in the order they are
+    /// tried.
+    pub fn candidates(&self) -> impl Iterator<Item = &InputMappingInfo> {
+        std::iter::successors(Some(self), |info| info.fallback.as_deref())
+    }
 }
 
 /// Cache for switch key detection with lock-free fast paths
@@ -0,0 +1,316 @@
+//! Win32 calls and constants used by the state.
+//!
+//! Everything the state needs from the OS goes through this module. Off
+//! Windows the hook message IDs are defined locally, the queries return
+//! neutral values and no output backend exists, so the state and its tests
+//! build and run on any host.
+
+#[cfg(windows)]
+use windows::Win32::Foundation::MAX_PATH;
+#[cfg(windows)]
+use windows::Win32::System::Threading::{
+    OpenProcess, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION, QueryFullProcessImageNameW,
+};
+#[cfg(windows)]
+use windows::Win32::UI::Input::KeyboardAndMouse::*;
+#[cfg(windows)]
+use windows::Win32::UI::WindowsAndMessaging::{
+    GetForegroundWindow, GetWindowThreadProcessId, SPI_GETKEYBOARDDELAY, SPI_GETKEYBOARDSPEED,
+    SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS, SystemParametersInfoW,
+};
+#[cfg(windows)]
+pub use windows::Win32::UI::WindowsAndMessaging::{
+    WM_KEYDOWN, WM_KEYUP, WM_LBUTTONDOWN, WM_LBUTTONUP, WM_MBUTTONDOWN, WM_MBUTTONUP,
+    WM_MOUSEHWHEEL, WM_MOUSEMOVE, WM_MOUSEWHEEL, WM_RBUTTONDOWN, WM_RBUTTONUP, WM_SYSKEYDOWN,
+    WM_SYSKEYUP, WM_XBUTTONDOWN, WM_XBUTTONUP,
+};
+#[cfg(windows)]
+use windows::core::PWSTR;
+
+#[cfg(windows)]
+use super::output::{OutputEvent, OutputSink};
+#[cfg(windows)]
+use super::text::LayoutKeys;
+#[cfg(windows)]
+use super::types::{MouseButton, SIMULATED_EVENT_MARKER};
+
+#[cfg(not(windows))]
+pub use fallback::*;
+
+/// Reads `SPI_GETKEYBOARDDELAY` and `SPI_GETKEYBOARDSPEED` into the given
+/// values. A failed call leaves its value untouched.
+#[cfg(windows)]
+pub(super) fn read_keyboard_repeat(delay_val: &mut u32, speed_val: &mut u32) {
+    // SAFETY: `delay_val` and `speed_val` are live `u32`s with no aliasing
+    // borrows for the duration of each FFI call.
+    unsafe {
+        let _ = SystemParametersInfoW(
+            SPI_GETKEYBOARDDELAY,
+            0,
+            Some(delay_val as *mut _ as *mut core::ffi::c_void),
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        );
+        let _ = SystemParametersInfoW(
+            SPI_GETKEYBOARDSPEED,
+            0,
+            Some(speed_val as *mut _ as *mut core::ffi::c_void),
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        );
+    }
+}
+
+/// Returns whether the lock key `vk` is toggled on.
+#[cfg(windows)]
+pub(super) fn lock_key_toggled(vk: u16) -> bool {
+    // SAFETY: GetKeyState only reads the calling thread's keyboard state.
+    let state = unsafe { GetKeyState(vk as i32) };
+    state & 0x0001 != 0
+}
+
+/// Returns the lowercased image path of the foreground window's process.
+#[cfg(windows)]
+pub(super) fn foreground_process_path() -> Option<String> {
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.0.is_null() {
+            return None;
+        }
+
+        let mut process_id: u32 = 0;
+        GetWindowThreadProcessId(hwnd, Some(&mut process_id as *mut u32));
+        if process_id == 0 {
+            return None;
+        }
+
+        let process_handle = match OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, process_id)
+        {
+            Ok(handle) => handle,
+            Err(_) => return None,
+        };
+
+        let mut buffer = [0u16; MAX_PATH as usize];
+        let mut size = buffer.len() as u32;
+
+        match QueryFullProcessImageNameW(
+            process_handle,
+            PROCESS_NAME_WIN32,
+            PWSTR(buffer.as_mut_ptr()),
+            &mut size,
+        ) {
+            Ok(_) => Some(String::from_utf16_lossy(&buffer[..size as usize]).to_lowercase()),
+            Err(_) => None,
+        }
+    }
+}
+
+/// Looks up the keys that type each character of `text` on the keyboard
+/// layout of the foreground window's thread. Characters that need more
+/// than Shift, such as AltGr ones, are left out.
+#[cfg(windows)]
+pub(super) fn layout_keys(text: &str) -> LayoutKeys {
+    // SAFETY: these calls only read window and layout state; a missing
+    // foreground window yields thread 0, the calling thread's layout.
+    let layout = unsafe {
+        let thread = GetWindowThreadProcessId(GetForegroundWindow(), None);
+        GetKeyboardLayout(thread)
+    };
+    let mut keys = LayoutKeys::new();
+    for ch in text.chars() {
+        // Characters past the BMP have no key on any layout.
+        let Ok(unit) = u16::try_from(ch as u32) else {
+            continue;
+        };
+        // SAFETY: VkKeyScanExW only reads the layout table.
+        let scan = unsafe { VkKeyScanExW(unit, layout) };
+        if scan == -1 {
+            continue;
+        }
+        // The high byte holds the modifiers: 1 Shift, 2 Ctrl, 4 Alt.
+        let [vk, modifiers] = (scan as u16).to_le_bytes();
+        if modifiers & !1 == 0 {
+            keys.insert(ch, (vk as u32, modifiers == 1));
+        }
+    }
+    keys
+}
+
+/// Production backend that forwards every batch to `SendInput`. All
+/// injected events carry `SIMULATED_EVENT_MARKER` so the hooks can tell
+/// them apart from physical input.
+#[cfg(windows)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Win32OutputSink;
+
+#[cfg(windows)]
+impl OutputSink for Win32OutputSink {
+    #[inline]
+    fn send(&self, events: &[OutputEvent]) {
+        let mut inputs: smallvec::SmallVec<[INPUT; 12]> = smallvec::SmallVec::new();
+        for event in events {
+            inputs.push(Self::build_input(*event));
+        }
+        if !inputs.is_empty() {
+            unsafe {
+                SendInput(&inputs, std::mem::size_of::<INPUT>() as i32);
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+impl Win32OutputSink {
+    #[inline(always)]
+    fn build_input(event: OutputEvent) -> INPUT {
+        match event {
+            OutputEvent::KeyDown(scancode) => Self::build_key_input(scancode, false),
+            OutputEvent::KeyUp(scancode) => Self::build_key_input(scancode, true),
+            OutputEvent::MouseDown(button) => Self::build_mouse_button_input(button, false),
+            OutputEvent::MouseUp(button) => Self::build_mouse_button_input(button, true),
+            OutputEvent::MouseMove { dx, dy } => {
+                Self::build_mouse_input(dx, dy, 0, MOUSEEVENTF_MOVE)
+            }
+            OutputEvent::Wheel(delta) => {
+                Self::build_mouse_input(0, 0, delta as u32, MOUSEEVENTF_WHEEL)
+            }
+            OutputEvent::HWheel(delta) => {
+                Self::build_mouse_input(0, 0, delta as u32, MOUSEEVENTF_HWHEEL)
+            }
+            OutputEvent::UnicodeDown(unit) => Self::build_unicode_input(unit, false),
+            OutputEvent::UnicodeUp(unit) => Self::build_unicode_input(unit, true),
+        }
+    }
+
+    #[inline(always)]
+    fn build_key_input(scancode: u16, up: bool) -> INPUT {
+        let mut flags = KEYEVENTF_SCANCODE;
+        if is_extended_scancode(scancode) {
+            flags |= KEYEVENTF_EXTENDEDKEY;
+        }
+        if up {
+            flags |= KEYEVENTF_KEYUP;
+        }
+        INPUT {
+            r#type: INPUT_KEYBOARD,
+            Anonymous: INPUT_0 {
+                ki: KEYBDINPUT {
+                    wVk: VIRTUAL_KEY(0),
+                    wScan: scancode,
+                    dwFlags: flags,
+                    time: 0,
+                    dwExtraInfo: SIMULATED_EVENT_MARKER,
+                },
+            },
+        }
+    }
+
+    #[inline(always)]
+    fn build_unicode_input(unit: u16, up: bool) -> INPUT {
+        let mut flags = KEYEVENTF_UNICODE;
+        if up {
+            flags |= KEYEVENTF_KEYUP;
+        }
+        INPUT {
+            r#type: INPUT_KEYBOARD,
+            Anonymous: INPUT_0 {
+                ki: KEYBDINPUT {
+                    wVk: VIRTUAL_KEY(0),
+                    wScan: unit,
+                    dwFlags: flags,
+                    time: 0,
+                    dwExtraInfo: SIMULATED_EVENT_MARKER,
+                },
+            },
+        }
+    }
+
+    #[inline(always)]
+    fn build_mouse_button_input(button: MouseButton, up: bool) -> INPUT {
+        let (down_flag, up_flag) = match button {
+            MouseButton::Left => (MOUSEEVENTF_LEFTDOWN, MOUSEEVENTF_LEFTUP),
+            MouseButton::Right => (MOUSEEVENTF_RIGHTDOWN, MOUSEEVENTF_RIGHTUP),
+            MouseButton::Middle => (MOUSEEVENTF_MIDDLEDOWN, MOUSEEVENTF_MIDDLEUP),
+            MouseButton::X1 | MouseButton::X2 => (MOUSEEVENTF_XDOWN, MOUSEEVENTF_XUP),
+        };
+        let mouse_data = match button {
+            MouseButton::X1 => 1u32,
+            MouseButton::X2 => 2u32,
+            _ => 0,
+        };
+        Self::build_mouse_input(0, 0, mouse_data, if up { up_flag } else { down_flag })
+    }
+
+    #[inline(always)]
+    fn build_mouse_input(dx: i32, dy: i32, mouse_data: u32, flags: MOUSE_EVENT_FLAGS) -> INPUT {
+        INPUT {
+            r#type: INPUT_MOUSE,
+            Anonymous: INPUT_0 {
+                mi: MOUSEINPUT {
+                    dx,
+                    dy,
+                    mouseData: mouse_data,
+                    dwFlags: flags,
+                    time: 0,
+                    dwExtraInfo: SIMULATED_EVENT_MARKER,
+                },
+            },
+        }
+    }
+}
+
+/// Returns whether `scancode` needs `KEYEVENTF_EXTENDEDKEY` (right-hand
+/// modifiers, navigation cluster, Windows keys).
+#[cfg(windows)]
+#[inline(always)]
+fn is_extended_scancode(scancode: u16) -> bool {
+    const EXTENDED_KEYS_BITMAP: u128 = (1u128 << 0x1D)
+        | (1u128 << 0x38)
+        | (1u128 << 0x47)
+        | (1u128 << 0x48)
+        | (1u128 << 0x49)
+        | (1u128 << 0x4B)
+        | (1u128 << 0x4D)
+        | (1u128 << 0x4F)
+        | (1u128 << 0x50)
+        | (1u128 << 0x51)
+        | (1u128 << 0x52)
+        | (1u128 << 0x53)
+        | (1u128 << 0x5B)
+        | (1u128 << 0x5C);
+
+    scancode < 128 && (EXTENDED_KEYS_BITMAP & (1u128 << scancode)) != 0
+}
+
+#[cfg(not(windows))]
+mod fallback {
+    use crate::state::text::LayoutKeys;
+
+    pub const WM_KEYDOWN: u32 = 0x0100;
+    pub const WM_KEYUP: u32 = 0x0101;
+    pub const WM_SYSKEYDOWN: u32 = 0x0104;
+    pub const WM_SYSKEYUP: u32 = 0x0105;
+    pub const WM_MOUSEMOVE: u32 = 0x0200;
+    pub const WM_LBUTTONDOWN: u32 = 0x0201;
+    pub const WM_LBUTTONUP: u32 = 0x0202;
+    pub const WM_RBUTTONDOWN: u32 = 0x0204;
+    pub const WM_RBUTTONUP: u32 = 0x0205;
+    pub const WM_MBUTTONDOWN: u32 = 0x0207;
+    pub const WM_MBUTTONUP: u32 = 0x0208;
+    pub const WM_MOUSEWHEEL: u32 = 0x020A;
+    pub const WM_XBUTTONDOWN: u32 = 0x020B;
+    pub const WM_XBUTTONUP: u32 = 0x020C;
+    pub const WM_MOUSEHWHEEL: u32 = 0x020E;
+
+    pub(in crate::state) fn read_keyboard_repeat(_delay_val: &mut u32, _speed_val: &mut u32) {}
+
+    pub(in crate::state) fn lock_key_toggled(_vk: u16) -> bool {
+        false
+    }
+
+    pub(in crate::state) fn foreground_process_path() -> Option<String> {
+        None
+    }
+
+    pub(in crate::state) fn layout_keys(_text: &str) -> LayoutKeys {
+        LayoutKeys::new()
+    }
+}
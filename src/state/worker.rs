@@ -0,0 +1,626 @@
+//! Turbo worker state.
+//!
+//! Each turbo worker thread owns a [`TurboWorker`] holding one entry per
+//! trigger it is driving, and feeds it the events the dispatcher routes
+//! there and a tick whenever its channel stays quiet. Everything here
+//! paces itself by the state's clock and sends through its output sink;
+//! the threads and channels around it belong to the keyboard hook.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use super::AppState;
+use super::script::ScriptRun;
+use super::types::*;
+use crate::util::{likely, unlikely};
+
+/// Per-device simulation state held by a turbo-worker's local cache.
+/// `synthetic_repeat` marks triggers that do not produce Windows key-repeat
+/// events such as mouse, XInput, and Raw Input. For those the worker drives
+/// its own repeat pump using `first_press` and the system keyboard-repeat
+/// settings. `is_holdable` is cached at insert time so the hot path skips
+/// rescanning `MultipleActions` on every Pressed event and synthetic-repeat
+/// tick. `is_sequential` marks targets that must fire a full `simulate_action`
+/// cycle per repeat tick rather than a `simulate_repeat_press`, so sequential
+/// targets loop their whole key chain on every Windows repeat.
+pub(super) struct DeviceSimState<A> {
+    last_time: Instant,
+    interval: u64,
+    event_duration: u64,
+    target_action: A,
+    turbo_enabled: bool,
+    first_press: Instant,
+    synthetic_repeat: bool,
+    is_holdable: bool,
+    is_sequential: bool,
+    /// True when the target is `OutputAction::MappingHold`. The first
+    /// press plays the whole sequence once and leaves the held subset
+    /// pressed; subsequent auto-repeat ticks drive `simulate_hold_repeat`
+    /// instead of replaying the sequence, and release drops the held set.
+    pub(super) is_sequential_hold: bool,
+    /// Set for `activation = "toggle"` entries, which outlive the
+    /// trigger's release.
+    latch: Option<Latch>,
+    /// When a latched output stops by itself.
+    auto_stop_at: Option<Instant>,
+    /// Set for `burst_count` entries, which stop after a fixed number of
+    /// shots.
+    burst: Option<BurstLeft>,
+    /// Random spread of the turbo gaps and press holds.
+    jitter: Option<Jitter>,
+    /// Gap before the next turbo shot: `interval`, redrawn with `jitter`
+    /// after every shot.
+    wait: u64,
+    /// Set for `macro` entries: how far the running script has got. A
+    /// shot runs it up to its next wait, and `wait` holds that wait.
+    script: Option<ScriptRun>,
+}
+
+/// How far a burst entry is through its shots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct BurstLeft {
+    /// Shots still to fire.
+    shots: u32,
+    /// Whether releasing the trigger drops the remaining shots.
+    cancel_on_release: bool,
+    /// The trigger is up; the entry goes once the last shot is out.
+    released: bool,
+}
+
+/// Where a latched entry is between its starting and stopping presses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Latch {
+    /// The starting press is still down.
+    Starting,
+    /// Running with the trigger up.
+    Latched,
+    /// The output is stopped; the entry goes once the trigger is up.
+    Stopping,
+}
+
+impl<A> DeviceSimState<A> {
+    /// Creates a turbo entry. Turbo entries never drive the synthetic
+    /// repeat pump because `simulate_action` already paces the output
+    /// cycle itself.
+    ///
+    /// `is_sequential_hold` must be set per-target so the worker's turbo
+    /// dispatch in `handle_input_event` and `handle_timeout` routes the
+    /// recurring tick to `simulate_hold_cycle` for `MappingHold` targets
+    /// rather than falling through to `simulate_action`. The latter
+    /// presses every body action without releasing the append list,
+    /// leaving the append refcount permanently above zero after the
+    /// second tick.
+    #[inline(always)]
+    pub(super) fn new_turbo(
+        target_action: A,
+        interval: u64,
+        event_duration: u64,
+        now: Instant,
+        is_sequential_hold: bool,
+    ) -> Self {
+        Self {
+            last_time: now,
+            interval,
+            event_duration,
+            target_action,
+            turbo_enabled: true,
+            first_press: now,
+            synthetic_repeat: false,
+            is_holdable: false,
+            is_sequential: false,
+            is_sequential_hold,
+            latch: None,
+            auto_stop_at: None,
+            burst: None,
+            jitter: None,
+            wait: interval,
+            script: None,
+        }
+    }
+
+    /// Creates a non-turbo entry. The caller must have already classified
+    /// the trigger and the target action.
+    #[inline(always)]
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn new_non_turbo(
+        target_action: A,
+        interval: u64,
+        event_duration: u64,
+        now: Instant,
+        synthetic_repeat: bool,
+        is_holdable: bool,
+        is_sequential: bool,
+        is_sequential_hold: bool,
+    ) -> Self {
+        Self {
+            last_time: now,
+            interval,
+            event_duration,
+            target_action,
+            turbo_enabled: false,
+            first_press: now,
+            synthetic_repeat,
+            is_holdable,
+            is_sequential,
+            is_sequential_hold,
+            latch: None,
+            auto_stop_at: None,
+            burst: None,
+            jitter: None,
+            wait: interval,
+            script: None,
+        }
+    }
+
+    /// Keeps the entry running past the release of the trigger, until
+    /// its next press or `auto_stop_at`.
+    #[inline(always)]
+    fn latched(mut self, auto_stop_at: Option<Instant>) -> Self {
+        self.latch = Some(Latch::Starting);
+        self.auto_stop_at = auto_stop_at;
+        self
+    }
+
+    /// Stops the entry after `count` shots, the first of which the
+    /// press that creates it fires.
+    #[inline(always)]
+    fn bursting(mut self, burst: Burst) -> Self {
+        self.burst = Some(BurstLeft {
+            shots: burst.count.saturating_sub(1),
+            cancel_on_release: burst.cancel_on_release,
+            released: false,
+        });
+        self
+    }
+
+    /// Varies the entry's turbo gaps and press holds by `jitter`, with
+    /// `wait` as the gap before its second shot.
+    #[inline(always)]
+    fn jittered(mut self, jitter: Option<Jitter>, wait: u64) -> Self {
+        self.jitter = jitter;
+        self.wait = wait;
+        self
+    }
+
+    /// Runs the entry's target as a script, one stretch between waits
+    /// per shot.
+    #[inline(always)]
+    fn scripted(mut self) -> Self {
+        self.script = Some(ScriptRun::default());
+        self
+    }
+
+    /// Whether the entry no longer produces output.
+    #[inline(always)]
+    fn is_stopped(&self) -> bool {
+        self.latch == Some(Latch::Stopping) || self.burst.is_some_and(|burst| burst.shots == 0)
+    }
+
+    /// Whether a burst fired its last shot after its trigger came up.
+    #[inline(always)]
+    fn is_spent(&self) -> bool {
+        self.burst
+            .is_some_and(|burst| burst.released && burst.shots == 0)
+    }
+}
+
+/// Returns true for output actions that have a meaningful held state:
+/// keyboard keys, key combos, mouse buttons, and recursive compositions of
+/// those. Movement, scroll, and sequential actions return false because
+/// `simulate_initial_press` cannot represent their held semantics.
+#[inline(always)]
+fn is_holdable_action(action: &OutputAction) -> bool {
+    match action {
+        OutputAction::KeyboardKey(_) | OutputAction::KeyCombo(_) | OutputAction::MouseButton(_) => {
+            true
+        }
+        OutputAction::MultipleActions(actions) => actions.iter().all(is_holdable_action),
+        _ => false,
+    }
+}
+
+/// Returns true for targets that must loop a full `simulate_action` pass
+/// on each repeat tick. Only a top-level `SequentialActions` or
+/// `TimedSequence` qualifies; nested uses inside `MultipleActions` are not
+/// supported because the worker can only drive one simulation cursor per
+/// device.
+#[inline(always)]
+fn is_sequential_action(action: &OutputAction) -> bool {
+    matches!(
+        action,
+        OutputAction::SequentialActions(..) | OutputAction::TimedSequence(_)
+    )
+}
+
+/// Returns true for targets that play the sequence once and then hold a
+/// chosen subset until release. Classified separately from
+/// `is_sequential_action` so the worker routes them to
+/// `simulate_hold_repeat` / `simulate_hold_release` instead of replaying
+/// the sequence on every auto-repeat tick.
+#[inline(always)]
+pub(super) fn is_sequential_hold_action(action: &OutputAction) -> bool {
+    matches!(action, OutputAction::MappingHold { .. })
+}
+
+/// Returns true for trigger devices that do not produce native Windows
+/// key-repeat events. Such triggers need a worker-driven synthetic
+/// repeat to emulate a held key in non-turbo mode. The match uses a
+/// positive whitelist so any future `InputDevice` variant forces a
+/// compile error here instead of silently inheriting synthetic repeat.
+#[inline(always)]
+fn needs_synthetic_repeat(device: &InputDevice) -> bool {
+    match device {
+        InputDevice::Keyboard(_) | InputDevice::KeyCombo(_) => false,
+        InputDevice::Mouse(_)
+        | InputDevice::MouseMove(_)
+        | InputDevice::XInputCombo { .. }
+        | InputDevice::GenericDevice { .. } => true,
+        // A notch is pressed and released at once, never held.
+        InputDevice::Wheel(_) | InputDevice::WheelCombo(..) => false,
+        // Held-key repeats come from the sequence's last input.
+        InputDevice::Sequence(inputs) => inputs.last().is_some_and(needs_synthetic_repeat),
+        InputDevice::Gesture(input, _) => needs_synthetic_repeat(input),
+    }
+}
+
+/// Triggers one turbo worker is driving, by device.
+#[derive(Default)]
+pub struct TurboWorker {
+    devices: HashMap<InputDevice, DeviceSimState<OutputAction>>,
+}
+
+impl TurboWorker {
+    pub fn new() -> Self {
+        Self {
+            devices: HashMap::with_capacity(16),
+        }
+    }
+
+    /// Whether no trigger is being driven.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.devices.is_empty()
+    }
+
+    /// Drops every entry without releasing anything, for when output
+    /// stops as a whole (pause, or a process outside the whitelist).
+    #[inline]
+    pub fn clear(&mut self) {
+        self.devices.clear();
+    }
+
+    /// Processes a single input event, applying turbo pacing or passing
+    /// through a press/release for non-turbo mappings.
+    #[inline]
+    pub fn handle_input_event(&mut self, state: &AppState, event: InputEvent) {
+        let device_states = &mut self.devices;
+        // Re-check the pause / whitelist state before firing any ref-counted
+        // press. Closes the narrow race where the handler thread toggled
+        // `is_paused` between its own top-level guard and `pool.dispatch`,
+        // letting a stale Pressed slip past early-return. Dropping the
+        // event here avoids `simulate_initial_press` leaking a ref count
+        // that no matching Released will arrive to balance.
+        if unlikely(state.is_paused() || !state.last_was_whitelisted()) {
+            return;
+        }
+
+        match event {
+            InputEvent::Pressed(device) => {
+                let now = state.clock().now();
+
+                if let Some(sim) = device_states.get_mut(&device) {
+                    if let Some(latch) = sim.latch {
+                        // A new press of a latched trigger stops it. The
+                        // workers' ticks drive a latched output, so the
+                        // auto-repeat of the starting or stopping press
+                        // changes nothing.
+                        if latch == Latch::Latched {
+                            release_output(state, sim);
+                            sim.latch = Some(Latch::Stopping);
+                        }
+                    } else if sim.burst.is_some() {
+                        // The workers' ticks pace a burst, and a finished
+                        // one waits for the trigger's release; the
+                        // auto-repeat of a held trigger adds nothing.
+                    } else if sim.turbo_enabled {
+                        if likely(
+                            now.duration_since(sim.last_time) >= Duration::from_millis(sim.wait),
+                        ) {
+                            fire_turbo(state, sim, now);
+                        }
+                    } else if !sim.synthetic_repeat {
+                        // Keyboard-like trigger rides Windows' own key-repeat
+                        // stream. Each arriving Pressed maps to one WM_KEYDOWN
+                        // delivery at Windows pacing.
+                        if sim.is_sequential_hold {
+                            // Sequence already played; refresh KEYDOWN on
+                            // the held subset so the OS auto-repeat stream
+                            // stays warm without re-triggering the body.
+                            state.simulate_hold_repeat(&sim.target_action);
+                            sim.last_time = now;
+                        } else if sim.is_sequential {
+                            // Sequential target runs a full press/release
+                            // cycle per tick so the whole key chain loops.
+                            state.simulate_jittered_action(
+                                sim.target_action.clone(),
+                                sim.event_duration,
+                                sim.jitter,
+                            );
+                            sim.last_time = now;
+                        } else if sim.is_holdable {
+                            // Ref-neutral repeat: the key is already held
+                            // (refcount >= 1 from the initial press), so we
+                            // only need to re-emit KEYDOWN to keep Windows'
+                            // auto-repeat stream alive.
+                            state.simulate_repeat_press(&sim.target_action);
+                            sim.last_time = now;
+                        }
+                        // Non-holdable non-sequential targets (MouseMove /
+                        // MouseScroll) have no repeat semantics; drop the tick.
+                    }
+                    // Non-keyboard triggers arrive at event-source rate which
+                    // is far faster than Windows key repeat. Fall through
+                    // without firing so handle_timeout can drive the synthetic
+                    // repeat at the real OS delay and rate.
+                } else if let Some(mapping) = state.get_input_mapping(&device) {
+                    let target_action_clone = mapping.target_action.clone();
+                    let turbo_enabled = mapping.turbo_enabled;
+                    let is_sequential = is_sequential_action(&mapping.target_action);
+                    let is_sequential_hold = is_sequential_hold_action(&mapping.target_action);
+                    let toggle = mapping.toggle;
+                    let burst = mapping.burst;
+                    let jitter = mapping.jitter;
+                    let is_script = matches!(mapping.target_action, OutputAction::Script(_));
+
+                    let sim = if turbo_enabled {
+                        DeviceSimState::new_turbo(
+                            mapping.target_action,
+                            mapping.interval,
+                            mapping.event_duration,
+                            now,
+                            is_sequential_hold,
+                        )
+                    } else {
+                        // A latched output outlives the trigger's own
+                        // key-repeat stream.
+                        let synthetic_repeat = toggle.is_some() || needs_synthetic_repeat(&device);
+                        let is_holdable = is_holdable_action(&mapping.target_action);
+                        DeviceSimState::new_non_turbo(
+                            mapping.target_action,
+                            mapping.interval,
+                            mapping.event_duration,
+                            now,
+                            synthetic_repeat,
+                            is_holdable,
+                            is_sequential,
+                            is_sequential_hold,
+                        )
+                    };
+                    let sim = match toggle {
+                        Some(toggle) => {
+                            sim.latched(toggle.auto_stop.map(|ms| now + Duration::from_millis(ms)))
+                        }
+                        None => sim,
+                    };
+                    let sim = match burst {
+                        Some(burst) => sim.bursting(burst),
+                        None => sim,
+                    };
+                    let sim = sim.jittered(jitter, state.jitter_interval(mapping.interval, jitter));
+                    if is_script {
+                        // The first stretch of the script runs now. A
+                        // burst counts whole runs, so a run still going
+                        // is not a shot yet.
+                        let mut sim = sim.scripted();
+                        if !fire_turbo(state, &mut sim, now)
+                            && let Some(burst) = sim.burst.as_mut()
+                        {
+                            burst.shots += 1;
+                        }
+                        device_states.insert(device, sim);
+                        return;
+                    }
+                    device_states.insert(device, sim);
+
+                    if turbo_enabled {
+                        if is_sequential_hold {
+                            // Turbo's first shot only needs to pulse the
+                            // sequence-properties subset; the full body
+                            // is not played at all in turbo mode.
+                            let hold = state.jitter_duration(mapping.event_duration, jitter);
+                            state.simulate_hold_cycle(&target_action_clone, hold);
+                        } else {
+                            state.simulate_jittered_action(
+                                target_action_clone,
+                                mapping.event_duration,
+                                jitter,
+                            );
+                        }
+                    } else if is_sequential || is_sequential_hold {
+                        // First fire of a sequential (loop or hold) target
+                        // runs the whole chain. For MappingHold, the
+                        // simulate_action arm leaves the held subset and
+                        // append list pressed for follow-up repeats.
+                        state.simulate_jittered_action(
+                            target_action_clone,
+                            mapping.event_duration,
+                            jitter,
+                        );
+                    } else {
+                        // Initial press increments per-key ref counts so
+                        // overlapping triggers (e.g. diagonal mouse-move
+                        // merge) can share a held key without racing.
+                        state.simulate_initial_press(&target_action_clone);
+                    }
+                }
+            }
+            InputEvent::Released(device) => {
+                if let Some(sim) = device_states.get_mut(&device) {
+                    match sim.latch {
+                        Some(Latch::Starting) => {
+                            sim.latch = Some(Latch::Latched);
+                            return;
+                        }
+                        Some(Latch::Latched) => return,
+                        Some(Latch::Stopping) => {}
+                        None => {
+                            if let Some(burst) = sim.burst.as_mut()
+                                && burst.shots > 0
+                                && !burst.cancel_on_release
+                            {
+                                // The rest of the burst still fires.
+                                burst.released = true;
+                                return;
+                            }
+                            release_output(state, sim);
+                        }
+                    }
+                }
+                device_states.remove(&device);
+            }
+            InputEvent::RetapHold(device) => {
+                // Same-direction re-arm: release the current hold, wait the
+                // mapping's interval, then press again. Polling-based games
+                // can observe the intermediate keyup so double-tap detection
+                // fires. Delay is per-mapping so the user tunes it together
+                // with turbo rate.
+                if let Some(sim) = device_states.get_mut(&device) {
+                    let delay_ms = sim.interval;
+                    let target = sim.target_action.clone();
+                    state.simulate_release(&target);
+                    state.clock().sleep(Duration::from_millis(delay_ms));
+                    // Re-seed the refcount-backed press so the target is
+                    // treated as freshly held after the retap window.
+                    state.simulate_initial_press(&target);
+                    let now = state.clock().now();
+                    sim.first_press = now;
+                    sim.last_time = now;
+                }
+                // If the device is not currently held, RetapHold is a no-op.
+            }
+        }
+    }
+
+    /// Fires pending turbo events and non-turbo synthetic repeats, stops
+    /// latched outputs whose auto-stop time has come, and drops bursts
+    /// that finished after their trigger was released.
+    #[inline]
+    pub fn handle_timeout(&mut self, state: &AppState) {
+        let device_states = &mut self.devices;
+        if unlikely(device_states.is_empty()) {
+            return;
+        }
+
+        let now = state.clock().now();
+        let repeat_delay = Duration::from_millis(state.kb_repeat_delay_ms());
+        let repeat_interval = Duration::from_millis(state.kb_repeat_interval_ms());
+
+        device_states.retain(|_, sim| {
+            if unlikely(sim.is_spent()) {
+                return false;
+            }
+            if unlikely(sim.auto_stop_at.is_some_and(|at| now >= at)) && !sim.is_stopped() {
+                release_output(state, sim);
+                // An entry whose trigger is still down stays until the
+                // release, so the trigger's auto-repeat cannot start it
+                // again.
+                if sim.latch == Some(Latch::Latched) {
+                    return false;
+                }
+                sim.latch = Some(Latch::Stopping);
+            }
+            true
+        });
+
+        for sim in device_states.values_mut() {
+            if sim.is_stopped() {
+                continue;
+            }
+            if sim.turbo_enabled {
+                if likely(now.duration_since(sim.last_time) >= Duration::from_millis(sim.wait))
+                    && fire_turbo(state, sim, now)
+                    && let Some(burst) = sim.burst.as_mut()
+                {
+                    burst.shots -= 1;
+                }
+            } else if sim.synthetic_repeat
+                && (sim.is_holdable || sim.is_sequential || sim.is_sequential_hold)
+                && now.duration_since(sim.first_press) >= repeat_delay
+                && now.duration_since(sim.last_time) >= repeat_interval
+            {
+                if sim.is_sequential_hold {
+                    // Drive the OS auto-repeat for the held subset without
+                    // replaying the sequence body.
+                    state.simulate_hold_repeat(&sim.target_action);
+                } else if sim.is_sequential {
+                    state.simulate_jittered_action(
+                        sim.target_action.clone(),
+                        sim.event_duration,
+                        sim.jitter,
+                    );
+                } else {
+                    // Synthetic repeat keeps the OS auto-repeat stream
+                    // warm. The key is already ref-counted from the
+                    // initial press, so only the KEYDOWN signal goes out.
+                    state.simulate_repeat_press(&sim.target_action);
+                }
+                sim.last_time = now;
+            }
+        }
+    }
+}
+
+/// Fires one turbo shot and draws the gap before the next. Returns
+/// false when the shot only ran a script up to one of its waits.
+#[inline]
+fn fire_turbo(state: &AppState, sim: &mut DeviceSimState<OutputAction>, now: Instant) -> bool {
+    if let Some(run) = sim.script.as_mut()
+        && let OutputAction::Script(script) = &sim.target_action
+    {
+        sim.last_time = now;
+        if let Some(wait) = state.step_script(script, run, sim.event_duration, sim.jitter) {
+            sim.wait = wait;
+            return false;
+        }
+        sim.wait = state.jitter_interval(sim.interval, sim.jitter);
+        return true;
+    }
+    if sim.is_sequential_hold {
+        // Turbo + MappingHold cycles only the user-chosen hold
+        // subset + append keys.
+        let hold = state.jitter_duration(sim.event_duration, sim.jitter);
+        state.simulate_hold_cycle(&sim.target_action, hold);
+    } else {
+        state.simulate_jittered_action(sim.target_action.clone(), sim.event_duration, sim.jitter);
+    }
+    sim.last_time = now;
+    sim.wait = state.jitter_interval(sim.interval, sim.jitter);
+    true
+}
+
+/// Releases what a non-turbo entry holds, and stops a running script.
+/// Other turbo entries release each shot as they fire it.
+#[inline]
+fn release_output(state: &AppState, sim: &mut DeviceSimState<OutputAction>) {
+    if let Some(run) = sim.script.as_mut()
+        && let OutputAction::Script(script) = &sim.target_action
+    {
+        state.cancel_script(script, run);
+        return;
+    }
+    if sim.turbo_enabled {
+        return;
+    }
+    if sim.is_sequential_hold {
+        // Drop the held subset + append list. Sequential
+        // body actions already self-balanced inside
+        // simulate_action, so only the pending hold set
+        // needs decrement + KEYUP.
+        state.simulate_hold_release(&sim.target_action);
+    } else if !sim.is_sequential {
+        // Pure SequentialActions self-balances inside each
+        // simulate_action pass, so no extra release is
+        // needed. Everything else decrements here.
+        state.simulate_release(&sim.target_action);
+    }
+}
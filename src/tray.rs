@@ -28,6 +28,10 @@ use crate::i18n::{CachedTranslations, Language};
 use crate::state::{NotificationEvent, get_global_state};
 
 const TRAY_MESSAGE_ID: u32 = WM_APP + 1;
+/// Command ID of the first entry in the Profiles submenu; entry `i` is
+/// `PROFILE_COMMAND_BASE + i`.
+const PROFILE_COMMAND_BASE: u16 = 1100;
+const MAX_PROFILE_ITEMS: u16 = 100;
 const AUMID: &str = "Sorahk.AutoKeyPress";
 
 /// Cache for UTF-16 encoded strings
@@ -78,6 +82,7 @@ pub struct TrayIcon {
     utf16_cache: Utf16Cache,
     translations: CachedTranslations,
     last_language: u8,
+    last_profile: String,
 }
 
 impl TrayIcon {
@@ -161,12 +166,10 @@ impl TrayIcon {
             utf16_cache: Utf16Cache::new(),
             translations,
             last_language: language_u8,
+            last_profile: state.active_profile_name(),
         };
 
-        let tooltip = instance.translations.app_title();
-        let tooltip_utf16 = instance.utf16_cache.encode_tooltip(tooltip);
-        let copy_len = tooltip_utf16.len().min(instance.nid.szTip.len());
-        instance.nid.szTip[..copy_len].copy_from_slice(&tooltip_utf16[..copy_len]);
+        instance.write_tooltip();
 
         unsafe {
             let _ = Shell_NotifyIconW(NIM_ADD, &instance.nid);
@@ -193,12 +196,38 @@ impl TrayIcon {
         }
     }
 
+    /// Refresh the tooltip after a profile switch
+    #[inline]
+    fn check_and_update_profile(&mut self) {
+        if let Some(state) = get_global_state() {
+            let current = state.active_profile_name();
+            if crate::util::unlikely(current != self.last_profile) {
+                self.last_profile = current;
+                let _ = self.update_tooltip();
+            }
+        }
+    }
+
+    /// Fill `szTip` with the app title, followed by the active profile
+    /// when more than one profile exists
+    fn write_tooltip(&mut self) {
+        let title = self.translations.app_title();
+        let tooltip = match get_global_state() {
+            Some(state) if state.profile_names().len() > 1 => {
+                format!("{} - {}", title, self.last_profile)
+            }
+            _ => title.to_string(),
+        };
+        let tooltip_utf16 = self.utf16_cache.encode_tooltip(&tooltip);
+        // Keep the terminating NUL when the text has to be cut short.
+        let copy_len = tooltip_utf16.len().min(self.nid.szTip.len() - 1);
+        self.nid.szTip[..copy_len].copy_from_slice(&tooltip_utf16[..copy_len]);
+        self.nid.szTip[copy_len] = 0;
+    }
+
     /// Update tray icon tooltip
     fn update_tooltip(&mut self) -> Result<()> {
-        let tooltip = self.translations.app_title();
-        let tooltip_utf16 = self.utf16_cache.encode_tooltip(tooltip);
-        let copy_len = tooltip_utf16.len().min(self.nid.szTip.len());
-        self.nid.szTip[..copy_len].copy_from_slice(&tooltip_utf16[..copy_len]);
+        self.write_tooltip();
 
         unsafe {
             let original_flags = self.nid.uFlags;
@@ -495,6 +524,7 @@ impl TrayIcon {
             if check_counter >= 10 {
                 check_counter = 0;
                 self.check_and_update_language();
+                self.check_and_update_profile();
             }
 
             while let Ok(event) = event_rx.try_recv() {
@@ -610,6 +640,17 @@ impl TrayIcon {
                     state.request_show_about();
                 }
                 1000 => state.exit(),
+                id if (PROFILE_COMMAND_BASE..PROFILE_COMMAND_BASE + MAX_PROFILE_ITEMS)
+                    .contains(&id) =>
+                {
+                    let names = state.profile_names();
+                    if let Some(name) = names.get(usize::from(id - PROFILE_COMMAND_BASE))
+                        && let Err(e) = state.switch_profile(name)
+                        && let Some(sender) = state.get_notification_sender()
+                    {
+                        let _ = sender.send(NotificationEvent::Error(e.to_string()));
+                    }
+                }
                 _ => {}
             }
         }
@@ -652,6 +693,42 @@ impl TrayIcon {
                 .collect();
 
             AppendMenuW(menu, MF_STRING, 1010, PCWSTR::from_raw(pause_text.as_ptr()))?;
+
+            let profiles = state.profile_names();
+            if profiles.len() > 1 {
+                let active = state.active_profile_name();
+                let submenu = CreatePopupMenu()?;
+                for (i, name) in profiles
+                    .iter()
+                    .take(usize::from(MAX_PROFILE_ITEMS))
+                    .enumerate()
+                {
+                    let text: SmallVec<[u16; 64]> =
+                        name.encode_utf16().chain(std::iter::once(0)).collect();
+                    let flags = if *name == active {
+                        MF_STRING | MF_CHECKED
+                    } else {
+                        MF_STRING
+                    };
+                    AppendMenuW(
+                        submenu,
+                        flags,
+                        usize::from(PROFILE_COMMAND_BASE) + i,
+                        PCWSTR::from_raw(text.as_ptr()),
+                    )?;
+                }
+                let profiles_text: SmallVec<[u16; 64]> = translations
+                    .tray_profiles()
+                    .encode_utf16()
+                    .chain(std::iter::once(0))
+                    .collect();
+                AppendMenuW(
+                    menu,
+                    MF_POPUP,
+                    submenu.0 as usize,
+                    PCWSTR::from_raw(profiles_text.as_ptr()),
+                )?;
+            }
             AppendMenuW(menu, MF_SEPARATOR, 0, PCWSTR::null())?;
             AppendMenuW(
                 menu,
@@ -67,6 +67,7 @@ pub fn likely(b: bool) -> bool {
 /// VK_INSERT or VK_END when NumLock is off. The capture dialog only sees
 /// VKs via `GetAsyncKeyState`, so it needs both directions to keep a
 /// numpad finalize key working regardless of NumLock state.
+#[cfg(windows)]
 pub mod numpad {
     use windows::Win32::UI::Input::KeyboardAndMouse::{
         VIRTUAL_KEY, VK_CLEAR, VK_DECIMAL, VK_DELETE, VK_DOWN, VK_END, VK_HOME, VK_INSERT, VK_LEFT,
@@ -129,7 +129,7 @@ impl XInputHandler {
 
                     // Register device display info
                     let stable_device_id = Self::hash_vid_pid_static(vid_pid) as u64;
-                    let display_info = crate::rawinput::DeviceDisplayInfo {
+                    let display_info = crate::state::DeviceDisplayInfo {
                         vendor_id: vid_pid.0,
                         product_id: vid_pid.1,
                         serial_number: None, // XInput does not provide serial numbers
@@ -357,17 +357,12 @@ impl XInputHandler {
 
         // If inputs unchanged, check if we need to continue sequence turbo
         if !inputs_changed {
-            use scc::Guard;
-            let guard = Guard::new();
-            let last_seq_device = state.last_sequence_device.load(Ordering::Acquire, &guard);
-
-            if let Some(seq_device) = last_seq_device.as_ref() {
-                // Check if it's an XInput sequence device
-                if matches!(seq_device, InputDevice::XInputCombo { device_type: dt, .. } if dt == &device_type)
-                {
-                    // Continue dispatching for turbo
-                    pool.dispatch(InputEvent::Pressed(seq_device.clone()));
-                }
+            // Check if the active sequence ends on this device
+            if let Some(seq_device) = state.active_sequence_ending(|last| {
+                matches!(last, InputDevice::XInputCombo { device_type: dt, .. } if dt == &device_type)
+            }) {
+                // Continue dispatching for turbo
+                pool.dispatch(InputEvent::Pressed(seq_device));
             }
             return;
         }
@@ -424,7 +419,7 @@ impl XInputHandler {
                     button_ids: combo.clone(),
                 };
 
-                let now = Instant::now();
+                let now = state.clock().now();
                 let sequence_match_result = state.record_and_match_sequence(device.clone(), now);
 
                 if let Some((matched_device, sequence_inputs)) = sequence_match_result {
@@ -490,39 +485,17 @@ impl XInputHandler {
                     button_ids: combo.clone(),
                 };
 
-                // Check if this device is part of an active sequence
-                use scc::Guard;
-                let guard = Guard::new();
-                let last_seq_inputs = state.last_sequence_inputs.load(Ordering::Acquire, &guard);
-
-                if let Some(seq_inputs) = last_seq_inputs.as_ref() {
-                    // Check if released device is the last input in the sequence
-                    if let Some(last_input) = seq_inputs.last() {
-                        let is_last_input = match (last_input, &device) {
-                            (
-                                InputDevice::XInputCombo {
-                                    device_type: dt1,
-                                    button_ids: b1,
-                                },
-                                InputDevice::XInputCombo {
-                                    device_type: dt2,
-                                    button_ids: b2,
-                                },
-                            ) => dt1 == dt2 && Self::combo_equals(b1.as_slice(), b2.as_slice()),
-                            _ => false,
-                        };
-
-                        if is_last_input {
-                            // Clear sequence state when last input is released
-                            use scc::Tag;
-                            let _ = state
-                                .last_sequence_device
-                                .swap((None, Tag::None), Ordering::Release);
-                            let _ = state
-                                .last_sequence_inputs
-                                .swap((None, Tag::None), Ordering::Release);
-                        }
-                    }
+                // Releasing the last input of the active sequence releases
+                // the sequence mapping too
+                let ended = state.end_sequence_on_release(|last| match last {
+                    InputDevice::XInputCombo {
+                        device_type: dt,
+                        button_ids,
+                    } => dt == &device_type && Self::combo_equals(button_ids, combo),
+                    _ => false,
+                });
+                if let Some(sequence) = ended {
+                    pool.dispatch(InputEvent::Released(sequence));
                 }
 
                 pool.dispatch(InputEvent::Released(device));
@@ -893,93 +866,6 @@ impl XInputHandler {
         )
     }
 
-    /// Converts input ID to readable button name.
-    #[inline(always)]
-    pub fn input_id_to_name(input_id: u32) -> &'static str {
-        match input_id {
-            // D-Pad
-            0x01 => "DPad_Up",
-            0x02 => "DPad_Down",
-            0x03 => "DPad_Left",
-            0x04 => "DPad_Right",
-            // Buttons
-            0x05 => "Start",
-            0x06 => "Back",
-            0x07 => "LS_Click",
-            0x08 => "RS_Click",
-            0x09 => "LB",
-            0x0A => "RB",
-            0x0B => "A",
-            0x0C => "B",
-            0x0D => "X",
-            0x0E => "Y",
-            // Left Stick
-            0x10 => "LS_Right",
-            0x11 => "LS_Left",
-            0x12 => "LS_Up",
-            0x13 => "LS_Down",
-            // Right Stick
-            0x14 => "RS_Right",
-            0x15 => "RS_Left",
-            0x16 => "RS_Up",
-            0x17 => "RS_Down",
-            // Triggers
-            0x18 => "LT",
-            0x19 => "RT",
-            _ => "Unknown",
-        }
-    }
-
-    /// Converts button name to input ID.
-    #[inline(always)]
-    pub fn name_to_input_id(name: &str) -> Option<u32> {
-        match name {
-            // D-Pad
-            "DPad_Up" | "DPAD_UP" => Some(0x01),
-            "DPad_Down" | "DPAD_DOWN" => Some(0x02),
-            "DPad_Left" | "DPAD_LEFT" => Some(0x03),
-            "DPad_Right" | "DPAD_RIGHT" => Some(0x04),
-            // Buttons
-            "Start" | "START" => Some(0x05),
-            "Back" | "BACK" => Some(0x06),
-            "LS_Click" | "LS_CLICK" => Some(0x07),
-            "RS_Click" | "RS_CLICK" => Some(0x08),
-            "LB" => Some(0x09),
-            "RB" => Some(0x0A),
-            "A" => Some(0x0B),
-            "B" => Some(0x0C),
-            "X" => Some(0x0D),
-            "Y" => Some(0x0E),
-            // Left Stick
-            "LS_Right" | "LS_RIGHT" => Some(0x10),
-            "LS_Left" | "LS_LEFT" => Some(0x11),
-            "LS_Up" | "LS_UP" => Some(0x12),
-            "LS_Down" | "LS_DOWN" => Some(0x13),
-            // Right Stick
-            "RS_Right" | "RS_RIGHT" => Some(0x14),
-            "RS_Left" | "RS_LEFT" => Some(0x15),
-            "RS_Up" | "RS_UP" => Some(0x16),
-            "RS_Down" | "RS_DOWN" => Some(0x17),
-            // Triggers
-            "LT" => Some(0x18),
-            "RT" => Some(0x19),
-            // Diagonal combinations
-            "LS_RightUp" | "LS_RIGHTUP" => None, // Special: needs to return [0x10, 0x12]
-            "LS_RightDown" | "LS_RIGHTDOWN" => None,
-            "LS_LeftUp" | "LS_LEFTUP" => None,
-            "LS_LeftDown" | "LS_LEFTDOWN" => None,
-            "RS_RightUp" | "RS_RIGHTUP" => None,
-            "RS_RightDown" | "RS_RIGHTDOWN" => None,
-            "RS_LeftUp" | "RS_LEFTUP" => None,
-            "RS_LeftDown" | "RS_LEFTDOWN" => None,
-            "DPad_UpRight" | "DPAD_UPRIGHT" => None,
-            "DPad_UpLeft" | "DPAD_UPLEFT" => None,
-            "DPad_DownRight" | "DPAD_DOWNRIGHT" => None,
-            "DPad_DownLeft" | "DPAD_DOWNLEFT" => None,
-            _ => None,
-        }
-    }
-
     /// Finds the most sustained frame index from captured frames.
     /// Prioritizes frames with more inputs (diagonal directions), then duration.
     #[inline(always)]
@@ -29,61 +29,41 @@ fn cleanup_test_file(path: &PathBuf) {
 fn test_config_with_complex_mappings() {
     let path = get_test_file_path("complex_mappings");
 
-    let mut config = AppConfig::default();
-    config.mappings = vec![
-        KeyMapping {
-            trigger_key: "A".to_string(),
-            target_keys: SmallVec::from_vec(vec!["1".to_string()]),
-            interval: Some(10),
-            event_duration: Some(5),
-            turbo_enabled: true,
-            move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        },
-        KeyMapping {
-            trigger_key: "B".to_string(),
-            target_keys: SmallVec::from_vec(vec!["2".to_string()]),
-            interval: None,
-            event_duration: None,
-            turbo_enabled: true,
-            move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        },
-        KeyMapping {
-            trigger_key: "F1".to_string(),
-            target_keys: SmallVec::from_vec(vec!["SPACE".to_string()]),
-            interval: Some(20),
-            event_duration: Some(10),
-            turbo_enabled: true,
-            move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        },
-        KeyMapping {
-            trigger_key: "LSHIFT".to_string(),
-            target_keys: SmallVec::from_vec(vec!["ENTER".to_string()]),
-            interval: Some(15),
-            event_duration: Some(8),
-            turbo_enabled: true,
-            move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        },
-    ];
+    let config = AppConfig {
+        mappings: vec![
+            KeyMapping {
+                trigger_key: "A".to_string(),
+                target_keys: SmallVec::from_vec(vec!["1".to_string()]),
+                interval: Some(10),
+                event_duration: Some(5),
+                move_speed: 10,
+                ..Default::default()
+            },
+            KeyMapping {
+                trigger_key: "B".to_string(),
+                target_keys: SmallVec::from_vec(vec!["2".to_string()]),
+                move_speed: 10,
+                ..Default::default()
+            },
+            KeyMapping {
+                trigger_key: "F1".to_string(),
+                target_keys: SmallVec::from_vec(vec!["SPACE".to_string()]),
+                interval: Some(20),
+                event_duration: Some(10),
+                move_speed: 10,
+                ..Default::default()
+            },
+            KeyMapping {
+                trigger_key: "LSHIFT".to_string(),
+                target_keys: SmallVec::from_vec(vec!["ENTER".to_string()]),
+                interval: Some(15),
+                event_duration: Some(8),
+                move_speed: 10,
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
 
     config.save_to_file(&path).expect("Failed to save config");
     let loaded_config = AppConfig::load_from_file(&path).expect("Failed to load config");
@@ -109,8 +89,10 @@ fn test_config_with_complex_mappings() {
 fn test_config_empty_process_whitelist() {
     let path = get_test_file_path("empty_whitelist");
 
-    let mut config = AppConfig::default();
-    config.process_whitelist = vec![];
+    let config = AppConfig {
+        process_whitelist: vec![],
+        ..Default::default()
+    };
 
     config.save_to_file(&path).expect("Failed to save config");
     let loaded_config = AppConfig::load_from_file(&path).expect("Failed to load config");
@@ -125,22 +107,19 @@ fn test_config_empty_process_whitelist() {
 fn test_config_maximum_mappings() {
     let path = get_test_file_path("max_mappings");
 
-    let mut config = AppConfig::default();
-    config.mappings = (0..50)
-        .map(|i| KeyMapping {
-            trigger_key: format!("F{}", (i % 12) + 1),
-            target_keys: SmallVec::from_vec(vec![format!("{}", i % 10)]),
-            interval: Some((i as u64 + 1) * 5),
-            event_duration: Some(5),
-            turbo_enabled: true,
-            move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        })
-        .collect();
+    let config = AppConfig {
+        mappings: (0..50)
+            .map(|i| KeyMapping {
+                trigger_key: format!("F{}", (i % 12) + 1),
+                target_keys: SmallVec::from_vec(vec![format!("{}", i % 10)]),
+                interval: Some((i as u64 + 1) * 5),
+                event_duration: Some(5),
+                move_speed: 10,
+                ..Default::default()
+            })
+            .collect(),
+        ..Default::default()
+    };
 
     config.save_to_file(&path).expect("Failed to save config");
     let loaded_config = AppConfig::load_from_file(&path).expect("Failed to load config");
@@ -184,8 +163,10 @@ fn test_concurrent_config_operations() {
         let handle = thread::spawn(move || {
             let path = get_test_file_path(&format!("concurrent_{}", i));
 
-            let mut config = AppConfig::default();
-            config.worker_count = i;
+            let config = AppConfig {
+                worker_count: i,
+                ..Default::default()
+            };
 
             barrier_clone.wait();
 
@@ -209,38 +190,33 @@ fn test_concurrent_config_operations() {
 fn test_config_multiple_target_keys() {
     let path = get_test_file_path("multiple_target_keys");
 
-    let mut config = AppConfig::default();
-    config.mappings = vec![
-        KeyMapping {
-            trigger_key: "Q".to_string(),
-            target_keys: SmallVec::from_vec(vec!["MOUSE_UP".to_string(), "MOUSE_LEFT".to_string()]),
-            interval: Some(5),
-            event_duration: Some(5),
-            turbo_enabled: true,
-            move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        },
-        KeyMapping {
-            trigger_key: "E".to_string(),
-            target_keys: SmallVec::from_vec(vec![
-                "MOUSE_UP".to_string(),
-                "MOUSE_RIGHT".to_string(),
-            ]),
-            interval: Some(5),
-            event_duration: Some(5),
-            turbo_enabled: true,
-            target_mode: 0,
-            move_speed: 10,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        },
-    ];
+    let config = AppConfig {
+        mappings: vec![
+            KeyMapping {
+                trigger_key: "Q".to_string(),
+                target_keys: SmallVec::from_vec(vec![
+                    "MOUSE_UP".to_string(),
+                    "MOUSE_LEFT".to_string(),
+                ]),
+                interval: Some(5),
+                event_duration: Some(5),
+                move_speed: 10,
+                ..Default::default()
+            },
+            KeyMapping {
+                trigger_key: "E".to_string(),
+                target_keys: SmallVec::from_vec(vec![
+                    "MOUSE_UP".to_string(),
+                    "MOUSE_RIGHT".to_string(),
+                ]),
+                interval: Some(5),
+                event_duration: Some(5),
+                move_speed: 10,
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
 
     config.save_to_file(&path).expect("Failed to save config");
     let loaded_config = AppConfig::load_from_file(&path).expect("Failed to load config");
@@ -261,26 +237,23 @@ fn test_config_multiple_target_keys() {
 fn test_config_many_target_keys() {
     let path = get_test_file_path("many_target_keys");
 
-    let mut config = AppConfig::default();
-    config.mappings = vec![KeyMapping {
-        trigger_key: "A".to_string(),
-        target_keys: SmallVec::from_vec(vec![
-            "1".to_string(),
-            "2".to_string(),
-            "3".to_string(),
-            "4".to_string(),
-            "5".to_string(),
-        ]),
-        target_mode: 0,
-        interval: Some(10),
-        event_duration: Some(5),
-        turbo_enabled: true,
-        move_speed: 10,
-        trigger_sequence: None,
-        sequence_window_ms: 500,
-        hold_indices: None,
-        append_keys: None,
-    }];
+    let config = AppConfig {
+        mappings: vec![KeyMapping {
+            trigger_key: "A".to_string(),
+            target_keys: SmallVec::from_vec(vec![
+                "1".to_string(),
+                "2".to_string(),
+                "3".to_string(),
+                "4".to_string(),
+                "5".to_string(),
+            ]),
+            interval: Some(10),
+            event_duration: Some(5),
+            move_speed: 10,
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
 
     config.save_to_file(&path).expect("Failed to save config");
     let loaded_config = AppConfig::load_from_file(&path).expect("Failed to load config");
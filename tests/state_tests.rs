@@ -29,20 +29,17 @@ fn test_state_pause_toggle() {
 
 #[test]
 fn test_state_input_mapping_lookup() {
-    let mut config = AppConfig::default();
-    config.mappings = vec![KeyMapping {
-        trigger_key: "A".to_string(),
-        target_keys: SmallVec::from_vec(vec!["B".to_string()]),
-        interval: Some(10),
-        event_duration: Some(5),
-        turbo_enabled: true,
-        move_speed: 10,
-        target_mode: 0,
-        trigger_sequence: None,
-        sequence_window_ms: 500,
-        hold_indices: None,
-        append_keys: None,
-    }];
+    let config = AppConfig {
+        mappings: vec![KeyMapping {
+            trigger_key: "A".to_string(),
+            target_keys: SmallVec::from_vec(vec!["B".to_string()]),
+            interval: Some(10),
+            event_duration: Some(5),
+            move_speed: 10,
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
 
     let state = AppState::new(config).expect("Failed to create state");
 
@@ -58,9 +55,11 @@ fn test_state_input_mapping_lookup() {
 
 #[test]
 fn test_state_reload_config() {
-    let mut config = AppConfig::default();
-    config.interval = 10;
-    config.event_duration = 8;
+    let mut config = AppConfig {
+        interval: 10,
+        event_duration: 8,
+        ..Default::default()
+    };
 
     let state = AppState::new(config.clone()).expect("Failed to create state");
 
@@ -137,48 +136,34 @@ fn test_state_input_device_key_combo() {
 
 #[test]
 fn test_state_multiple_mappings() {
-    let mut config = AppConfig::default();
-    config.mappings = vec![
-        KeyMapping {
-            trigger_key: "A".to_string(),
-            target_keys: SmallVec::from_vec(vec!["1".to_string()]),
-            interval: Some(10),
-            event_duration: Some(5),
-            turbo_enabled: true,
-            move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        },
-        KeyMapping {
-            trigger_key: "B".to_string(),
-            target_keys: SmallVec::from_vec(vec!["2".to_string()]),
-            interval: Some(15),
-            event_duration: Some(8),
-            turbo_enabled: true,
-            move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        },
-        KeyMapping {
-            trigger_key: "C".to_string(),
-            target_keys: SmallVec::from_vec(vec!["3".to_string()]),
-            interval: None,
-            event_duration: None,
-            turbo_enabled: false,
-            move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        },
-    ];
+    let config = AppConfig {
+        mappings: vec![
+            KeyMapping {
+                trigger_key: "A".to_string(),
+                target_keys: SmallVec::from_vec(vec!["1".to_string()]),
+                interval: Some(10),
+                event_duration: Some(5),
+                move_speed: 10,
+                ..Default::default()
+            },
+            KeyMapping {
+                trigger_key: "B".to_string(),
+                target_keys: SmallVec::from_vec(vec!["2".to_string()]),
+                interval: Some(15),
+                event_duration: Some(8),
+                move_speed: 10,
+                ..Default::default()
+            },
+            KeyMapping {
+                trigger_key: "C".to_string(),
+                target_keys: SmallVec::from_vec(vec!["3".to_string()]),
+                turbo_enabled: false,
+                move_speed: 10,
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
 
     let state = AppState::new(config).expect("Failed to create state");
 
@@ -211,20 +196,17 @@ fn test_state_nonexistent_mapping() {
 
 #[test]
 fn test_state_config_reload_clears_mappings() {
-    let mut config = AppConfig::default();
-    config.mappings = vec![KeyMapping {
-        trigger_key: "A".to_string(),
-        target_keys: SmallVec::from_vec(vec!["B".to_string()]),
-        interval: Some(10),
-        event_duration: Some(5),
-        turbo_enabled: true,
-        move_speed: 10,
-        target_mode: 0,
-        trigger_sequence: None,
-        sequence_window_ms: 500,
-        hold_indices: None,
-        append_keys: None,
-    }];
+    let mut config = AppConfig {
+        mappings: vec![KeyMapping {
+            trigger_key: "A".to_string(),
+            target_keys: SmallVec::from_vec(vec!["B".to_string()]),
+            interval: Some(10),
+            event_duration: Some(5),
+            move_speed: 10,
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
 
     let state = AppState::new(config.clone()).expect("Failed to create state");
 
@@ -241,35 +223,28 @@ fn test_state_config_reload_clears_mappings() {
 
 #[test]
 fn test_state_turbo_enabled_in_mapping() {
-    let mut config = AppConfig::default();
-    config.mappings = vec![
-        KeyMapping {
-            trigger_key: "A".to_string(),
-            target_keys: SmallVec::from_vec(vec!["1".to_string()]),
-            interval: Some(10),
-            event_duration: Some(5),
-            turbo_enabled: true,
-            move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        },
-        KeyMapping {
-            trigger_key: "B".to_string(),
-            target_keys: SmallVec::from_vec(vec!["2".to_string()]),
-            interval: Some(10),
-            event_duration: Some(5),
-            turbo_enabled: false,
-            move_speed: 10,
-            target_mode: 0,
-            trigger_sequence: None,
-            sequence_window_ms: 500,
-            hold_indices: None,
-            append_keys: None,
-        },
-    ];
+    let config = AppConfig {
+        mappings: vec![
+            KeyMapping {
+                trigger_key: "A".to_string(),
+                target_keys: SmallVec::from_vec(vec!["1".to_string()]),
+                interval: Some(10),
+                event_duration: Some(5),
+                move_speed: 10,
+                ..Default::default()
+            },
+            KeyMapping {
+                trigger_key: "B".to_string(),
+                target_keys: SmallVec::from_vec(vec!["2".to_string()]),
+                interval: Some(10),
+                event_duration: Some(5),
+                turbo_enabled: false,
+                move_speed: 10,
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
 
     let state = AppState::new(config).expect("Failed to create state");
 
@@ -283,3 +258,33 @@ fn test_state_turbo_enabled_in_mapping() {
     assert!(mapping_b.is_some());
     assert!(!mapping_b.unwrap().turbo_enabled);
 }
+
+#[test]
+fn test_state_seeded_jitter_repeats() {
+    let config = AppConfig {
+        mappings: vec![KeyMapping {
+            trigger_key: "A".to_string(),
+            target_keys: SmallVec::from_vec(vec!["1".to_string()]),
+            interval: Some(40),
+            interval_jitter_ms: Some(15),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+
+    let state = AppState::new(config).expect("Failed to create state");
+    let mapping = state
+        .get_input_mapping(&InputDevice::Keyboard(0x41))
+        .expect("mapping should exist");
+    let draws = || -> Vec<u64> {
+        (0..16)
+            .map(|_| state.jitter_interval(mapping.interval, mapping.jitter))
+            .collect()
+    };
+
+    state.seed_jitter(11);
+    let first = draws();
+    assert!(first.iter().all(|d| (25..=55).contains(d)));
+    state.seed_jitter(11);
+    assert_eq!(draws(), first);
+}